@@ -0,0 +1,87 @@
+use compact_str::CompactString;
+use rand::seq::SliceRandom;
+use serde::{Deserialize, Serialize};
+
+use crate::error::PError;
+use crate::world::World;
+use crate::{ptry, punwrap};
+
+use super::stat::StatList;
+
+pub const SHRINE_INTERACTION_RANGE: f32 = 64.0;
+
+// Applied on top of the previous ranks when an ability is upgraded
+#[derive(Debug, Clone)]
+pub struct AbilityUpgrade {
+    pub stats: StatList, // added to the descriptor's base stats
+    pub cooldown_multiplier: f32,
+    pub mana_cost_multiplier: f32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AbilityLevelReward {
+    pub level: usize,
+    pub ability: CompactString,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AbilityUnlockResult {
+    Unlocked,
+    Upgraded(usize), // new rank
+    AlreadyMaxed,
+}
+
+impl World {
+    pub fn get_ability_descriptor_id(&self, name: &str) -> Option<usize> {
+        self.player_ability_descriptors.iter().position(|x| x.name == name)
+    }
+    // gives the player the ability with this name, or upgrades it if they already have it
+    pub fn unlock_ability(&mut self, name: &str) -> Result<AbilityUnlockResult, PError> {
+        let descriptor_id = punwrap!(self.get_ability_descriptor_id(name), NotFound, "no player ability named {}", name);
+        let max_rank = self.player_ability_descriptors[descriptor_id].max_rank();
+        if let Some(ability) = self.inventory.player_abilities.iter_mut().find(|x| x.descriptor_id == descriptor_id) {
+            if ability.rank >= max_rank {
+                return Ok(AbilityUnlockResult::AlreadyMaxed);
+            }
+            ability.rank += 1;
+            return Ok(AbilityUnlockResult::Upgraded(ability.rank));
+        }
+        let ability = self.player_ability_descriptors[descriptor_id].create_player_ability(descriptor_id);
+        self.inventory.player_abilities.push(ability);
+        let ability_id = self.inventory.player_abilities.len() - 1;
+        if let Some(slot) = self.inventory.ability_slots.iter_mut().find(|x| x.cur_ability.is_none()) {
+            slot.cur_ability = Some(ability_id);
+        }
+        Ok(AbilityUnlockResult::Unlocked)
+    }
+    // shrines teach an ability the player doesn't have yet, or upgrade one they do once they have them all
+    pub fn activate_shrine(&mut self, shrine: usize) -> Result<(), PError> {
+        let shrine_component = punwrap!(self.components.shrine_components.get(shrine).and_then(|x| x.as_ref()), Invalid, "entity {} was activated as a shrine but has no shrine component", shrine);
+        if shrine_component.borrow().used {
+            return Ok(());
+        }
+        shrine_component.borrow_mut().used = true;
+        let mut locked = Vec::new();
+        let mut upgradable = Vec::new();
+        for (id, descriptor) in self.player_ability_descriptors.iter().enumerate() {
+            match self.inventory.player_abilities.iter().find(|x| x.descriptor_id == id) {
+                None => locked.push(descriptor.name.clone()),
+                Some(ability) if ability.rank < descriptor.max_rank() => upgradable.push(descriptor.name.clone()),
+                _ => {}
+            }
+        }
+        let mut rng = rand::thread_rng();
+        let choice = locked.choose(&mut rng).or_else(|| upgradable.choose(&mut rng)).cloned();
+        if let Some(name) = choice {
+            ptry!(self.unlock_ability(&name), "while activating shrine {}", shrine);
+        }
+        Ok(())
+    }
+    pub fn unlock_level_rewards(&mut self, from_level: usize, to_level: usize) -> Result<(), PError> {
+        let rewards: Vec<CompactString> = self.progression.ability_rewards.iter().filter(|x| x.level > from_level && x.level <= to_level).map(|x| x.ability.clone()).collect();
+        for ability in rewards {
+            ptry!(self.unlock_ability(&ability), "while giving level up rewards");
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,317 @@
+use compact_str::{format_compact, CompactString};
+use serde::{Deserialize, Serialize};
+
+use crate::error::PError;
+use crate::rendering_engine::abstractions::{RenderData, SpriteContainer};
+use crate::world::World;
+use crate::{perror, punwrap};
+
+use super::camera::Camera;
+use super::components::AnimationComponent;
+use super::json_parsing::animation_clip_json;
+use super::pathfinding::EntityDirectionOptions;
+use super::player::{PlayerDir, PlayerState};
+
+pub const PLAYER_ANIMATION: &str = "player";
+pub const BURNING_CLIP: &str = "burning"; // drawn over anything that's on fire
+
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LoopMode {
+    #[default]
+    Loop,
+    Once, // stops on the last frame
+    PingPong, // plays forwards then backwards
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum AnimationState {
+    #[default]
+    Idle,
+    Walk,
+    Attack,
+    Hurt,
+    Death,
+}
+
+impl AnimationState {
+    pub fn name(&self) -> &'static str {
+        match self {
+            AnimationState::Idle => "idle",
+            AnimationState::Walk => "walk",
+            AnimationState::Attack => "attack",
+            AnimationState::Hurt => "hurt",
+            AnimationState::Death => "death",
+        }
+    }
+}
+
+// diagonals face left or right, like the player's sprites do
+pub fn direction_name(direction: PlayerDir) -> &'static str {
+    match direction {
+        PlayerDir::Up => "up",
+        PlayerDir::Down => "down",
+        PlayerDir::Left | PlayerDir::UpLeft | PlayerDir::DownLeft => "left",
+        PlayerDir::Right | PlayerDir::UpRight | PlayerDir::DownRight => "right",
+    }
+}
+
+// named like a clip the animation player picks for dying, prefix_death or prefix_death_direction
+pub fn is_death_clip(name: &str) -> bool {
+    let death = AnimationState::Death.name();
+    ["", "_up", "_down", "_left", "_right"].iter().any(|direction| name.ends_with(&format!("_{}{}", death, direction)))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AnimationFrame {
+    pub sprite: usize,
+    pub duration: usize, // in frames
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnimationClip {
+    pub frames: Vec<AnimationFrame>,
+    pub mode: LoopMode,
+    pub events: Vec<(usize, CompactString)>, // frame and the event fired when it starts being shown
+}
+
+impl AnimationClip {
+    pub fn from_json(clip: &animation_clip_json, sprites: &SpriteContainer) -> Result<Self, PError> {
+        if clip.frames.is_empty() {
+            return Err(perror!(JSONValidationError, "Animation: {} has no frames", clip.name));
+        }
+        // death animations are only removed once they finish
+        if clip.mode != LoopMode::Once && is_death_clip(&clip.name) {
+            return Err(perror!(JSONValidationError, "Animation: {} is a death clip set to {:?}, it has to play once", clip.name, clip.mode));
+        }
+        let mut frames = Vec::new();
+        for frame in clip.frames.iter() {
+            let duration = frame.duration.unwrap_or(clip.frame_duration);
+            if duration == 0 {
+                return Err(perror!(JSONValidationError, "Animation: {} shows sprite {} for 0 frames, it should be shown for at least 1", clip.name, frame.sprite));
+            }
+            let sprite = punwrap!(sprites.get_sprite_id(&frame.sprite), JSONValidationError, "Animation: {} uses sprite {} which doesn't exist", clip.name, frame.sprite);
+            frames.push(AnimationFrame {sprite, duration});
+        }
+        for event in clip.events.iter() {
+            if event.frame >= frames.len() {
+                return Err(perror!(JSONValidationError, "Animation: {} fires {} on frame {} but it only has {} frames", clip.name, event.name, event.frame, frames.len()));
+            }
+        }
+        Ok(Self {
+            frames,
+            mode: clip.mode,
+            events: clip.events.iter().map(|x| (x.frame, x.name.clone())).collect(),
+        })
+    }
+    // the frames in the order they're shown in one go through the clip
+    fn order(&self) -> Vec<usize> {
+        let count = self.frames.len();
+        match self.mode {
+            LoopMode::PingPong if count > 2 => (0..count).chain((1..count - 1).rev()).collect(),
+            _ => (0..count).collect(),
+        }
+    }
+    // how many frames have started since the clip began and which frame is showing, once the clip has played for the time
+    pub fn step_at(&self, time: usize) -> (usize, usize) {
+        let order = self.order();
+        let length: usize = order.iter().map(|x| self.frames[*x].duration).sum();
+        let (cycles, mut time) = match self.mode {
+            LoopMode::Once => (0, time.min(length - 1)),
+            _ => (time / length, time % length),
+        };
+        for (step, frame) in order.iter().enumerate() {
+            if time < self.frames[*frame].duration {
+                return (cycles * order.len() + step, *frame);
+            }
+            time -= self.frames[*frame].duration;
+        }
+        (cycles * order.len() + order.len() - 1, order[order.len() - 1])
+    }
+    pub fn sprite_at(&self, time: usize) -> usize {
+        self.frames[self.step_at(time).1].sprite
+    }
+    pub fn length(&self) -> usize {
+        self.order().iter().map(|x| self.frames[*x].duration).sum()
+    }
+    fn events_on(&self, frame: usize) -> impl Iterator<Item = &CompactString> {
+        self.events.iter().filter(move |x| x.0 == frame).map(|x| &x.1)
+    }
+}
+
+// Plays the clip for whatever the player or an entity is doing, clips are named prefix_state_direction
+#[derive(Debug, Clone, Default)]
+pub struct AnimationPlayer {
+    pub prefix: CompactString,
+    pub clip: Option<CompactString>, // none when there's no clip for the state, the plain sprite is drawn instead
+    pub time: usize, // frames the clip has been playing for
+    pub events: Vec<CompactString>, // fired this frame
+}
+
+impl AnimationPlayer {
+    pub fn new(prefix: CompactString) -> Self {
+        Self {prefix, ..Default::default()}
+    }
+    // the state facing the direction, then the state facing anywhere, then the same for idle
+    pub fn find_clip(&self, sprites: &SpriteContainer, state: AnimationState, direction: PlayerDir) -> Option<CompactString> {
+        let mut states = vec![state];
+        if state != AnimationState::Idle && state != AnimationState::Death {
+            states.push(AnimationState::Idle);
+        }
+        for state in states {
+            for name in [format_compact!("{}_{}_{}", self.prefix, state.name(), direction_name(direction)), format_compact!("{}_{}", self.prefix, state.name())] {
+                if sprites.get_animation(&name).is_some() {
+                    return Some(name);
+                }
+            }
+        }
+        None
+    }
+    // switches clips when the state or direction changes and moves on a frame otherwise
+    pub fn update(&mut self, sprites: &SpriteContainer, state: AnimationState, direction: PlayerDir) {
+        self.events.clear();
+        let clip_name = self.find_clip(sprites, state, direction);
+        let restarted = clip_name != self.clip;
+        if restarted {
+            self.clip = clip_name;
+            self.time = 0;
+        }
+        let Some(clip) = self.clip.as_ref().and_then(|x| sprites.get_animation(x)) else {
+            return;
+        };
+        // events fire as their frame starts, including the first frame when the clip starts
+        let frame = if restarted {
+            clip.step_at(0).1
+        } else {
+            let (before, _) = clip.step_at(self.time);
+            self.time += 1;
+            let (after, frame) = clip.step_at(self.time);
+            if after == before {
+                return;
+            }
+            frame
+        };
+        self.events.extend(clip.events_on(frame).cloned());
+    }
+    pub fn sprite(&self, sprites: &SpriteContainer) -> Option<usize> {
+        self.clip.as_ref().and_then(|x| sprites.get_animation(x)).map(|x| x.sprite_at(self.time))
+    }
+    // clips that play once are finished after their last frame has been shown for its whole duration
+    pub fn finished(&self, sprites: &SpriteContainer) -> bool {
+        self.clip.as_ref().and_then(|x| sprites.get_animation(x)).is_none_or(|x| x.mode == LoopMode::Once && self.time + 1 >= x.length())
+    }
+}
+
+// What's left of an entity with a death clip, played out where it died after the entity is gone
+#[derive(Debug, Clone)]
+pub struct DeathAnimation {
+    pub x: f32,
+    pub y: f32,
+    pub facing: PlayerDir,
+    pub animation: AnimationPlayer,
+}
+
+impl World {
+    pub fn get_player_animation_state(&self) -> AnimationState {
+        let player = self.player.borrow();
+        if player.health <= 0.0 {
+            return AnimationState::Death;
+        }
+        match player.player_state {
+            // iframes while walking around are from being hit, dashing and rolling give them too
+            PlayerState::Idle | PlayerState::Walking if player.iframes > 0.0 => AnimationState::Hurt,
+            PlayerState::Idle | PlayerState::Blocking => AnimationState::Idle,
+            PlayerState::Walking | PlayerState::Rolling => AnimationState::Walk,
+            PlayerState::AttackingMelee | PlayerState::AttackingRanged | PlayerState::ChargingAbility | PlayerState::EndingAbility => AnimationState::Attack,
+        }
+    }
+    pub fn update_player_anim(&self) {
+        let state = self.get_player_animation_state();
+        let mut player = self.player.borrow_mut();
+        let direction = player.direction;
+        player.animation.update(&self.sprites, state, direction);
+        let (x, y) = (player.x + 16.0, player.y + 16.0);
+        let events = player.animation.events.clone();
+        drop(player);
+        for event in events.iter() {
+            self.spawn_particles(event, x, y);
+        }
+    }
+    // the player's death clip has played out, or there isn't one
+    pub fn player_death_finished(&self) -> bool {
+        self.player.borrow().animation.finished(&self.sprites)
+    }
+    // what the entity is doing and which way it's heading, if it's moving
+    pub fn get_entity_animation_state(&self, entity: usize) -> (AnimationState, Option<PlayerDir>) {
+        let direction = self.components.pathfinding_components[entity].as_ref().and_then(|x| match x.borrow().cur_direction {
+            EntityDirectionOptions::Up => Some(PlayerDir::Up),
+            EntityDirectionOptions::Down => Some(PlayerDir::Down),
+            EntityDirectionOptions::Left => Some(PlayerDir::Left),
+            EntityDirectionOptions::Right => Some(PlayerDir::Right),
+            EntityDirectionOptions::None => None,
+        });
+        let state = if self.components.damageable_components[entity].as_ref().is_some_and(|x| x.borrow().hitstun > 0.0) {
+            AnimationState::Hurt
+        } else if self.entity_attacks.borrow().iter().any(|x| x.entity == entity) {
+            AnimationState::Attack
+        } else if direction.is_some() {
+            AnimationState::Walk
+        } else {
+            AnimationState::Idle
+        };
+        (state, direction)
+    }
+    pub fn update_entity_animation(&self, entity: usize, animation_component: &mut AnimationComponent) {
+        let (state, direction) = self.get_entity_animation_state(entity);
+        if let Some(direction) = direction {
+            animation_component.facing = direction;
+        }
+        animation_component.animation.update(&self.sprites, state, animation_component.facing);
+        if let Some(position) = self.components.position_components[entity].as_ref() {
+            let position = position.borrow();
+            for event in animation_component.animation.events.iter() {
+                self.spawn_particles(event, position.x + 16.0, position.y + 16.0);
+            }
+        }
+    }
+    // leaves the entity's death clip playing where it is, if it has one
+    pub fn start_death_animation(&mut self, entity: usize) {
+        let (Some(animation_component), Some(position)) = (self.components.animation_components[entity].as_ref(), self.components.position_components[entity].as_ref()) else {
+            return;
+        };
+        let animation_component = animation_component.borrow();
+        let mut death = DeathAnimation {
+            x: position.borrow().x,
+            y: position.borrow().y,
+            facing: animation_component.facing,
+            animation: AnimationPlayer::new(animation_component.animation.prefix.clone()),
+        };
+        if death.animation.find_clip(&self.sprites, AnimationState::Death, death.facing).is_some() {
+            death.animation.update(&self.sprites, AnimationState::Death, death.facing);
+            drop(animation_component);
+            self.death_animations.push(death);
+        }
+    }
+    pub fn update_death_animations(&mut self) {
+        let sprites = &self.sprites;
+        self.death_animations.retain_mut(|death| {
+            death.animation.update(sprites, AnimationState::Death, death.facing);
+            !death.animation.finished(sprites)
+        });
+    }
+}
+
+impl Camera {
+    // entities that have died and are still playing their death clip
+    pub fn render_death_animations(&self, world: &World, fog_of_war: bool, index_offset: u32) -> Result<RenderData, PError> {
+        let mut render_data = RenderData::new();
+        for death in world.death_animations.iter().filter(|x| !fog_of_war || world.is_position_visible(x.x, x.y)) {
+            let sprite_id = punwrap!(death.animation.sprite(&world.sprites), Expected, "death animation {:?} has no clip", death.animation.clip);
+            let sprite = punwrap!(world.sprites.get_sprite(sprite_id), Expected, "death animation {:?} refers to a non-existent sprite {}", death.animation.clip, sprite_id);
+            let draw_data = sprite.draw_data(death.x, death.y, 32, 32, self.viewpoint_width, self.viewpoint_height, index_offset + render_data.vertex.len() as u32, -self.camera_x.floor() as i32, -self.camera_y.floor() as i32);
+            render_data.vertex.extend(draw_data.vertex);
+            render_data.index.extend(draw_data.index);
+        }
+        Ok(render_data)
+    }
+}
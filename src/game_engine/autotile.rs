@@ -0,0 +1,98 @@
+use compact_str::CompactString;
+use rustc_hash::FxHashMap;
+
+use crate::error::PError;
+use crate::world::World;
+use crate::{perror, ptry, punwrap};
+
+use super::json_parsing::terrain_archetype_json;
+
+// Autotile terrain archetypes list their sprites by the neighbours that are the same archetype.
+// With 16 sprites the index is the sum of the sides that connect, N = 1, E = 2, S = 4, W = 8.
+// With 47 sprites (blob) the corners count too, NE = 2, SE = 8, SW = 32, NW = 128 and N = 1, E = 4, S = 16, W = 64,
+// a corner only counts when both sides next to it connect and the index is where the mask is in BLOB_MASKS
+const NEIGHBOURS: [(i64, i64); 8] = [(0, -1), (1, -1), (1, 0), (1, 1), (0, 1), (-1, 1), (-1, 0), (-1, -1)];
+
+pub const CARDINAL_TILE_COUNT: usize = 16;
+pub const BLOB_TILE_COUNT: usize = 47;
+
+// every 8 neighbour mask that can happen once lone corners are dropped, in ascending order
+pub const BLOB_MASKS: [u8; BLOB_TILE_COUNT] = [
+    0, 1, 4, 5, 7, 16, 17, 20, 21, 23, 28, 29, 31, 64, 65, 68, 69, 71, 80, 81, 84, 85, 87, 92, 93, 95,
+    112, 113, 116, 117, 119, 124, 125, 127, 193, 197, 199, 209, 213, 215, 221, 223, 241, 245, 247, 253, 255,
+];
+
+// connected is indexed like NEIGHBOURS, clockwise from north
+pub fn cardinal_index(connected: [bool; 8]) -> usize {
+    (connected[0] as usize) | (connected[2] as usize) << 1 | (connected[4] as usize) << 2 | (connected[6] as usize) << 3
+}
+
+pub fn blob_index(connected: [bool; 8]) -> usize {
+    let mut mask = 0u8;
+    for (i, connects) in connected.iter().enumerate() {
+        // corners are odd, they need both sides around them
+        let counts = if i % 2 == 1 {*connects && connected[i - 1] && connected[(i + 1) % 8]} else {*connects};
+        if counts {
+            mask |= 1 << i;
+        }
+    }
+    BLOB_MASKS.iter().position(|x| *x == mask).expect("a reduced blob mask is always one of the 47")
+}
+
+// how an autotile archetype picks its sprites, which depends on how many it has. Checked when terrain archetypes load
+pub fn autotile_index(archetype: &terrain_archetype_json) -> Result<fn([bool; 8]) -> usize, PError> {
+    match archetype.sprites.len() {
+        CARDINAL_TILE_COUNT => Ok(cardinal_index),
+        BLOB_TILE_COUNT => Ok(blob_index),
+        count => Err(perror!(JSONValidationError, "Terrain archetype {} is an autotile with {} sprites, it needs {} or {}", archetype.name, count, CARDINAL_TILE_COUNT, BLOB_TILE_COUNT)),
+    }
+}
+
+impl World {
+    // picks the sprite of every autotile of the archetype in the tiles, neighbours outside of them are looked at too
+    pub fn resolve_autotiles(&mut self, x: usize, y: usize, width: usize, height: usize, archetype: &terrain_archetype_json) -> Result<(), PError> {
+        let index = ptry!(autotile_index(archetype));
+        let mut sprites = Vec::with_capacity(archetype.sprites.len());
+        for sprite in archetype.sprites.iter() {
+            sprites.push(punwrap!(self.sprites.get_sprite_id(sprite), Invalid, "Could not find sprite: {} while generating world from json data", sprite));
+        }
+
+        let tiles = self.get_autotiles(x.saturating_sub(1), y.saturating_sub(1), width + 2, height + 2, &archetype.name);
+        for ty in y..y + height {
+            for tx in x..x + width {
+                let Some(terrain) = tiles.get(&[tx, ty]) else {
+                    continue;
+                };
+                let mut connected = [false; 8];
+                for (i, (dx, dy)) in NEIGHBOURS.iter().enumerate() {
+                    connected[i] = match (tx.checked_add_signed(*dx as isize), ty.checked_add_signed(*dy as isize)) {
+                        (Some(nx), Some(ny)) => tiles.contains_key(&[nx, ny]),
+                        _ => false,
+                    };
+                }
+                self.set_terrain_sprite(*terrain, sprites[index(connected)]);
+            }
+        }
+        Ok(())
+    }
+    // terrain of the archetype in the tiles by tile
+    fn get_autotiles(&self, x: usize, y: usize, width: usize, height: usize, archetype: &CompactString) -> FxHashMap<[usize; 2], usize> {
+        let mut tiles = FxHashMap::default();
+        let chunks_ref = self.chunks.borrow();
+        for chunk_x in World::coord_to_chunk_coord(x * 32)..=World::coord_to_chunk_coord((x + width) * 32) {
+            for chunk_y in World::coord_to_chunk_coord(y * 32)..=World::coord_to_chunk_coord((y + height) * 32) {
+                let Some(chunk) = self.get_chunk_from_chunk_xy(chunk_x, chunk_y) else {
+                    continue;
+                };
+                for terrain_id in chunks_ref[chunk].terrain_ids.iter() {
+                    let terrain = self.terrain[terrain_id];
+                    let (tx, ty) = (terrain.x / 32, terrain.y / 32);
+                    if tx >= x && tx < x + width && ty >= y && ty < y + height && self.get_terrain_archetype(*terrain_id) == Some(archetype) {
+                        tiles.insert([tx, ty], *terrain_id);
+                    }
+                }
+            }
+        }
+        tiles
+    }
+}
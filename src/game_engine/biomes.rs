@@ -0,0 +1,166 @@
+use compact_str::CompactString;
+use rand::Rng;
+use rustc_hash::FxHashMap;
+use serde::{Deserialize, Serialize};
+
+use crate::error::PError;
+use crate::{perror, punwrap};
+use crate::world::World;
+
+use super::json_parsing::{room_descriptor_json, spawn_archetype_json, terrain_archetype_json};
+use super::loot::LootTable;
+
+pub const NO_TINT: [f32; 4] = [1.0, 1.0, 1.0, 1.0];
+
+// A theme for a run of floors, the rooms generated while in it are picked from its pool and restyled with it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Biome {
+    pub name: CompactString,
+    #[serde(default = "default_biome_floors")]
+    pub floors: usize, // floors spent in this biome before moving on to the next one, the last biome never ends
+    #[serde(default)]
+    pub rooms: Vec<CompactString>, // rooms that can be generated, every room if empty
+    #[serde(default)]
+    pub terrain: FxHashMap<CompactString, CompactString>, // terrain archetypes swapped for themed ones
+    #[serde(default)]
+    pub spawn_archetype: Option<CompactString>, // used instead of the room's own
+    #[serde(default)]
+    pub bonus_points: usize, // added to the points every room spawns with
+    #[serde(default)]
+    pub loot_tables: FxHashMap<CompactString, CompactString>, // loot tables swapped for themed ones
+    #[serde(default = "default_loot_multiplier")]
+    pub loot_multiplier: f32, // multiplies the loot stat when rolling drops
+    #[serde(default = "default_tint")]
+    pub tint: [f32; 4], // multiplied into everything drawn in the world
+}
+
+fn default_biome_floors() -> usize {
+    1
+}
+
+fn default_loot_multiplier() -> f32 {
+    1.0
+}
+
+fn default_tint() -> [f32; 4] {
+    NO_TINT
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Biomes {
+    pub rooms_per_floor: usize,
+    pub biomes: Vec<Biome>, // in the order they're gone through
+}
+
+impl Default for Biomes {
+    fn default() -> Self {
+        Self {
+            rooms_per_floor: 1,
+            biomes: Vec::new()
+        }
+    }
+}
+
+impl Biomes {
+    pub fn floor_at_depth(&self, depth: usize) -> usize {
+        depth / self.rooms_per_floor.max(1)
+    }
+    pub fn biome_on_floor(&self, floor: usize) -> Option<&Biome> {
+        let mut floors_so_far = 0;
+        for biome in self.biomes.iter() {
+            floors_so_far += biome.floors;
+            if floor < floors_so_far {
+                return Some(biome);
+            }
+        }
+        self.biomes.last()
+    }
+    pub fn validate(&self, terrain_archetypes: &FxHashMap<CompactString, terrain_archetype_json>, rooms: &FxHashMap<CompactString, room_descriptor_json>, spawn_archetypes: &FxHashMap<CompactString, spawn_archetype_json>, loot_tables: &FxHashMap<CompactString, LootTable>) -> Result<(), PError> {
+        if self.rooms_per_floor == 0 {
+            return Err(perror!(JSONValidationError, "there should be at least 1 room per floor"));
+        }
+        for biome in self.biomes.iter() {
+            let name = &biome.name;
+            if biome.floors == 0 {
+                return Err(perror!(JSONValidationError, "Biome: {} lasts 0 floors, it should last at least 1", name));
+            }
+            if let Some(room) = biome.rooms.iter().find(|x| !rooms.contains_key(*x)) {
+                return Err(perror!(JSONValidationError, "Biome: {} can generate room {} which doesn't exist", name, room));
+            }
+            for (from, to) in biome.terrain.iter() {
+                if !terrain_archetypes.contains_key(from) || !terrain_archetypes.contains_key(to) {
+                    return Err(perror!(JSONValidationError, "Biome: {} swaps terrain archetype {} for {} but one of them doesn't exist", name, from, to));
+                }
+            }
+            if let Some(spawn_archetype) = biome.spawn_archetype.as_ref().filter(|x| !spawn_archetypes.contains_key(*x)) {
+                return Err(perror!(JSONValidationError, "Biome: {} uses spawn archetype {} which doesn't exist", name, spawn_archetype));
+            }
+            for (from, to) in biome.loot_tables.iter() {
+                if !loot_tables.contains_key(from) || !loot_tables.contains_key(to) {
+                    return Err(perror!(JSONValidationError, "Biome: {} swaps loot table {} for {} but one of them doesn't exist", name, from, to));
+                }
+            }
+            if biome.loot_multiplier < 0.0 {
+                return Err(perror!(JSONValidationError, "Biome: {} has a negative loot multiplier", name));
+            }
+            if biome.tint.iter().any(|x| !(0.0..=1.0).contains(x)) {
+                return Err(perror!(JSONValidationError, "Biome: {} has tint {:?}, every channel should be between 0 and 1", name, biome.tint));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl World {
+    pub fn get_floor(&self) -> usize {
+        self.biomes.floor_at_depth(self.depth)
+    }
+    pub fn get_biome(&self) -> Option<&Biome> {
+        self.biomes.biome_on_floor(self.get_floor())
+    }
+    pub fn get_ambient_tint(&self) -> [f32; 4] {
+        self.get_biome().map(|x| x.tint).unwrap_or(NO_TINT)
+    }
+    // a room from the current biome's pool, any room if the pool is empty
+    pub fn pick_room<R: Rng>(&self, rng: &mut R) -> Option<CompactString> {
+        let mut pool: Vec<&CompactString> = match self.get_biome() {
+            Some(biome) if !biome.rooms.is_empty() => biome.rooms.iter().collect(),
+            _ => self.room_descriptors.keys().collect(),
+        };
+        // hash map order isn't stable, sorting keeps a seeded rng picking the same room
+        pool.sort();
+        if pool.is_empty() {
+            return None;
+        }
+        Some(pool[rng.gen_range(0..pool.len())].clone())
+    }
+    // restyles a room and its spawns with the current biome, procedural rooms are restyled before they're laid out
+    pub fn apply_biome(&self, room: &mut room_descriptor_json, spawn_archetype: &mut spawn_archetype_json) -> Result<(), PError> {
+        let Some(biome) = self.get_biome() else {
+            return Ok(());
+        };
+        let theme = |archetype: &mut CompactString| {
+            if let Some(themed) = biome.terrain.get(archetype) {
+                *archetype = themed.clone();
+            }
+        };
+        room.terrain.iter_mut().for_each(|x| theme(&mut x.terrain_archetype));
+        if let Some(generation) = room.generation.as_mut() {
+            theme(&mut generation.wall);
+            generation.floor.iter_mut().for_each(theme);
+            generation.decorations.iter_mut().for_each(|x| theme(&mut x.terrain_archetype));
+        }
+        if let Some(themed) = biome.spawn_archetype.as_ref() {
+            *spawn_archetype = punwrap!(self.spawn_archetype_descriptors.get(themed), NotFound, "Biome {} uses spawn archetype {} which doesn't exist", biome.name, themed).clone();
+        }
+        spawn_archetype.total_points_to_spawn += biome.bonus_points;
+        Ok(())
+    }
+    pub fn get_biome_loot_table<'a>(&'a self, table: &'a CompactString) -> &'a CompactString {
+        self.get_biome().and_then(|x| x.loot_tables.get(table)).unwrap_or(table)
+    }
+    pub fn get_biome_loot_multiplier(&self) -> f32 {
+        self.get_biome().map(|x| x.loot_multiplier).unwrap_or(1.0)
+    }
+}
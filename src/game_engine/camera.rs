@@ -12,9 +12,10 @@ use itertools::izip;
 use wgpu_text::glyph_brush::{HorizontalAlign, Section as TextSection};
 use rustc_hash::FxHashMap;
 
+use super::animation::BURNING_CLIP;
 use super::entity_components::{DamageableComponent, PositionComponent};
-use super::player::EXP_REQS;
 use super::ui::UIESprite;
+use super::visibility::UNSEEN_BRIGHTNESS;
 
 #[derive(Debug, Clone)]
 pub enum Font{
@@ -71,7 +72,7 @@ impl Camera{
         let health_bar_width = f32::max(0.0, (player.health / player.max_health as f32) * 250.0);
         health_bar.sprite.width = health_bar_width;
         let exp_bar = punwrap!(self.get_ui_element_mut_by_name("exp_bar_inside".into()), "Could not find exp bar inside ui element");
-        let exp_bar_width = f32::max(0.0, f32::min(player.exp/EXP_REQS[player.level], 1.0) * 446.0);
+        let exp_bar_width = f32::max(0.0, f32::min(player.exp/world.progression.exp_curve.exp_required(player.level), 1.0) * 446.0);
         exp_bar.sprite.width = exp_bar_width;
         let mana_bar = punwrap!(self.get_ui_element_mut_by_name(CompactString::from("mana_bar_inside")), "Could not find mana bar inside ui element");
         let mana_bar_width = f32::max(0.0, f32::min(world.mana/ptry!(world.inventory.get_combined_stats()).max_mana.map(|x| x.get_value()).unwrap_or(1.0), 1.0) * 244.0);
@@ -80,8 +81,7 @@ impl Camera{
         if let Some(ltm) = level_text_mut {
             ltm.text = format!("Lv. {}", player.level);
         }
-        let mut player_mut = world.player.borrow_mut();
-        player_mut.add_exp(0.8);
+        ptry!(world.add_player_exp(0.8));
         Ok(())
     }
     pub fn get_ui_element_mut_by_name(&mut self, name: CompactString) -> Option<&mut UIElement> {
@@ -184,6 +184,8 @@ impl Camera{
 
         let mut chunks_loaded = Vec::new();
         let mut entities_to_render = Vec::new();
+        // the level editor sees everything
+        let fog_of_war = !self.level_editor;
         for x in camera_left_chunk_x..=camera_right_chunk_x{
             for y in camera_top_chunk_y..=camera_bot_chunk_y{
                 
@@ -208,13 +210,25 @@ impl Camera{
                     let vertex_offset_y = -self.camera_y as i32;
 
                     let terrain = punwrap!(world.get_terrain(*terrain_id), Invalid, "chunk with id {} contains terrain with id {} but that terrain does not exist", chunk_id, terrain_id);
-                    let draw_data = sprite.draw_data(terrain.x as f32, terrain.y as f32, 32, 32, self.viewpoint_width, self.viewpoint_height, terrain_index_offset, vertex_offset_x, vertex_offset_y);
+                    // terrain that was seen before is drawn darker, terrain never seen isn't drawn at all
+                    let tile = [terrain.x / 32, terrain.y / 32];
+                    let brightness = if !fog_of_war || world.is_tile_visible(tile) {
+                        1.0
+                    } else if world.is_explored(tile) {
+                        UNSEEN_BRIGHTNESS
+                    } else {
+                        continue;
+                    };
+                    let mut draw_data = sprite.draw_data(terrain.x as f32, terrain.y as f32, 32, 32, self.viewpoint_width, self.viewpoint_height, terrain_index_offset, vertex_offset_x, vertex_offset_y);
+                    for vertex in draw_data.vertex.iter_mut() {
+                        vertex.color = [brightness, brightness, brightness, 1.0];
+                    }
                     terrain_index_offset += 4;
                     terrain_data.vertex.extend(draw_data.vertex);
                     terrain_data.index.extend(draw_data.index);
                 }
 
-                entities_to_render.extend(chunk.entities_ids.clone());
+                entities_to_render.extend(chunk.entities_ids.iter().copied().filter(|x| !fog_of_war || world.is_entity_visible(*x)));
             }
         }
 
@@ -232,7 +246,9 @@ impl Camera{
                 else if entities_to_render[entity_to_render_index] == i && x.is_some() && y.is_some() {entity_to_render_index += 1; Some((i,(x.as_ref().unwrap().borrow(), y.as_ref().unwrap().borrow()))) }
                 else { None }
             ) {
-                let sprite = punwrap!(world.sprites.get_sprite(sprite_component.sprite), Expected, "Sprite in sprite_component for entity with id {} is a non-existent sprite", i);
+                // entities without a clip for what they're doing keep their plain sprite
+                let sprite_id = world.components.animation_components[i].as_ref().and_then(|x| x.borrow().animation.sprite(&world.sprites)).unwrap_or(sprite_component.sprite);
+                let sprite = punwrap!(world.sprites.get_sprite(sprite_id), Expected, "Sprite in sprite_component for entity with id {} is a non-existent sprite", i);
 
                 let dd = self.render_entity(sprite, &position_component,entity_data.vertex.len() as u32);
                 entity_data.vertex.extend(dd.vertex);
@@ -244,35 +260,24 @@ impl Camera{
             // fire bars
             entity_to_render_index = 0;
 
-            for (i, (position_component, damageable_component, anim_component)) in izip!(
+            for (_, (position_component, damageable_component)) in izip!(
                 world.components.position_components.iter(),
-                world.components.damageable_components.iter(),
-                world.components.animation_components.iter()
+                world.components.damageable_components.iter()
             ).enumerate().filter_map(
-                |(i, (x, y, anim_component))| 
+                |(i, (x, y))| 
                 if entity_to_render_index == entities_to_render.len() {None} 
-                else if entities_to_render[entity_to_render_index] == i && x.is_some() && y.is_some() {entity_to_render_index += 1; Some((i,(x.as_ref().unwrap().borrow(), y.as_ref().unwrap().borrow(), anim_component))) }
+                else if entities_to_render[entity_to_render_index] == i && x.is_some() && y.is_some() {entity_to_render_index += 1; Some((i,(x.as_ref().unwrap().borrow(), y.as_ref().unwrap().borrow()))) }
                 else { None }
             ) {
                 
-                if damageable_component.fire.is_some(){
-                    let anim_frame = anim_component.as_ref().map(|x| x.borrow().animation_frame).unwrap_or(0);
-                    let dd = if anim_frame < 60 {
-                        let sprite = punwrap!(world.sprites.get_sprite_by_name("fire1"), MissingExpectedGlobalSprite, "no fire1 sprite");
+                if let Some(fire) = damageable_component.fire {
+                    let burning = punwrap!(world.sprites.get_animation(BURNING_CLIP), MissingExpectedGlobalSprite, "no burning animation");
+                    let sprite = punwrap!(world.sprites.get_sprite(burning.sprite_at(fire.time_alive as usize)), Expected, "burning animation refers to a non-existent sprite");
 
-                        let vertex_offset_x = (-1.0 * self.camera_x).floor() as i32;
-                        let vertex_offset_y = (-1.0 * self.camera_y).floor() as i32;
+                    let vertex_offset_x = (-1.0 * self.camera_x).floor() as i32;
+                    let vertex_offset_y = (-1.0 * self.camera_y).floor() as i32;
 
-                        sprite.draw_data(position_component.x, position_component.y, 32, 32, self.viewpoint_width, self.viewpoint_height, extra_data.vertex.len() as u32, vertex_offset_x, vertex_offset_y)
-                    }
-                    else {
-                        let sprite = punwrap!(world.sprites.get_sprite_by_name("fire2"), MissingExpectedGlobalSprite, "no fire2 sprite");
-
-                        let vertex_offset_x = (-1.0 * self.camera_x).floor() as i32;
-                        let vertex_offset_y = (-1.0 * self.camera_y).floor() as i32;
-
-                        sprite.draw_data(position_component.x, position_component.y, 32, 32, self.viewpoint_width, self.viewpoint_height, extra_data.vertex.len() as u32, vertex_offset_x, vertex_offset_y)
-                    };
+                    let dd = sprite.draw_data(position_component.x, position_component.y, 32, 32, self.viewpoint_width, self.viewpoint_height, extra_data.vertex.len() as u32, vertex_offset_x, vertex_offset_y);
                     extra_data.vertex.extend(dd.vertex);
                     extra_data.index.extend(dd.index);
                 }
@@ -331,7 +336,8 @@ impl Camera{
             let x = e[0] * 32 - 7;
             let y = e[1] * 32 - 7;
 
-            let sprite = world.sprites.get_sprite_by_name("health").unwrap();
+            // the exit shows up dark until the room is cleared
+            let sprite = world.sprites.get_sprite_by_name(if world.is_exit_locked() {"health_bar_back"} else {"health"}).unwrap();
             let dd = sprite.draw_data(x as f32, y as f32, 46, 46, self.viewpoint_width, self.viewpoint_height, render_data.vertex.len() as u32, -self.camera_x as i32, -self.camera_y as i32);
             render_data.vertex.extend(dd.vertex);
             render_data.index.extend(dd.index);
@@ -349,7 +355,7 @@ impl Camera{
 
         let mut item_on_floor_render_data = RenderData::new();
         self.temp_uie2.clear();
-        for item_on_floor in world.items_on_floor.borrow().iter() {
+        for item_on_floor in world.items_on_floor.borrow().iter().filter(|x| !fog_of_war || world.is_position_visible(x.x, x.y)) {
             let sprite_id = punwrap!(world.sprites.get_sprite_id(&item_on_floor.item.sprite), Expected, "Item on floor with refers to a non-existent sprite {}", item_on_floor.item.sprite);
             let sprite = punwrap!(world.sprites.get_sprite(sprite_id), Expected, "Item on floor with refers to a non-existent sprite {}", item_on_floor.item.sprite);
             let draw_data = sprite.draw_data(item_on_floor.x, item_on_floor.y, 24, 24, self.viewpoint_width, self.viewpoint_height, item_on_floor_render_data.vertex.len() as u32, -self.camera_x.floor() as i32, -self.camera_y.floor() as i32);
@@ -368,7 +374,18 @@ impl Camera{
                 item_on_floor_render_data.index.extend(draw_data.index);
             }
         }
-       
+        for gold_on_floor in world.gold_on_floor.borrow().iter().filter(|x| !fog_of_war || world.is_position_visible(x.x, x.y)) {
+            let display = gold_on_floor.display();
+            self.temp_uie2.extend(display.text);
+            for ui in display.sprites {
+                let sprite_id = punwrap!(world.sprites.get_sprite_id(&ui.sprite), Expected, "gold on floor display refers to a non-existent sprite {}", ui.sprite);
+                let sprite = punwrap!(world.sprites.get_sprite(sprite_id), Expected, "gold on floor display refers to a non-existent sprite {}", ui.sprite);
+                let draw_data = sprite.draw_data(ui.x, ui.y, ui.width as usize, ui.height as usize, self.viewpoint_width, self.viewpoint_height, item_on_floor_render_data.vertex.len() as u32, -self.camera_x.floor() as i32, -self.camera_y.floor() as i32);
+                item_on_floor_render_data.vertex.extend(draw_data.vertex);
+                item_on_floor_render_data.index.extend(draw_data.index);
+            }
+        }
+
         item_on_floor_render_data.offset(render_data.vertex.len() as u32);
         render_data.vertex.extend(item_on_floor_render_data.vertex);
         render_data.index.extend(item_on_floor_render_data.index);
@@ -427,10 +444,30 @@ impl Camera{
         player_effect_draw_data.offset(render_data.vertex.len() as u32);
         render_data.vertex.extend(player_effect_draw_data.vertex);
         render_data.index.extend(player_effect_draw_data.index);
+        let death_data = ptry!(self.render_death_animations(world, fog_of_war, render_data.vertex.len() as u32), "while rendering death animations");
+        render_data.vertex.extend(death_data.vertex);
+        render_data.index.extend(death_data.index);
+        let particle_data = ptry!(self.render_particles(world, fog_of_war, render_data.vertex.len() as u32), "while rendering particles");
+        render_data.vertex.extend(particle_data.vertex);
+        render_data.index.extend(particle_data.index);
         render_data.index_behind_text = render_data.index.len() as u32;
+        // everything so far is in the world, the hud below keeps its own colors
+        let tint = world.get_ambient_tint();
+        for vertex in render_data.vertex.iter_mut() {
+            for (channel, tint) in vertex.color.iter_mut().zip(tint) {
+                *channel *= tint;
+            }
+        }
+        // the level editor is lit like the starting level
+        if !self.level_editor {
+            render_data.ambient_light = world.get_ambient_light();
+            render_data.lights = self.get_lights(world, fog_of_war);
+            render_data.light_aspect = self.viewpoint_width as f32 / self.viewpoint_height as f32;
+        }
 
         world.set_loaded_chunks(chunks_loaded);
-        let mut sorted_ui_elements: Vec<&UIESprite> = self.ui_elements.values().filter_map(|x| if x.visible { Some(&x.sprite) } else { None }).collect();
+        // the hud is hidden while editing levels
+        let mut sorted_ui_elements: Vec<&UIESprite> = self.ui_elements.values().filter_map(|x| if x.visible && !self.level_editor { Some(&x.sprite) } else { None }).collect();
         sorted_ui_elements.extend(&uie.sprites);
         sorted_ui_elements.sort_by(|a, b| a.z.partial_cmp(&b.z).unwrap());
 
@@ -440,6 +477,11 @@ impl Camera{
             render_data.vertex.extend(draw_data.vertex);
             render_data.index.extend(draw_data.index);
         }
+        if !self.level_editor {
+            let map_data = ptry!(self.render_map(world, render_data.vertex.len() as u32), "while rendering the map");
+            render_data.vertex.extend(map_data.vertex);
+            render_data.index.extend(map_data.index);
+        }
         let temp_uie_clone = uie.text.clone();
         self.temp_uie = temp_uie_clone; // THIS IS THE JANKIEST THING IVE EVER SEEN BUT ITS THE ONLY WAY IT WORKS FOR SOME REASON
         
@@ -1,5 +1,10 @@
 use compact_str::CompactString;
 
+use super::animation::AnimationPlayer;
+use super::factions::{FactionComponent, MinionComponent};
+use super::interactables::InteractableComponent;
+use super::item::Item;
+use super::player::PlayerDir;
 use super::entity_components::{AggroComponent, CollisionBox, DamageableComponent, EntityAttackComponent, PathfindingComponent, PositionComponent};
 use std::cell::RefCell;
 #[macro_export]
@@ -42,6 +47,7 @@ pub struct SpriteComponent {
 pub struct CollisionComponent {
     pub collision_box: CollisionBox,
     pub respects_collision: bool,
+    pub flying: bool, // crosses water and isn't affected by terrain effects
 }
 
 impl Default for CollisionComponent {
@@ -49,6 +55,7 @@ impl Default for CollisionComponent {
         Self {
             collision_box: CollisionBox::default(),
             respects_collision: false,
+            flying: false,
         }
     }
 }
@@ -58,7 +65,16 @@ pub struct LootComponent {
 }
 
 pub struct AnimationComponent {
-    pub animation_frame: usize // 0-119
+    pub animation: AnimationPlayer,
+    pub facing: PlayerDir, // kept when the entity stops moving
+}
+
+pub struct VendorComponent {
+    pub stock: Vec<Item>
+}
+
+pub struct ShrineComponent {
+    pub used: bool
 }
 
 setup_components!{
@@ -70,7 +86,12 @@ setup_components!{
     sprite_components => SpriteComponent,
     collision_components => CollisionComponent,
     loot_components => LootComponent,
-    animation_components => AnimationComponent
+    animation_components => AnimationComponent,
+    vendor_components => VendorComponent,
+    shrine_components => ShrineComponent,
+    faction_components => FactionComponent,
+    minion_components => MinionComponent,
+    interactable_components => InteractableComponent
 }
 
 
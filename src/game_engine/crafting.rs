@@ -0,0 +1,245 @@
+use compact_str::CompactString;
+use rustc_hash::FxHashMap;
+
+use crate::error::PError;
+use crate::world::World;
+use crate::{perror, ptry, punwrap};
+
+use super::item::{Item, ItemType, Rarity};
+use super::stat::StatC;
+
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RecipeMode {
+    Create, // consumes the inputs and creates a new item of the output archetype
+    Reroll, // rerolls the first input's stats from its archetype, this resets its rarity and any upgrades
+    Upgrade, // moves the first input up one rarity and multiplies its upgraded stats
+}
+
+// Each field that is set has to match, an input with nothing set accepts any item
+#[derive(Debug, Clone, Default)]
+pub struct ItemMatcher {
+    pub archetype: Option<CompactString>,
+    pub rarity: Option<Rarity>,
+    pub item_type: Option<ItemType>,
+}
+
+impl ItemMatcher {
+    pub fn matches(&self, item: &Item) -> bool {
+        if let Some(archetype) = &self.archetype {
+            if item.archetype != *archetype {
+                return false;
+            }
+        }
+        if let Some(rarity) = &self.rarity {
+            if item.rarity != *rarity {
+                return false;
+            }
+        }
+        if let Some(item_type) = &self.item_type {
+            if item.item_type != *item_type {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Recipe {
+    pub name: CompactString,
+    pub inputs: Vec<ItemMatcher>,
+    pub inputs_must_match: bool, // all inputs have to share archetype and rarity, used for combining two equal items
+    pub materials: Vec<(CompactString, usize)>,
+    pub mode: RecipeMode,
+    pub output: Option<CompactString>,
+    pub upgrade_multiplier: f32,
+    pub upgraded_stats: Vec<CompactString>,
+}
+
+impl Recipe {
+    // Returns the item ids ordered by the input they satisfy, None if the items can't make this recipe
+    pub fn match_items(&self, items: &[(usize, &Item)]) -> Option<Vec<usize>> {
+        if items.len() != self.inputs.len() {
+            return None;
+        }
+        let mut used = vec![false; items.len()];
+        let mut matched = Vec::new();
+        if !Self::assign_inputs(&self.inputs, items, &mut used, &mut matched) {
+            return None;
+        }
+        if self.inputs_must_match {
+            let (_, first) = items[0];
+            if items.iter().any(|(_, item)| item.archetype != first.archetype || item.rarity != first.rarity) {
+                return None;
+            }
+        }
+        Some(matched)
+    }
+    fn assign_inputs(inputs: &[ItemMatcher], items: &[(usize, &Item)], used: &mut [bool], matched: &mut Vec<usize>) -> bool {
+        let Some(input) = inputs.first() else {
+            return true;
+        };
+        for (i, (id, item)) in items.iter().enumerate() {
+            if used[i] || !input.matches(item) {
+                continue;
+            }
+            used[i] = true;
+            matched.push(*id);
+            if Self::assign_inputs(&inputs[1..], items, used, matched) {
+                return true;
+            }
+            used[i] = false;
+            matched.pop();
+        }
+        false
+    }
+    pub fn has_materials(&self, materials: &FxHashMap<CompactString, usize>) -> bool {
+        self.materials.iter().all(|(material, amount)| materials.get(material).copied().unwrap_or(0) >= *amount)
+    }
+    pub fn can_craft(&self, items: &[(usize, &Item)], materials: &FxHashMap<CompactString, usize>) -> bool {
+        if !self.has_materials(materials) {
+            return false;
+        }
+        match self.match_items(items) {
+            Some(matched) => {
+                if self.mode == RecipeMode::Upgrade {
+                    let target = items.iter().find(|(id, _)| *id == matched[0]);
+                    return target.and_then(|(_, item)| item.rarity.next()).is_some();
+                }
+                true
+            },
+            None => false
+        }
+    }
+    pub fn get_description(&self) -> String {
+        let mut t = format!("{}\n------------\n", self.name);
+        for input in self.inputs.iter() {
+            let mut parts = Vec::new();
+            if let Some(rarity) = &input.rarity {
+                parts.push(format!("{:?}", rarity));
+            }
+            if let Some(archetype) = &input.archetype {
+                parts.push(archetype.to_string());
+            } else if let Some(item_type) = &input.item_type {
+                parts.push(format!("{:?}", item_type));
+            } else {
+                parts.push(String::from("any item"));
+            }
+            t.push_str(&format!("{}\n", parts.join(" ")));
+        }
+        if self.inputs_must_match {
+            t.push_str("(inputs must match)\n");
+        }
+        for (material, amount) in self.materials.iter() {
+            t.push_str(&format!("{} x{}\n", material, amount));
+        }
+        t
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SalvageEntry {
+    pub matches: ItemMatcher,
+    pub materials: Vec<(CompactString, usize)>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct CraftingBook {
+    pub materials: Vec<CompactString>,
+    pub recipes: Vec<Recipe>,
+    pub salvage: Vec<SalvageEntry>, // first matching entry is used, so more specific entries go first
+}
+
+impl CraftingBook {
+    pub fn get_salvage(&self, item: &Item) -> Option<&SalvageEntry> {
+        self.salvage.iter().find(|entry| entry.matches.matches(item))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CraftingRequest {
+    Craft(usize),
+    Salvage,
+}
+
+impl World {
+    pub fn process_crafting_request(&mut self) -> Result<(), PError> {
+        match self.inventory.crafting_request.take() {
+            Some(CraftingRequest::Craft(recipe)) => {
+                ptry!(self.craft(recipe), "while processing crafting request for recipe {}", recipe);
+            },
+            Some(CraftingRequest::Salvage) => {
+                ptry!(self.salvage_crafting_slots(), "while processing salvage request");
+            },
+            None => {}
+        }
+        Ok(())
+    }
+    pub fn craft(&mut self, recipe_id: usize) -> Result<(), PError> {
+        let recipe = punwrap!(self.crafting_book.recipes.get(recipe_id), NotFound, "there is no recipe with id {}", recipe_id).clone();
+        let mut consumed = {
+            let items = self.inventory.get_crafting_items();
+            if !recipe.can_craft(&items, &self.inventory.materials) {
+                return Err(perror!(Invalid, "the crafting slots and materials do not satisfy recipe {}", recipe.name));
+            }
+            punwrap!(recipe.match_items(&items), Invalid, "the crafting slots do not satisfy recipe {}", recipe.name)
+        };
+        match recipe.mode {
+            RecipeMode::Create => {
+                let output = punwrap!(recipe.output.clone(), Invalid, "recipe {} creates an item but has no output archetype", recipe.name);
+                let item = ptry!(self.create_item_with_archetype(output), "while crafting recipe {}", recipe.name);
+                for id in consumed.iter() {
+                    ptry!(self.inventory.destroy_item(*id), "while consuming the inputs of recipe {}", recipe.name);
+                }
+                ptry!(self.inventory.add_to_crafting_slot(item), "while crafting recipe {}", recipe.name);
+                consumed.clear();
+            },
+            RecipeMode::Reroll => {
+                let target = consumed.remove(0);
+                let archetype = punwrap!(self.inventory.get_item(&target), NotFound, "no item with id {}", target).archetype.clone();
+                let rerolled = ptry!(self.create_item_with_archetype(archetype), "while rerolling item {}", target);
+                let item = punwrap!(self.inventory.get_item_mut(&target), NotFound, "no item with id {}", target);
+                item.stats = rerolled.stats;
+                item.rarity = rerolled.rarity;
+            },
+            RecipeMode::Upgrade => {
+                let target = consumed.remove(0);
+                let item = punwrap!(self.inventory.get_item_mut(&target), NotFound, "no item with id {}", target);
+                item.rarity = punwrap!(item.rarity.next(), Invalid, "item {} is already at the highest rarity", item.name);
+                for stat in recipe.upgraded_stats.iter() {
+                    let s = punwrap!(item.stats.get_stat_mut(stat), Invalid, "recipe {} upgrades unknown stat {}", recipe.name, stat);
+                    if let Some(s) = s.as_mut() {
+                        *s = StatC {
+                            flat: s.flat * recipe.upgrade_multiplier,
+                            percent: s.percent * recipe.upgrade_multiplier
+                        };
+                    }
+                }
+            }
+        }
+        for id in consumed.iter() {
+            ptry!(self.inventory.destroy_item(*id), "while consuming the inputs of recipe {}", recipe.name);
+        }
+        for (material, amount) in recipe.materials.iter() {
+            let count = punwrap!(self.inventory.materials.get_mut(material), Invalid, "missing material {} while crafting {}", material, recipe.name);
+            *count -= amount;
+        }
+        Ok(())
+    }
+    pub fn salvage_crafting_slots(&mut self) -> Result<(), PError> {
+        let mut salvaged = Vec::new();
+        for (id, item) in self.inventory.get_crafting_items() {
+            if let Some(entry) = self.crafting_book.get_salvage(item) {
+                salvaged.push((id, entry.materials.clone()));
+            }
+        }
+        for (id, materials) in salvaged {
+            ptry!(self.inventory.destroy_item(id), "while salvaging item {}", id);
+            for (material, amount) in materials {
+                *self.inventory.materials.entry(material).or_insert(0) += amount;
+            }
+        }
+        Ok(())
+    }
+}
@@ -6,14 +6,25 @@ use crate::{ptry, punwrap};
 use std::cell::RefCell;
 use super::camera::Camera;
 use super::entity_attacks::EntityAttackBox;
-use super::entity_components::{self, CollisionBox, EntityAttackComponent, PathfindingComponent, PositionComponent};
+use super::entity_components::{self, CollisionBox, EntityAttackComponent, PathfindingComponent, PositionComponent, KNOCKBACK_DECAY, MIN_KNOCKBACK};
 use super::json_parsing::entity_archetype_json;
 use super::world::{Chunk, World};
-use super::player::Player;
+use super::animation::AnimationPlayer;
+use super::player::{Player, PlayerDir};
+use super::interactables::{InteractableComponent, InteractableKind};
 use super::pathfinding::{self, EntityDirectionOptions};
+use super::stat::DamageType;
+use super::terrain::{TerrainTags, ICE_TRACTION};
 
 impl World {
     pub fn move_entity(&self, position_component: &mut PositionComponent, entity_id: &usize, movement: [f32; 2], chunkref: &mut std::cell::RefMut<'_, Vec<Chunk>>, respects_collision: bool, has_collision: bool) -> Result<(), PError>{ 
+        let movement = if respects_collision && !self.is_flying(Some(*entity_id)) {
+            let collision_box = punwrap!(self.components.collision_components.get(*entity_id).and_then(|x| x.as_ref()), Expected, "entity with id {} respects collision but has no collision component", entity_id).borrow().collision_box;
+            let multiplier = World::get_terrain_speed_multiplier(&self.get_terrain_effects(position_component.x + collision_box.x_offset, position_component.y + collision_box.y_offset, collision_box.w, collision_box.h));
+            [movement[0] * multiplier, movement[1] * multiplier]
+        } else {
+            movement
+        };
         if respects_collision && ptry!(self.check_collision(false, Some(*entity_id), (position_component.x + movement[0]).floor(), (position_component.y + movement[1]).floor(), 32,32, true)){
             return Ok(());
         }
@@ -57,7 +68,8 @@ impl World {
         self.pathfinding_frame %= 5;
         let player: Player = self.player.borrow().clone();
         let mut entities_to_update = Vec::new();
-        let chunkref: &mut std::cell::RefMut<'_, Vec<Chunk>> = &mut self.chunks.borrow_mut();
+        let mut chunks = self.chunks.borrow_mut();
+        let chunkref: &mut std::cell::RefMut<'_, Vec<Chunk>> = &mut chunks;
         for chunk in self.loaded_chunks.iter() {
             entities_to_update.extend(chunkref[*chunk].entities_ids.clone());
         }
@@ -80,27 +92,58 @@ impl World {
         }
 
 
+        // knockback and hitstun updates
+        entities_to_update_index = 0;
+        for (i, mut damageable_component, mut position_component, collision_component) in izip!(
+            self.components.damageable_components.iter(),
+            self.components.position_components.iter(),
+            self.components.collision_components.iter()
+        ).enumerate().filter_map(
+            |(i, (damageable_component, position_component, collision_component))|
+            if entities_to_update_index == entities_to_update.len() {None}
+            else if i == entities_to_update[entities_to_update_index] && damageable_component.is_some() && position_component.is_some() {entities_to_update_index += 1; Some((i, damageable_component.as_ref().unwrap().borrow_mut(), position_component.as_ref().unwrap().borrow_mut(), collision_component.as_ref().map(|x| x.borrow())))}
+            else {None}
+        ){
+            if damageable_component.hitstun > 0.0 {
+                damageable_component.hitstun -= 1.0;
+            }
+            let knockback = damageable_component.knockback;
+            if f32::sqrt(knockback[0].powf(2.0) + knockback[1].powf(2.0)) < MIN_KNOCKBACK {
+                damageable_component.knockback = [0.0, 0.0];
+                continue;
+            }
+            ptry!(self.move_entity(&mut position_component, &i, knockback, chunkref, collision_component.as_ref().map(|x| x.respects_collision).unwrap_or(false), collision_component.is_some()), "while applying knockback to entity {}", i);
+            damageable_component.knockback = [knockback[0] * KNOCKBACK_DECAY, knockback[1] * KNOCKBACK_DECAY];
+        }
+
         // pathfind towards player updates
         entities_to_update_index = 0;
-        for (i, mut pathfinding_component, mut position_component, aggro_component, collision_component) in izip!(
+        for (i, mut pathfinding_component, mut position_component, aggro_component, collision_component, damageable_component) in izip!(
             self.components.pathfinding_components.iter(),
             self.components.position_components.iter(),
             self.components.aggro_components.iter(),
-            self.components.collision_components.iter()
+            self.components.collision_components.iter(),
+            self.components.damageable_components.iter()
         ).enumerate().filter_map(
-            |(i, (pathfinding_component, position_component, aggro_component, collision_component))|
+            |(i, (pathfinding_component, position_component, aggro_component, collision_component, damageable_component))|
             if entities_to_update_index == entities_to_update.len() {None}
-            else if i == entities_to_update[entities_to_update_index] && pathfinding_component.is_some() && position_component.is_some() && aggro_component.is_some() {entities_to_update_index += 1; Some((i, pathfinding_component.as_ref().unwrap().borrow_mut(), position_component.as_ref().unwrap().borrow_mut(), aggro_component.as_ref().unwrap().borrow(), collision_component.as_ref().map(|x| x.borrow())))}
+            else if i == entities_to_update[entities_to_update_index] && pathfinding_component.is_some() && position_component.is_some() && aggro_component.is_some() {entities_to_update_index += 1; Some((i, pathfinding_component.as_ref().unwrap().borrow_mut(), position_component.as_ref().unwrap().borrow_mut(), aggro_component.as_ref().unwrap().borrow(), collision_component.as_ref().map(|x| x.borrow()), damageable_component.as_ref().map(|x| x.borrow())))}
             else {None}
         ){
-            let player_ref = self.player.borrow();
-            let player_x = player_ref.x + player_ref.collision_box.x_offset;
-            let player_y = player_ref.y + player_ref.collision_box.y_offset;
+            if damageable_component.is_some_and(|x| x.hitstun > 0.0) {
+                continue;
+            }
+            let Some([target_x, target_y]) = self.get_movement_target(i, position_component.x, position_component.y, &aggro_component) else {
+                continue;
+            };
             if aggro_component.aggroed {
-                ptry!(self.move_entity_towards_player(&i, &collision_component.as_ref().map(|x| x.collision_box).unwrap_or(CollisionBox::default()), &mut position_component, &mut pathfinding_component, chunkref,  player_x, player_y, collision_component.as_ref().map(|x| x.respects_collision).unwrap_or(false), collision_component.is_some()));
+                ptry!(self.move_entity_towards_target(&i, &collision_component.as_ref().map(|x| x.collision_box).unwrap_or(CollisionBox::default()), &mut position_component, &mut pathfinding_component, chunkref,  target_x, target_y, collision_component.as_ref().map(|x| x.respects_collision).unwrap_or(false), collision_component.is_some()));
             }
         }
 
+        // noticing the player looks at the terrain in the chunks
+        drop(chunks);
+
         // aggro component updates
         entities_to_update_index = 0;
         for (i, mut aggro_component, position_component) in izip!(
@@ -112,6 +155,7 @@ impl World {
             else if i == entities_to_update[entities_to_update_index] && aggro_component.is_some() && position_component.is_some() {entities_to_update_index += 1; Some((i, aggro_component.as_ref().unwrap().borrow_mut(), position_component.as_ref().unwrap().borrow()))}
             else {None}
         ){
+            ptry!(self.update_aggro_target(i, position_component.x, position_component.y, &mut aggro_component, &entities_to_update), "while updating the aggro target of entity {}", i);
             let player_ref = self.player.borrow();
             let player_x = player_ref.x + player_ref.collision_box.x_offset;
             let player_y = player_ref.y + player_ref.collision_box.y_offset;
@@ -119,7 +163,7 @@ impl World {
                 (position_component.y as f64 - (player_y) as f64).powf(2.0) + (position_component.x as f64 - (player_x) as f64).powf(2.0),
             );
             if aggro_component.aggroed {
-            } else if distance <= aggro_component.aggro_range as f64 && (aggro_component.aggro_through_walls || ptry!(self.is_line_of_sight(position_component.x, position_component.y, player_x, player_y))) {
+            } else if distance <= aggro_component.aggro_range as f64 && (aggro_component.aggro_through_walls || self.can_see([position_component.x, position_component.y], [player_x, player_y])) {
                 aggro_component.aggroed = true;
             }
         }
@@ -127,46 +171,51 @@ impl World {
         // attack component updates
         entities_to_update_index = 0;
 
-        for (i, position_component, mut attack_component) in izip!(
+        for (i, position_component, mut attack_component, damageable_component) in izip!(
             self.components.position_components.iter(),
-            self.components.attack_components.iter()
+            self.components.attack_components.iter(),
+            self.components.damageable_components.iter()
         ).enumerate().filter_map(
-            |(i, (position_component, attack_component))|
+            |(i, (position_component, attack_component, damageable_component))|
             if entities_to_update_index == entities_to_update.len() {None}
-            else if i == entities_to_update[entities_to_update_index] && position_component.is_some() && attack_component.is_some() {entities_to_update_index += 1; Some((i, position_component.as_ref().unwrap().borrow(), attack_component.as_ref().unwrap().borrow_mut()))}
+            else if i == entities_to_update[entities_to_update_index] && position_component.is_some() && attack_component.is_some() {entities_to_update_index += 1; Some((i, position_component.as_ref().unwrap().borrow(), attack_component.as_ref().unwrap().borrow_mut(), damageable_component.as_ref().map(|x| x.borrow())))}
             else {None}
         ){
-             
-            let player_ref = self.player.borrow();
-            let player_x = player_ref.x + player_ref.collision_box.x_offset;
-            let player_y = player_ref.y + player_ref.collision_box.y_offset;
+            if damageable_component.is_some_and(|x| x.hitstun > 0.0) {
+                continue;
+            }
+
+            let Some([target_x, target_y]) = self.get_attack_target(i) else {
+                continue;
+            };
             let distance = f64::sqrt(
-                (position_component.y as f64 - (player_y) as f64).powf(2.0) + (position_component.x as f64 - (player_x) as f64).powf(2.0),
+                (position_component.y as f64 - (target_y) as f64).powf(2.0) + (position_component.x as f64 - (target_x) as f64).powf(2.0),
             );
             if distance < attack_component.attack_range as f64 {
                 let attack_pattern = punwrap!(self.entity_attack_pattern_lookup.get(&attack_component.entity_attack_pattern), Expected, "entity attack component on entity with id {} refers to non-existent entity attack pattern {}", i, attack_component.entity_attack_pattern);
                 if attack_component.cur_attack_cooldown <= 0.0 {
-                    let direction_to_player_unnormalized = [
-                        player_x - position_component.x,
-                        player_y - position_component.y
+                    let direction_to_target_unnormalized = [
+                        target_x - position_component.x,
+                        target_y - position_component.y
                     ];
-                    let magnitude = f32::sqrt(direction_to_player_unnormalized[0].powf(2.0) + direction_to_player_unnormalized[1].powf(2.0));
-                    let direction_to_player = [
-                        direction_to_player_unnormalized[0] / magnitude,
-                        direction_to_player_unnormalized[1] / magnitude
+                    let magnitude = f32::sqrt(direction_to_target_unnormalized[0].powf(2.0) + direction_to_target_unnormalized[1].powf(2.0));
+                    let direction_to_target = [
+                        direction_to_target_unnormalized[0] / magnitude,
+                        direction_to_target_unnormalized[1] / magnitude
                     ];
-                    let angle = f32::atan2(direction_to_player[1], direction_to_player[0]);
+                    let angle = f32::atan2(direction_to_target[1], direction_to_target[0]);
                     let descriptor = punwrap!(self.get_attack_descriptor_by_name(&attack_pattern.attacks[attack_component.cur_attack]), Invalid, "attack pattern {} refers to a non-existent attack {}", &attack_component.entity_attack_pattern, attack_pattern.attacks[attack_component.cur_attack]);
-                    if ptry!(self.is_line_of_sight(position_component.x, position_component.y, player_x, player_y), "while updating entity with id {}", i) {
+                    if ptry!(self.is_line_of_sight(position_component.x, position_component.y, target_x, target_y), "while updating entity with id {}", i) {
                         match descriptor.r#type {
                             AttackType::Magic => {
                                 let max_dist = descriptor.reach as f32/2.0 + descriptor.max_start_dist_from_entity.unwrap_or(0) as f32;
-                                let dist_to_player = f32::sqrt((player_x - position_component.x).powf(2.0) + (player_y - position_component.y).powf(2.0));
-                                if dist_to_player < max_dist {
+                                let dist_to_target = f32::sqrt((target_x - position_component.x).powf(2.0) + (target_y - position_component.y).powf(2.0));
+                                if dist_to_target < max_dist {
                                     self.entity_attacks.borrow_mut().push(EntityAttackBox {
                                         archetype: attack_pattern.attacks[attack_component.cur_attack].clone(),
-                                        x: player_x,
-                                        y: player_y,
+                                        entity: i,
+                                        x: target_x,
+                                        y: target_y,
                                         time_charged: 0.0,
                                         rotation: angle,
                                     });
@@ -174,8 +223,9 @@ impl World {
                                     self.entity_attacks.borrow_mut().push(
                                         EntityAttackBox {
                                             archetype: attack_pattern.attacks[attack_component.cur_attack].clone(),
-                                            x: position_component.x + direction_to_player[0] * (max_dist),
-                                            y: position_component.y + direction_to_player[1] * (max_dist),
+                                        entity: i,
+                                            x: position_component.x + direction_to_target[0] * (max_dist),
+                                            y: position_component.y + direction_to_target[1] * (max_dist),
                                             time_charged: 0.0,
                                             rotation: angle,
                                         }
@@ -186,6 +236,7 @@ impl World {
                                 self.entity_attacks.borrow_mut().push(
                                     EntityAttackBox {
                                         archetype: attack_pattern.attacks[attack_component.cur_attack].clone(),
+                                        entity: i,
                                         x: position_component.x + angle.cos() * (descriptor.reach as f32/2.0),
                                         y: position_component.y + angle.sin() * (descriptor.reach as f32/2.0),
                                         time_charged: 0.0,
@@ -228,7 +279,7 @@ impl World {
                 true
             });
             if poison_tick.abs() > 0.0 {
-                self.damage_entity_dot(&position_component, &mut damageable_component, poison_tick, camera, [0.6, 0.0, 0.8, 1.0]);
+                self.damage_entity_dot(&position_component, &mut damageable_component, poison_tick, DamageType::Poison, camera);
             }
             let mut remove_fire = false;
             let fire_tick = if let Some(fire) = &mut damageable_component.fire {
@@ -242,21 +293,21 @@ impl World {
             }else{0.0};
                 
             if fire_tick.abs() > 0.0 {
-                self.damage_entity_dot(&position_component, &mut damageable_component, fire_tick, camera, [1.0, 0.4, 0.0, 1.0]);
+                self.damage_entity_dot(&position_component, &mut damageable_component, fire_tick, DamageType::Fire, camera);
             }
             if remove_fire {
                 damageable_component.fire = None;
             }
         }
-        // anim frame updates
-        for mut anim_component in self.components.animation_components.iter().enumerate().filter_map(
+        // animation updates
+        entities_to_update_index = 0;
+        for (i, mut anim_component) in self.components.animation_components.iter().enumerate().filter_map(
             |(i, anim_component)|
             if entities_to_update_index == entities_to_update.len() {None}
-            else if i == entities_to_update[entities_to_update_index] && anim_component.is_some() {entities_to_update_index += 1; Some(anim_component.as_ref().unwrap().borrow_mut())}
+            else if i == entities_to_update[entities_to_update_index] && anim_component.is_some() {entities_to_update_index += 1; Some((i, anim_component.as_ref().unwrap().borrow_mut()))}
             else {None}
         ){
-            anim_component.animation_frame += 1;
-            anim_component.animation_frame %= 120;
+            self.update_entity_animation(i, &mut anim_component);
         }
         // attack cooldown updates
         entities_to_update_index = 0;
@@ -292,91 +343,43 @@ impl World {
         }
         Ok(true)
     }
-    pub fn move_entity_towards_player(&self, entity_id: &usize,collision_box: &CollisionBox, position_component: &mut PositionComponent, pathfinding_component: &mut PathfindingComponent, chunkref: &mut std::cell::RefMut<'_, Vec<Chunk>>, player_x: f32, player_y: f32, respects_collision: bool, has_collision: bool) -> Result<(), PError>{
-        let direction: [f32; 2] = [player_x - position_component.x, player_y - position_component.y];
+    pub fn move_entity_towards_target(&self, entity_id: &usize,collision_box: &CollisionBox, position_component: &mut PositionComponent, pathfinding_component: &mut PathfindingComponent, chunkref: &mut std::cell::RefMut<'_, Vec<Chunk>>, target_x: f32, target_y: f32, respects_collision: bool, has_collision: bool) -> Result<(), PError>{
+        let direction: [f32; 2] = [target_x - position_component.x, target_y - position_component.y];
         let entity_pathfinding_frame = punwrap!(self.pathfinding_frames.get(entity_id), Expected, "all entities that follow player should have a pathfinding frame, entity with id {} doesn't, was the entity properly created?", entity_id);
         if direction[0] == 0.0 && direction[1] == 0.0 {
             return Ok(());
         }
         let movement_speed = pathfinding_component.movement_speed;
-        if self.pathfinding_frame != *entity_pathfinding_frame && respects_collision{
-            let magnitude: f32 = f32::sqrt(direction[0].powf(2.0) + direction[1].powf(2.0));
-            if magnitude > 128.0{
-                match pathfinding_component.cur_direction {
-                    EntityDirectionOptions::Down => {
-                        ptry!(self.move_entity(position_component, entity_id, [0.0, movement_speed], chunkref, respects_collision, has_collision));
-                    },
-                    EntityDirectionOptions::Up => {
-                        ptry!(self.move_entity(position_component, entity_id, [0.0, -movement_speed], chunkref, respects_collision, has_collision));
-                    },
-                    EntityDirectionOptions::Left => {
-                        ptry!(self.move_entity(position_component, entity_id, [-movement_speed, 0.0], chunkref, respects_collision, has_collision));
-                    },
-                    EntityDirectionOptions::Right => {
-                        ptry!(self.move_entity(position_component, entity_id, [movement_speed, 0.0], chunkref, respects_collision, has_collision));
-                    },
-                    EntityDirectionOptions::None => {
-                    },
-                }
-                return Ok(());
-            }
-        }
         let magnitude: f32 = f32::sqrt(direction[0].powf(2.0) + direction[1].powf(2.0));
-        if respects_collision {
-            if magnitude > 128.0{
-                let direction: EntityDirectionOptions= ptry!(pathfinding::pathfind_by_block(position_component, *collision_box, *entity_id, self));
-                match direction {
-                    EntityDirectionOptions::Down => {
-                        ptry!(self.move_entity(position_component, entity_id, [0.0, movement_speed], chunkref, respects_collision, has_collision));
-                        pathfinding_component.cur_direction = EntityDirectionOptions::Down;
-                    },
-                    EntityDirectionOptions::Up => {
-                        ptry!(self.move_entity(position_component, entity_id, [0.0, -movement_speed], chunkref, respects_collision, has_collision));
-                        pathfinding_component.cur_direction = EntityDirectionOptions::Up;
-                    },
-                    EntityDirectionOptions::Left => {
-                        ptry!(self.move_entity(position_component, entity_id, [-movement_speed, 0.0], chunkref, respects_collision, has_collision));
-                        pathfinding_component.cur_direction = EntityDirectionOptions::Left;
-                    },
-                    EntityDirectionOptions::Right => {
-                        ptry!(self.move_entity(position_component, entity_id, [movement_speed, 0.0], chunkref, respects_collision, has_collision));
-                        pathfinding_component.cur_direction = EntityDirectionOptions::Right;
-                    },
-                    EntityDirectionOptions::None => {
-                        pathfinding_component.cur_direction = EntityDirectionOptions::None;
-                    },
-                };
-            } else if magnitude > 60.0{
-                let direction: EntityDirectionOptions = ptry!(pathfinding::pathfind_high_granularity(position_component, *collision_box,*entity_id, self));
-                match direction {
-                    EntityDirectionOptions::Down => {
-                        pathfinding_component.cur_direction = EntityDirectionOptions::Down;
-                        ptry!(self.move_entity(position_component, entity_id, [0.0, movement_speed], chunkref, respects_collision, has_collision));
-                    },
-                    EntityDirectionOptions::Up => {
-                        pathfinding_component.cur_direction = EntityDirectionOptions::Up;
-                        ptry!(self.move_entity(position_component, entity_id, [0.0, -movement_speed], chunkref, respects_collision, has_collision));
-                    },
-                    EntityDirectionOptions::Left => {
-                        pathfinding_component.cur_direction = EntityDirectionOptions::Left;
-                        ptry!(self.move_entity(position_component, entity_id, [-movement_speed, 0.0], chunkref, respects_collision, has_collision));
-                    },
-                    EntityDirectionOptions::Right => {
-                        pathfinding_component.cur_direction = EntityDirectionOptions::Right;
-                        ptry!(self.move_entity(position_component, entity_id, [movement_speed, 0.0], chunkref, respects_collision, has_collision));
-                    },
-                    EntityDirectionOptions::None => {
-                        pathfinding_component.cur_direction = EntityDirectionOptions::None;
-                    },
-                }
-            }else {
-                let movement = [direction[0] / magnitude * movement_speed, direction[1] / magnitude * movement_speed];
-                ptry!(self.move_entity(position_component, entity_id,  movement, chunkref,  respects_collision, has_collision));
-            }
-        }else{
-            let movement = [direction[0] / magnitude * movement_speed, direction[1] / magnitude * movement_speed];
-            ptry!(self.move_entity(position_component, entity_id,  movement, chunkref,  respects_collision, has_collision));
+        let wanted = if self.pathfinding_frame != *entity_pathfinding_frame && respects_collision && magnitude > 128.0 {
+            direction_movement(pathfinding_component.cur_direction, movement_speed)
+        } else if respects_collision && magnitude > 128.0 {
+            let direction: EntityDirectionOptions = ptry!(pathfinding::pathfind_by_block(position_component, *collision_box, *entity_id, self, [target_x, target_y]));
+            pathfinding_component.cur_direction = direction;
+            direction_movement(direction, movement_speed)
+        } else if respects_collision && magnitude > 60.0 {
+            let direction: EntityDirectionOptions = ptry!(pathfinding::pathfind_high_granularity(position_component, *collision_box, *entity_id, self, [target_x, target_y]));
+            pathfinding_component.cur_direction = direction;
+            direction_movement(direction, movement_speed)
+        } else {
+            [direction[0] / magnitude * movement_speed, direction[1] / magnitude * movement_speed]
+        };
+        // on ice entities pick up the movement they want as slowly as the player does and slide past turns
+        let on_ice = respects_collision && !self.is_flying(Some(*entity_id)) && self.get_terrain_effects(position_component.x + collision_box.x_offset, position_component.y + collision_box.y_offset, collision_box.w, collision_box.h).contains(&TerrainTags::Ice);
+        let movement = if on_ice {
+            let velocity = pathfinding_component.velocity;
+            [velocity[0] + (wanted[0] - velocity[0]) * ICE_TRACTION, velocity[1] + (wanted[1] - velocity[1]) * ICE_TRACTION]
+        } else {
+            wanted
+        };
+        if movement == [0.0, 0.0] {
+            pathfinding_component.velocity = movement;
+            return Ok(());
         }
+        let (x, y) = (position_component.x, position_component.y);
+        ptry!(self.move_entity(position_component, entity_id, movement, chunkref, respects_collision, has_collision));
+        // running into something stops the slide
+        pathfinding_component.velocity = if position_component.x == x && position_component.y == y {[0.0, 0.0]} else {movement};
         Ok(())
     }
     pub fn add_entity(&mut self, x: f32, y: f32) -> usize{
@@ -414,6 +417,12 @@ impl World {
         let mut aggressive = false;
         let mut damageable = false;
         let mut attacker = false;
+        let mut vendor = false;
+        let mut shrine = false;
+        let mut ally = false;
+        let mut flying = false;
+        let mut interactable = None;
+        let mut opens_on_clear = false;
         
         for tag in archetype.basic_tags.iter() {
             let tag = tag.as_str();
@@ -433,6 +442,33 @@ impl World {
                 "attacker" => {
                     attacker = true;
                 },
+                "vendor" => {
+                    vendor = true;
+                },
+                "shrine" => {
+                    shrine = true;
+                },
+                "ally" => {
+                    ally = true;
+                },
+                "flying" => {
+                    flying = true;
+                },
+                "door" => {
+                    interactable = Some(InteractableKind::Door);
+                },
+                "lever" => {
+                    interactable = Some(InteractableKind::Lever);
+                },
+                "chest" => {
+                    interactable = Some(InteractableKind::Chest);
+                },
+                "breakable" => {
+                    interactable = Some(InteractableKind::Breakable);
+                },
+                "opensOnClear" => {
+                    opens_on_clear = true;
+                },
                 _ => {}
             }
         }
@@ -450,6 +486,7 @@ impl World {
                 self.components.collision_components.insert(entity, Some(RefCell::new(super::components::CollisionComponent{
                     collision_box,
                     respects_collision,
+                    flying,
                 })));
             } else {
                 self.components.collision_components.insert(entity, Some(RefCell::new(super::components::CollisionComponent{
@@ -460,6 +497,7 @@ impl World {
                         h: 32.0,
                     },
                     respects_collision,
+                    flying,
                 })));
             }
         }
@@ -468,14 +506,22 @@ impl World {
                 sprite: punwrap!(self.sprites.get_sprite_id(sprite), JSONValidationError, "entity archetype {} refers to sprite {} but that sprite doesn't exist", archetype.name, sprite)
             })));
         }
+        if let Some(animation) = &archetype.animation {
+            self.components.animation_components.insert(entity, Some(RefCell::new(super::components::AnimationComponent {
+                animation: AnimationPlayer::new(animation.clone()),
+                facing: PlayerDir::Down
+            })));
+        }
         if aggressive {
             self.components.aggro_components.insert(entity, Some(RefCell::new(entity_components::AggroComponent{
                 aggroed: false,
                 aggro_through_walls: respects_collision,
+                target: None,
                 aggro_range: punwrap!(archetype.aggro_range, JSONValidationError, "entity archetype {} has aggressive tag but no aggro range", archetype.name),
             })));
             self.components.pathfinding_components.insert(entity, Some(RefCell::new(entity_components::PathfindingComponent {
                 cur_direction: EntityDirectionOptions::None,
+                velocity: [0.0, 0.0],
                 movement_speed: punwrap!(archetype.movement_speed, JSONValidationError, "entity archetype {} has aggressive tag but no movement speed, give it a movement speed of 0 if you don't want it to move", archetype.name)
         })));
         }
@@ -490,7 +536,10 @@ impl World {
                     h: 32.0,
                 }),
                 poisons: vec![],
-                fire: None
+                fire: None,
+                resistances: archetype.resistances,
+                knockback: [0.0, 0.0],
+                hitstun: 0.0
             })));
         }
 
@@ -499,6 +548,38 @@ impl World {
                 loot_tables: archetype.loot_table.clone()
             }))); 
         }
+        if vendor {
+            let stock_table = punwrap!(archetype.stock_table.as_ref(), JSONValidationError, "entity archetype {} has vendor tag but no stock table", archetype.name);
+            let stock = ptry!(self.roll_vendor_stock(stock_table, archetype.stock_size.unwrap_or(super::vendor::DEFAULT_STOCK_SIZE)), "while creating vendor with archetype {}", archetype.name);
+            self.components.vendor_components.insert(entity, Some(RefCell::new(super::components::VendorComponent {
+                stock
+            })));
+        }
+        if shrine {
+            self.components.shrine_components.insert(entity, Some(RefCell::new(super::components::ShrineComponent {
+                used: false
+            })));
+        }
+        if ally {
+            self.components.faction_components.insert(entity, Some(RefCell::new(super::factions::FactionComponent {
+                faction: super::factions::Faction::Player
+            })));
+        }
+        if let Some(kind) = interactable {
+            let open_sprite = match &archetype.open_sprite {
+                Some(sprite) => Some(punwrap!(self.sprites.get_sprite_id(sprite), JSONValidationError, "entity archetype {} refers to open sprite {} but that sprite doesn't exist", archetype.name, sprite)),
+                None => None
+            };
+            self.components.interactable_components.insert(entity, Some(RefCell::new(InteractableComponent {
+                kind,
+                open: false,
+                opens_on_clear,
+                key: archetype.key.clone(),
+                channel: archetype.channel.clone(),
+                closed_sprite: self.components.sprite_components[entity].as_ref().map(|x| x.borrow().sprite),
+                open_sprite,
+            })));
+        }
         Ok(entity)
     }
     pub fn add_entity_archetype(&mut self, name: CompactString, archetype: entity_archetype_json){
@@ -563,3 +644,13 @@ impl EntityAttackPattern{
     //     None
     // }
 }
+
+fn direction_movement(direction: EntityDirectionOptions, movement_speed: f32) -> [f32; 2] {
+    match direction {
+        EntityDirectionOptions::Down => [0.0, movement_speed],
+        EntityDirectionOptions::Up => [0.0, -movement_speed],
+        EntityDirectionOptions::Left => [-movement_speed, 0.0],
+        EntityDirectionOptions::Right => [movement_speed, 0.0],
+        EntityDirectionOptions::None => [0.0, 0.0],
+    }
+}
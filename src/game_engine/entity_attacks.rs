@@ -1,10 +1,11 @@
 use compact_str::CompactString;
 
-use super::{entities::AttackType, json_parsing::{FireDescriptor, PoisonDescriptor}};
+use super::{entities::AttackType, json_parsing::{FireDescriptor, PoisonDescriptor}, stat::DamageType};
 
 #[derive(Clone, Debug)]
 pub struct EntityAttackBox {
     pub archetype: CompactString,
+    pub entity: usize, // the entity that made this attack
     pub x: f32,
     pub y: f32,
     pub rotation: f32,
@@ -20,6 +21,8 @@ pub struct EntityAttackDescriptor{
     pub time_to_charge: usize,
     pub max_start_dist_from_entity: Option<usize>,
     pub sprite: CompactString,
+    pub damage_type: DamageType,
+    pub knockback: f32,
     pub fire: Option<FireDescriptor>,
     pub poison: Option<PoisonDescriptor>
 }
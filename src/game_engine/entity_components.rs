@@ -2,6 +2,7 @@ use compact_str::CompactString;
 use serde::{Deserialize, Serialize};
 
 use super::pathfinding::EntityDirectionOptions;
+use super::stat::Resistances;
 
 #[derive(Clone, Debug, PartialEq)]
 pub struct PositionComponent{
@@ -21,6 +22,7 @@ pub struct EntityAttackComponent{
 pub struct PathfindingComponent{
     pub cur_direction: EntityDirectionOptions,
     pub movement_speed: f32,
+    pub velocity: [f32; 2], // last frame's movement, kept so entities slide on ice
 }
 #[derive(Clone, Debug, PartialEq)]
 pub struct DamageableComponent{
@@ -28,9 +30,16 @@ pub struct DamageableComponent{
     pub max_health: usize,
     pub damage_box: CollisionBox,
     pub poisons: Vec<Poison>,
-    pub fire: Option<Fire>
+    pub fire: Option<Fire>,
+    pub resistances: Resistances,
+    pub knockback: [f32; 2], // current knockback velocity, decays every frame
+    pub hitstun: f32, // frames left where the entity can't move or attack, interrupts charging attacks
 }
 
+// knockback velocity is multiplied by this every frame until it drops below MIN_KNOCKBACK
+pub const KNOCKBACK_DECAY: f32 = 0.75;
+pub const MIN_KNOCKBACK: f32 = 0.3;
+
 
 #[derive(Clone, Debug, Copy, PartialEq)]
 pub struct Poison {
@@ -53,6 +62,7 @@ pub struct AggroComponent{
     pub aggroed: bool,
     pub aggro_range: usize,
     pub aggro_through_walls: bool,
+    pub target: Option<usize>, // entity being chased instead of the player, allies only move and attack when they have one
 }
 
 #[derive(Clone, Debug, Copy, PartialEq, Serialize, Deserialize)]
@@ -0,0 +1,225 @@
+use std::cell::RefCell;
+use std::f32::consts::PI;
+
+use compact_str::CompactString;
+use serde::{Deserialize, Serialize};
+
+use crate::create_stat_list;
+use crate::error::PError;
+use crate::world::World;
+use crate::{ptry, punwrap};
+
+use super::camera::Camera;
+use super::entity_attacks::{EntityAttackBox, EntityAttackDescriptor};
+use super::entity_components::AggroComponent;
+use super::stat::{DamageType, StatC, StatList};
+
+// allies stop following once they are this close to the player
+pub const ALLY_FOLLOW_DISTANCE: f32 = 96.0;
+// allies drop their target and come back if they end up this far from the player
+pub const ALLY_LEASH_DISTANCE: f32 = 600.0;
+pub const SUMMON_RING_RADIUS: f32 = 40.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Faction {
+    #[default]
+    Hostile, // entities without a faction component are hostile
+    Player, // fights alongside the player, player attacks pass through it
+}
+
+pub struct FactionComponent {
+    pub faction: Faction
+}
+
+// Entities summoned by the player, they are removed once their lifetime runs out
+pub struct MinionComponent {
+    pub summoned_from: CompactString, // archetype the minion was summoned as, used for the per summon cap
+    pub lifetime: Option<f32>, // frames left, none lasts until killed
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SummonDescriptor {
+    pub archetype: CompactString, // entity archetype to summon
+    #[serde(default = "default_summon_count")]
+    pub count: usize,
+    #[serde(default)]
+    pub lifetime: Option<f32>,
+    #[serde(default = "default_max_minions")]
+    pub max_minions: usize, // once over this the oldest minions of the same archetype are unsummoned
+}
+
+fn default_summon_count() -> usize {
+    1
+}
+
+fn default_max_minions() -> usize {
+    3
+}
+
+impl World {
+    pub fn get_faction(&self, entity: usize) -> Faction {
+        self.components.faction_components.get(entity).and_then(|x| x.as_ref()).map(|x| x.borrow().faction).unwrap_or_default()
+    }
+    // attacker is none for the player
+    pub fn are_enemies(&self, attacker: Option<usize>, entity: usize) -> bool {
+        let attacker_faction = attacker.map(|x| self.get_faction(x)).unwrap_or(Faction::Player);
+        attacker_faction != self.get_faction(entity)
+    }
+    pub fn summon_minions(&mut self, summon: &SummonDescriptor, x: f32, y: f32) -> Result<Vec<usize>, PError> {
+        punwrap!(self.entity_archetype_descriptor_lookup.get(&summon.archetype), NotFound, "tried to summon entity archetype {} but it doesn't exist", summon.archetype);
+        let mut summoned = Vec::new();
+        for i in 0..summon.count {
+            let angle = 2.0 * PI * i as f32/summon.count as f32;
+            let (sx, sy) = if summon.count > 1 {(x + angle.cos() * SUMMON_RING_RADIUS, y + angle.sin() * SUMMON_RING_RADIUS)} else {(x, y)};
+            let entity = ptry!(self.create_entity_with_archetype(sx.max(0.0), sy.max(0.0), summon.archetype.clone()), "while summoning {}", summon.archetype);
+            self.components.faction_components[entity] = Some(RefCell::new(FactionComponent {faction: Faction::Player}));
+            self.components.minion_components[entity] = Some(RefCell::new(MinionComponent {
+                summoned_from: summon.archetype.clone(),
+                lifetime: summon.lifetime
+            }));
+            // minions don't drop loot
+            self.components.loot_components[entity] = None;
+            if let Some(aggro) = self.components.aggro_components[entity].as_ref() {
+                aggro.borrow_mut().aggroed = true;
+            }
+            summoned.push(entity);
+        }
+
+        let mut minions: Vec<usize> = self.components.minion_components.iter().enumerate()
+            .filter(|(_, x)| x.as_ref().is_some_and(|x| x.borrow().summoned_from == summon.archetype))
+            .map(|(i, _)| i)
+            .collect();
+        minions.retain(|x| !self.entities_to_be_killed_at_end_of_frame.borrow().contains(x));
+        if minions.len() > summon.max_minions {
+            // entity ids only go up so the lowest ids are the oldest minions
+            for minion in minions.iter().take(minions.len() - summon.max_minions) {
+                self.kill_entity(*minion);
+            }
+        }
+        Ok(summoned)
+    }
+    pub fn update_minions(&self) -> Result<(), PError> {
+        for (i, minion) in self.components.minion_components.iter().enumerate() {
+            let Some(minion) = minion.as_ref() else {
+                continue;
+            };
+            let mut minion = minion.borrow_mut();
+            if let Some(lifetime) = minion.lifetime.as_mut() {
+                *lifetime -= 1.0;
+                if *lifetime <= 0.0 {
+                    self.kill_entity(i);
+                }
+            }
+        }
+        Ok(())
+    }
+    // where an aggroed entity should walk to, none if it should stay where it is
+    pub fn get_movement_target(&self, entity: usize, x: f32, y: f32, aggro: &AggroComponent) -> Option<[f32; 2]> {
+        if let Some(target) = aggro.target.and_then(|x| self.get_target_position(x)) {
+            return Some(target);
+        }
+        let player = self.player.borrow();
+        let player_position = [player.x + player.collision_box.x_offset, player.y + player.collision_box.y_offset];
+        if self.get_faction(entity) == Faction::Player && f32::sqrt((player_position[0] - x).powi(2) + (player_position[1] - y).powi(2)) < ALLY_FOLLOW_DISTANCE {
+            return None;
+        }
+        Some(player_position)
+    }
+    // where an entity should aim its attacks, allies without a target don't attack
+    pub fn get_attack_target(&self, entity: usize) -> Option<[f32; 2]> {
+        let target = self.components.aggro_components.get(entity).and_then(|x| x.as_ref()).and_then(|x| x.borrow().target);
+        if let Some(target) = target.and_then(|x| self.get_target_position(x)) {
+            return Some(target);
+        }
+        if self.get_faction(entity) == Faction::Player {
+            return None;
+        }
+        let player = self.player.borrow();
+        Some([player.x + player.collision_box.x_offset, player.y + player.collision_box.y_offset])
+    }
+    pub fn get_target_position(&self, target: usize) -> Option<[f32; 2]> {
+        let position = self.components.position_components.get(target)?.as_ref()?.borrow();
+        let damage_box = self.components.damageable_components.get(target)?.as_ref()?.borrow().damage_box;
+        Some([position.x + damage_box.x_offset, position.y + damage_box.y_offset])
+    }
+    // closest damageable entity out of candidates that is an enemy of the entity and can be seen from it
+    pub fn find_closest_enemy(&self, entity: usize, x: f32, y: f32, range: f32, candidates: &[usize]) -> Result<Option<usize>, PError> {
+        let mut closest = None;
+        let mut closest_distance = range;
+        for candidate in candidates.iter() {
+            if *candidate == entity || !self.are_enemies(Some(entity), *candidate) || self.get_interactable_kind(*candidate).is_some() || self.entities_to_be_killed_at_end_of_frame.borrow().contains(candidate) {
+                continue;
+            }
+            let Some([cx, cy]) = self.get_target_position(*candidate) else {
+                continue;
+            };
+            let distance = f32::sqrt((cx - x).powi(2) + (cy - y).powi(2));
+            if distance < closest_distance && ptry!(self.is_line_of_sight(x, y, cx, cy)) {
+                closest = Some(*candidate);
+                closest_distance = distance;
+            }
+        }
+        Ok(closest)
+    }
+    // picks what an entity's aggro should be pointed at, hostile entities only retarget when attacked by an ally
+    pub fn update_aggro_target(&self, entity: usize, x: f32, y: f32, aggro: &mut AggroComponent, candidates: &[usize]) -> Result<(), PError> {
+        if aggro.target.is_some_and(|x| self.get_target_position(x).is_none() || self.entities_to_be_killed_at_end_of_frame.borrow().contains(&x)) {
+            aggro.target = None;
+        }
+        if self.get_faction(entity) != Faction::Player {
+            return Ok(());
+        }
+        let player = self.player.borrow();
+        let distance_to_player = f32::sqrt((player.x - x).powi(2) + (player.y - y).powi(2));
+        drop(player);
+        if distance_to_player > ALLY_LEASH_DISTANCE {
+            aggro.target = None;
+            return Ok(());
+        }
+        if aggro.target.is_none() {
+            aggro.target = ptry!(self.find_closest_enemy(entity, x, y, aggro.aggro_range as f32, candidates));
+        }
+        Ok(())
+    }
+    // damages the entity if it isn't on the attacker's side, attacker is none for the player. Returns whether damage was dealt
+    pub fn damage_entity_from(&self, attacker: Option<usize>, entity: usize, stats: &StatList, damage_type: DamageType, angle: f32, camera: &mut Camera) -> Result<bool, PError> {
+        if !self.are_enemies(attacker, entity) {
+            return Ok(false);
+        }
+        let Some(damageable) = self.components.damageable_components.get(entity).and_then(|x| x.as_ref()) else {
+            return Ok(false);
+        };
+        let position = punwrap!(self.components.position_components[entity].as_ref(), Invalid, "damageable entity {} has no position", entity).borrow();
+        let aggro = self.components.aggro_components[entity].as_ref();
+        ptry!(self.damage_entity(&position, Some(&mut damageable.borrow_mut()), aggro.map(|x| x.borrow_mut()).as_deref_mut(), stats, damage_type, angle, camera));
+        if let (Some(attacker), Some(aggro)) = (attacker, aggro) {
+            aggro.borrow_mut().target = Some(attacker);
+        }
+        Ok(true)
+    }
+    pub fn get_entity_attack_stats(descriptor: &EntityAttackDescriptor) -> StatList {
+        let mut stats = create_stat_list!(
+            damage => StatC {flat: descriptor.damage, percent: 0.0},
+            knockback => StatC {flat: descriptor.knockback, percent: 0.0}
+        );
+        if let Some(fire) = &descriptor.fire {
+            stats.fire_damage = Some(StatC {flat: fire.damage, percent: 0.0});
+            stats.fire_duration = Some(StatC {flat: fire.lifetime, percent: 0.0});
+        }
+        if let Some(poison) = &descriptor.poison {
+            stats.poison_damage = Some(StatC {flat: poison.damage, percent: 0.0});
+            stats.poison_duration = Some(StatC {flat: poison.lifetime, percent: 0.0});
+        }
+        stats
+    }
+    // entity attacks that land hit every entity of the other faction inside them, the player is handled separately
+    pub fn entity_attack_hit_entities(&self, attack: &EntityAttackBox, descriptor: &EntityAttackDescriptor, camera: &mut Camera) -> Result<(), PError> {
+        let player_side = self.get_faction(attack.entity) == Faction::Player;
+        let collisions = ptry!(self.get_attacked_rotated_rect(player_side, Some(attack.entity), attack.x.max(0.0) as usize, attack.y.max(0.0) as usize, descriptor.reach, descriptor.width, attack.rotation * 180.0/PI, true));
+        let stats = World::get_entity_attack_stats(descriptor);
+        for collision in collisions {
+            ptry!(self.damage_entity_from(Some(attack.entity), collision, &stats, descriptor.damage_type, attack.rotation * 180.0/PI, camera), "while resolving attack {} from entity {}", attack.archetype, attack.entity);
+        }
+        Ok(())
+    }
+}
@@ -3,9 +3,9 @@ use rustc_hash::FxHashMap;
 
 use winit::{event, keyboard::{Key, NamedKey}};
 
-use crate::{error::PError, ptry, punwrap, rendering_engine::renderer::Renderer};
+use crate::{error::PError, ptry, punwrap, rendering_engine::{abstractions::TextSprite, renderer::Renderer}};
 
-use super::{camera::Camera, world::World};
+use super::{camera::Camera, level_editor::{LevelEditor, EDITOR_PLAYTEST_KEY}, progression::SKILL_TREE_KEY, world::World};
 #[derive(Debug, Copy, Clone)]
 pub struct MousePosition{
     pub x_world: f32,
@@ -35,7 +35,9 @@ pub enum GameState {
     start,
     play,
     inventory,
+    skill_tree,
     death,
+    editor,
 }
 pub struct Game<'a> {
     pub world: World,
@@ -43,17 +45,23 @@ pub struct Game<'a> {
     pub renderer: Renderer<'a>,
     input: InputState,
     state: GameState,
+    pub editor: Option<LevelEditor>, // only set when running with --editor
 }
 
 impl<'a> Game<'a> {
-    pub fn new(world: World, camera: Camera, renderer: Renderer<'a>) -> Game<'a> {
+    pub fn new(world: World, mut camera: Camera, renderer: Renderer<'a>, editor: Option<LevelEditor>) -> Game<'a> {
+        if let Some(editor) = editor.as_ref() {
+            camera.level_editor = true;
+            editor.focus_camera(&world, &mut camera);
+        }
         let cx = camera.camera_x;
         let cy = camera.camera_y;
         Self {
             world,
             camera,
             renderer,
-            state: GameState::start,
+            state: if editor.is_some() {GameState::editor} else {GameState::start},
+            editor,
             input: InputState {
                 keys_down: FxHashMap::default(),
                 mouse_position: MousePosition { 
@@ -102,7 +110,15 @@ impl<'a> Game<'a> {
         if self.state == GameState::play {
             ptry!(self.world.on_mouse_click(self.input.mouse_position, self.input.mouse_left, self.input.mouse_right, self.camera.viewpoint_width as f32, self.camera.viewpoint_height as f32));
         }else if self.state == GameState::inventory {
-            ptry!(self.world.inventory.on_mouse_click(self.input.mouse_position, self.input.mouse_left, self.input.mouse_right));
+            ptry!(self.world.inventory.on_mouse_click(self.input.mouse_position, self.input.mouse_left, self.input.mouse_right, &self.world.crafting_book));
+            ptry!(self.world.process_crafting_request());
+        }else if self.state == GameState::skill_tree {
+            ptry!(self.world.on_skill_tree_click(self.input.mouse_position));
+        }else if self.state == GameState::editor {
+            let editor = punwrap!(self.editor.as_mut(), Expected, "in the editor state without an editor");
+            if ptry!(editor.on_mouse_click(&mut self.world, &mut self.camera, self.input.mouse_position, self.input.mouse_left, self.input.mouse_right)) {
+                ptry!(self.toggle_playtest());
+            }
         }
         Ok(())
     }
@@ -128,7 +144,37 @@ impl<'a> Game<'a> {
             }
             return Ok(());
         }
-        let uie = ptry!(self.world.inventory.render_ui(&self.world.player_ability_descriptors, self.world.mana));
+        let uie = if self.state == GameState::skill_tree {
+            ptry!(self.world.render_skill_tree_ui(self.input.mouse_position))
+        } else if self.state == GameState::editor {
+            ptry!(punwrap!(self.editor.as_ref(), Expected, "in the editor state without an editor").render_ui(&self.camera, self.input.mouse_position))
+        } else {
+            let mut uie = ptry!(self.world.inventory.render_ui(&self.world.player_ability_descriptors, self.world.mana, &self.world.crafting_book));
+            if self.state == GameState::death && self.world.player_death_finished() {
+                uie.text.push(TextSprite {
+                    text: String::from("You died"),
+                    font_size: 60.0,
+                    x: 376.0,
+                    y: 300.0,
+                    w: 400.0,
+                    h: 100.0,
+                    color: [1.0, 0.2, 0.2, 1.0],
+                    align: wgpu_text::glyph_brush::HorizontalAlign::Center
+                });
+            } else if let Some(objective) = self.world.get_room_objective_text() {
+                uie.text.push(TextSprite {
+                    text: objective,
+                    font_size: 30.0,
+                    x: 376.0,
+                    y: 20.0,
+                    w: 400.0,
+                    h: 50.0,
+                    color: [1.0, 1.0, 1.0, 1.0],
+                    align: wgpu_text::glyph_brush::HorizontalAlign::Center
+                });
+            }
+            uie
+        };
         match self.renderer.render(ptry!(self.camera.render(&mut self.world, uie, self.renderer.config.width as f32, self.renderer.config.height as f32))){
             Ok(_) => {Ok(())}
             Err(e) => {
@@ -143,24 +189,46 @@ impl<'a> Game<'a> {
             ptry!(self.process_input());
             ptry!(self.world.update_entities(&mut self.camera));
             ptry!(self.world.update_entity_attacks(&mut self.camera));
+            ptry!(self.world.update_terrain_effects(&mut self.camera));
+            ptry!(self.world.update_room_objective());
+            ptry!(self.world.update_interactables());
+            self.world.update_visibility();
+            self.world.update_explored();
+            ptry!(self.world.update_minions());
             ptry!(self.world.update_player_abilities(&self.input));
+            ptry!(self.world.update_queued_shots());
             ptry!(self.world.update_player_attacks(&mut self.camera));
             ptry!(self.world.update_damage_text(&mut self.camera));
+            self.world.update_particles();
             ptry!(self.world.update_items_on_ground());
             ptry!(self.world.kill_entities_to_be_killed());
+            self.world.update_death_animations();
             self.input.mouse_position.x_world = self.camera.camera_x + self.input.mouse_position.x_screen;
             self.input.mouse_position.y_world = self.camera.camera_y + self.input.mouse_position.y_screen;
             ptry!(self.world.update_items_in_inventory_cd());
             ptry!(self.world.update_gen());
             ptry!(self.world.update_player_dots(&mut self.camera));
+            ptry!(self.world.update_player_hit_state());
+            ptry!(self.world.update_player_defense());
+            ptry!(self.world.update_combo_window());
             self.world.update_player_anim();
             ptry!(self.world.player_health_regen(&mut self.camera));
             ptry!(self.world.player_mana_regen());
             self.world.update_player_ability_cds();
             if self.world.player.borrow().health <= 0.0 {
-                panic!("\n\nplayer died\n\n");
+                self.state = GameState::death;
             }
-        }else if self.state == GameState::inventory {
+        }else if self.state == GameState::death {
+            // everything stops except the player's death clip and what was already dying, there's no restarting a run yet
+            self.world.update_player_anim();
+            self.world.update_death_animations();
+            self.world.update_particles();
+        }else if self.state == GameState::editor {
+            let editor = punwrap!(self.editor.as_mut(), Expected, "in the editor state without an editor");
+            ptry!(editor.update(&mut self.world, &mut self.camera, &self.input));
+            self.input.mouse_position.x_world = self.camera.camera_x + self.input.mouse_position.x_screen;
+            self.input.mouse_position.y_world = self.camera.camera_y + self.input.mouse_position.y_screen;
+        }else if self.state == GameState::inventory || self.state == GameState::skill_tree {
             ptry!(self.camera.update_ui(&mut self.world));
             ptry!(self.process_input());
             self.input.mouse_position.x_world = self.camera.camera_x + self.input.mouse_position.x_screen;
@@ -209,7 +277,33 @@ impl<'a> Game<'a> {
         Ok(())
     }
 
+    // switches between editing and playing the level being edited, the world is rebuilt from the level data both ways
+    // so playtests play exactly what would be saved and edits don't carry over anything from playing
+    pub fn toggle_playtest(&mut self) -> Result<(), PError> {
+        let editor = punwrap!(self.editor.as_ref(), Expected, "tried to playtest without an editor");
+        match self.state {
+            GameState::editor => {
+                ptry!(editor.rebuild(&mut self.world));
+                self.camera.level_editor = false;
+                self.state = GameState::play;
+            },
+            GameState::play | GameState::inventory | GameState::skill_tree | GameState::death => {
+                ptry!(editor.rebuild(&mut self.world));
+                self.camera.level_editor = true;
+                self.state = GameState::editor;
+            },
+            _ => {}
+        }
+        Ok(())
+    }
     pub fn on_key_down(&mut self, key: &CompactString) -> Result<(), PError>{
+        if self.editor.is_some() && key == EDITOR_PLAYTEST_KEY {
+            return self.toggle_playtest();
+        }
+        if self.state == GameState::editor {
+            let editor = punwrap!(self.editor.as_mut(), Expected, "in the editor state without an editor");
+            return editor.on_key_down(key, &mut self.world, &mut self.camera);
+        }
         if key == "e" {
             self.state = match self.state {
                 GameState::play => {
@@ -231,8 +325,20 @@ impl<'a> Game<'a> {
             };
             return Ok(());
         }
+        if key == SKILL_TREE_KEY {
+            self.state = match self.state {
+                GameState::play => GameState::skill_tree,
+                GameState::skill_tree => GameState::play,
+                _ => self.state,
+            };
+            return Ok(());
+        }
         if self.state == GameState::play {
             ptry!(self.world.on_key_down(key, &self.input));
+            if self.world.inventory.shop.is_some() {
+                self.world.inventory.show_inventory();
+                self.state = GameState::inventory;
+            }
         } else if self.state == GameState::inventory {
             self.world.inventory.on_key_down(key);
         }
@@ -0,0 +1,147 @@
+use compact_str::CompactString;
+
+use crate::error::PError;
+use crate::world::World;
+use crate::{ptry, punwrap};
+
+use super::factions::Faction;
+
+pub const INTERACTABLE_RANGE: f32 = 64.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum InteractableKind {
+    Door,
+    Lever,
+    Chest,
+    Breakable, // broken by attacking it, drops its loot like any other entity
+}
+
+// Doors, levers, chests and breakables are entities so they can be placed in rooms like any other entity
+pub struct InteractableComponent {
+    pub kind: InteractableKind,
+    pub open: bool, // doors let things through, levers are pulled and chests are looted
+    pub opens_on_clear: bool, // doors that open once every hostile entity around is dead
+    pub key: Option<CompactString>, // item archetype used up to open a door
+    pub channel: Option<CompactString>, // levers toggle every door on the same channel
+    pub closed_sprite: Option<usize>,
+    pub open_sprite: Option<usize>,
+}
+
+impl World {
+    pub fn get_interactable_in_range(&self) -> Option<usize> {
+        self.get_closest_in_range_where(&self.components.interactable_components, INTERACTABLE_RANGE, |x| x.kind != InteractableKind::Breakable)
+    }
+    pub fn get_interactable_kind(&self, entity: usize) -> Option<InteractableKind> {
+        self.components.interactable_components.get(entity)?.as_ref().map(|x| x.borrow().kind)
+    }
+    pub fn is_interactable_open(&self, entity: usize) -> bool {
+        self.components.interactable_components.get(entity).and_then(|x| x.as_ref()).is_some_and(|x| x.borrow().open)
+    }
+    pub fn activate_interactable(&mut self, entity: usize) -> Result<(), PError> {
+        let interactable = punwrap!(self.components.interactable_components.get(entity).and_then(|x| x.as_ref()), Invalid, "entity {} was activated as an interactable but has no interactable component", entity);
+        let (kind, open, key, channel) = {
+            let interactable = interactable.borrow();
+            (interactable.kind, interactable.open, interactable.key.clone(), interactable.channel.clone())
+        };
+        match kind {
+            InteractableKind::Door => {
+                // doors without a key are opened by levers or by clearing the room
+                let Some(key) = key else {
+                    return Ok(());
+                };
+                if open {
+                    return Ok(());
+                }
+                let Some(item) = self.inventory.find_item_with_archetype(&key) else {
+                    return Ok(());
+                };
+                ptry!(self.inventory.destroy_item(item), "while using key {} on door {}", key, entity);
+                ptry!(self.set_interactable_open(entity, true));
+            },
+            InteractableKind::Lever => {
+                ptry!(self.set_interactable_open(entity, !open));
+                if let Some(channel) = channel {
+                    ptry!(self.toggle_channel(&channel), "while pulling lever {}", entity);
+                }
+            },
+            InteractableKind::Chest => {
+                if open {
+                    return Ok(());
+                }
+                ptry!(self.set_interactable_open(entity, true));
+                ptry!(self.drop_entity_loot(entity), "while opening chest {}", entity);
+            },
+            InteractableKind::Breakable => {}
+        }
+        Ok(())
+    }
+    // flips every door on the channel, levers on it are left alone so they don't trigger each other
+    pub fn toggle_channel(&self, channel: &str) -> Result<(), PError> {
+        let doors: Vec<usize> = self.components.interactable_components.iter().enumerate().filter_map(|(id, x)| {
+            let interactable = x.as_ref()?.borrow();
+            (interactable.kind == InteractableKind::Door && interactable.channel.as_deref() == Some(channel)).then_some(id)
+        }).collect();
+        for door in doors {
+            ptry!(self.set_interactable_open(door, !self.is_interactable_open(door)));
+        }
+        Ok(())
+    }
+    // swaps the sprite and takes the entity out of or puts it back in the collision cache right away,
+    // so nothing can walk through a door in the frame it closes
+    pub fn set_interactable_open(&self, entity: usize, open: bool) -> Result<(), PError> {
+        let interactable = punwrap!(self.components.interactable_components.get(entity).and_then(|x| x.as_ref()), Invalid, "entity {} has no interactable component", entity);
+        let mut interactable = interactable.borrow_mut();
+        interactable.open = open;
+        let sprite = if open {interactable.open_sprite} else {interactable.closed_sprite};
+        if let (Some(sprite), Some(sprite_component)) = (sprite, self.components.sprite_components[entity].as_ref()) {
+            sprite_component.borrow_mut().sprite = sprite;
+        }
+        drop(interactable);
+
+        let Some(collision_component) = self.components.collision_components[entity].as_ref() else {
+            return Ok(());
+        };
+        let collision_box = collision_component.borrow().collision_box;
+        let position = punwrap!(self.components.position_components[entity].as_ref(), Invalid, "interactable {} has no position", entity).borrow();
+        let mut collision_cache = self.collision_cache.borrow_mut();
+        for tile in World::get_terrain_tiles(position.x as usize, position.y as usize, collision_box.w as usize, collision_box.h as usize) {
+            let entry = collision_cache.entry(tile).or_default();
+            entry.retain(|x| *x != entity);
+            if !open {
+                entry.push(entity);
+            }
+        }
+        Ok(())
+    }
+    // rooms with an objective are cleared when it's done, otherwise once no hostile entity that fights is left in the loaded chunks
+    pub fn is_room_cleared(&self) -> bool {
+        if let Some(room) = &self.cur_room {
+            return room.cleared;
+        }
+        for chunk in self.loaded_chunks.iter() {
+            for entity in self.chunks.borrow()[*chunk].entities_ids.iter() {
+                if self.components.aggro_components[*entity].is_some() && self.get_faction(*entity) == Faction::Hostile && !self.entities_to_be_killed_at_end_of_frame.borrow().contains(entity) {
+                    return false;
+                }
+            }
+        }
+        true
+    }
+    pub fn update_interactables(&mut self) -> Result<(), PError> {
+        let mut waiting = Vec::new();
+        for chunk in self.loaded_chunks.iter() {
+            for entity in self.chunks.borrow()[*chunk].entities_ids.iter() {
+                if self.components.interactable_components[*entity].as_ref().is_some_and(|x| x.borrow().opens_on_clear && !x.borrow().open) {
+                    waiting.push(*entity);
+                }
+            }
+        }
+        if waiting.is_empty() || !self.is_room_cleared() {
+            return Ok(());
+        }
+        for door in waiting {
+            ptry!(self.set_interactable_open(door, true));
+        }
+        Ok(())
+    }
+}
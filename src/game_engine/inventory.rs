@@ -5,7 +5,7 @@ use rustc_hash::FxHashMap;
 
 use crate::{error::PError, error_prolif_allow, game_engine::item::Item, perror, ptry, punwrap, rendering_engine::abstractions::{TextSprite, UIEFull}};
 
-use super::{game::MousePosition, item::ItemType, player::TICKS_PER_REGEN_TICK, player_abilities::PlayerAbilityDescriptor, ui::UIESprite};
+use super::{crafting::{CraftingBook, CraftingRequest}, vendor::Shop, game::MousePosition, item::ItemType, player::TICKS_PER_REGEN_TICK, player_abilities::PlayerAbilityDescriptor, ui::UIESprite};
 
 #[derive(Debug, Clone)]
 pub struct ItemOnMouse{
@@ -29,9 +29,23 @@ pub struct Inventory {
     pub player_abilities: Vec<PlayerAbility>, // id in vec = ability id
     pub ability_slots: Vec<AbilitySlot>,
     pub hotkey_to_slot: FxHashMap<CompactString, usize>,
-    pub ability_slot_clicked: Option<usize>
+    pub ability_slot_clicked: Option<usize>,
+    crafting_slots: Vec<usize>,
+    pub materials: FxHashMap<CompactString, usize>, // material name to amount held
+    pub crafting_request: Option<CraftingRequest>,
+    pub gold: usize,
+    pub shop: Option<Shop>,
+    pub progression_stats: StatList, // from stat points and skills, kept up to date by the world
 }
 
+const CRAFTING_PANEL_X: f32 = 846.0;
+const CRAFTING_PANEL_Y: f32 = 186.5;
+const CRAFTING_RECIPES_Y: f32 = 290.0;
+const CRAFTING_RECIPE_HEIGHT: f32 = 22.0;
+const SALVAGE_BUTTON: [f32; 4] = [990.0, 232.0, 116.0, 36.0]; // x, y, w, h
+const SHOP_STOCK_Y: f32 = 240.0;
+const SHOP_ROW_HEIGHT: f32 = 26.0;
+
 
 #[derive(Debug, Clone)] 
 pub struct AbilitySlot {
@@ -135,8 +149,13 @@ impl Default for Inventory{
             player_abilities: Vec::new(),
             ability_slots: Vec::new(),
             hotkey_to_slot: FxHashMap::default(),
-            ability_slot_clicked: None
-            
+            ability_slot_clicked: None,
+            crafting_slots: Vec::new(),
+            materials: FxHashMap::default(),
+            crafting_request: None,
+            gold: 0,
+            shop: None,
+            progression_stats: StatList::default()
         }
     }
 }
@@ -200,6 +219,10 @@ impl Inventory {
         self.helm_slot = Some(self.add_slot(Slot::new(369,242, vec![ItemType::HelmetArmor])));
         self.chest_slot = Some(self.add_slot(Slot::new(369,300, vec![ItemType::ChestplateArmor])));
         self.boot_slot = Some(self.add_slot(Slot::new(369,358, vec![ItemType::BootsArmor])));
+
+        let c1 = self.add_slot(Slot::new(866, 226, ItemType::all()));
+        let c2 = self.add_slot(Slot::new(924, 226, ItemType::all()));
+        self.crafting_slots = vec![c1, c2];
     }
     pub fn add_hotbar_slot(&mut self, slot: Slot) {
         self.hotbar.push(self.slots.len());
@@ -221,6 +244,7 @@ impl Inventory {
     }
     pub fn get_combined_stats(&self) -> Result<StatList, PError> {
         let mut stats = StatList::base();
+        stats.to_sum_with(&self.progression_stats);
         if let Some(item) = self.get_cur_held_item() {
             stats.to_sum_with(&item.stats);
         }
@@ -421,6 +445,9 @@ impl Inventory {
             if stat.0 == "poison_damage" {
                 t.push_str("\n--- DOT ---\n");
             }
+            if stat.0 == "fire_resistance" {
+                t.push_str("\n--- RESISTANCES ---\n");
+            }
             if stat.0 == "loot" {
                 t.push_str("\n--- MISC ---\n");
             }
@@ -454,13 +481,58 @@ impl Inventory {
             if i == self.helm_slot.unwrap_or(usize::MAX) { continue; }
             if i == self.chest_slot.unwrap_or(usize::MAX) { continue; }
             if i == self.boot_slot.unwrap_or(usize::MAX) { continue; }
+            if self.crafting_slots.contains(&i) { continue; }
             if slot.item.is_none() {
                 ptry!(slot.set_item(it, &self.items));
                 return Ok(());
             }
         }
+        self.items.remove(&it);
         Err(perror!(NoSpace, "No space for item"))
     }
+    pub fn add_to_crafting_slot(&mut self, item: Item) -> Result<(), PError> {
+        for slot_id in self.crafting_slots.clone() {
+            let slot = punwrap!(self.slots.get(slot_id), Invalid, "crafting slot refers to non-existent slot {}", slot_id);
+            if slot.item.is_none() {
+                let it = self.add_item(item);
+                ptry!(self.set_slot_item(slot_id, it));
+                return Ok(());
+            }
+        }
+        self.add_to_slot(item)
+    }
+    pub fn get_crafting_items(&self) -> Vec<(usize, &Item)> {
+        self.crafting_slots.iter()
+            .filter_map(|x| self.slots.get(*x))
+            .filter_map(|x| x.item)
+            .filter_map(|x| self.get_item(&x).map(|i| (x, i)))
+            .collect()
+    }
+    pub fn find_item_with_archetype(&self, archetype: &str) -> Option<usize> {
+        self.items.iter().filter(|(_, item)| item.archetype == archetype).map(|(id, _)| *id).min()
+    }
+    // takes one use from the item, destroying it once it runs out
+    pub fn use_item(&mut self, id: usize) -> Result<(), PError> {
+        let item = punwrap!(self.get_item_mut(&id), NotFound, "attempted to use non-existent item with id {}", id);
+        let uses = punwrap!(item.uses.as_mut(), Invalid, "attempted to use item {} which has no uses", item.name);
+        *uses = uses.saturating_sub(1);
+        if *uses == 0 {
+            ptry!(self.destroy_item(id), "while destroying used up item {}", id);
+        }
+        Ok(())
+    }
+    pub fn destroy_item(&mut self, id: usize) -> Result<(), PError> {
+        // removes the item along with any slot refering to it
+        for slot in self.slots.iter_mut() {
+            if slot.item == Some(id) {
+                slot.remove_item();
+            }
+        }
+        if self.item_on_mouse.as_ref().is_some_and(|x| x.item_id == id) {
+            self.item_on_mouse = None;
+        }
+        self.remove_item(id)
+    }
     pub fn set_slot_item(&mut self, slot: usize, item_id: usize) -> Result<(), PError> {
         let s = punwrap!(self.slots.get_mut(slot), NotFound, "There is no {}th slot", slot);
         ptry!(s.set_item(item_id, &self.items));
@@ -478,7 +550,229 @@ impl Inventory {
             .and_then(|x| self.ability_slots.get(*x))
             .and_then(|x| x.cur_ability)
     }
-    pub fn render_ui(&mut self, player_ability_descriptors: &[PlayerAbilityDescriptor], mana: f32) -> Result<UIEFull, PError> {
+    pub fn render_crafting_ui(&self, crafting_book: &CraftingBook, ui: &mut Vec<UIESprite>, text: &mut Vec<TextSprite>) {
+        ui.push(UIESprite {
+            x: CRAFTING_PANEL_X,
+            y: CRAFTING_PANEL_Y,
+            z: 0.5,
+            width: 280.0,
+            height: 347.0,
+            sprite: CompactString::from("level_editor_menu_background")
+        });
+        text.push(TextSprite {
+            text: "Crafting".into(),
+            font_size: 25.0,
+            x: CRAFTING_PANEL_X + 140.0,
+            y: CRAFTING_PANEL_Y + 8.0,
+            w: 260.0,
+            h: 25.0,
+            color: [1.0, 1.0, 1.0, 1.0],
+            align: wgpu_text::glyph_brush::HorizontalAlign::Center
+        });
+        ui.push(UIESprite {
+            x: SALVAGE_BUTTON[0],
+            y: SALVAGE_BUTTON[1],
+            z: 5.2,
+            width: SALVAGE_BUTTON[2],
+            height: SALVAGE_BUTTON[3],
+            sprite: CompactString::from("level_editor_button_background")
+        });
+        text.push(TextSprite {
+            text: "Salvage".into(),
+            font_size: 22.0,
+            x: SALVAGE_BUTTON[0] + SALVAGE_BUTTON[2]/2.0,
+            y: SALVAGE_BUTTON[1] + 8.0,
+            w: SALVAGE_BUTTON[2],
+            h: SALVAGE_BUTTON[3],
+            color: [1.0, 1.0, 1.0, 1.0],
+            align: wgpu_text::glyph_brush::HorizontalAlign::Center
+        });
+
+        let items = self.get_crafting_items();
+        for (i, recipe) in crafting_book.recipes.iter().enumerate() {
+            let y = CRAFTING_RECIPES_Y + i as f32 * CRAFTING_RECIPE_HEIGHT;
+            let craftable = recipe.can_craft(&items, &self.materials);
+            ui.push(UIESprite {
+                x: CRAFTING_PANEL_X + 20.0,
+                y,
+                z: 5.2,
+                width: 240.0,
+                height: CRAFTING_RECIPE_HEIGHT - 2.0,
+                sprite: CompactString::from("level_editor_button_background")
+            });
+            text.push(TextSprite {
+                text: recipe.name.to_string(),
+                font_size: 18.0,
+                x: CRAFTING_PANEL_X + 140.0,
+                y: y + 2.0,
+                w: 240.0,
+                h: CRAFTING_RECIPE_HEIGHT,
+                color: if craftable {[1.0, 1.0, 1.0, 1.0]} else {[0.5, 0.5, 0.5, 1.0]},
+                align: wgpu_text::glyph_brush::HorizontalAlign::Center
+            });
+            if self.mouse_position.x_screen > CRAFTING_PANEL_X + 20.0 && self.mouse_position.x_screen < CRAFTING_PANEL_X + 260.0 && self.mouse_position.y_screen > y && self.mouse_position.y_screen < y + CRAFTING_RECIPE_HEIGHT - 2.0 {
+                ui.push(UIESprite {
+                    x: self.mouse_position.x_screen - 180.0,
+                    y: self.mouse_position.y_screen + 10.0,
+                    z: 5.6,
+                    width: 170.0,
+                    height: 150.0,
+                    sprite: CompactString::from("level_editor_menu_background")
+                });
+                text.push(TextSprite {
+                    text: recipe.get_description(),
+                    font_size: 15.0,
+                    x: self.mouse_position.x_screen - 175.0,
+                    y: self.mouse_position.y_screen + 14.0,
+                    w: 160.0,
+                    h: 145.0,
+                    color: [1.0, 1.0, 1.0, 1.0],
+                    align: wgpu_text::glyph_brush::HorizontalAlign::Left
+                });
+            }
+        }
+
+        let mut t = format!("Gold: {}\nMaterials:\n", self.gold);
+        for material in crafting_book.materials.iter() {
+            t.push_str(format!("{}: {}\n", material, self.materials.get(material).copied().unwrap_or(0)).as_str());
+        }
+        text.push(TextSprite {
+            text: t,
+            font_size: 18.0,
+            x: CRAFTING_PANEL_X + 20.0,
+            y: CRAFTING_PANEL_Y + 255.0,
+            w: 240.0,
+            h: 90.0,
+            color: [1.0, 1.0, 1.0, 1.0],
+            align: wgpu_text::glyph_brush::HorizontalAlign::Left
+        });
+    }
+    pub fn render_shop_ui(&self, ui: &mut Vec<UIESprite>, text: &mut Vec<TextSprite>) -> Result<(), PError> {
+        let shop = punwrap!(self.shop.as_ref(), None, "attempted to render the shop while no shop is open");
+        ui.push(UIESprite {
+            x: CRAFTING_PANEL_X,
+            y: CRAFTING_PANEL_Y,
+            z: 0.5,
+            width: 280.0,
+            height: 347.0,
+            sprite: CompactString::from("level_editor_menu_background")
+        });
+        text.push(TextSprite {
+            text: format!("Shop\nGold: {}", self.gold),
+            font_size: 25.0,
+            x: CRAFTING_PANEL_X + 140.0,
+            y: CRAFTING_PANEL_Y + 8.0,
+            w: 260.0,
+            h: 50.0,
+            color: [1.0, 0.84, 0.0, 1.0],
+            align: wgpu_text::glyph_brush::HorizontalAlign::Center
+        });
+        for (i, item) in shop.stock.iter().enumerate() {
+            let y = SHOP_STOCK_Y + i as f32 * SHOP_ROW_HEIGHT;
+            if y + SHOP_ROW_HEIGHT > CRAFTING_PANEL_Y + 310.0 {
+                break;
+            }
+            ui.push(UIESprite {
+                x: CRAFTING_PANEL_X + 20.0,
+                y,
+                z: 5.2,
+                width: 240.0,
+                height: SHOP_ROW_HEIGHT - 2.0,
+                sprite: CompactString::from("level_editor_button_background")
+            });
+            text.push(TextSprite {
+                text: item.name.to_string(),
+                font_size: 18.0,
+                x: CRAFTING_PANEL_X + 26.0,
+                y: y + 3.0,
+                w: 170.0,
+                h: SHOP_ROW_HEIGHT,
+                color: item.rarity.color(),
+                align: wgpu_text::glyph_brush::HorizontalAlign::Left
+            });
+            let price = item.get_price();
+            text.push(TextSprite {
+                text: format!("{}g", price),
+                font_size: 18.0,
+                x: CRAFTING_PANEL_X + 254.0,
+                y: y + 3.0,
+                w: 60.0,
+                h: SHOP_ROW_HEIGHT,
+                color: if price <= self.gold {[1.0, 0.84, 0.0, 1.0]} else {[0.5, 0.5, 0.5, 1.0]},
+                align: wgpu_text::glyph_brush::HorizontalAlign::Right
+            });
+            if self.mouse_position.x_screen > CRAFTING_PANEL_X + 20.0 && self.mouse_position.x_screen < CRAFTING_PANEL_X + 260.0 && self.mouse_position.y_screen > y && self.mouse_position.y_screen < y + SHOP_ROW_HEIGHT - 2.0 {
+                let mut t = format!(
+                    "{}----------------------------------------\n\n{}\n\n", item.get_title(), item.lore
+                );
+                t.push_str(&self.get_stat_string(&item.stats));
+                ui.push(UIESprite {
+                    x: self.mouse_position.x_screen - 240.0,
+                    y: self.mouse_position.y_screen - 165.0,
+                    z: 5.6,
+                    width: 220.0,
+                    height: 320.0,
+                    sprite: CompactString::from("level_editor_menu_background")
+                });
+                text.push(TextSprite {
+                    text: t,
+                    font_size: 20.0,
+                    x: self.mouse_position.x_screen - 230.0,
+                    y: self.mouse_position.y_screen - 150.0,
+                    w: 200.0,
+                    h: 300.0,
+                    color: [1.0, 1.0, 1.0, 1.0],
+                    align: wgpu_text::glyph_brush::HorizontalAlign::Left
+                });
+            }
+        }
+        let sell_text = match self.item_on_mouse.as_ref().and_then(|x| self.get_item(&x.item_id)) {
+            Some(item) => format!("Click here to sell for {}g", item.get_sell_price()),
+            None => String::from("Drop an item here to sell it")
+        };
+        text.push(TextSprite {
+            text: sell_text,
+            font_size: 18.0,
+            x: CRAFTING_PANEL_X + 140.0,
+            y: CRAFTING_PANEL_Y + 318.0,
+            w: 260.0,
+            h: 25.0,
+            color: [1.0, 1.0, 1.0, 1.0],
+            align: wgpu_text::glyph_brush::HorizontalAlign::Center
+        });
+        Ok(())
+    }
+    pub fn on_shop_click(&mut self, position: MousePosition) -> Result<(), PError> {
+        if !(position.x_screen > CRAFTING_PANEL_X && position.x_screen < CRAFTING_PANEL_X + 280.0 && position.y_screen > CRAFTING_PANEL_Y && position.y_screen < CRAFTING_PANEL_Y + 347.0) {
+            return Ok(());
+        }
+        if let Some(item_on_mouse) = self.item_on_mouse.take() {
+            let item = punwrap!(self.items.remove(&item_on_mouse.item_id), NotFound, "item on mouse refers to non-existent item with id {}", item_on_mouse.item_id);
+            self.gold += item.get_sell_price();
+            let shop = punwrap!(self.shop.as_mut(), None, "attempted to sell an item while no shop is open");
+            shop.stock.push(item);
+            return Ok(());
+        }
+        let shop = punwrap!(self.shop.as_ref(), None, "attempted to buy an item while no shop is open");
+        let row = ((position.y_screen - SHOP_STOCK_Y) / SHOP_ROW_HEIGHT).floor();
+        if row < 0.0 || position.x_screen < CRAFTING_PANEL_X + 20.0 || position.x_screen > CRAFTING_PANEL_X + 260.0 {
+            return Ok(());
+        }
+        if let Some(item) = shop.stock.get(row as usize) {
+            let price = item.get_price();
+            if price > self.gold {
+                return Ok(());
+            }
+            let res = error_prolif_allow!(self.add_to_slot(item.clone()), NoSpace);
+            if res.is_ok() {
+                self.gold -= price;
+                let shop = punwrap!(self.shop.as_mut(), None, "attempted to buy an item while no shop is open");
+                shop.stock.remove(row as usize);
+            }
+        }
+        Ok(())
+    }
+    pub fn render_ui(&mut self, player_ability_descriptors: &[PlayerAbilityDescriptor], mana: f32, crafting_book: &CraftingBook) -> Result<UIEFull, PError> {
         let mut ui = Vec::new();
         let mut text = Vec::new(); 
         if self.show_inventory {
@@ -498,13 +792,16 @@ impl Inventory {
                 height: 720.0,
                 sprite: CompactString::from("inventory_background")
             });
-            for slot in self.slots.iter() {
+            for (slot_id, slot) in self.slots.iter().enumerate() {
+                if self.shop.is_some() && self.crafting_slots.contains(&slot_id) {
+                    continue;
+                }
                 ui.extend(slot.get_ui());
                 if (slot.x as f32) < self.mouse_position.x_screen && (slot.x as f32 + 48.0) > self.mouse_position.x_screen && (slot.y as f32) < self.mouse_position.y_screen && (slot.y as f32 + 48.0) > self.mouse_position.y_screen{
                     if let Some(i) = slot.item{
                         let item = punwrap!(self.get_item(&i), Invalid, "slot: {:?} is refering a non-existent item with id {}", slot, i);
                         let mut t = format!(
-                            "{}\n----------------------------------------\n\n{}\n\n", item.name, item.lore
+                            "{}----------------------------------------\n\n{}\n\n", item.get_title(), item.lore
                         );
 
                         let stats = &item.stats;
@@ -559,6 +856,11 @@ impl Inventory {
                     align: wgpu_text::glyph_brush::HorizontalAlign::Left
                 });
 
+            if self.shop.is_some() {
+                ptry!(self.render_shop_ui(&mut ui, &mut text));
+            } else {
+                self.render_crafting_ui(crafting_book, &mut ui, &mut text);
+            }
 
             if let Some(item_on_mouse) = self.item_on_mouse.as_ref(){
                 let item = self.get_item(&item_on_mouse.item_id);
@@ -642,7 +944,7 @@ impl Inventory {
                     text.push( TextSprite {
                         x: 364.0 + 58.0 * slot.id as f32,
                         y: 423.0 + i as f32 * 12.0,
-                        text: format!("{}", ability_descriptor.name.clone()),
+                        text: ability_descriptor.get_title(ability.rank),
                         font_size: 15.0,
                         w: 62.0,
                         h: 15.0,
@@ -741,7 +1043,7 @@ impl Inventory {
                     if ability.cooldown_time_left <= 0.0 {
                         let mana_percent = ptry!(self.get_combined_stats()).mana_cost.map(|x| x.get_value()).unwrap_or(0.0);
                         let mana_multi = if mana_percent < 0.0 {mana_percent.abs()/100.0 +1.0} else {1.0/(mana_percent/100.0 + 1.0)};
-                        let adjmc = ability_desc.mana_cost * mana_multi;
+                        let adjmc = ability_desc.get_mana_cost(ability.rank) * mana_multi;
                         if mana < adjmc {
                             text.push(TextSprite {
                             text: "NO MANA".into(),
@@ -824,11 +1126,31 @@ impl Inventory {
             }
         }
     }
-    pub fn on_mouse_click(&mut self, position: MousePosition, left: bool, right: bool) -> Result<(), PError> {
+    pub fn on_mouse_click(&mut self, position: MousePosition, left: bool, right: bool, crafting_book: &CraftingBook) -> Result<(), PError> {
         if left {
+            if self.shop.is_some() {
+                ptry!(self.on_shop_click(position));
+            } else {
+                if position.x_screen > SALVAGE_BUTTON[0] && position.x_screen < SALVAGE_BUTTON[0] + SALVAGE_BUTTON[2] && position.y_screen > SALVAGE_BUTTON[1] && position.y_screen < SALVAGE_BUTTON[1] + SALVAGE_BUTTON[3] {
+                    self.crafting_request = Some(CraftingRequest::Salvage);
+                }
+                let items = self.get_crafting_items();
+                let recipe_clicked = crafting_book.recipes.iter().enumerate().position(|(i, recipe)| {
+                    let y = CRAFTING_RECIPES_Y + i as f32 * CRAFTING_RECIPE_HEIGHT;
+                    position.x_screen > CRAFTING_PANEL_X + 20.0 && position.x_screen < CRAFTING_PANEL_X + 260.0 && position.y_screen > y && position.y_screen < y + CRAFTING_RECIPE_HEIGHT - 2.0 && recipe.can_craft(&items, &self.materials)
+                });
+                if let Some(recipe) = recipe_clicked {
+                    self.crafting_request = Some(CraftingRequest::Craft(recipe));
+                }
+            }
+
             let mut slot_clicked = None;
             let mut i = 0;
             for slot in self.slots.iter_mut() {
+                if self.shop.is_some() && self.crafting_slots.contains(&i) {
+                    i += 1;
+                    continue;
+                }
                 if slot.x < position.x_screen as usize && slot.x + 48 > position.x_screen as usize && slot.y < position.y_screen as usize && slot.y + 48 > position.y_screen as usize {
                     slot_clicked = Some(slot);
                     break;
@@ -882,7 +1204,14 @@ impl Inventory {
                 for i in 0..self.player_abilities.len() {
                     if position.x_screen > 330.0 + 58.0 * cur as f32 && position.x_screen < 400.0 + 58.0 * cur as f32 && position.y_screen > 422.0 + i as f32 * 12.0 && position.y_screen < 432.0 + i as f32 * 12.0{
                         let slot = punwrap!(self.ability_slots.get_mut(cur), Invalid, "cur ability slot clicked refers to non existent ability slot");
+                        let previous = slot.cur_ability;
                         slot.cur_ability = Some(i);
+                        // an ability can only be on one slot, so the slot it was on gets this slot's old ability
+                        for (slot_id, other) in self.ability_slots.iter_mut().enumerate() {
+                            if slot_id != cur && other.cur_ability == Some(i) {
+                                other.cur_ability = previous;
+                            }
+                        }
                         self.ability_slot_clicked = None;
                     } 
                 }
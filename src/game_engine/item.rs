@@ -209,7 +209,10 @@
 use compact_str::CompactString;
 use serde::{Deserialize, Serialize};
 
-use super::stat::{GearStatList, StatList};
+use super::factions::SummonDescriptor;
+use super::melee_combos::MeleeSwing;
+use super::ranged_weapons::RangedDescriptor;
+use super::stat::{DamageType, GearStatList, StatList};
 
 
 #[derive(Clone, Debug)]
@@ -222,6 +225,10 @@ pub struct Item {
     pub sprite: CompactString,
     pub attack_sprite: Option<CompactString>,
     pub time_til_usable: f32,
+    pub archetype: CompactString,
+    pub rarity: Rarity,
+    pub damage_type: DamageType, // type of the damage dealt by attacks made with this item
+    pub uses: Option<usize>, // uses left for items that get used up, like ammo
 }
 
 #[derive(Debug, Clone)]
@@ -232,7 +239,84 @@ pub struct ItemArchetype {
     pub item_type: ItemType,
     pub width_to_length_ratio: Option<f32>,
     pub sprite: CompactString,
-    pub attack_sprite: Option<CompactString>
+    pub attack_sprite: Option<CompactString>,
+    pub rarity: Rarity,
+    pub damage_type: DamageType,
+    pub uses: Option<usize>,
+    pub ranged: Option<RangedDescriptor>,
+    pub combo: Option<Vec<MeleeSwing>>,
+    pub teaches_ability: Option<CompactString>, // picking the item up unlocks or upgrades this ability instead
+    pub summon: Option<SummonDescriptor> // attacking summons allies instead
+}
+
+impl Item {
+    pub fn get_price(&self) -> usize {
+        let mut stat_total = 0.0;
+        for (_, stat) in &self.stats {
+            if let Some(stat) = stat {
+                stat_total += stat.flat.abs() + stat.percent.abs();
+            }
+        }
+        (self.rarity.base_price() as f32 + stat_total * 0.25).round() as usize
+    }
+    pub fn get_sell_price(&self) -> usize {
+        (self.get_price() / 4).max(1)
+    }
+    pub fn get_title(&self) -> String {
+        let mut t = format!("{}\n{:?}\n", self.name, self.rarity);
+        if self.damage_type != DamageType::Physical {
+            t.push_str(&format!("{:?} damage\n", self.damage_type));
+        }
+        if let Some(uses) = self.uses {
+            t.push_str(&format!("{} left\n", uses));
+        }
+        t
+    }
+}
+
+// Follows the rarity tiers from the design notes above, upgrading an item moves it one tier up
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub enum Rarity {
+    #[default]
+    Common,
+    Rare,
+    Epic,
+    Mythical,
+    Legendary,
+    Supreme,
+}
+
+impl Rarity {
+    pub fn next(&self) -> Option<Rarity> {
+        match self {
+            Rarity::Common => Some(Rarity::Rare),
+            Rarity::Rare => Some(Rarity::Epic),
+            Rarity::Epic => Some(Rarity::Mythical),
+            Rarity::Mythical => Some(Rarity::Legendary),
+            Rarity::Legendary => Some(Rarity::Supreme),
+            Rarity::Supreme => None,
+        }
+    }
+    pub fn base_price(&self) -> usize {
+        match self {
+            Rarity::Common => 10,
+            Rarity::Rare => 30,
+            Rarity::Epic => 80,
+            Rarity::Mythical => 200,
+            Rarity::Legendary => 500,
+            Rarity::Supreme => 1500,
+        }
+    }
+    pub fn color(&self) -> [f32; 4] {
+        match self {
+            Rarity::Common => [1.0, 1.0, 1.0, 1.0],
+            Rarity::Rare => [0.3, 0.6, 1.0, 1.0],
+            Rarity::Epic => [0.7, 0.3, 1.0, 1.0],
+            Rarity::Mythical => [1.0, 0.3, 0.3, 1.0],
+            Rarity::Legendary => [1.0, 0.7, 0.1, 1.0],
+            Rarity::Supreme => [0.2, 1.0, 0.8, 1.0],
+        }
+    }
 }
 
 
@@ -267,4 +351,5 @@ setup_item_types!{
     BaubleBelt,
     BaubleBack,
     BaubleBody,
+    Ammo,
 }
@@ -72,3 +72,39 @@ impl ItemOnFloor {
         }
     }
 }
+
+#[derive(Clone, Debug)]
+pub struct GoldOnFloor {
+    pub x: f32,
+    pub y: f32,
+    pub amount: usize
+}
+
+impl GoldOnFloor {
+    pub fn display(&self) -> UIEFull {
+        UIEFull {
+            sprites: vec![
+                UIESprite {
+                    x: self.x - 5.0,
+                    y: self.y - 2.0,
+                    z: 5.6,
+                    width: 60.0,
+                    height: 20.0,
+                    sprite: CompactString::from("level_editor_menu_background")
+                }
+            ],
+            text: vec![
+                TextSprite {
+                    text: format!("{} gold", self.amount),
+                    font_size: 23.0,
+                    x: self.x + 25.0,
+                    y: self.y + 1.5,
+                    w: 55.0,
+                    h: 16.0,
+                    color: [1.0, 0.84, 0.0, 1.0],
+                    align: wgpu_text::glyph_brush::HorizontalAlign::Center
+                }
+            ]
+        }
+    }
+}
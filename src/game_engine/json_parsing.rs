@@ -6,15 +6,28 @@ use std::io::{BufReader, BufWriter, Write};
 use std::fs::File;
 use crate::error::PError;
 use crate::game_engine::entities::EntityAttackPattern;
-use crate::perror;
+use crate::{perror, ptry};
 use crate::rendering_engine::abstractions::SpriteContainer;
 
 use super::entities::AttackType;
 use super::entity_attacks::EntityAttackDescriptor;
 use super::entity_components::CollisionBox;
-use super::item::{ItemArchetype, ItemType};
+use super::progression::Progression;
+use super::autotile::autotile_index;
+use super::player_abilities::get_player_ability_descriptors;
+use super::animation::LoopMode;
+use super::biomes::Biomes;
+use super::particles::ParticleEmitter;
+use super::room_format::validate_room;
+use super::room_generation::{RoomGeneration, RoomType};
+use super::room_objectives::RoomObjective;
+use super::crafting::{CraftingBook, ItemMatcher, Recipe, RecipeMode, SalvageEntry};
+use super::item::{ItemArchetype, ItemType, Rarity};
 use super::loot::{LootTable, LootTableEntry};
-use super::stat::GearStatList;
+use super::factions::SummonDescriptor;
+use super::melee_combos::MeleeSwing;
+use super::ranged_weapons::RangedDescriptor;
+use super::stat::{DamageType, GearStatList, Resistances};
 
 
 pub struct PathBundle{
@@ -27,7 +40,11 @@ pub struct PathBundle{
     pub item_archetypes_path: &'static str,
     pub loot_table_path: &'static str,
     pub rooms_path: &'static str,
-    pub spawn_archetypes_path: &'static str
+    pub spawn_archetypes_path: &'static str,
+    pub crafting_path: &'static str,
+    pub progression_path: &'static str,
+    pub biomes_path: &'static str,
+    pub particles_path: &'static str
 }
 
 pub const PATH_BUNDLE: PathBundle = PathBundle{
@@ -40,7 +57,11 @@ pub const PATH_BUNDLE: PathBundle = PathBundle{
     item_archetypes_path: "src/game_data/items.json",
     loot_table_path: "src/game_data/loot_tables.json",
     rooms_path: "src/game_data/rooms.json",
-    spawn_archetypes_path: "src/game_data/spawn_archetypes.json"
+    spawn_archetypes_path: "src/game_data/spawn_archetypes.json",
+    crafting_path: "src/game_data/crafting.json",
+    progression_path: "src/game_data/progression.json",
+    biomes_path: "src/game_data/biomes.json",
+    particles_path: "src/game_data/particles.json"
 };
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
@@ -53,6 +74,10 @@ pub struct entity_attack_descriptor_json {
     pub width: usize,
     pub time_to_charge: usize,
     pub sprite: CompactString,
+    #[serde(default)]
+    pub damage_type: DamageType,
+    #[serde(default)]
+    pub knockback: f32,
     pub poison: Option<PoisonDescriptor>,
     pub fire: Option<FireDescriptor>
 }
@@ -87,7 +112,15 @@ pub struct entity_archetype_json {
     pub attack_type: CompactString,
     pub attack_pattern: Option<CompactString>,
     pub loot_table: Vec<CompactString>,
-    pub sprite: Option<CompactString>
+    pub sprite: Option<CompactString>,
+    pub stock_table: Option<CompactString>,
+    pub stock_size: Option<usize>,
+    #[serde(default)]
+    pub resistances: Resistances,
+    pub open_sprite: Option<CompactString>, // sprite of opened doors and chests and pulled levers
+    pub key: Option<CompactString>, // item archetype that opens a door
+    pub channel: Option<CompactString>, // levers toggle the doors with the same channel
+    pub animation: Option<CompactString> // clips are looked up as this followed by the state and direction
 }
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct entity_attack_pattern_json {
@@ -98,7 +131,9 @@ pub struct entity_attack_pattern_json {
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct sprites_json_descriptor {
     pub basic_sprites: Vec<sprite_json>,
-    pub spritesheets: Vec<sprite_sheet_json>
+    pub spritesheets: Vec<sprite_sheet_json>,
+    #[serde(default)]
+    pub animations: Vec<animation_clip_json>
 }
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct sprite_sheet_json{
@@ -123,6 +158,30 @@ pub struct sprite_json {
     pub path: String,
 }
 #[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct animation_clip_json {
+    pub name: CompactString,
+    pub frames: Vec<animation_frame_json>,
+    #[serde(default = "default_frame_duration")]
+    pub frame_duration: usize, // frames each frame is shown for unless it sets its own duration
+    #[serde(default)]
+    pub mode: LoopMode,
+    #[serde(default)]
+    pub events: Vec<animation_event_json>
+}
+fn default_frame_duration() -> usize {
+    8
+}
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct animation_frame_json {
+    pub sprite: CompactString,
+    pub duration: Option<usize>
+}
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct animation_event_json {
+    pub frame: usize, // fired when this frame starts being shown
+    pub name: CompactString
+}
+#[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct player_json{
     pub x: f32,
     pub y: f32,
@@ -151,7 +210,13 @@ pub struct terrain_archetype_json{
     pub r#type: CompactString,
     pub random_chances: Option<Vec<f32>>,
     pub sprites: Vec<CompactString>,
-    pub basic_tags: Vec<CompactString>
+    pub basic_tags: Vec<CompactString>,
+    pub movement_multiplier: Option<f32>,
+    pub contact_damage: Option<f32>,
+    pub contact_interval: Option<usize>, // in frames
+    pub status: Option<CompactString>,
+    pub light_radius: Option<f32>, // in pixels
+    pub light_color: Option<[f32; 4]>
 }
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct starting_level_json{
@@ -207,7 +272,16 @@ pub struct item_archetype_json {
     pub width_to_length_ratio: Option<f32>,
     pub item_type: ItemType,
     pub lore: String,
-    pub stats: GearStatList
+    pub stats: GearStatList,
+    #[serde(default)]
+    pub rarity: Rarity,
+    #[serde(default)]
+    pub damage_type: DamageType,
+    pub uses: Option<usize>,
+    pub ranged: Option<RangedDescriptor>,
+    pub combo: Option<Vec<MeleeSwing>>,
+    pub teaches_ability: Option<CompactString>,
+    pub summon: Option<SummonDescriptor>
 }
 
 
@@ -219,9 +293,48 @@ pub struct item_loot_table_json {
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct loot_table_entry_json {
     pub item: Option<CompactString>,
+    pub gold: Option<[usize; 2]>,
     pub weight: usize
 }
 
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct crafting_json {
+    pub materials: Vec<CompactString>,
+    pub recipes: Vec<recipe_json>,
+    pub salvage: Vec<salvage_json>
+}
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct recipe_json {
+    pub name: CompactString,
+    pub inputs: Vec<item_matcher_json>,
+    #[serde(default)]
+    pub inputs_must_match: bool,
+    #[serde(default)]
+    pub materials: Vec<material_amount_json>,
+    pub mode: CompactString,
+    pub output: Option<CompactString>,
+    pub upgrade_multiplier: Option<f32>,
+    #[serde(default)]
+    pub upgraded_stats: Vec<CompactString>
+}
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct item_matcher_json {
+    pub archetype: Option<CompactString>,
+    pub rarity: Option<Rarity>,
+    pub item_type: Option<ItemType>
+}
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct material_amount_json {
+    pub material: CompactString,
+    pub amount: usize
+}
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct salvage_json {
+    #[serde(default)]
+    pub matches: item_matcher_json,
+    pub materials: Vec<material_amount_json>
+}
+
 #[derive(Clone)]
 pub struct JSON_parser {
     pub entity_archetypes_json: FxHashMap<CompactString, entity_archetype_json>,
@@ -233,12 +346,16 @@ pub struct JSON_parser {
     pub item_archetype_json: Vec<item_archetype_json>,
     pub loot_table_json: Vec<item_loot_table_json>,
     pub rooms_json: FxHashMap<CompactString, room_descriptor_json>,
-    pub spawn_archetypes_json: FxHashMap<CompactString, spawn_archetype_json>
+    pub spawn_archetypes_json: FxHashMap<CompactString, spawn_archetype_json>,
+    pub crafting_json: crafting_json,
+    pub progression_json: Progression,
+    pub biomes_json: Biomes,
+    pub particles_json: Vec<ParticleEmitter>
 }
 
 
 
-#[derive(Debug, Deserialize, Serialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
 pub struct room_descriptor_json {
     pub name: CompactString,
     pub terrain: Vec<terrain_json>,
@@ -247,24 +364,56 @@ pub struct room_descriptor_json {
     pub spawnable: Vec<[usize; 2]>,
     pub spawn_archetype: CompactString,
     pub entrance: [usize; 2],
-    pub exit: [usize; 2]
+    pub exit: [usize; 2],
+    #[serde(default)]
+    pub entities: Vec<special_spawn_json>, // entities always placed in the room, such as vendors
+    #[serde(default)]
+    pub objective: RoomObjective, // what unlocks the exit
+    #[serde(default)]
+    pub reward: Option<CompactString>, // entity archetype placed once the room is cleared, like a chest
+    #[serde(default)]
+    pub reward_position: Option<[usize; 2]>, // where the reward is placed, the entrance if not given
+    #[serde(default, rename = "type")]
+    pub room_type: RoomType, // procedural rooms ignore terrain and spawnable and generate their own
+    #[serde(default)]
+    pub generation: Option<RoomGeneration>,
+    #[serde(default)]
+    pub darkness: f32, // how much of the ambient light is taken away, 0 is fully lit and 1 only shows what lights reach
 }
 
-#[derive(Debug, Deserialize, Serialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
 pub struct spawn_archetype_json {
     pub name: CompactString,
     pub basic: Vec<entity_spawn_json>,
     pub total_points_to_spawn: usize,
-    pub special: Vec<special_spawn_json>
+    pub special: Vec<special_spawn_json>, // always placed at their position in the room, they don't cost points
+    #[serde(default)]
+    pub points_per_depth: usize, // added to the points to spawn for every room the player has gone through
+    #[serde(default)]
+    pub elite_chance_per_depth: f32,
 }
 
-#[derive(Debug, Deserialize, Serialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
 pub struct entity_spawn_json {
     pub archetype: CompactString,
     pub points: usize,
+    #[serde(default = "default_spawn_weight")]
+    pub weight: f32, // how likely this option is picked compared to the others
+    #[serde(default)]
+    pub min: usize, // spawned before anything else, even if it goes over the points
+    #[serde(default)]
+    pub max: Option<usize>,
+    #[serde(default)]
+    pub elite: Option<CompactString>, // entity archetype spawned instead for ELITE_POINT_MULTIPLIER times the points
+    #[serde(default)]
+    pub elite_chance: f32,
 }
 
-#[derive(Debug, Deserialize, Serialize, Clone)]
+fn default_spawn_weight() -> f32 {
+    1.0
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
 pub struct special_spawn_json {
     pub x: usize,
     pub y: usize,
@@ -288,7 +437,8 @@ impl JSON_parser {
 
             sprites_json: sprites_json_descriptor {
                 basic_sprites: Vec::new(),
-                spritesheets: Vec::new()
+                spritesheets: Vec::new(),
+                animations: Vec::new()
             },
             item_archetype_json: Vec::new(),
             starting_level_json: starting_level_json {
@@ -305,7 +455,15 @@ impl JSON_parser {
             },
             loot_table_json: Vec::new(),
             rooms_json: FxHashMap::default(),
-            spawn_archetypes_json: FxHashMap::default()
+            spawn_archetypes_json: FxHashMap::default(),
+            crafting_json: crafting_json {
+                materials: Vec::new(),
+                recipes: Vec::new(),
+                salvage: Vec::new()
+            },
+            progression_json: Progression::default(),
+            biomes_json: Biomes::default(),
+            particles_json: Vec::new()
         }
     }
 
@@ -385,6 +543,30 @@ impl JSON_parser {
             self.spawn_archetypes_json.insert(archetype.name.clone(), archetype);
         }
     }
+    pub fn parse_crafting(&mut self, path: &str) {
+        let file = File::open(path).expect("\nCould not open the crafting file.");
+        let reader = BufReader::new(file);
+        let data: crafting_json = serde_json::from_reader(reader).expect("Crafting JSON was not well-formatted");
+        self.crafting_json = data;
+    }
+    pub fn parse_progression(&mut self, path: &str) {
+        let file = File::open(path).expect("\nCould not open the progression file.");
+        let reader = BufReader::new(file);
+        let data: Progression = serde_json::from_reader(reader).expect("Progression JSON was not well-formatted");
+        self.progression_json = data;
+    }
+    pub fn parse_biomes(&mut self, path: &str) {
+        let file = File::open(path).expect("\nCould not open the biomes file.");
+        let reader = BufReader::new(file);
+        let data: Biomes = serde_json::from_reader(reader).expect("Biomes JSON was not well-formatted");
+        self.biomes_json = data;
+    }
+    pub fn parse_particles(&mut self, path: &str) {
+        let file = File::open(path).expect("\nCould not open the particles file.");
+        let reader = BufReader::new(file);
+        let data: Vec<ParticleEmitter> = serde_json::from_reader(reader).expect("Particles JSON was not well-formatted");
+        self.particles_json = data;
+    }
 
 
     
@@ -409,6 +591,8 @@ impl JSON_parser {
                 width: entity_attack.width,
                 time_to_charge: entity_attack.time_to_charge,
                 sprite: entity_attack.sprite.clone(),
+                damage_type: entity_attack.damage_type,
+                knockback: entity_attack.knockback,
                 fire: entity_attack.fire.clone(),
                 poison: entity_attack.poison.clone()
             });
@@ -423,9 +607,18 @@ impl JSON_parser {
         for loot_table in self.loot_table_json.iter() {
             let mut entries = Vec::new();
             for entry in loot_table.loot.iter() {
+                if entry.item.is_some() && entry.gold.is_some() {
+                    panic!("When parsing loot tables, an entry in loot table: {} has both an item and gold, entries can only drop one", loot_table.name);
+                }
+                if let Some([min, max]) = entry.gold {
+                    if min > max {
+                        panic!("When parsing loot tables, an entry in loot table: {} drops between {} and {} gold, the minimum is larger than the maximum", loot_table.name, min, max);
+                    }
+                }
                 entries.push(
                     LootTableEntry{
                         item: entry.item.clone(), 
+                        gold: entry.gold,
                         weight: entry.weight
                     });
             }
@@ -443,10 +636,19 @@ impl JSON_parser {
             data.entity_archetypes.insert(entity_archetype.name.clone(), entity_archetype.clone());
         }
         for (.., terrain_archetype) in &self.terrain_archetypes_json {
+            if terrain_archetype.r#type == "autotile" {
+                crate::ok_or_panic!(autotile_index(terrain_archetype));
+            }
             data.terrain_archetypes.insert(terrain_archetype.name.clone(), terrain_archetype.clone());
         }
 
+        let player_abilities = get_player_ability_descriptors();
         for item_archetype in &self.item_archetype_json {
+            if let Some(ability) = &item_archetype.teaches_ability {
+                if !player_abilities.iter().any(|x| x.name == *ability) {
+                    panic!("When parsing item archetypes, item archetype: {} teaches ability {}, which doesn't exist", item_archetype.name, ability);
+                }
+            }
             data.item_archetypes.insert(item_archetype.name.clone(), ItemArchetype{
                 name: item_archetype.name.clone(),
                 stats: item_archetype.stats.clone(),
@@ -454,15 +656,60 @@ impl JSON_parser {
                 item_type: item_archetype.item_type.clone(),
                 width_to_length_ratio: item_archetype.width_to_length_ratio,
                 sprite: item_archetype.sprite.clone(),
-                attack_sprite: item_archetype.attack_sprite.clone()
+                attack_sprite: item_archetype.attack_sprite.clone(),
+                rarity: item_archetype.rarity,
+                damage_type: item_archetype.damage_type,
+                uses: item_archetype.uses,
+                ranged: item_archetype.ranged.clone(),
+                combo: item_archetype.combo.clone(),
+                teaches_ability: item_archetype.teaches_ability.clone(),
+                summon: item_archetype.summon.clone()
         });
         }
         data.rooms = self.rooms_json.clone();
+        for spawn_archetype in self.spawn_archetypes_json.values() {
+            crate::ok_or_panic!(JSON_parser::validate_spawn_archetype(spawn_archetype));
+        }
         data.spawn_archetypes = self.spawn_archetypes_json.clone();
+        crate::ok_or_panic!(self.validate_rooms());
+        data.crafting_book = crate::ok_or_panic!(JSON_parser::convert_crafting(&self.crafting_json, &data.item_archetypes));
+        crate::ok_or_panic!(self.progression_json.validate(&player_abilities));
+        data.progression = self.progression_json.clone();
+        crate::ok_or_panic!(self.biomes_json.validate(&self.terrain_archetypes_json, &self.rooms_json, &self.spawn_archetypes_json, &data.loot_table_lookup));
+        data.biomes = self.biomes_json.clone();
+        for emitter in self.particles_json.iter() {
+            crate::ok_or_panic!(emitter.validate(&data.sprites));
+        }
+        data.particle_emitters = self.particles_json.clone();
 
         data
     }
 
+    pub fn validate_spawn_archetype(archetype: &spawn_archetype_json) -> Result<(), PError>{
+        let name = &archetype.name;
+        for option in archetype.basic.iter() {
+            if option.points == 0 {
+                return Err(perror!(JSONValidationError, "Spawn archetype: {} spawns {} for 0 points, it should cost at least 1", name, option.archetype));
+            }
+            if !(option.weight >= 0.0 && option.weight.is_finite()) {
+                return Err(perror!(JSONValidationError, "Spawn archetype: {} spawns {} with weight {}, it should be 0 or more", name, option.archetype, option.weight));
+            }
+            if option.max.is_some_and(|max| max < option.min) {
+                return Err(perror!(JSONValidationError, "Spawn archetype: {} spawns {} at least {} times but at most {:?} times", name, option.archetype, option.min, option.max));
+            }
+            if !(0.0..=1.0).contains(&option.elite_chance) {
+                return Err(perror!(JSONValidationError, "Spawn archetype: {} spawns {} with elite chance {}, it should be between 0 and 1", name, option.archetype, option.elite_chance));
+            }
+            if option.elite_chance > 0.0 && option.elite.is_none() {
+                return Err(perror!(JSONValidationError, "Spawn archetype: {} spawns {} with an elite chance but no elite archetype", name, option.archetype));
+            }
+        }
+        if archetype.elite_chance_per_depth < 0.0 {
+            return Err(perror!(JSONValidationError, "Spawn archetype: {} has a negative elite chance per depth", name));
+        }
+        Ok(())
+    }
+
     pub fn validate_entity_archetype(archetype: &entity_archetype_json) -> Result<(), PError>{
         let name = &archetype.name;
         let mut has_collision = false;
@@ -498,12 +745,34 @@ impl JSON_parser {
                     has_collision = true;
                 },
                 "animated" => {},
+                "shrine" => {},
+                "ally" => {},
+                "flying" => {},
+                "door" | "lever" | "chest" => {},
+                "breakable" => {
+                    if !archetype.basic_tags.iter().any(|tag| tag.as_str() == "damageable") {
+                        return Err(perror!(JSONValidationError, "Entity archetype: {} has the breakable tag but isn't damageable", name));
+                    }
+                },
+                "opensOnClear" => {
+                    if !archetype.basic_tags.iter().any(|tag| tag.as_str() == "door") {
+                        return Err(perror!(JSONValidationError, "Entity archetype: {} has the opensOnClear tag but no door tag", name));
+                    }
+                },
+                "vendor" => {
+                    if archetype.stock_table.is_none() {
+                        return Err(perror!(JSONValidationError, "Entity archetype: {} has the vendor tag but no stock table", name));
+                    }
+                },
                 _ => {
                     return Err(perror!(JSONValidationError, "Entity archetype: {} has an unrecognized tag: {}", name, tag));
                 }
             }
         }
 
+        if archetype.basic_tags.iter().any(|tag| tag.as_str() == "flying") && !respects_collision {
+            return Err(perror!(JSONValidationError, "Entity archetype: {} has the flying tag but doesn't respect collision", name));
+        }
         if has_collision && !respects_collision {
             return Err(perror!(JSONValidationError, "Entity archetype {} has collision but doesn't respect collision, this causes very uncertain behavior and is not recommended", name));
         }
@@ -531,8 +800,110 @@ impl JSON_parser {
         if archetype.range.is_some() {
             archetype.basic_tags.iter().find(|tag| tag.as_str() == "attacker").ok_or_else(|| perror!(JSONValidationError, "Entity archetype: {} has a range but no attacker tag", name))?;
         }
+        let interactable_tags = archetype.basic_tags.iter().filter(|tag| ["door", "lever", "chest", "breakable"].contains(&tag.as_str())).count();
+        if interactable_tags > 1 {
+            return Err(perror!(JSONValidationError, "Entity archetype: {} can only be one of a door, lever, chest or breakable", name));
+        }
+        if archetype.key.is_some() {
+            archetype.basic_tags.iter().find(|tag| tag.as_str() == "door").ok_or_else(|| perror!(JSONValidationError, "Entity archetype: {} has a key but no door tag", name))?;
+        }
+        if archetype.channel.is_some() && !archetype.basic_tags.iter().any(|tag| tag.as_str() == "door" || tag.as_str() == "lever") {
+            return Err(perror!(JSONValidationError, "Entity archetype: {} has a channel but isn't a door or lever", name));
+        }
+        if archetype.stock_table.is_some() || archetype.stock_size.is_some() {
+            archetype.basic_tags.iter().find(|tag| tag.as_str() == "vendor").ok_or_else(|| perror!(JSONValidationError, "Entity archetype: {} has a stock table or stock size but no vendor tag", name))?;
+        }
         Ok(())
     }
+    pub fn convert_crafting(crafting: &crafting_json, item_archetypes: &FxHashMap<CompactString, ItemArchetype>) -> Result<CraftingBook, PError> {
+        let convert_matcher = |matcher: &item_matcher_json, source: &str| -> Result<ItemMatcher, PError> {
+            if let Some(archetype) = &matcher.archetype {
+                if !item_archetypes.contains_key(archetype) {
+                    return Err(perror!(JSONValidationError, "{} refers to item archetype {} which doesn't exist", source, archetype));
+                }
+            }
+            Ok(ItemMatcher {
+                archetype: matcher.archetype.clone(),
+                rarity: matcher.rarity,
+                item_type: matcher.item_type.clone()
+            })
+        };
+        let convert_materials = |materials: &[material_amount_json], source: &str| -> Result<Vec<(CompactString, usize)>, PError> {
+            let mut converted = Vec::new();
+            for m in materials {
+                if !crafting.materials.contains(&m.material) {
+                    return Err(perror!(JSONValidationError, "{} uses material {} which isn't in the materials list", source, m.material));
+                }
+                converted.push((m.material.clone(), m.amount));
+            }
+            Ok(converted)
+        };
+
+        let mut book = CraftingBook {
+            materials: crafting.materials.clone(),
+            recipes: Vec::new(),
+            salvage: Vec::new()
+        };
+        for recipe in &crafting.recipes {
+            let source = format!("Recipe {}", recipe.name);
+            let mode = match recipe.mode.as_str() {
+                "create" => RecipeMode::Create,
+                "reroll" => RecipeMode::Reroll,
+                "upgrade" => RecipeMode::Upgrade,
+                _ => {
+                    return Err(perror!(JSONValidationError, "{} has unrecognized mode: {}", source, recipe.mode));
+                }
+            };
+            if recipe.inputs.is_empty() {
+                return Err(perror!(JSONValidationError, "{} has no inputs", source));
+            }
+            match &recipe.output {
+                Some(output) => {
+                    if mode != RecipeMode::Create {
+                        return Err(perror!(JSONValidationError, "{} has an output but only create recipes produce new items", source));
+                    }
+                    if !item_archetypes.contains_key(output) {
+                        return Err(perror!(JSONValidationError, "{} outputs item archetype {} which doesn't exist", source, output));
+                    }
+                },
+                None => {
+                    if mode == RecipeMode::Create {
+                        return Err(perror!(JSONValidationError, "{} is a create recipe but has no output", source));
+                    }
+                }
+            }
+            if mode != RecipeMode::Upgrade && (recipe.upgrade_multiplier.is_some() || !recipe.upgraded_stats.is_empty()) {
+                return Err(perror!(JSONValidationError, "{} has upgrade settings but is not an upgrade recipe", source));
+            }
+            for stat in &recipe.upgraded_stats {
+                if crate::stat::StatList::default().get_stat_mut(stat).is_none() {
+                    return Err(perror!(JSONValidationError, "{} upgrades unknown stat {}", source, stat));
+                }
+            }
+            let mut inputs = Vec::new();
+            for input in &recipe.inputs {
+                inputs.push(convert_matcher(input, &source)?);
+            }
+            book.recipes.push(Recipe {
+                name: recipe.name.clone(),
+                inputs,
+                inputs_must_match: recipe.inputs_must_match,
+                materials: convert_materials(&recipe.materials, &source)?,
+                mode,
+                output: recipe.output.clone(),
+                upgrade_multiplier: recipe.upgrade_multiplier.unwrap_or(1.0),
+                upgraded_stats: recipe.upgraded_stats.clone()
+            });
+        }
+        for (i, salvage) in crafting.salvage.iter().enumerate() {
+            let source = format!("Salvage entry {}", i);
+            book.salvage.push(SalvageEntry {
+                matches: convert_matcher(&salvage.matches, &source)?,
+                materials: convert_materials(&salvage.materials, &source)?
+            });
+        }
+        Ok(book)
+    }
     pub fn parse_and_convert_game_data(&mut self, paths: PathBundle) -> ParsedData{
         self.parse_entity_archetypes(paths.entity_archetypes_path);
         self.parse_entity_attack_patterns(paths.entity_attack_patterns_path);
@@ -544,15 +915,50 @@ impl JSON_parser {
         self.parse_loot_tables(paths.loot_table_path);
         self.parse_rooms(paths.rooms_path);
         self.parse_spawn_archetypes(paths.spawn_archetypes_path);
+        self.parse_crafting(paths.crafting_path);
+        self.parse_progression(paths.progression_path);
+        self.parse_biomes(paths.biomes_path);
+        self.parse_particles(paths.particles_path);
         self.convert()
     }
 
     pub fn write(&self, paths: PathBundle) -> Result<(), Box<dyn std::error::Error>> {
-        let file = File::create(paths.starting_level_path)?;
+        self.write_starting_level(paths.starting_level_path)?;
+        self.write_rooms(paths.rooms_path)?;
+        self.write_spawn_archetypes(paths.spawn_archetypes_path)?;
+        Ok(())
+    }
+    pub fn write_starting_level(&self, path: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let file = File::create(path)?;
         let mut writer = BufWriter::new(file);
         write!(writer, "{}", serde_json::to_string(&self.starting_level_json)?)?;
         Ok(())
     }
+    // rooms and spawn archetypes are kept sorted by name so saving doesn't shuffle the files around
+    pub fn write_rooms(&self, path: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let mut rooms: Vec<&room_descriptor_json> = self.rooms_json.values().collect();
+        rooms.sort_by(|a, b| a.name.cmp(&b.name));
+        JSON_parser::write_pretty(path, &rooms)
+    }
+    pub fn write_spawn_archetypes(&self, path: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let mut archetypes: Vec<&spawn_archetype_json> = self.spawn_archetypes_json.values().collect();
+        archetypes.sort_by(|a, b| a.name.cmp(&b.name));
+        JSON_parser::write_pretty(path, &archetypes)
+    }
+    fn write_pretty<T: Serialize>(path: &str, data: &T) -> Result<(), Box<dyn std::error::Error>> {
+        let file = File::create(path)?;
+        let mut writer = BufWriter::new(file);
+        let mut serializer = serde_json::Serializer::with_formatter(&mut writer, serde_json::ser::PrettyFormatter::with_indent(b"\t"));
+        data.serialize(&mut serializer)?;
+        writer.flush()?;
+        Ok(())
+    }
+    pub fn validate_rooms(&self) -> Result<(), PError> {
+        for room in self.rooms_json.values() {
+            ptry!(validate_room(room, &self.terrain_archetypes_json, &self.spawn_archetypes_json));
+        }
+        Ok(())
+    }
     pub fn get_entity_archetype_json(&self, name: &str) -> Option<&entity_archetype_json> {
         self.entity_archetypes_json.get(name)
     }
@@ -576,6 +982,10 @@ pub struct ParsedData{
     pub starting_level_descriptor: starting_level_json,
     pub item_archetypes: FxHashMap<CompactString, ItemArchetype>,
     pub loot_table_lookup: FxHashMap<CompactString, LootTable>,
+    pub crafting_book: CraftingBook,
+    pub progression: Progression,
+    pub biomes: Biomes,
+    pub particle_emitters: Vec<ParticleEmitter>,
 }
 
 impl Default for ParsedData {
@@ -608,7 +1018,11 @@ impl ParsedData{
             },
             loot_table_lookup: FxHashMap::default(),
             rooms: FxHashMap::default(),
-            spawn_archetypes: FxHashMap::default()
+            spawn_archetypes: FxHashMap::default(),
+            crafting_book: CraftingBook::default(),
+            progression: Progression::default(),
+            biomes: Biomes::default(),
+            particle_emitters: Vec::new()
         }
     }
     pub fn get_terrain_archetype(&self, name: &str) -> Option<&terrain_archetype_json> {
@@ -0,0 +1,96 @@
+use crate::rendering_engine::vertex::{Light, MAX_LIGHTS};
+use crate::world::World;
+
+use super::camera::Camera;
+use super::player_attacks::PlayerAttackType;
+use super::terrain::TerrainTags;
+
+pub const TORCH_COLOR: [f32; 4] = [1.0, 0.75, 0.45, 1.0];
+pub const PLAYER_LIGHT_RADIUS: f32 = 224.0;
+pub const PLAYER_LIGHT_COLOR: [f32; 4] = [1.0, 0.92, 0.8, 1.0];
+pub const FIRE_LIGHT_RADIUS: f32 = 96.0;
+pub const FIRE_LIGHT_COLOR: [f32; 4] = [1.0, 0.55, 0.2, 1.0];
+pub const PROJECTILE_LIGHT_RADIUS: f32 = 80.0;
+
+// A light in the world, positions are the center of what's lit in pixels
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LightSource {
+    pub x: f32,
+    pub y: f32,
+    pub radius: f32,
+    pub intensity: f32,
+    pub color: [f32; 4],
+}
+
+impl World {
+    // how much light there is without any light sources, rooms take away their darkness and the starting level is fully lit
+    pub fn get_ambient_light(&self) -> f32 {
+        1.0 - self.cur_room.as_ref().map(|x| x.darkness).unwrap_or(0.0)
+    }
+    // the player, burning entities, projectiles and glowing terrain that could light the rectangle, closest to the player first
+    pub fn get_light_sources(&self, x: f32, y: f32, width: f32, height: f32) -> Vec<LightSource> {
+        let (player_x, player_y) = {
+            let player = self.player.borrow();
+            (player.x + 16.0, player.y + 16.0)
+        };
+        let mut lights = vec![LightSource {x: player_x, y: player_y, radius: PLAYER_LIGHT_RADIUS, intensity: 1.0, color: PLAYER_LIGHT_COLOR}];
+        let reaches = |light: &LightSource| light.x + light.radius > x && light.x - light.radius < x + width && light.y + light.radius > y && light.y - light.radius < y + height;
+
+        for (damageable, position) in self.components.damageable_components.iter().zip(self.components.position_components.iter()) {
+            if let (Some(damageable), Some(position)) = (damageable, position) {
+                if damageable.borrow().fire.is_some() {
+                    let position = position.borrow();
+                    lights.push(LightSource {x: position.x + 16.0, y: position.y + 16.0, radius: FIRE_LIGHT_RADIUS, intensity: 1.0, color: FIRE_LIGHT_COLOR});
+                }
+            }
+        }
+        for attack in self.player_attacks.borrow().iter() {
+            if matches!(attack.attack_type, PlayerAttackType::Ranged | PlayerAttackType::RangedAbility) {
+                lights.push(LightSource {x: attack.x, y: attack.y, radius: PROJECTILE_LIGHT_RADIUS, intensity: 0.8, color: attack.damage_type.color()});
+            }
+        }
+
+        let left = (x - width).max(0.0) as usize;
+        let top = (y - height).max(0.0) as usize;
+        let chunks_ref = self.chunks.borrow();
+        for chunk_x in World::coord_to_chunk_coord(left)..=World::coord_to_chunk_coord((x + width * 2.0) as usize) {
+            for chunk_y in World::coord_to_chunk_coord(top)..=World::coord_to_chunk_coord((y + height * 2.0) as usize) {
+                let Some(chunk) = self.get_chunk_from_chunk_xy(chunk_x, chunk_y) else {
+                    continue;
+                };
+                for terrain_id in chunks_ref[chunk].terrain_ids.iter() {
+                    for tag in self.get_terrain_tags(*terrain_id).into_iter().flatten() {
+                        if let TerrainTags::Light{radius, color} = tag {
+                            let terrain = self.terrain[terrain_id];
+                            lights.push(LightSource {x: terrain.x as f32 + 16.0, y: terrain.y as f32 + 16.0, radius: *radius, intensity: 1.0, color: *color});
+                        }
+                    }
+                }
+            }
+        }
+
+        lights.retain(reaches);
+        lights.sort_by(|a, b| {
+            let distance = |light: &LightSource| (light.x - player_x).powi(2) + (light.y - player_y).powi(2);
+            distance(a).total_cmp(&distance(b))
+        });
+        lights
+    }
+}
+
+impl Camera {
+    // the lights that reach the screen in screen space, lights the player can't see are left out
+    pub fn get_lights(&self, world: &World, fog_of_war: bool) -> Vec<Light> {
+        let (width, height) = (self.viewpoint_width as f32, self.viewpoint_height as f32);
+        world.get_light_sources(self.camera_x, self.camera_y, width, height).into_iter()
+            .filter(|x| !fog_of_war || world.is_position_visible(x.x - 16.0, x.y - 16.0))
+            .take(MAX_LIGHTS)
+            .map(|x| Light {
+                position: [(x.x - self.camera_x) / width, (x.y - self.camera_y) / height],
+                radius: x.radius / height,
+                intensity: x.intensity,
+                color: x.color,
+            })
+            .collect()
+    }
+}
@@ -4,13 +4,20 @@ use rand::Rng;
 pub struct LootTable {
     entries: Vec<LootTableEntry>,
 }
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum LootDrop {
+    Item(CompactString), // item archetype
+    Gold(usize)
+}
+
 impl LootTable{
     pub fn new(entries: Vec<LootTableEntry>) -> Self{
         Self{entries}
     }
-    pub fn roll(&self, loot_percent: f32) -> Vec<CompactString> { // returns item archetypes
+    pub fn roll(&self, loot_percent: f32) -> Vec<LootDrop> {
         let mut lpl = loot_percent;
-        let mut items = vec![];
+        let mut drops = vec![];
 
         let mut rng = rand::thread_rng();
         let mut total_weight = 0;
@@ -18,35 +25,40 @@ impl LootTable{
             total_weight += entry.weight;
         }
         while lpl >= 100.0 {
-            let num = rng.gen_range(0..total_weight);
-            let mut current_weight = 0;
-            for entry in &self.entries {
-                if let Some(item) = &entry.item {
-                    current_weight += entry.weight;
-                    if num < current_weight {
-                        items.push(item.clone());
-                        break;
-                    }
-                }
-            }
+            drops.extend(self.roll_once(total_weight, &mut rng));
             lpl -= 100.0;
         }
         if rng.gen_range(0.0..100.0) < lpl {
-            let num = rng.gen_range(0..total_weight);
-            let mut current_weight = 0;
-            for entry in &self.entries {
+            drops.extend(self.roll_once(total_weight, &mut rng));
+        }
+
+
+        drops
+    }
+    fn roll_once(&self, total_weight: usize, rng: &mut impl Rng) -> Option<LootDrop> {
+        let num = rng.gen_range(0..total_weight);
+        let mut current_weight = 0;
+        for entry in &self.entries {
+            if entry.item.is_none() && entry.gold.is_none() {
+                continue;
+            }
+            current_weight += entry.weight;
+            if num < current_weight {
                 if let Some(item) = &entry.item {
-                    current_weight += entry.weight;
-                    if num < current_weight {
-                        items.push(item.clone());
-                        break;
-                    }
+                    return Some(LootDrop::Item(item.clone()));
+                }
+                if let Some([min, max]) = entry.gold {
+                    return Some(LootDrop::Gold(rng.gen_range(min..=max)));
                 }
             }
         }
-
-
-        items
+        None
+    }
+    pub fn roll_items(&self, loot_percent: f32) -> Vec<CompactString> { // returns item archetypes, ignoring gold
+        self.roll(loot_percent).into_iter().filter_map(|x| match x {
+            LootDrop::Item(item) => Some(item),
+            LootDrop::Gold(_) => None
+        }).collect()
     }
 }
 
@@ -55,5 +67,6 @@ impl LootTable{
 #[derive(Clone, Debug)]
 pub struct LootTableEntry {
     pub item: Option<CompactString>,
+    pub gold: Option<[usize; 2]>, // min and max gold dropped
     pub weight: usize,
 }
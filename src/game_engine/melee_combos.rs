@@ -0,0 +1,172 @@
+use std::f32::consts::PI;
+
+use compact_str::CompactString;
+use serde::{Deserialize, Serialize};
+
+use crate::error::PError;
+use crate::world::World;
+use crate::{ptry, punwrap};
+
+use super::camera::Camera;
+use super::item::Item;
+use super::player::Player;
+use super::player_attacks::{PlayerAttack, PlayerAttackType};
+use super::stat::{StatC, StatList};
+
+// Effects that only make sense on some swings, usually the last one of a combo
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SwingEffect {
+    Knockback(f32), // multiplies the knockback of the swing
+    Stun(f32), // hitstun given on top of the weapon's own
+    Lunge(f32), // moves the player this far towards the mouse when the swing starts
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MeleeSwing {
+    pub arc: f32, // degrees swept over the swing, 0 is a straight thrust and negative sweeps the other way
+    pub duration: f32, // frames the swing lasts for
+    pub damage_multiplier: f32,
+    pub reach_multiplier: f32,
+    pub width_multiplier: f32,
+    pub window: f32, // frames after the swing ends where attacking again continues the combo, should be longer than the attack cooldown
+    pub effects: Vec<SwingEffect>,
+}
+
+impl Default for MeleeSwing {
+    fn default() -> Self {
+        Self {
+            arc: 0.0,
+            duration: 3.0,
+            damage_multiplier: 1.0,
+            reach_multiplier: 1.0,
+            width_multiplier: 1.0,
+            window: 0.0,
+            effects: Vec::new()
+        }
+    }
+}
+
+// Attached to melee player attacks made from a combo, weapons without a combo still do a single static hit
+#[derive(Debug, Clone)]
+pub struct SwingState {
+    pub start_angle: f32, // degrees
+    pub arc: f32,
+    pub duration: f32,
+    pub window: f32,
+    pub hit: Vec<usize>, // entities already hit by this swing, a sweep only hits each entity once
+}
+
+impl MeleeSwing {
+    pub fn apply_to_stats(&self, stats: &StatList) -> StatList {
+        let mut stats = stats.clone();
+        stats.damage = stats.damage.map(|x| StatC {flat: x.flat * self.damage_multiplier, percent: x.percent});
+        stats.reach = stats.reach.map(|x| StatC {flat: x.flat * self.reach_multiplier, percent: x.percent});
+        stats.width = stats.width.map(|x| StatC {flat: x.flat * self.width_multiplier, percent: x.percent});
+        for effect in self.effects.iter() {
+            match effect {
+                SwingEffect::Knockback(multiplier) => {
+                    stats.knockback = stats.knockback.map(|x| StatC {flat: x.flat * multiplier, percent: x.percent});
+                },
+                SwingEffect::Stun(stun) => {
+                    let hitstun = stats.hitstun.unwrap_or(StatC {flat: 0.0, percent: 0.0});
+                    stats.hitstun = Some(StatC {flat: hitstun.flat + stun, percent: hitstun.percent});
+                },
+                SwingEffect::Lunge(_) => {}
+            }
+        }
+        stats
+    }
+}
+
+impl World {
+    pub fn get_melee_combo(&self, archetype: &CompactString) -> Option<&Vec<MeleeSwing>> {
+        self.get_item_archetype(archetype).and_then(|x| x.combo.as_ref()).filter(|x| !x.is_empty())
+    }
+    // picks the swing to use for the next attack with the held weapon and advances the combo
+    pub fn next_melee_swing(&self, player: &mut Player) -> Option<MeleeSwing> {
+        let item = self.inventory.get_cur_held_item()?;
+        let combo = self.get_melee_combo(&item.archetype)?;
+        let step = if player.combo_window > 0.0 && player.combo_archetype.as_deref() == Some(item.archetype.as_str()) {
+            (player.combo_step + 1) % combo.len()
+        } else {
+            0
+        };
+        player.combo_step = step;
+        player.combo_window = 0.0;
+        player.combo_archetype = Some(item.archetype.clone());
+        Some(combo[step].clone())
+    }
+    // angle is in radians and points from the player towards the mouse, the swing is centered on it
+    pub fn start_melee_swing(&self, player: &mut Player, stats: &StatList, item: &Item, swing: &MeleeSwing, angle: f32) -> Result<(), PError> {
+        for effect in swing.effects.iter() {
+            if let SwingEffect::Lunge(distance) = effect {
+                ptry!(self.attempt_move_player(player, [angle.cos() * distance, angle.sin() * distance]), "while lunging with {}", item.name);
+            }
+        }
+        let start_angle = angle * 180.0/PI - swing.arc/2.0;
+        let radians = start_angle.to_radians();
+        let mut attack = PlayerAttack::new(
+            swing.apply_to_stats(stats),
+            PlayerAttackType::Melee,
+            item.damage_type,
+            punwrap!(item.attack_sprite.clone(), Expected, "all melee weapons should have an attack sprite"),
+            item.width_to_length_ratio.unwrap_or(1.0),
+            player.x + 16.0 + radians.cos() * 25.0,
+            player.y + 22.0 + radians.sin() * 25.0,
+            start_angle,
+            vec![]);
+        attack.swing = Some(SwingState {
+            start_angle,
+            arc: swing.arc,
+            duration: swing.duration,
+            window: swing.window,
+            hit: Vec::new()
+        });
+        self.player_attacks.borrow_mut().push(attack);
+        Ok(())
+    }
+    pub fn update_combo_window(&mut self) -> Result<(), PError> {
+        let mut player = self.player.borrow_mut();
+        if player.combo_window > 0.0 {
+            player.combo_window -= 1.0;
+        }
+        Ok(())
+    }
+    // sweeps the attack around the player and damages anything it passes over. Returns true once the swing is over
+    pub fn update_melee_swing(&self, attack: &mut PlayerAttack, camera: &mut Camera) -> Result<bool, PError> {
+        let swing = punwrap!(attack.swing.as_mut(), Expected, "updating a melee swing on an attack with no swing state");
+        attack.time_alive += 1.0;
+        if attack.time_alive > swing.duration {
+            let mut player = self.player.borrow_mut();
+            player.combo_window = swing.window;
+            return Ok(true);
+        }
+        let progress = f32::min(attack.time_alive/swing.duration.max(1.0), 1.0);
+        attack.angle = swing.start_angle + swing.arc * progress;
+        let player = self.player.borrow();
+        let radians = attack.angle.to_radians();
+        attack.x = player.x + 16.0 + radians.cos() * 25.0;
+        attack.y = player.y + 22.0 + radians.sin() * 25.0;
+        drop(player);
+
+        let height = attack.stats.reach.map(|x| x.get_value()).unwrap_or(0.0);
+        let width = attack.stats.width.map(|x| x.get_value()).unwrap_or(0.0);
+        let collisions = ptry!(self.get_attacked_rotated_rect(true, None, attack.x as usize, attack.y as usize, height.floor() as usize, width.floor() as usize, attack.angle, true));
+        for collision in collisions.iter() {
+            if swing.hit.contains(collision) {
+                continue;
+            }
+            if let Some(damageable) = self.components.damageable_components[*collision].as_ref() {
+                let mut health_component = damageable.borrow_mut();
+                let entity_position = punwrap!(self.components.position_components[*collision].as_ref(), Invalid, "entity {} hit by a melee swing has no position", collision).borrow();
+                let aggro = self.components.aggro_components[*collision].as_ref();
+                ptry!(self.damage_entity(&entity_position, Some(&mut health_component), aggro.map(|x| x.borrow_mut()).as_deref_mut(), &attack.stats, attack.damage_type, attack.angle, camera));
+                swing.hit.push(*collision);
+                attack.dealt_damage = true;
+            }
+        }
+        Ok(false)
+    }
+}
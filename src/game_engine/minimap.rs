@@ -0,0 +1,176 @@
+use rustc_hash::{FxHashMap, FxHashSet};
+use serde::{Deserialize, Serialize};
+
+use crate::error::PError;
+use crate::rendering_engine::abstractions::RenderData;
+use crate::world::World;
+use crate::perror;
+
+use super::camera::Camera;
+use super::factions::Faction;
+use super::terrain::TerrainTags;
+
+pub const MAP_KEY: &str = "m";
+pub const MINIMAP_RADIUS: usize = 20; // tiles shown around the player on the minimap
+pub const MINIMAP_TILE_SIZE: f32 = 4.0;
+pub const MINIMAP_MARGIN: f32 = 10.0;
+pub const FULL_MAP_MAX_TILE_SIZE: f32 = 12.0;
+
+const BACKGROUND_COLOR: [f32; 4] = [0.0, 0.0, 0.0, 0.6];
+const FLOOR_COLOR: [f32; 4] = [0.3, 0.3, 0.35, 0.9];
+const WALL_COLOR: [f32; 4] = [0.8, 0.8, 0.85, 1.0];
+const PLAYER_COLOR: [f32; 4] = [0.2, 1.0, 0.3, 1.0];
+const ENEMY_COLOR: [f32; 4] = [1.0, 0.2, 0.2, 1.0];
+const ITEM_COLOR: [f32; 4] = [0.3, 0.7, 1.0, 1.0];
+const EXIT_COLOR: [f32; 4] = [1.0, 0.85, 0.1, 1.0];
+const LOCKED_EXIT_COLOR: [f32; 4] = [0.6, 0.4, 0.1, 1.0];
+
+// Tiles the player has seen, kept across rooms so the full map shows everything gone through
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Minimap {
+    pub explored: FxHashSet<[usize; 2]>,
+    #[serde(skip)]
+    pub full_map: bool, // the map covers the whole screen instead of sitting in the corner
+}
+
+impl World {
+    pub fn player_tile(&self) -> [usize; 2] {
+        let player = self.player.borrow();
+        [(player.x.max(0.0) as usize + 16) / 32, (player.y.max(0.0) as usize + 16) / 32]
+    }
+    pub fn is_explored(&self, tile: [usize; 2]) -> bool {
+        self.minimap.explored.contains(&tile)
+    }
+    // only what the player can see is explored, so nothing behind walls shows up on the map early
+    pub fn update_explored(&mut self) {
+        self.minimap.explored.extend(self.visible_tiles.iter().copied());
+    }
+    pub fn toggle_full_map(&mut self) {
+        self.minimap.full_map = !self.minimap.full_map;
+    }
+    // exploration is the only part of the map that's kept, the full map view is not.
+    // there's no save system for runs yet, so nothing calls these outside the tests; a run save should write and load the map through them
+    pub fn save_map(&self) -> Result<String, PError> {
+        serde_json::to_string(&self.minimap).map_err(|e| perror!(Error, "failed to serialize the explored map: {}", e))
+    }
+    pub fn load_map(&mut self, save: &str) -> Result<(), PError> {
+        let minimap: Minimap = serde_json::from_str(save).map_err(|e| perror!(Invalid, "failed to load the explored map: {}", e))?;
+        self.minimap.explored = minimap.explored;
+        Ok(())
+    }
+    // whether each explored tile in the rectangle blocks movement, tiles without terrain are left out
+    pub fn get_map_tiles(&self, x: usize, y: usize, width: usize, height: usize) -> FxHashMap<[usize; 2], bool> {
+        let mut tiles = FxHashMap::default();
+        let chunks_ref = self.chunks.borrow();
+        for chunk_x in World::coord_to_chunk_coord(x * 32)..=World::coord_to_chunk_coord((x + width) * 32) {
+            for chunk_y in World::coord_to_chunk_coord(y * 32)..=World::coord_to_chunk_coord((y + height) * 32) {
+                let Some(chunk) = self.get_chunk_from_chunk_xy(chunk_x, chunk_y) else {
+                    continue;
+                };
+                for terrain_id in chunks_ref[chunk].terrain_ids.iter() {
+                    let terrain = self.terrain[terrain_id];
+                    let tile = [terrain.x / 32, terrain.y / 32];
+                    if tile[0] < x || tile[0] >= x + width || tile[1] < y || tile[1] >= y + height || !self.is_explored(tile) {
+                        continue;
+                    }
+                    let blocks = self.get_terrain_tags(*terrain_id).is_some_and(|tags| tags.iter().any(|tag| matches!(tag, TerrainTags::BlocksMovement | TerrainTags::Water)));
+                    *tiles.entry(tile).or_insert(false) |= blocks;
+                }
+            }
+        }
+        tiles
+    }
+    // hostile fighters and items on the floor the player can see in the rectangle
+    pub fn get_map_markers(&self, x: usize, y: usize, width: usize, height: usize) -> (Vec<[usize; 2]>, Vec<[usize; 2]>) {
+        let in_view = |tile: [usize; 2]| tile[0] >= x && tile[0] < x + width && tile[1] >= y && tile[1] < y + height && self.is_tile_visible(tile);
+        let to_tile = |px: f32, py: f32| [(px.max(0.0) as usize + 16) / 32, (py.max(0.0) as usize + 16) / 32];
+        let mut enemies = Vec::new();
+        for (entity, position) in self.components.position_components.iter().enumerate() {
+            let Some(position) = position else {
+                continue;
+            };
+            if self.components.aggro_components[entity].is_none() || self.get_faction(entity) != Faction::Hostile {
+                continue;
+            }
+            let position = position.borrow();
+            let tile = to_tile(position.x, position.y);
+            if in_view(tile) {
+                enemies.push(tile);
+            }
+        }
+        let items = self.items_on_floor.borrow().iter().map(|x| to_tile(x.x, x.y)).filter(|x| in_view(*x)).collect();
+        (enemies, items)
+    }
+}
+
+impl Camera {
+    // the minimap in the corner, or every explored tile scaled to fit the screen when the full map is open
+    pub fn render_map(&self, world: &World, index_offset: u32) -> Result<RenderData, PError> {
+        let mut render_data = RenderData::new();
+        // worlds without the sprite, like headless tests, just don't draw a map
+        let Some(sprite) = world.sprites.get_sprite_by_name("map_tile") else {
+            return Ok(render_data);
+        };
+        let draw = |render_data: &mut RenderData, x: f32, y: f32, w: f32, h: f32, color: [f32; 4]| {
+            let mut draw_data = sprite.draw_data(x, y, w.ceil() as usize, h.ceil() as usize, self.viewpoint_width, self.viewpoint_height, index_offset + render_data.vertex.len() as u32, 0, 0);
+            for vertex in draw_data.vertex.iter_mut() {
+                vertex.color = color;
+            }
+            render_data.vertex.extend(draw_data.vertex);
+            render_data.index.extend(draw_data.index);
+        };
+
+        let player = world.player_tile();
+        let (left, top, width, height, tile_size, screen_x, screen_y) = if world.minimap.full_map {
+            if world.minimap.explored.is_empty() {
+                return Ok(render_data);
+            }
+            let left = world.minimap.explored.iter().map(|x| x[0]).min().unwrap_or(0);
+            let top = world.minimap.explored.iter().map(|x| x[1]).min().unwrap_or(0);
+            let width = world.minimap.explored.iter().map(|x| x[0]).max().unwrap_or(0) - left + 1;
+            let height = world.minimap.explored.iter().map(|x| x[1]).max().unwrap_or(0) - top + 1;
+            let tile_size = ((self.viewpoint_width as f32 - MINIMAP_MARGIN * 2.0) / width as f32)
+                .min((self.viewpoint_height as f32 - MINIMAP_MARGIN * 2.0) / height as f32)
+                .min(FULL_MAP_MAX_TILE_SIZE);
+            // centered on the screen
+            let screen_x = (self.viewpoint_width as f32 - width as f32 * tile_size) / 2.0;
+            let screen_y = (self.viewpoint_height as f32 - height as f32 * tile_size) / 2.0;
+            draw(&mut render_data, 0.0, 0.0, self.viewpoint_width as f32, self.viewpoint_height as f32, BACKGROUND_COLOR);
+            (left, top, width, height, tile_size, screen_x, screen_y)
+        } else {
+            let size = MINIMAP_RADIUS * 2 + 1;
+            let screen_x = self.viewpoint_width as f32 - MINIMAP_MARGIN - size as f32 * MINIMAP_TILE_SIZE;
+            draw(&mut render_data, screen_x, MINIMAP_MARGIN, size as f32 * MINIMAP_TILE_SIZE, size as f32 * MINIMAP_TILE_SIZE, BACKGROUND_COLOR);
+            // the player stays in the middle, tiles off the left or top of the world are left empty
+            let left = player[0] as i64 - MINIMAP_RADIUS as i64;
+            let top = player[1] as i64 - MINIMAP_RADIUS as i64;
+            let screen_x = screen_x - left.min(0) as f32 * MINIMAP_TILE_SIZE;
+            let screen_y = MINIMAP_MARGIN - top.min(0) as f32 * MINIMAP_TILE_SIZE;
+            (left.max(0) as usize, top.max(0) as usize, (size as i64 + left.min(0)) as usize, (size as i64 + top.min(0)) as usize, MINIMAP_TILE_SIZE, screen_x, screen_y)
+        };
+        let to_screen = |tile: [usize; 2]| (screen_x + (tile[0] - left) as f32 * tile_size, screen_y + (tile[1] - top) as f32 * tile_size);
+
+        let mut tiles: Vec<([usize; 2], bool)> = world.get_map_tiles(left, top, width, height).into_iter().collect();
+        // walls go on top so room outlines aren't covered by the floor next to them
+        tiles.sort_by_key(|x| x.1);
+        for (tile, blocks) in tiles {
+            let (x, y) = to_screen(tile);
+            draw(&mut render_data, x, y, tile_size, tile_size, if blocks {WALL_COLOR} else {FLOOR_COLOR});
+        }
+        let (enemies, items) = world.get_map_markers(left, top, width, height);
+        for (tile, color) in items.into_iter().map(|x| (x, ITEM_COLOR)).chain(enemies.into_iter().map(|x| (x, ENEMY_COLOR))) {
+            let (x, y) = to_screen(tile);
+            draw(&mut render_data, x, y, tile_size, tile_size, color);
+        }
+        // the exit is shown once it's been seen
+        if let Some(exit) = world.cur_exit.filter(|x| world.is_explored(*x) && x[0] >= left && x[0] < left + width && x[1] >= top && x[1] < top + height) {
+            let (x, y) = to_screen(exit);
+            draw(&mut render_data, x - tile_size * 0.5, y - tile_size * 0.5, tile_size * 2.0, tile_size * 2.0, if world.is_exit_locked() {LOCKED_EXIT_COLOR} else {EXIT_COLOR});
+        }
+        if player[0] >= left && player[0] < left + width && player[1] >= top && player[1] < top + height {
+            let (x, y) = to_screen(player);
+            draw(&mut render_data, x, y, tile_size, tile_size, PLAYER_COLOR);
+        }
+        Ok(render_data)
+    }
+}
@@ -2,6 +2,7 @@ pub mod world;
 pub mod camera;
 pub mod entities;
 pub mod terrain;
+pub mod autotile;
 pub mod loot;
 pub mod item;
 pub mod stat;
@@ -12,6 +13,10 @@ pub mod json_parsing;
 pub mod starting_level_generator;
 pub mod pathfinding;
 pub mod level_editor;
+pub mod room_format;
+pub mod room_objectives;
+pub mod room_generation;
+pub mod tiled_import;
 pub mod inventory;
 pub mod game;
 pub mod entity_components;
@@ -21,3 +26,18 @@ pub mod entity_attacks;
 pub mod items_on_floor;
 pub mod player_abilities;
 pub mod components;
+pub mod crafting;
+pub mod vendor;
+pub mod player_defense;
+pub mod ranged_weapons;
+pub mod melee_combos;
+pub mod progression;
+pub mod biomes;
+pub mod ability_unlocks;
+pub mod factions;
+pub mod interactables;
+pub mod minimap;
+pub mod visibility;
+pub mod lighting;
+pub mod particles;
+pub mod animation;
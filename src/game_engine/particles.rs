@@ -0,0 +1,239 @@
+use std::cell::RefCell;
+use std::f32::consts::PI;
+
+use compact_str::CompactString;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::error::PError;
+use crate::rendering_engine::abstractions::{RenderData, SpriteContainer};
+use crate::world::World;
+use crate::{perror, punwrap};
+
+use super::camera::Camera;
+
+// emitters gameplay spawns by name, a trigger with no emitter of that name in particles.json shows nothing
+pub const HIT_EMITTER: &str = "hit";
+pub const DEATH_EMITTER: &str = "death";
+pub const CAST_EMITTER: &str = "cast";
+pub const POISON_EMITTER: &str = "poison";
+pub const BURN_EMITTER: &str = "burn";
+pub const MAX_PARTICLES: usize = 2048; // new particles are dropped past this
+
+// How an effect looks, times are in frames, speeds in pixels per frame and angles in degrees
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ParticleEmitter {
+    pub name: CompactString,
+    pub sprite: CompactString,
+    #[serde(default)]
+    pub burst: usize, // particles spawned as soon as the emitter starts
+    #[serde(default)]
+    pub rate: f32, // particles spawned every frame after that, fractions carry over to the next frame
+    #[serde(default)]
+    pub duration: f32, // frames the emitter keeps spawning at its rate
+    #[serde(default = "default_lifetime")]
+    pub lifetime: f32,
+    #[serde(default)]
+    pub lifetime_spread: f32, // each particle lives up to this much more or less
+    #[serde(default)]
+    pub speed: f32,
+    #[serde(default)]
+    pub speed_spread: f32,
+    #[serde(default)]
+    pub direction: f32, // 0 is right and 90 is down
+    #[serde(default = "default_direction_spread")]
+    pub direction_spread: f32, // particles head up to this far either side of the direction
+    #[serde(default)]
+    pub gravity: f32, // added to the downwards speed every frame
+    #[serde(default = "default_color")]
+    pub start_color: [f32; 4],
+    #[serde(default = "default_color")]
+    pub end_color: [f32; 4],
+    #[serde(default = "default_size")]
+    pub start_size: f32,
+    #[serde(default = "default_size")]
+    pub end_size: f32,
+}
+
+fn default_lifetime() -> f32 {
+    30.0
+}
+
+fn default_direction_spread() -> f32 {
+    180.0
+}
+
+fn default_color() -> [f32; 4] {
+    [1.0, 1.0, 1.0, 1.0]
+}
+
+fn default_size() -> f32 {
+    6.0
+}
+
+impl ParticleEmitter {
+    pub fn validate(&self, sprites: &SpriteContainer) -> Result<(), PError> {
+        let name = &self.name;
+        if sprites.get_sprite_id(&self.sprite).is_none() {
+            return Err(perror!(JSONValidationError, "Particle emitter: {} uses sprite {} which doesn't exist", name, self.sprite));
+        }
+        if !(self.lifetime > 0.0 && self.lifetime.is_finite()) {
+            return Err(perror!(JSONValidationError, "Particle emitter: {} has lifetime {}, it should be more than 0", name, self.lifetime));
+        }
+        if !(self.lifetime_spread >= 0.0 && self.lifetime_spread < self.lifetime) {
+            return Err(perror!(JSONValidationError, "Particle emitter: {} has lifetime spread {}, it should be 0 or more and less than its lifetime", name, self.lifetime_spread));
+        }
+        for (field, value) in [("rate", self.rate), ("duration", self.duration), ("speed spread", self.speed_spread), ("direction spread", self.direction_spread), ("start size", self.start_size), ("end size", self.end_size)] {
+            if !(value >= 0.0 && value.is_finite()) {
+                return Err(perror!(JSONValidationError, "Particle emitter: {} has {} {}, it should be 0 or more", name, field, value));
+            }
+        }
+        if self.burst == 0 && (self.rate == 0.0 || self.duration == 0.0) {
+            return Err(perror!(JSONValidationError, "Particle emitter: {} never spawns anything, it needs a burst or a rate and duration", name));
+        }
+        for color in [self.start_color, self.end_color] {
+            if color.iter().any(|x| !(0.0..=1.0).contains(x)) {
+                return Err(perror!(JSONValidationError, "Particle emitter: {} has color {:?}, every channel should be between 0 and 1", name, color));
+            }
+        }
+        Ok(())
+    }
+    fn spawn(&self, emitter: usize, x: f32, y: f32, particles: &mut Vec<Particle>) {
+        if particles.len() >= MAX_PARTICLES {
+            return;
+        }
+        let mut rng = rand::thread_rng();
+        let angle = (self.direction + rng.gen_range(-1.0..=1.0) * self.direction_spread) * PI / 180.0;
+        let speed = (self.speed + rng.gen_range(-1.0..=1.0) * self.speed_spread).max(0.0);
+        particles.push(Particle {
+            emitter,
+            x,
+            y,
+            vx: angle.cos() * speed,
+            vy: angle.sin() * speed,
+            age: 0.0,
+            lifetime: self.lifetime + rng.gen_range(-1.0..=1.0) * self.lifetime_spread,
+        });
+    }
+}
+
+// A single particle, its position is its center in pixels
+#[derive(Debug, Clone, Copy)]
+pub struct Particle {
+    pub emitter: usize,
+    pub x: f32,
+    pub y: f32,
+    pub vx: f32,
+    pub vy: f32,
+    pub age: f32,
+    pub lifetime: f32,
+}
+
+impl Particle {
+    pub fn progress(&self) -> f32 {
+        (self.age / self.lifetime).clamp(0.0, 1.0)
+    }
+}
+
+// An emitter that's still spawning particles at its rate
+#[derive(Debug, Clone, Copy)]
+pub struct ActiveEmitter {
+    pub emitter: usize,
+    pub x: f32,
+    pub y: f32,
+    pub age: f32,
+    pub owed: f32, // particles that are due but haven't been spawned yet
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ParticleSystem {
+    pub emitters: Vec<ParticleEmitter>,
+    pub particles: RefCell<Vec<Particle>>,
+    pub active: RefCell<Vec<ActiveEmitter>>,
+}
+
+impl ParticleSystem {
+    pub fn get_emitter_id(&self, name: &str) -> Option<usize> {
+        self.emitters.iter().position(|x| x.name == name)
+    }
+}
+
+fn lerp(a: f32, b: f32, t: f32) -> f32 {
+    a + (b - a) * t
+}
+
+impl World {
+    // starts the emitter with the name at the position, gameplay calls this for hits, deaths, casts and status ticks
+    pub fn spawn_particles(&self, name: &str, x: f32, y: f32) {
+        let Some(emitter_id) = self.particles.get_emitter_id(name) else {
+            return;
+        };
+        let emitter = &self.particles.emitters[emitter_id];
+        let mut particles = self.particles.particles.borrow_mut();
+        for _ in 0..emitter.burst {
+            emitter.spawn(emitter_id, x, y, &mut particles);
+        }
+        if emitter.rate > 0.0 && emitter.duration > 0.0 {
+            self.particles.active.borrow_mut().push(ActiveEmitter {emitter: emitter_id, x, y, age: 0.0, owed: 0.0});
+        }
+    }
+    pub fn spawn_player_particles(&self, name: &str) {
+        let (x, y) = {
+            let player = self.player.borrow();
+            (player.x + 16.0, player.y + 16.0)
+        };
+        self.spawn_particles(name, x, y);
+    }
+    pub fn update_particles(&mut self) {
+        let emitters = &self.particles.emitters;
+        let mut particles = self.particles.particles.borrow_mut();
+        self.particles.active.borrow_mut().retain_mut(|active| {
+            let emitter = &emitters[active.emitter];
+            active.owed += emitter.rate;
+            while active.owed >= 1.0 {
+                emitter.spawn(active.emitter, active.x, active.y, &mut particles);
+                active.owed -= 1.0;
+            }
+            active.age += 1.0;
+            active.age < emitter.duration
+        });
+        particles.retain_mut(|particle| {
+            particle.vy += emitters[particle.emitter].gravity;
+            particle.x += particle.vx;
+            particle.y += particle.vy;
+            particle.age += 1.0;
+            particle.age < particle.lifetime
+        });
+    }
+}
+
+impl Camera {
+    // every particle on screen, colored and sized for how far through its life it is
+    pub fn render_particles(&self, world: &World, fog_of_war: bool, index_offset: u32) -> Result<RenderData, PError> {
+        let mut render_data = RenderData::new();
+        for particle in world.particles.particles.borrow().iter() {
+            let emitter = punwrap!(world.particles.emitters.get(particle.emitter), Expected, "particle refers to emitter with id {} but there is no emitter with id {}", particle.emitter, particle.emitter);
+            let t = particle.progress();
+            let size = lerp(emitter.start_size, emitter.end_size, t);
+            let (x, y) = (particle.x - size / 2.0, particle.y - size / 2.0);
+            if size <= 0.0 || x + size < self.camera_x || y + size < self.camera_y || x > self.camera_x + self.viewpoint_width as f32 || y > self.camera_y + self.viewpoint_height as f32 {
+                continue;
+            }
+            if fog_of_war && !world.is_position_visible(particle.x - 16.0, particle.y - 16.0) {
+                continue;
+            }
+            let sprite = punwrap!(world.sprites.get_sprite_by_name(&emitter.sprite), Expected, "particle emitter {} refers to a non-existent sprite {}", emitter.name, emitter.sprite);
+            let mut draw_data = sprite.draw_data(x, y, size.ceil() as usize, size.ceil() as usize, self.viewpoint_width, self.viewpoint_height, index_offset + render_data.vertex.len() as u32, -self.camera_x.floor() as i32, -self.camera_y.floor() as i32);
+            let mut color = emitter.start_color;
+            for (channel, end) in color.iter_mut().zip(emitter.end_color) {
+                *channel = lerp(*channel, end, t);
+            }
+            for vertex in draw_data.vertex.iter_mut() {
+                vertex.color = color;
+            }
+            render_data.vertex.extend(draw_data.vertex);
+            render_data.index.extend(draw_data.index);
+        }
+        Ok(render_data)
+    }
+}
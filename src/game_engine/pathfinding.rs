@@ -1,4 +1,4 @@
-use std::collections::{BinaryHeap, HashMap};
+use std::collections::{BinaryHeap, HashMap, VecDeque};
 use std::cmp::Ordering;
 use crate::error::PError;
 use crate::ptry;
@@ -46,27 +46,26 @@ impl Ord for PathfindingNode {
     }
 }
 
-pub fn pathfind_by_block(position_component: &PositionComponent, collision_component: CollisionBox, entity_id: usize, world: &World) -> Result<EntityDirectionOptions, PError> {
-    let player = world.player.borrow();
+// goal is the position the entity is trying to reach
+pub fn pathfind_by_block(position_component: &PositionComponent, collision_component: CollisionBox, entity_id: usize, world: &World, goal: [f32; 2]) -> Result<EntityDirectionOptions, PError> {
     let ex = position_component.x + collision_component.x_offset;
     let ey = position_component.y + collision_component.y_offset;
     let ew = collision_component.w;
     let eh = collision_component.h;
-    let px = player.x + player.collision_box.x_offset;
-    let py = player.y + player.collision_box.y_offset;
+    let [px, py] = goal;
 
-    let (player_x, player_y) = ((px.floor() / 32.0).floor() as usize, (py.floor() / 32.0).floor() as usize);
+    let (goal_x, goal_y) = ((px.floor() / 32.0).floor() as usize, (py.floor() / 32.0).floor() as usize);
     let (entity_x, entity_y) = ((ex / 32.0).floor() as usize, (ey / 32.0).floor() as usize);
     let entity_x_offset = ex - entity_x as f32 * 32.0;
     let entity_y_offset = ey - entity_y as f32 * 32.0;
 
-    let distance = ((player_x as isize - entity_x as isize).abs() + (player_y as isize - entity_y as isize).abs()) as f32;
+    let distance = ((goal_x as isize - entity_x as isize).abs() + (goal_y as isize - entity_y as isize).abs()) as f32;
 
     let mut nodes: HashMap<[usize; 2], PathfindingNode> = HashMap::new();
     let mut open_set = BinaryHeap::new();
     let mut closed_set: HashMap<[usize; 2], bool> = HashMap::new();
 
-    let start_node = PathfindingNode::new(entity_x, entity_y, None, player_x, player_y, 0);
+    let start_node = PathfindingNode::new(entity_x, entity_y, None, goal_x, goal_y, 0);
     let start_node_clone = start_node.clone();
     open_set.push(start_node.clone());
     nodes.insert([entity_x, entity_y], start_node);
@@ -75,7 +74,7 @@ pub fn pathfind_by_block(position_component: &PositionComponent, collision_compo
 
 
     while let Some(current) = open_set.pop() {
-        let distance_from_goal = f32::sqrt((player_x as f32 - current.x as f32).powf(2.0) + (player_y as f32 - current.y as f32).powf(2.0));
+        let distance_from_goal = f32::sqrt((goal_x as f32 - current.x as f32).powf(2.0) + (goal_y as f32 - current.y as f32).powf(2.0));
 
         if distance_from_goal < 2.0 {
             let mut path = Vec::new();
@@ -106,7 +105,7 @@ pub fn pathfind_by_block(position_component: &PositionComponent, collision_compo
             let nx = current.x.wrapping_add_signed(dx);
             let ny = current.y.wrapping_add_signed(dy);
 
-            let distance_from_goal = ((player_x as isize - nx as isize).abs() + (player_y as isize - ny as isize).abs()) as f32;
+            let distance_from_goal = ((goal_x as isize - nx as isize).abs() + (goal_y as isize - ny as isize).abs()) as f32;
             if distance_from_goal > distance + 10.0 {
                 continue;
             }
@@ -121,7 +120,7 @@ pub fn pathfind_by_block(position_component: &PositionComponent, collision_compo
                 continue;
             }
 
-            let neighbor = PathfindingNode::new(nx, ny, Some([current.x, current.y]), player_x, player_y, current.depth + 1);
+            let neighbor = PathfindingNode::new(nx, ny, Some([current.x, current.y]), goal_x, goal_y, current.depth + 1);
 
             if let Some(existing) = nodes.get(&[nx, ny]) {
                 if neighbor.f < existing.f {
@@ -137,31 +136,30 @@ pub fn pathfind_by_block(position_component: &PositionComponent, collision_compo
 
     Ok(EntityDirectionOptions::None)
 }
-pub fn pathfind_high_granularity(position_component: &PositionComponent, collision_component: CollisionBox, entity_id: usize, world: &World) -> Result<EntityDirectionOptions, PError> {
-    let player = world.player.borrow();
+// goal is the position the entity is trying to reach
+pub fn pathfind_high_granularity(position_component: &PositionComponent, collision_component: CollisionBox, entity_id: usize, world: &World, goal: [f32; 2]) -> Result<EntityDirectionOptions, PError> {
 
     let ex = position_component.x + collision_component.x_offset;
     let ey = position_component.y + collision_component.y_offset;
     let ew = collision_component.w;
     let eh = collision_component.h;
 
-    let px = player.x + player.collision_box.x_offset;
-    let py = player.y + player.collision_box.y_offset;
+    let [px, py] = goal;
 
 
-    let (player_x, player_y) = ((px.floor() / 4.0).floor() as usize, (py.floor() / 4.0).floor() as usize);
+    let (goal_x, goal_y) = ((px.floor() / 4.0).floor() as usize, (py.floor() / 4.0).floor() as usize);
     let (entity_x, entity_y) = ((ex / 4.0).floor() as usize, (ey / 4.0).floor() as usize);
     let entity_x_offset = ex - entity_x as f32 * 4.0;
     let entity_y_offset = ey - entity_y as f32 * 4.0;
 
 
-    let distance = ((player_x as isize - entity_x as isize).abs() + (player_y as isize - entity_y as isize).abs()) as f32;
+    let distance = ((goal_x as isize - entity_x as isize).abs() + (goal_y as isize - entity_y as isize).abs()) as f32;
 
     let mut nodes: HashMap<[usize; 2], PathfindingNode> = HashMap::new();
     let mut open_set = BinaryHeap::new();
     let mut closed_set: HashMap<[usize; 2], bool> = HashMap::new();
 
-    let start_node = PathfindingNode::new(entity_x, entity_y, None, player_x, player_y, 0);
+    let start_node = PathfindingNode::new(entity_x, entity_y, None, goal_x, goal_y, 0);
     let start_node_clone = start_node.clone();
     open_set.push(start_node.clone());
     nodes.insert([entity_x, entity_y], start_node);
@@ -169,7 +167,7 @@ pub fn pathfind_high_granularity(position_component: &PositionComponent, collisi
     let directions = [(0, 1), (0, -1), (1, 0), (-1, 0)];
 
     while let Some(current) = open_set.pop() {
-        let cur_distance_from_goal = ((player_x as isize - current.x as isize).abs() + (player_y as isize - current.y as isize).abs()) as f32;
+        let cur_distance_from_goal = ((goal_x as isize - current.x as isize).abs() + (goal_y as isize - current.y as isize).abs()) as f32;
         if cur_distance_from_goal < 12.0 {
             let mut path = Vec::new();
             let mut node = current;
@@ -205,7 +203,7 @@ pub fn pathfind_high_granularity(position_component: &PositionComponent, collisi
             }
 
 
-            let distance_from_goal = ((player_x as isize - nx as isize).abs() + (player_y as isize - ny as isize).abs()) as f32;
+            let distance_from_goal = ((goal_x as isize - nx as isize).abs() + (goal_y as isize - ny as isize).abs()) as f32;
             
             if distance_from_goal > distance + 10.0 {
                 continue;
@@ -219,7 +217,7 @@ pub fn pathfind_high_granularity(position_component: &PositionComponent, collisi
                 continue;
             }
 
-            let neighbor = PathfindingNode::new(nx, ny, Some([current.x, current.y]), player_x, player_y, current.depth + 1);
+            let neighbor = PathfindingNode::new(nx, ny, Some([current.x, current.y]), goal_x, goal_y, current.depth + 1);
 
             if let Some(existing) = nodes.get(&[nx, ny]) {
                 if neighbor.f < existing.f {
@@ -236,3 +234,27 @@ pub fn pathfind_high_granularity(position_component: &PositionComponent, collisi
 
     Ok(EntityDirectionOptions::None)
 }
+
+// flood fills the block grid from start using the same moves as pathfind_by_block, result is indexed [y][x]
+pub fn reachable_blocks(start: [usize; 2], width: usize, height: usize, walkable: &[Vec<bool>]) -> Vec<Vec<bool>> {
+    let mut reached = vec![vec![false; width]; height];
+    if start[0] >= width || start[1] >= height || !walkable[start[1]][start[0]] {
+        return reached;
+    }
+    let directions = [(0, 1), (0, -1), (1, 0), (-1, 0)];
+    let mut queue = VecDeque::new();
+    reached[start[1]][start[0]] = true;
+    queue.push_back(start);
+    while let Some([x, y]) = queue.pop_front() {
+        for &(dx, dy) in &directions {
+            let nx = x.wrapping_add_signed(dx);
+            let ny = y.wrapping_add_signed(dy);
+            if nx >= width || ny >= height || reached[ny][nx] || !walkable[ny][nx] {
+                continue;
+            }
+            reached[ny][nx] = true;
+            queue.push_back([nx, ny]);
+        }
+    }
+    reached
+}
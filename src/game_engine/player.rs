@@ -1,7 +1,11 @@
+use compact_str::CompactString;
+use rustc_hash::FxHashMap;
+
 use crate::rendering_engine::abstractions::RenderData;
 use crate::error::PError;
 use crate::punwrap;
 
+use super::animation::{AnimationPlayer, BURNING_CLIP, PLAYER_ANIMATION};
 use super::entity_components::{CollisionBox, Fire, Poison};
 use super::world::World;
 #[derive(Clone, Debug, PartialEq, Copy)]
@@ -16,6 +20,22 @@ pub enum PlayerDir {
     Right
 }
 
+impl PlayerDir {
+    // normalized vector pointing in this direction
+    pub fn to_vector(&self) -> [f32; 2] {
+        match self {
+            PlayerDir::Up => [0.0, -1.0],
+            PlayerDir::Down => [0.0, 1.0],
+            PlayerDir::Left => [-1.0, 0.0],
+            PlayerDir::Right => [1.0, 0.0],
+            PlayerDir::UpLeft => [-0.70710677, -0.70710677],
+            PlayerDir::UpRight => [0.70710677, -0.70710677],
+            PlayerDir::DownLeft => [-0.70710677, 0.70710677],
+            PlayerDir::DownRight => [0.70710677, 0.70710677],
+        }
+    }
+}
+
 #[derive(Clone, Debug, PartialEq)]
 pub enum PlayerState {
     Idle,
@@ -23,16 +43,14 @@ pub enum PlayerState {
     AttackingRanged,
     AttackingMelee,
     ChargingAbility,
-    EndingAbility
+    EndingAbility,
+    Blocking,
+    Rolling
 }
 
-pub const EXP_REQS: [f32; 3] = [
-    100.0, // exp required to level from 0 to 1
-    150.0, // exp required to level from 1 to 2
-    200.0, // ...
-];
-pub const MAX_LEVEL: usize = 2;
 pub const TICKS_PER_REGEN_TICK: usize = 60;
+pub const PLAYER_IFRAMES: f32 = 30.0; // frames of invulnerability after being hit
+pub const DASH_IFRAMES: f32 = 4.0; // kept topped up while dashing, so the player stays invulnerable a little after
 
 #[derive(Clone, Debug)]
 pub struct Player {
@@ -50,8 +68,23 @@ pub struct Player {
     pub level: usize,
     pub fire: Vec<Fire>,
     pub poison: Vec<Poison>,
-    pub anim_frame: usize, // 0-119
+    pub animation: AnimationPlayer,
     pub time_since_regen_tick: usize,
+    pub knockback: [f32; 2],
+    pub velocity: [f32; 2], // movement last frame, carried over while on ice
+    pub iframes: f32,
+    pub roll_time_left: f32,
+    pub roll_cooldown: f32,
+    pub roll_direction: [f32; 2],
+    pub block_time: f32, // frames since the player started blocking
+    pub ranged_charge: f32, // frames the current ranged shot has been charged for
+    pub combo_step: usize, // swing of the held weapon's combo that was last used
+    pub combo_window: f32, // frames left to continue the combo
+    pub combo_archetype: Option<CompactString>, // weapon the combo was started with, switching weapons starts over
+    pub stat_points: usize,
+    pub skill_points: usize,
+    pub allocated_stats: FxHashMap<CompactString, usize>, // stat name to points put into it
+    pub unlocked_skills: Vec<CompactString>,
 }
 impl Player {
     pub fn new(x: f32, y: f32, health: f32, max_health: i32, movement_speed: f32, sprite_id: usize) -> Self {
@@ -74,8 +107,23 @@ impl Player {
             level: 0,
             fire: vec![],
             poison: vec![],
-            anim_frame: 0,
-            time_since_regen_tick: 0
+            animation: AnimationPlayer::new(PLAYER_ANIMATION.into()),
+            time_since_regen_tick: 0,
+            knockback: [0.0, 0.0],
+            velocity: [0.0, 0.0],
+            iframes: 0.0,
+            roll_time_left: 0.0,
+            roll_cooldown: 0.0,
+            roll_direction: [0.0, 0.0],
+            block_time: 0.0,
+            ranged_charge: 0.0,
+            combo_step: 0,
+            combo_window: 0.0,
+            combo_archetype: None,
+            stat_points: 0,
+            skill_points: 0,
+            allocated_stats: FxHashMap::default(),
+            unlocked_skills: Vec::new()
         }
     }
     pub fn get_held_item_position(&self) -> (f32, f32) {
@@ -95,19 +143,14 @@ impl Player {
         }
     }
 
-    pub fn add_exp(&mut self, exp: f32) {
-        self.exp += exp; 
-        while self.level < MAX_LEVEL && self.exp > EXP_REQS[self.level] {
-            self.exp -= EXP_REQS[self.level];
-            self.level += 1;
-        }
-    }
 
 
 
     pub fn draw_data(&self, world: &World, window_size_width: usize, window_size_height: usize, index_offset:u32, vertex_offset_x: i32, vertex_offset_y: i32) -> Result<RenderData, PError>{
         let mut d = RenderData::new();
         let held_item_pos = self.get_held_item_position();
+        // the plain sprite for the direction is drawn when there's no clip for what the player is doing
+        let sprite_id = self.animation.sprite(&world.sprites).unwrap_or(self.sprite_id);
         if self.direction == PlayerDir::Up {
             if let Some(holding_sprite) = self.holding_texture_sprite{
                 let sprite = punwrap!(world.sprites.get_sprite(holding_sprite), Expected, "held item sprite doesnt exist");
@@ -115,12 +158,12 @@ impl Player {
                 d.vertex.extend(s.vertex);
                 d.index.extend(s.index);
             }
-            let sprite = punwrap!(world.sprites.get_sprite(self.sprite_id), Expected, "player sprite doesn't exist");
+            let sprite = punwrap!(world.sprites.get_sprite(sprite_id), Expected, "player sprite doesn't exist");
             let dd = sprite.draw_data(self.x.floor(), self.y.floor(), 38, 52,window_size_width, window_size_height, index_offset + d.vertex.len() as u32, vertex_offset_x, vertex_offset_y);
             d.vertex.extend(dd.vertex);
             d.index.extend(dd.index);
         }else {
-            let sprite = punwrap!(world.sprites.get_sprite(self.sprite_id), Expected, "player sprite doesn't exist");
+            let sprite = punwrap!(world.sprites.get_sprite(sprite_id), Expected, "player sprite doesn't exist");
             let dd = sprite.draw_data(self.x.floor(), self.y.floor(), 38, 52,window_size_width, window_size_height, index_offset + d.vertex.len() as u32, vertex_offset_x, vertex_offset_y);
             d.vertex.extend(dd.vertex);
             d.index.extend(dd.index);
@@ -131,12 +174,9 @@ impl Player {
                 d.index.extend(s.index);
             }
         }
-        if !self.fire.is_empty() {
-            let sprite = if self.anim_frame < 60 {
-               punwrap!(world.sprites.get_sprite_by_name("fire1"), MissingExpectedGlobalSprite, "no fire1 sprite?") 
-            }else {
-               punwrap!(world.sprites.get_sprite_by_name("fire2"), MissingExpectedGlobalSprite, "no fire2 sprite?")
-            };
+        if let Some(fire) = self.fire.first() {
+            let burning = punwrap!(world.sprites.get_animation(BURNING_CLIP), MissingExpectedGlobalSprite, "no burning animation?");
+            let sprite = punwrap!(world.sprites.get_sprite(burning.sprite_at(fire.time_alive as usize)), Expected, "burning animation refers to a non-existent sprite");
             let dd = sprite.draw_data(self.x.floor(), self.y.floor(), 38, 52,window_size_width, window_size_height, index_offset + d.vertex.len() as u32, vertex_offset_x, vertex_offset_y);
             d.vertex.extend(dd.vertex);
             d.index.extend(dd.index);
@@ -13,9 +13,11 @@ use crate::ptry;
 use crate::punwrap;
 use crate::game_engine::player::PlayerState;
 
+use super::ability_unlocks::AbilityUpgrade;
+use super::factions::SummonDescriptor;
 use super::item::ItemType;
 use super::player::PlayerDir;
-use super::stat::StatList;
+use super::stat::{DamageType, StatList};
 
 
 pub struct PlayerAbilityActionDescriptor {
@@ -39,7 +41,8 @@ pub struct PlayerAbilityDescriptor {
     pub time_to_charge: f32, 
     pub end_time: f32,
     pub actions: PlayerAbilityActionDescriptor,
-    pub usable_with: UsableWith 
+    pub usable_with: UsableWith,
+    pub upgrades: Vec<AbilityUpgrade>, // upgrades[n] takes the ability from rank n to n + 1
 }
 
 
@@ -67,15 +70,43 @@ impl PlayerAbilityDescriptor {
             descriptor_id: descriptor_id_of_this_descriptor,
             end_without_end_action: false,
             on_start_state: None,
-            on_end_start_state: None
+            on_end_start_state: None,
+            rank: 0
+        }
+    }
+    pub fn max_rank(&self) -> usize {
+        self.upgrades.len()
+    }
+    pub fn get_base_stats(&self, rank: usize) -> StatList {
+        let mut stats = self.base_stats.clone();
+        for upgrade in self.upgrades.iter().take(rank) {
+            stats.to_sum_with(&upgrade.stats);
+        }
+        stats
+    }
+    pub fn get_cooldown(&self, rank: usize) -> f32 {
+        self.upgrades.iter().take(rank).fold(self.cooldown, |cooldown, x| cooldown * x.cooldown_multiplier)
+    }
+    pub fn get_mana_cost(&self, rank: usize) -> f32 {
+        self.upgrades.iter().take(rank).fold(self.mana_cost, |cost, x| cost * x.mana_cost_multiplier)
+    }
+    pub fn get_mana_cost_while_charging(&self, rank: usize) -> f32 {
+        self.upgrades.iter().take(rank).fold(self.mana_cost_while_charging, |cost, x| cost * x.mana_cost_multiplier)
+    }
+    pub fn get_title(&self, rank: usize) -> String {
+        if rank > 0 {
+            format!("{} +{}", self.name, rank)
+        } else {
+            self.name.to_string()
         }
     }
     pub fn setup_player_ability(&self, ability: &mut PlayerAbility, stats: &StatList) {
-        let mut s = self.base_stats.clone();
+        let base_stats = self.get_base_stats(ability.rank);
+        let mut s = base_stats.clone();
         
         s.to_sum_with(stats);
         
-        let base_damage = self.base_stats.damage.map(|x| x.flat).unwrap_or(0.0);
+        let base_damage = base_stats.damage.map(|x| x.flat).unwrap_or(0.0);
         let added_damage = stats.damage.map(|x| x.flat).unwrap_or(0.0);
         let percent_damage = s.damage.map(|x| x.percent).unwrap_or(0.0);
         s.damage = Some(
@@ -86,7 +117,7 @@ impl PlayerAbilityDescriptor {
             }
         );
 
-        ability.adjusted_cooldown = self.cooldown / (s.cooldown_regen.map(|x| x.get_value()).unwrap_or(0.0) + 1.0);
+        ability.adjusted_cooldown = self.get_cooldown(ability.rank) / (s.cooldown_regen.map(|x| x.get_value()).unwrap_or(0.0) + 1.0);
         ability.adjusted_time_to_charge = self.time_to_charge / (s.charge_time_reduction.map(|x| x.get_value()).unwrap_or(0.0) + 1.0);
         ability.end_time_left = self.end_time;
         ability.time_to_charge_left = ability.adjusted_time_to_charge;
@@ -106,7 +137,8 @@ pub struct PlayerAbility {
     pub descriptor_id: usize,
     pub end_without_end_action: bool,
     pub on_start_state: Option<AbilityStateInformation>,
-    pub on_end_start_state: Option<AbilityStateInformation>
+    pub on_end_start_state: Option<AbilityStateInformation>,
+    pub rank: usize, // number of upgrades applied, see PlayerAbilityDescriptor::upgrades
 }
 
 #[derive(Debug, Clone)]
@@ -122,9 +154,21 @@ pub enum PlayerAbilityDescriptorName {
     Dash,
     LightningTrap,
     LightningBolts,
-    SlimeBall
+    SlimeBall,
+    RaiseDead
 }
 
+// every ability the player can have, an ability's descriptor id is its index in this
+pub fn get_player_ability_descriptors() -> Vec<PlayerAbilityDescriptor> {
+    vec![
+        get_ability_descriptor(PlayerAbilityDescriptorName::Cyclone),
+        get_ability_descriptor(PlayerAbilityDescriptorName::Dash),
+        get_ability_descriptor(PlayerAbilityDescriptorName::LightningTrap),
+        get_ability_descriptor(PlayerAbilityDescriptorName::LightningBolts),
+        get_ability_descriptor(PlayerAbilityDescriptorName::SlimeBall),
+        get_ability_descriptor(PlayerAbilityDescriptorName::RaiseDead),
+    ]
+}
 
 pub fn get_ability_descriptor(name: PlayerAbilityDescriptorName) -> PlayerAbilityDescriptor {
     match name {
@@ -149,7 +193,22 @@ pub fn get_ability_descriptor(name: PlayerAbilityDescriptorName) -> PlayerAbilit
                     usable_with_nothing: false,
                 },
                 mana_cost: 1.0,
-                mana_cost_while_charging: 0.2
+                mana_cost_while_charging: 0.2,
+                upgrades: vec![
+                    AbilityUpgrade {
+                        stats: create_stat_list!(damage => StatC { flat: 1.2, percent: 0.0}),
+                        cooldown_multiplier: 1.0,
+                        mana_cost_multiplier: 0.85
+                    },
+                    AbilityUpgrade {
+                        stats: create_stat_list!(
+                            width => StatC { flat: 20.0, percent: 0.0},
+                            reach => StatC { flat: 20.0, percent: 0.0}
+                        ),
+                        cooldown_multiplier: 1.0,
+                        mana_cost_multiplier: 0.85
+                    }
+                ]
             }
         },
         PlayerAbilityDescriptorName::Dash => {
@@ -176,7 +235,19 @@ pub fn get_ability_descriptor(name: PlayerAbilityDescriptorName) -> PlayerAbilit
                     usable_with_nothing: true,
                 },
                 mana_cost: 8.0,
-                mana_cost_while_charging: 0.0
+                mana_cost_while_charging: 0.0,
+                upgrades: vec![
+                    AbilityUpgrade {
+                        stats: StatList::default(),
+                        cooldown_multiplier: 0.8,
+                        mana_cost_multiplier: 0.75
+                    },
+                    AbilityUpgrade {
+                        stats: StatList::default(),
+                        cooldown_multiplier: 0.7,
+                        mana_cost_multiplier: 0.75
+                    }
+                ]
             }
         }
         PlayerAbilityDescriptorName::LightningTrap => {
@@ -207,7 +278,19 @@ pub fn get_ability_descriptor(name: PlayerAbilityDescriptorName) -> PlayerAbilit
                     usable_with_nothing: true,
                 },
                 mana_cost: 9.0,
-                mana_cost_while_charging: 0.0
+                mana_cost_while_charging: 0.0,
+                upgrades: vec![
+                    AbilityUpgrade {
+                        stats: create_stat_list!(damage => StatC { flat: 4.0, percent: 0.0}),
+                        cooldown_multiplier: 0.9,
+                        mana_cost_multiplier: 1.0
+                    },
+                    AbilityUpgrade {
+                        stats: create_stat_list!(lifetime => StatC { flat: 100.0, percent: 0.0}),
+                        cooldown_multiplier: 0.9,
+                        mana_cost_multiplier: 0.9
+                    }
+                ]
             }
         },
         PlayerAbilityDescriptorName::LightningBolts => {
@@ -239,7 +322,24 @@ pub fn get_ability_descriptor(name: PlayerAbilityDescriptorName) -> PlayerAbilit
                     usable_with_nothing: true,
                 },
                 mana_cost: 10.0,
-                mana_cost_while_charging: 0.0
+                mana_cost_while_charging: 0.0,
+                upgrades: vec![
+                    AbilityUpgrade {
+                        stats: create_stat_list!(shots => StatC { flat: 1.0, percent: 0.0}),
+                        cooldown_multiplier: 1.0,
+                        mana_cost_multiplier: 1.0
+                    },
+                    AbilityUpgrade {
+                        stats: create_stat_list!(damage => StatC { flat: 5.0, percent: 0.0}),
+                        cooldown_multiplier: 0.85,
+                        mana_cost_multiplier: 0.9
+                    },
+                    AbilityUpgrade {
+                        stats: create_stat_list!(shots => StatC { flat: 2.0, percent: 0.0}),
+                        cooldown_multiplier: 1.0,
+                        mana_cost_multiplier: 1.1
+                    }
+                ]
             }
         },
         PlayerAbilityDescriptorName::SlimeBall => {
@@ -270,7 +370,56 @@ pub fn get_ability_descriptor(name: PlayerAbilityDescriptorName) -> PlayerAbilit
                     usable_with_nothing: true,
                 },
                 mana_cost: 19.0,
-                mana_cost_while_charging: 0.0
+                mana_cost_while_charging: 0.0,
+                upgrades: vec![
+                    AbilityUpgrade {
+                        stats: create_stat_list!(damage => StatC { flat: 20.0, percent: 0.0}),
+                        cooldown_multiplier: 0.85,
+                        mana_cost_multiplier: 1.0
+                    },
+                    AbilityUpgrade {
+                        stats: create_stat_list!(size => StatC { flat: 30.0, percent: 0.0}),
+                        cooldown_multiplier: 0.75,
+                        mana_cost_multiplier: 0.9
+                    }
+                ]
+            }
+        },
+        PlayerAbilityDescriptorName::RaiseDead => {
+            PlayerAbilityDescriptor {
+                base_stats: create_stat_list!(
+                                shots => StatC {flat: 2.0, percent: 0.0},
+                                lifetime => StatC { flat: 1200.0, percent: 0.0}
+                            ),
+                flat_added_damage_effectiveness: 0.0,
+                name: CompactString::from("Raise Dead"),
+                description: String::from("Raise Dead\n------------\nRaises ghosts around you that fight for you until they fall apart\n\nMinions: 2\nMinion Lifetime: 20s\nMax Minions: Minions + 1\n\nCooldown: 8s\nCharge Time: 0.25s\nMana Cost: 30.0"),
+                cooldown: 480.0,
+                time_to_charge: 15.0,
+                end_time: 9.0,
+                actions: super::player_abilities::RAISE_DEAD,
+                usable_with: UsableWith {
+                    item_types: vec![
+                        ItemType::RangedWeapon,
+                        ItemType::MagicWeapon,
+                        ItemType::MeleeWeapon
+                    ],
+                    usable_with_nothing: true,
+                },
+                mana_cost: 30.0,
+                mana_cost_while_charging: 0.0,
+                upgrades: vec![
+                    AbilityUpgrade {
+                        stats: create_stat_list!(lifetime => StatC { flat: 600.0, percent: 0.0}),
+                        cooldown_multiplier: 0.9,
+                        mana_cost_multiplier: 1.0
+                    },
+                    AbilityUpgrade {
+                        stats: create_stat_list!(shots => StatC { flat: 1.0, percent: 0.0}),
+                        cooldown_multiplier: 1.0,
+                        mana_cost_multiplier: 1.1
+                    }
+                ]
             }
         }
         
@@ -318,6 +467,7 @@ pub const CYCLONE_ACTIONS: PlayerAbilityActionDescriptor = PlayerAbilityActionDe
                                 CompactString::from("melee_attack"),
                                 1.0,
                                 crate::game_engine::player_attacks::PlayerAttackType::MeleeAbility,
+                                DamageType::Physical,
                                 player.x + 16.0 + angle.cos() * 37.0,
                                 player.y + 22.0 + angle.sin() * 37.0,
                                 angle * 180.0/PI, vec![]));
@@ -394,6 +544,7 @@ pub const TRAP: PlayerAbilityActionDescriptor = PlayerAbilityActionDescriptor {
                     CompactString::from("lightning_bolt"),
                     0.25,
                     crate::game_engine::player_attacks::PlayerAttackType::RangedAbility,
+                    DamageType::Lightning,
                     player.x + 16.0 + angle.cos() * 37.0,
                     player.y + 22.0 + angle.sin() * 37.0,
                     angle * 180.0/PI, vec![PlayerAbilityAttackTag::Splitting(SplittingDescriptor {
@@ -454,6 +605,7 @@ pub const BOLTS: PlayerAbilityActionDescriptor = PlayerAbilityActionDescriptor {
                     CompactString::from("lightning_bolt"),
                     0.25,
                     crate::game_engine::player_attacks::PlayerAttackType::RangedAbility,
+                    DamageType::Lightning,
                     player.x + 16.0 + angle.cos() * 37.0,
                     player.y + 22.0 + angle.sin() * 37.0,
                     angle * 180.0/PI, vec![PlayerAbilityAttackTag::Chaining(2000)]));
@@ -507,6 +659,7 @@ pub const SLIMEBALL: PlayerAbilityActionDescriptor = PlayerAbilityActionDescript
                 CompactString::from("slime_ball"),
                 1.0,
                 crate::game_engine::player_attacks::PlayerAttackType::RangedAbility,
+                DamageType::Poison,
                 player.x + 16.0 + angle.cos() * 37.0,
                 player.y + 22.0 + angle.sin() * 37.0,
                 angle * 180.0/PI, vec![PlayerAbilityAttackTag::Bouncing(2000)]));
@@ -516,6 +669,47 @@ pub const SLIMEBALL: PlayerAbilityActionDescriptor = PlayerAbilityActionDescript
 };
 
 
+pub const RAISE_DEAD: PlayerAbilityActionDescriptor = PlayerAbilityActionDescriptor {
+    on_start: |world, ability, state| {
+        world.player.borrow_mut().player_state = PlayerState::ChargingAbility;
+        world.cur_ability_charging = Some(ability);
+        Ok(())
+    },
+    while_charging: |world, ability, state| {
+        let mut_ability_ref = punwrap!(world.inventory.get_ability_mut(ability), Invalid, "while_charging was called with ability id {}, however there is no current ability with ability id {}", ability, ability);
+        if !(state.ability_key_held) {
+            mut_ability_ref.time_to_charge_left = 0.0; 
+            mut_ability_ref.end_without_end_action = true;
+        }
+        Ok(())
+    },
+    on_ending_start: |world, ability, state| {
+        Ok(())
+    },
+    while_ending: |world, ability, state | {
+        Ok(())
+    },
+    on_end: |world, ability, state| {
+        let mut player = world.player.borrow_mut();
+        let ability_ref = punwrap!(world.inventory.get_ability(ability), Invalid, "on_end was called with ability id {}, however there is no current ability with ability id {}", ability, ability);
+        if !(player.player_state == PlayerState::EndingAbility) {
+            return Err(perror!(Invalid, "Player State is {:?} at the end of ability charging, however it should be PlayerState::ChargingAbility", player.player_state));
+        }
+        world.cur_ability_charging = None;
+        player.player_state = PlayerState::Idle;
+        let minions = ability_ref.stats.shots.map(|x| x.get_value()).unwrap_or(1.0).floor().max(1.0) as usize;
+        let summon = SummonDescriptor {
+            archetype: CompactString::from("ghost"),
+            count: minions,
+            lifetime: ability_ref.stats.lifetime.map(|x| x.get_value()),
+            max_minions: minions + 1
+        };
+        let (x, y) = (player.x, player.y);
+        drop(player);
+        ptry!(world.summon_minions(&summon, x, y), "while raising the dead");
+        Ok(())
+    }
+};
 
 pub const DASH: PlayerAbilityActionDescriptor = PlayerAbilityActionDescriptor {
     on_start: |world, ability, state| {
@@ -545,7 +739,9 @@ pub const DASH: PlayerAbilityActionDescriptor = PlayerAbilityActionDescriptor {
             super::player::PlayerDir::UpRight => [7.07107, -7.07107],
 
         };
-        ptry!(world.attempt_move_player_ignore_damageable(&mut world.player.borrow_mut(), direction_normalized_10));
+        let mut player = world.player.borrow_mut();
+        player.iframes = player.iframes.max(super::player::DASH_IFRAMES);
+        ptry!(world.attempt_move_player_ignore_damageable(&mut player, direction_normalized_10));
         Ok(())
     },
     on_end: |world, ability, state| {
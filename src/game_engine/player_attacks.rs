@@ -1,12 +1,14 @@
 use compact_str::CompactString;
 
-use super::stat::StatList;
+use super::melee_combos::SwingState;
+use super::stat::{DamageType, StatList};
 
 
 #[derive(Clone, Debug)]
 pub struct PlayerAttack{
     pub stats: StatList,
     pub attack_type: PlayerAttackType,
+    pub damage_type: DamageType,
     pub sprite: CompactString,
     pub width_to_length_ratio: f32,
     pub time_alive: f32,
@@ -16,15 +18,18 @@ pub struct PlayerAttack{
     pub dealt_damage: bool,
     pub last_damage: Option<f32>,
     pub enemies_pierced: usize,
-    pub ability_tags: Vec<PlayerAbilityAttackTag>
-
+    pub ability_tags: Vec<PlayerAbilityAttackTag>,
+    pub gravity: f32,
+    pub velocity: Option<[f32; 2]>, // only used once gravity has to bend the projectile's path
+    pub swing: Option<SwingState>,
 }
 impl PlayerAttack{
-    pub fn new(stats: StatList, attack_type: PlayerAttackType,  sprite: CompactString, width_to_length_ratio: f32, x: f32, y: f32, angle: f32, ability_tags: Vec<PlayerAbilityAttackTag>) -> Self{
+    pub fn new(stats: StatList, attack_type: PlayerAttackType, damage_type: DamageType, sprite: CompactString, width_to_length_ratio: f32, x: f32, y: f32, angle: f32, ability_tags: Vec<PlayerAbilityAttackTag>) -> Self{
         Self{
             stats,
             sprite,
             attack_type,
+            damage_type,
             width_to_length_ratio,
             time_alive: 0.0,
             x,
@@ -33,7 +38,10 @@ impl PlayerAttack{
             dealt_damage: false,
             last_damage: None,
             enemies_pierced: 0,
-            ability_tags
+            ability_tags,
+            gravity: 0.0,
+            velocity: None,
+            swing: None
         }
     }
 }
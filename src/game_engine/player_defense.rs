@@ -0,0 +1,100 @@
+use std::f32::consts::PI;
+
+use crate::error::PError;
+use crate::world::World;
+use crate::ptry;
+
+use super::item::ItemType;
+use super::player::PlayerState;
+
+pub const ROLL_KEY: &str = " ";
+pub const ROLL_FRAMES: f32 = 14.0; // the player is invulnerable for the whole roll
+pub const ROLL_SPEED: f32 = 7.0;
+pub const ROLL_COOLDOWN: f32 = 45.0;
+pub const BLOCK_MANA_COST: f32 = 0.15; // per frame spent blocking
+pub const BLOCK_ARC: f32 = PI * 2.0/3.0; // hits coming from within this arc in front of the player are blocked
+pub const PARRY_WINDOW: f32 = 10.0; // frames after starting to block where a hit is parried instead
+pub const PARRY_STUN: f32 = 60.0; // hitstun given to the attacker of a parried hit
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BlockResult {
+    NotBlocked,
+    Blocked(f32), // damage multiplier
+    Parried
+}
+
+impl World {
+    pub fn start_roll(&mut self) -> Result<(), PError> {
+        let mut player = self.player.borrow_mut();
+        if player.roll_cooldown > 0.0 {
+            return Ok(());
+        }
+        if !(player.player_state == PlayerState::Idle || player.player_state == PlayerState::Walking || player.player_state == PlayerState::Blocking) {
+            return Ok(());
+        }
+        let direction = player.direction.to_vector();
+        player.player_state = PlayerState::Rolling;
+        player.roll_time_left = ROLL_FRAMES;
+        player.roll_direction = direction;
+        player.iframes = player.iframes.max(ROLL_FRAMES);
+        Ok(())
+    }
+    // starts or stops blocking depending on if the block button is held with a shield
+    pub fn update_block(&mut self, block_held: bool) -> Result<(), PError> {
+        let holding_shield = self.inventory.get_cur_held_item().map(|x| x.item_type == ItemType::Shield).unwrap_or(false);
+        let mut player = self.player.borrow_mut();
+        if player.player_state == PlayerState::Blocking {
+            if !block_held || !holding_shield {
+                player.player_state = PlayerState::Idle;
+            }
+        } else if block_held && holding_shield && self.mana >= BLOCK_MANA_COST && (player.player_state == PlayerState::Idle || player.player_state == PlayerState::Walking) {
+            player.player_state = PlayerState::Blocking;
+            player.block_time = 0.0;
+        }
+        Ok(())
+    }
+    pub fn update_player_defense(&mut self) -> Result<(), PError> {
+        let mut player = self.player.borrow_mut();
+        if player.roll_cooldown > 0.0 {
+            player.roll_cooldown -= 1.0;
+        }
+        match player.player_state {
+            PlayerState::Rolling => {
+                let movement = [player.roll_direction[0] * ROLL_SPEED, player.roll_direction[1] * ROLL_SPEED];
+                ptry!(self.attempt_move_player(&mut player, movement), "while rolling");
+                player.roll_time_left -= 1.0;
+                if player.roll_time_left <= 0.0 {
+                    player.player_state = PlayerState::Idle;
+                    player.roll_cooldown = ROLL_COOLDOWN;
+                }
+            },
+            PlayerState::Blocking => {
+                player.block_time += 1.0;
+                if self.mana < BLOCK_MANA_COST {
+                    player.player_state = PlayerState::Idle;
+                } else {
+                    self.mana -= BLOCK_MANA_COST;
+                }
+            },
+            _ => {}
+        }
+        Ok(())
+    }
+    // attack_rotation is the angle the attack was made at, pointing from the attacker towards the player
+    pub fn get_block_result(&self, attack_rotation: f32) -> Result<BlockResult, PError> {
+        let player = self.player.borrow();
+        if player.player_state != PlayerState::Blocking {
+            return Ok(BlockResult::NotBlocked);
+        }
+        let facing = player.direction.to_vector();
+        let incoming = [-attack_rotation.cos(), -attack_rotation.sin()];
+        if facing[0] * incoming[0] + facing[1] * incoming[1] < f32::cos(BLOCK_ARC/2.0) {
+            return Ok(BlockResult::NotBlocked);
+        }
+        if player.block_time <= PARRY_WINDOW {
+            return Ok(BlockResult::Parried);
+        }
+        let block = ptry!(self.inventory.get_combined_stats()).block.map(|x| x.get_value()).unwrap_or(0.0);
+        Ok(BlockResult::Blocked(1.0 - block.clamp(0.0, 100.0)/100.0))
+    }
+}
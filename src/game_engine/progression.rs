@@ -0,0 +1,334 @@
+use compact_str::CompactString;
+use serde::{Deserialize, Serialize};
+
+use crate::error::PError;
+use crate::rendering_engine::abstractions::{TextSprite, UIEFull};
+use crate::world::World;
+use crate::{perror, ptry, punwrap};
+
+use super::ability_unlocks::AbilityLevelReward;
+use super::game::MousePosition;
+use super::player_abilities::PlayerAbilityDescriptor;
+use super::stat::{StatC, StatList};
+use super::ui::UIESprite;
+
+pub const SKILL_TREE_KEY: &str = "k";
+const PANEL: [f32; 4] = [176.0, 80.0, 800.0, 560.0]; // x, y, w, h
+const STAT_ROWS_X: f32 = 196.0;
+const STAT_ROWS_Y: f32 = 150.0;
+const STAT_ROW_WIDTH: f32 = 240.0;
+const STAT_ROW_HEIGHT: f32 = 26.0;
+const TREE_X: f32 = 456.0; // node positions are relative to this corner
+const TREE_Y: f32 = 150.0;
+const NODE_WIDTH: f32 = 110.0;
+const NODE_HEIGHT: f32 = 30.0;
+
+// exp required to go from level n to n + 1 is base * growth^n + increment * n
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExpCurve {
+    pub base: f32,
+    pub growth: f32,
+    pub increment: f32,
+    pub max_level: usize,
+}
+
+impl Default for ExpCurve {
+    fn default() -> Self {
+        Self {
+            base: 100.0,
+            growth: 1.0,
+            increment: 50.0,
+            max_level: 2
+        }
+    }
+}
+
+impl ExpCurve {
+    pub fn exp_required(&self, level: usize) -> f32 {
+        self.base * self.growth.powi(level as i32) + self.increment * level as f32
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AllocatableStat {
+    pub stat: CompactString, // name of the StatList field a point goes into
+    pub per_point: StatC,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SkillNode {
+    pub name: CompactString,
+    pub description: String,
+    pub position: [f32; 2], // in the skill tree panel
+    #[serde(default = "default_skill_cost")]
+    pub cost: usize,
+    #[serde(default)]
+    pub prerequisites: Vec<CompactString>,
+    #[serde(default)]
+    pub stats: StatList,
+    pub ability: Option<CompactString>, // name of the player ability unlocked by this node
+}
+
+fn default_skill_cost() -> usize {
+    1
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Progression {
+    pub exp_curve: ExpCurve,
+    pub stat_points_per_level: usize,
+    pub skill_points_per_level: usize,
+    pub allocatable_stats: Vec<AllocatableStat>,
+    pub skill_tree: Vec<SkillNode>,
+    pub starting_abilities: Vec<CompactString>,
+    pub ability_rewards: Vec<AbilityLevelReward>, // abilities unlocked, or upgraded if already owned, on reaching a level
+}
+
+impl Progression {
+    pub fn get_node(&self, name: &str) -> Option<&SkillNode> {
+        self.skill_tree.iter().find(|x| x.name == name)
+    }
+    pub fn validate(&self, abilities: &[PlayerAbilityDescriptor]) -> Result<(), PError> {
+        for allocatable in self.allocatable_stats.iter() {
+            if StatList::default().get_stat_mut(&allocatable.stat).is_none() {
+                return Err(perror!(JSONValidationError, "stat points can be put into {}, which is not a stat", allocatable.stat));
+            }
+        }
+        for node in self.skill_tree.iter() {
+            for prerequisite in node.prerequisites.iter() {
+                if self.get_node(prerequisite).is_none() {
+                    return Err(perror!(JSONValidationError, "skill {} requires skill {}, which doesn't exist", node.name, prerequisite));
+                }
+            }
+            if let Some(ability) = &node.ability {
+                if !abilities.iter().any(|x| x.name == *ability) {
+                    return Err(perror!(JSONValidationError, "skill {} unlocks ability {}, which doesn't exist", node.name, ability));
+                }
+            }
+        }
+        for ability in self.starting_abilities.iter() {
+            if !abilities.iter().any(|x| x.name == *ability) {
+                return Err(perror!(JSONValidationError, "the player starts with ability {}, which doesn't exist", ability));
+            }
+        }
+        for reward in self.ability_rewards.iter() {
+            if !abilities.iter().any(|x| x.name == reward.ability) {
+                return Err(perror!(JSONValidationError, "reaching level {} gives ability {}, which doesn't exist", reward.level, reward.ability));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl World {
+    pub fn add_player_exp(&mut self, exp: f32) -> Result<(), PError> {
+        let mut player = self.player.borrow_mut();
+        let curve = &self.progression.exp_curve;
+        let starting_level = player.level;
+        player.exp += exp;
+        while player.level < curve.max_level && player.exp > curve.exp_required(player.level) {
+            player.exp -= curve.exp_required(player.level);
+            player.level += 1;
+            player.stat_points += self.progression.stat_points_per_level;
+            player.skill_points += self.progression.skill_points_per_level;
+        }
+        let level = player.level;
+        drop(player);
+        if level > starting_level {
+            ptry!(self.unlock_level_rewards(starting_level, level));
+        }
+        Ok(())
+    }
+    pub fn allocate_stat_point(&mut self, stat: &str) -> Result<(), PError> {
+        let mut player = self.player.borrow_mut();
+        if player.stat_points == 0 {
+            return Ok(());
+        }
+        player.stat_points -= 1;
+        *player.allocated_stats.entry(CompactString::from(stat)).or_insert(0) += 1;
+        drop(player);
+        ptry!(self.refresh_progression_stats(), "while allocating a point into {}", stat);
+        Ok(())
+    }
+    pub fn can_unlock_skill(&self, name: &str) -> bool {
+        let Some(node) = self.progression.get_node(name) else {
+            return false;
+        };
+        let player = self.player.borrow();
+        !player.unlocked_skills.iter().any(|x| x == name)
+            && player.skill_points >= node.cost
+            && node.prerequisites.iter().all(|x| player.unlocked_skills.contains(x))
+    }
+    pub fn unlock_skill(&mut self, name: &str) -> Result<(), PError> {
+        if !self.can_unlock_skill(name) {
+            return Ok(());
+        }
+        let node = punwrap!(self.progression.get_node(name), NotFound, "no skill named {}", name).clone();
+        // checked before any points are spent so a bad ability doesn't leave the skill half unlocked
+        if let Some(ability) = &node.ability {
+            punwrap!(self.get_ability_descriptor_id(ability), NotFound, "skill {} unlocks ability {}, which doesn't exist", name, ability);
+        }
+        let mut player = self.player.borrow_mut();
+        player.skill_points -= node.cost;
+        player.unlocked_skills.push(node.name.clone());
+        drop(player);
+        if let Some(ability) = &node.ability {
+            ptry!(self.unlock_ability(ability), "while unlocking skill {}", name);
+        }
+        ptry!(self.refresh_progression_stats(), "while unlocking skill {}", name);
+        Ok(())
+    }
+    // recalculates the stats given by allocated stat points and unlocked skills
+    pub fn refresh_progression_stats(&mut self) -> Result<(), PError> {
+        let player = self.player.borrow();
+        let mut stats = StatList::default();
+        for (stat, points) in player.allocated_stats.iter() {
+            let allocatable = punwrap!(self.progression.allocatable_stats.iter().find(|x| x.stat == *stat), NotFound, "points were put into {}, which can't have points put into it", stat);
+            let field = punwrap!(stats.get_stat_mut(stat), Invalid, "{} is not a stat", stat);
+            let added = StatC {flat: allocatable.per_point.flat * *points as f32, percent: allocatable.per_point.percent * *points as f32};
+            *field = Some(field.map(|x| x + added).unwrap_or(added));
+        }
+        for skill in player.unlocked_skills.iter() {
+            let node = punwrap!(self.progression.get_node(skill), NotFound, "player has unlocked skill {}, which doesn't exist", skill);
+            stats.to_sum_with(&node.stats);
+        }
+        drop(player);
+        self.inventory.progression_stats = stats;
+        Ok(())
+    }
+    fn get_hovered_skill_node(&self, mouse_position: MousePosition) -> Option<&SkillNode> {
+        self.progression.skill_tree.iter().find(|node| {
+            let x = TREE_X + node.position[0];
+            let y = TREE_Y + node.position[1];
+            mouse_position.x_screen > x && mouse_position.x_screen < x + NODE_WIDTH && mouse_position.y_screen > y && mouse_position.y_screen < y + NODE_HEIGHT
+        })
+    }
+    pub fn on_skill_tree_click(&mut self, mouse_position: MousePosition) -> Result<(), PError> {
+        for (i, allocatable) in self.progression.allocatable_stats.iter().enumerate() {
+            let y = STAT_ROWS_Y + i as f32 * STAT_ROW_HEIGHT;
+            if mouse_position.x_screen > STAT_ROWS_X && mouse_position.x_screen < STAT_ROWS_X + STAT_ROW_WIDTH && mouse_position.y_screen > y && mouse_position.y_screen < y + STAT_ROW_HEIGHT - 2.0 {
+                let stat = allocatable.stat.clone();
+                return self.allocate_stat_point(&stat);
+            }
+        }
+        if let Some(node) = self.get_hovered_skill_node(mouse_position) {
+            let name = node.name.clone();
+            ptry!(self.unlock_skill(&name));
+        }
+        Ok(())
+    }
+    pub fn render_skill_tree_ui(&self, mouse_position: MousePosition) -> Result<UIEFull, PError> {
+        let mut ui = Vec::new();
+        let mut text = Vec::new();
+        let player = self.player.borrow();
+        ui.push(UIESprite {
+            x: 0.0,
+            y: 0.0,
+            z: -1.0,
+            width: 1152.0,
+            height: 720.0,
+            sprite: CompactString::from("inventory_background")
+        });
+        ui.push(UIESprite {
+            x: PANEL[0],
+            y: PANEL[1],
+            z: 0.5,
+            width: PANEL[2],
+            height: PANEL[3],
+            sprite: CompactString::from("level_editor_menu_background")
+        });
+        text.push(TextSprite {
+            text: format!("Level {}\nStat Points: {}    Skill Points: {}", player.level, player.stat_points, player.skill_points),
+            font_size: 25.0,
+            x: PANEL[0] + PANEL[2]/2.0,
+            y: PANEL[1] + 10.0,
+            w: PANEL[2] - 20.0,
+            h: 60.0,
+            color: [1.0, 0.84, 0.0, 1.0],
+            align: wgpu_text::glyph_brush::HorizontalAlign::Center
+        });
+        for (i, allocatable) in self.progression.allocatable_stats.iter().enumerate() {
+            let y = STAT_ROWS_Y + i as f32 * STAT_ROW_HEIGHT;
+            ui.push(UIESprite {
+                x: STAT_ROWS_X,
+                y,
+                z: 5.2,
+                width: STAT_ROW_WIDTH,
+                height: STAT_ROW_HEIGHT - 2.0,
+                sprite: CompactString::from("level_editor_button_background")
+            });
+            let points = player.allocated_stats.get(&allocatable.stat).copied().unwrap_or(0);
+            let per_point = if allocatable.per_point.percent != 0.0 {
+                format!("+{}%", allocatable.per_point.percent)
+            } else {
+                format!("+{}", allocatable.per_point.flat)
+            };
+            text.push(TextSprite {
+                text: format!("{}: {} ({} each)", allocatable.stat, points, per_point),
+                font_size: 18.0,
+                x: STAT_ROWS_X + 6.0,
+                y: y + 3.0,
+                w: STAT_ROW_WIDTH - 12.0,
+                h: STAT_ROW_HEIGHT,
+                color: if player.stat_points > 0 {[1.0, 1.0, 1.0, 1.0]} else {[0.6, 0.6, 0.6, 1.0]},
+                align: wgpu_text::glyph_brush::HorizontalAlign::Left
+            });
+        }
+        for node in self.progression.skill_tree.iter() {
+            let unlocked = player.unlocked_skills.contains(&node.name);
+            let available = node.prerequisites.iter().all(|x| player.unlocked_skills.contains(x));
+            ui.push(UIESprite {
+                x: TREE_X + node.position[0],
+                y: TREE_Y + node.position[1],
+                z: 5.2,
+                width: NODE_WIDTH,
+                height: NODE_HEIGHT,
+                sprite: CompactString::from("level_editor_button_background")
+            });
+            text.push(TextSprite {
+                text: node.name.to_string(),
+                font_size: 16.0,
+                x: TREE_X + node.position[0] + NODE_WIDTH/2.0,
+                y: TREE_Y + node.position[1] + 7.0,
+                w: NODE_WIDTH - 6.0,
+                h: NODE_HEIGHT,
+                color: if unlocked {[1.0, 0.84, 0.0, 1.0]} else if available {[1.0, 1.0, 1.0, 1.0]} else {[0.45, 0.45, 0.45, 1.0]},
+                align: wgpu_text::glyph_brush::HorizontalAlign::Center
+            });
+        }
+        if let Some(node) = self.get_hovered_skill_node(mouse_position) {
+            let mut t = format!("{}\n------------\n{}\n\nCost: {}\n", node.name, node.description, node.cost);
+            if !node.prerequisites.is_empty() {
+                t.push_str(&format!("Requires: {}\n", node.prerequisites.join(", ")));
+            }
+            if let Some(ability) = &node.ability {
+                t.push_str(&format!("Unlocks ability: {}\n", ability));
+            }
+            t.push_str(&self.inventory.get_stat_string(&node.stats));
+            ui.push(UIESprite {
+                x: mouse_position.x_screen + 20.0,
+                y: mouse_position.y_screen - 80.0,
+                z: 5.6,
+                width: 220.0,
+                height: 200.0,
+                sprite: CompactString::from("level_editor_menu_background")
+            });
+            text.push(TextSprite {
+                text: t,
+                font_size: 18.0,
+                x: mouse_position.x_screen + 30.0,
+                y: mouse_position.y_screen - 70.0,
+                w: 200.0,
+                h: 180.0,
+                color: [1.0, 1.0, 1.0, 1.0],
+                align: wgpu_text::glyph_brush::HorizontalAlign::Left
+            });
+        }
+        Ok(UIEFull {
+            sprites: ui,
+            text
+        })
+    }
+}
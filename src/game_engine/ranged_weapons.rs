@@ -0,0 +1,143 @@
+use std::f32::consts::PI;
+
+use compact_str::CompactString;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::error::PError;
+use crate::world::World;
+use crate::{ptry, punwrap};
+
+use super::game::MousePosition;
+use super::item::Item;
+use super::stat::{StatC, StatList};
+
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ShotPattern {
+    #[default]
+    Spread, // all shots are fired at once in a fan, focus narrows the fan
+    Burst, // shots are fired one after another, focus reduces how inaccurate they are
+}
+
+// Optional ranged behaviour on item archetypes, weapons without one fire on click like before
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RangedDescriptor {
+    pub charge_time: f32, // frames to fully charge a shot, 0 fires while the button is held
+    pub min_charge: f32, // damage and speed multiplier when released without any charge
+    pub max_charge: f32, // damage and speed multiplier at full charge
+    pub pattern: ShotPattern,
+    pub burst_delay: f32, // frames between the shots of a burst
+    pub ammo: Option<CompactString>, // item archetype that is used up for every volley
+    pub gravity: f32, // pulls projectiles down the screen every frame so they fly in an arc
+}
+
+impl Default for RangedDescriptor {
+    fn default() -> Self {
+        Self {
+            charge_time: 0.0,
+            min_charge: 1.0,
+            max_charge: 1.0,
+            pattern: ShotPattern::Spread,
+            burst_delay: 6.0,
+            ammo: None,
+            gravity: 0.0
+        }
+    }
+}
+
+pub const BURST_INACCURACY: f32 = PI/16.0;
+
+#[derive(Debug, Clone)]
+pub struct QueuedShot {
+    pub delay: f32,
+    pub stats: StatList,
+    pub item: Item,
+    pub angle: f32, // radians
+}
+
+impl World {
+    pub fn get_ranged_descriptor(&self, item: &Item) -> RangedDescriptor {
+        self.get_item_archetype(&item.archetype).and_then(|x| x.ranged.clone()).unwrap_or_default()
+    }
+    // charge is how much of the charge time was held for, from 0 to 1. Returns false if nothing was fired
+    pub fn fire_ranged_weapon(&mut self, mouse_position: MousePosition, charge: f32) -> Result<bool, PError> {
+        let item = punwrap!(self.inventory.get_cur_held_item(), Expected, "firing a ranged weapon without holding anything").clone();
+        let descriptor = self.get_ranged_descriptor(&item);
+        let mut stats = ptry!(self.inventory.get_combined_stats());
+        if let Some(ammo) = &descriptor.ammo {
+            let Some(ammo_id) = self.inventory.find_item_with_archetype(ammo) else {
+                return Ok(false);
+            };
+            let ammo_item = punwrap!(self.inventory.get_item(&ammo_id), NotFound, "no ammo item with id {}", ammo_id);
+            stats.to_sum_with(&ammo_item.stats);
+            ptry!(self.inventory.use_item(ammo_id), "while using up ammo {} for {}", ammo, item.name);
+        }
+        let multiplier = descriptor.min_charge + (descriptor.max_charge - descriptor.min_charge) * charge.clamp(0.0, 1.0);
+        stats.damage = stats.damage.map(|x| StatC {flat: x.flat * multiplier, percent: x.percent});
+        stats.speed = stats.speed.map(|x| StatC {flat: x.flat * multiplier, percent: x.percent});
+
+        let player = self.player.borrow();
+        let (px, py) = (player.x, player.y);
+        drop(player);
+        let mouse_direction_unnormalized = [(mouse_position.x_world - px - 16.0), (mouse_position.y_world - py - 22.0)];
+        let main_angle = mouse_direction_unnormalized[1].atan2(mouse_direction_unnormalized[0]);
+        let shots = stats.shots.map(|x| x.get_value()).unwrap_or(1.0).floor().max(1.0) as usize;
+        let focus = stats.focus.map(|x| x.get_value()).unwrap_or(1.0).max(0.1);
+        match descriptor.pattern {
+            ShotPattern::Spread => {
+                let spread = f32::min(PI/8.0, PI/shots as f32)/focus;
+                let angle = main_angle - (shots as f32 - 1.0) * spread/2.0;
+                for i in 0..shots {
+                    let ang_adjusted = angle + spread * i as f32;
+                    ptry!(self.add_player_attack(
+                            &stats,
+                            &item,
+                            px + 16.0 + ang_adjusted.cos() * 25.0,
+                            py + 22.0 + ang_adjusted.sin() * 25.0,
+                            ang_adjusted * 180.0/PI));
+                }
+            },
+            ShotPattern::Burst => {
+                let mut rng = rand::thread_rng();
+                for i in 0..shots {
+                    let inaccuracy = BURST_INACCURACY/focus;
+                    let angle = main_angle + if i == 0 {0.0} else {rng.gen_range(-inaccuracy..=inaccuracy)};
+                    self.queued_shots.borrow_mut().push(QueuedShot {
+                        delay: descriptor.burst_delay * i as f32,
+                        stats: stats.clone(),
+                        item: item.clone(),
+                        angle
+                    });
+                }
+                ptry!(self.update_queued_shots());
+            }
+        }
+        Ok(true)
+    }
+    // fires queued burst shots from the player's current position once their delay runs out
+    pub fn update_queued_shots(&self) -> Result<(), PError> {
+        let mut ready = Vec::new();
+        self.queued_shots.borrow_mut().retain_mut(|shot| {
+            if shot.delay <= 0.0 {
+                ready.push(shot.clone());
+                return false;
+            }
+            shot.delay -= 1.0;
+            true
+        });
+        let player = self.player.borrow();
+        let (px, py) = (player.x, player.y);
+        drop(player);
+        for shot in ready {
+            ptry!(self.add_player_attack(
+                    &shot.stats,
+                    &shot.item,
+                    px + 16.0 + shot.angle.cos() * 25.0,
+                    py + 22.0 + shot.angle.sin() * 25.0,
+                    shot.angle * 180.0/PI));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,200 @@
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+
+use compact_str::CompactString;
+use rand::{rngs::StdRng, SeedableRng};
+use rustc_hash::FxHashMap;
+
+use crate::error::PError;
+use crate::{perror, ptry, punwrap};
+
+use super::json_parsing::{entity_json, player_json, room_descriptor_json, spawn_archetype_json, special_spawn_json, starting_level_json, terrain_archetype_json, JSON_parser, PATH_BUNDLE};
+use super::pathfinding::reachable_blocks;
+use super::room_generation::{generate_room_layout, RoomType};
+use super::room_objectives::RoomObjective;
+use super::starting_level_generator::match_terrain_tags;
+use super::terrain::TerrainTags;
+
+pub const ROOM_LEVEL_COMMAND: &str = "room-level";
+
+// The web editor only reads and writes the starting level format, these convert rooms to and from it.
+// Terrain is in tiles in both formats, entities and the player are in pixels in the starting level format
+pub fn room_to_level(room: &room_descriptor_json, player: &player_json) -> starting_level_json {
+    let mut player = player.clone();
+    player.x = room.entrance[0] as f32 * 32.0;
+    player.y = room.entrance[1] as f32 * 32.0;
+    starting_level_json {
+        player,
+        entities: room.entities.iter().map(|x| entity_json {
+            x: x.x as f32 * 32.0,
+            y: x.y as f32 * 32.0,
+            archetype: x.archetype.clone()
+        }).collect(),
+        terrain: room.terrain.clone()
+    }
+}
+
+// base supplies what the starting level format can't hold (name, exit, spawnable and spawn archetype),
+// the size is fit to the terrain and the player position becomes the entrance
+pub fn room_from_level(level: &starting_level_json, base: &room_descriptor_json) -> room_descriptor_json {
+    let mut room = base.clone();
+    room.terrain = level.terrain.clone();
+    room.entities = level.entities.iter().map(|x| special_spawn_json {
+        x: (x.x / 32.0).floor().max(0.0) as usize,
+        y: (x.y / 32.0).floor().max(0.0) as usize,
+        archetype: x.archetype.clone()
+    }).collect();
+    room.entrance = [(level.player.x / 32.0).floor().max(0.0) as usize, (level.player.y / 32.0).floor().max(0.0) as usize];
+    room.width = room.terrain.iter().map(|x| x.x + x.width).max().unwrap_or(0);
+    room.height = room.terrain.iter().map(|x| x.y + x.height).max().unwrap_or(0);
+    for position in room.entities.iter().map(|x| [x.x, x.y]).chain(room.spawnable.iter().copied()).chain([room.entrance, room.exit]) {
+        room.width = room.width.max(position[0] + 1);
+        room.height = room.height.max(position[1] + 1);
+    }
+    room
+}
+
+// whether each tile of the room can be walked on, indexed [y][x]
+pub fn walkable_tiles(room: &room_descriptor_json, terrain_archetypes: &FxHashMap<CompactString, terrain_archetype_json>) -> Result<Vec<Vec<bool>>, PError> {
+    let mut walkable = vec![vec![true; room.width]; room.height];
+    for terrain in room.terrain.iter() {
+        let archetype = punwrap!(terrain_archetypes.get(&terrain.terrain_archetype), JSONValidationError, "Room: {} uses terrain archetype {} which doesn't exist", room.name, terrain.terrain_archetype);
+        let tags = ptry!(match_terrain_tags(archetype), "while checking the terrain of room {}", room.name);
+        if !tags.contains(&TerrainTags::BlocksMovement) && !tags.contains(&TerrainTags::Water) {
+            continue;
+        }
+        for row in walkable.iter_mut().take(terrain.y + terrain.height).skip(terrain.y) {
+            for tile in row.iter_mut().take(terrain.x + terrain.width).skip(terrain.x) {
+                *tile = false;
+            }
+        }
+    }
+    Ok(walkable)
+}
+
+pub fn validate_room(room: &room_descriptor_json, terrain_archetypes: &FxHashMap<CompactString, terrain_archetype_json>, spawn_archetypes: &FxHashMap<CompactString, spawn_archetype_json>) -> Result<(), PError> {
+    let name = &room.name;
+    if !spawn_archetypes.contains_key(&room.spawn_archetype) {
+        return Err(perror!(JSONValidationError, "Room: {} uses spawn archetype {} which doesn't exist", name, room.spawn_archetype));
+    }
+    if !(0.0..=1.0).contains(&room.darkness) {
+        return Err(perror!(JSONValidationError, "Room: {} has a darkness of {}, it should be between 0 and 1", name, room.darkness));
+    }
+    if room.room_type != RoomType::Authored {
+        // specials are kept as floor like they are when the room is generated in game
+        let specials: Vec<[usize; 2]> = spawn_archetypes[&room.spawn_archetype].special.iter().map(|x| [x.x, x.y]).collect();
+        // a fixed seed so a bad room fails the same way every time
+        let layout = ptry!(generate_room_layout(room, &specials, terrain_archetypes, &mut StdRng::seed_from_u64(0)));
+        return validate_room(&layout, terrain_archetypes, spawn_archetypes);
+    }
+    match &room.objective {
+        RoomObjective::Survive{seconds} if *seconds <= 0.0 => {
+            return Err(perror!(JSONValidationError, "Room: {} has to be survived for {} seconds, it should be more than 0", name, seconds));
+        },
+        RoomObjective::Waves{waves} => {
+            if let Some(wave) = waves.iter().find(|x| !spawn_archetypes.contains_key(*x)) {
+                return Err(perror!(JSONValidationError, "Room: {} has a wave of spawn archetype {} which doesn't exist", name, wave));
+            }
+        },
+        _ => {}
+    }
+    let walkable = ptry!(walkable_tiles(room, terrain_archetypes));
+    let in_bounds = |[x, y]: [usize; 2]| x < room.width && y < room.height;
+    for (label, position) in [("entrance", room.entrance), ("exit", room.exit)].into_iter().chain(room.reward_position.map(|x| ("reward", x))) {
+        if !in_bounds(position) {
+            return Err(perror!(JSONValidationError, "Room: {} has its {} at {:?} which is outside of the room ({}x{})", name, label, position, room.width, room.height));
+        }
+        if !walkable[position[1]][position[0]] {
+            return Err(perror!(JSONValidationError, "Room: {} has its {} at {:?} which is on a tile that blocks movement", name, label, position));
+        }
+    }
+    let waves = if let RoomObjective::Waves{waves} = &room.objective {waves.as_slice()} else {&[]};
+    for spawn_archetype in std::iter::once(&room.spawn_archetype).chain(waves.iter()).filter_map(|x| spawn_archetypes.get(x)) {
+        for special in spawn_archetype.special.iter() {
+            let position = [special.x, special.y];
+            if !in_bounds(position) || !walkable[position[1]][position[0]] {
+                return Err(perror!(JSONValidationError, "Room: {} uses spawn archetype {} which places {} at {:?}, outside of the room or on a tile that blocks movement", name, spawn_archetype.name, special.archetype, position));
+            }
+        }
+    }
+    let reachable = reachable_blocks(room.entrance, room.width, room.height, &walkable);
+    for position in room.spawnable.iter() {
+        if !in_bounds(*position) {
+            return Err(perror!(JSONValidationError, "Room: {} has a spawn point at {:?} which is outside of the room ({}x{})", name, position, room.width, room.height));
+        }
+        if !reachable[position[1]][position[0]] {
+            return Err(perror!(JSONValidationError, "Room: {} has a spawn point at {:?} which can't be reached from the entrance", name, position));
+        }
+    }
+    Ok(())
+}
+
+pub fn read_level(path: &Path) -> Result<starting_level_json, PError> {
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(e) => return Err(perror!(NotFound, "could not open level {}: {}", path.display(), e))
+    };
+    match serde_json::from_reader(BufReader::new(file)) {
+        Ok(level) => Ok(level),
+        Err(e) => Err(perror!(Invalid, "level {} is not in the starting level format: {}", path.display(), e))
+    }
+}
+
+// room-level <level.json> export <room> | import <room> [--print]
+// export writes the room out for the web editor to open (it loads level_editor/cur_level.json),
+// import puts the level the web editor downloads back into the room
+pub fn run_room_level_command(args: &[String]) -> Result<(), PError> {
+    let usage = "usage: room-level <level.json> export <room> | room-level <level.json> import <room> [--print]";
+    let print = args.iter().any(|x| x == "--print");
+    let args: Vec<&String> = args.iter().filter(|x| *x != "--print").collect();
+    let path = punwrap!(args.first(), InputFailed, "{}", usage);
+    let direction = punwrap!(args.get(1), InputFailed, "{}", usage);
+    let name = CompactString::from(punwrap!(args.get(2), InputFailed, "{}", usage).as_str());
+
+    let mut parser = JSON_parser::new();
+    parser.parse_terrain_archetypes(PATH_BUNDLE.terrain_archetypes_path);
+    parser.parse_entity_archetypes(PATH_BUNDLE.entity_archetypes_path);
+    parser.parse_starting_level(PATH_BUNDLE.starting_level_path);
+    parser.parse_rooms(PATH_BUNDLE.rooms_path);
+    parser.parse_spawn_archetypes(PATH_BUNDLE.spawn_archetypes_path);
+    let base = punwrap!(parser.rooms_json.get(&name), NotFound, "there is no room named {}", name).clone();
+
+    match direction.as_str() {
+        "export" => {
+            let level = room_to_level(&base, &parser.starting_level_json.player);
+            let json = match serde_json::to_string(&level) {
+                Ok(json) => json,
+                Err(e) => return Err(perror!(Error, "failed to serialize room {}: {}", name, e))
+            };
+            if let Err(e) = std::fs::write(path.as_str(), json) {
+                return Err(perror!(Error, "failed to write room {} to {}: {}", name, path, e));
+            }
+            println!("Exported {} to {}", name, path);
+        },
+        "import" => {
+            let level = ptry!(read_level(Path::new(path.as_str())));
+            for entity in level.entities.iter() {
+                if !parser.entity_archetypes_json.contains_key(&entity.archetype) {
+                    return Err(perror!(JSONValidationError, "level {} places entity archetype {} which doesn't exist", path, entity.archetype));
+                }
+            }
+            let room = room_from_level(&level, &base);
+            ptry!(validate_room(&room, &parser.terrain_archetypes_json, &parser.spawn_archetypes_json), "while importing {} into room {}", path, name);
+            if print {
+                match serde_json::to_string_pretty(&room) {
+                    Ok(json) => println!("{}", json),
+                    Err(e) => return Err(perror!(Error, "failed to serialize room {}: {}", name, e))
+                }
+                return Ok(());
+            }
+            parser.rooms_json.insert(name.clone(), room);
+            if let Err(e) = parser.write_rooms(PATH_BUNDLE.rooms_path) {
+                return Err(perror!(Error, "failed to write the imported room {}: {}", name, e));
+            }
+            println!("Imported {} as room {}", path, name);
+        },
+        _ => return Err(perror!(InputFailed, "{}", usage))
+    }
+    Ok(())
+}
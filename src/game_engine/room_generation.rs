@@ -0,0 +1,286 @@
+use compact_str::CompactString;
+use rand::Rng;
+use rustc_hash::FxHashMap;
+use serde::{Deserialize, Serialize};
+
+use crate::error::PError;
+use crate::{perror, ptry, punwrap};
+
+use super::json_parsing::{room_descriptor_json, terrain_archetype_json, terrain_json};
+use super::pathfinding::reachable_blocks;
+use super::starting_level_generator::match_terrain_tags;
+use super::terrain::TerrainTags;
+
+// Authored rooms use the terrain in rooms.json, the others get a new interior every time they're generated
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RoomType {
+    #[default]
+    Authored,
+    Cave, // cellular automata
+    Bsp, // rooms from a binary space partition joined by corridors
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RoomGeneration {
+    pub wall: CompactString, // terrain archetype for everything that isn't floor
+    #[serde(default)]
+    pub floor: Option<CompactString>,
+    #[serde(default = "default_fill_chance")]
+    pub fill_chance: f32, // caves: chance a tile starts as wall
+    #[serde(default = "default_smoothing_steps")]
+    pub smoothing_steps: usize, // caves
+    #[serde(default = "default_min_leaf_size")]
+    pub min_leaf_size: usize, // bsp: smallest area a room is carved in, walls included
+    #[serde(default)]
+    pub decorations: Vec<Decoration>,
+}
+
+// Terrain scattered over the floor where the noise is above the threshold, like pillars, rubble or pools.
+// Decorations that block movement are never placed where they would cut part of the room off
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Decoration {
+    pub terrain_archetype: CompactString,
+    #[serde(default = "default_noise_scale")]
+    pub scale: f32, // tiles per noise cell, bigger makes bigger clumps
+    pub threshold: f32, // between 0 and 1, higher places less
+}
+
+fn default_fill_chance() -> f32 {
+    0.45
+}
+
+fn default_smoothing_steps() -> usize {
+    4
+}
+
+fn default_min_leaf_size() -> usize {
+    6
+}
+
+fn default_noise_scale() -> f32 {
+    4.0
+}
+
+// tiles around the entrance that spawns stay out of
+const SPAWN_SAFE_DISTANCE: usize = 3;
+
+// Fills in the terrain and spawnable positions of a procedural room. The result is an authored room so it can be
+// validated and generated like one. keep are extra positions that have to be reachable floor, like special spawns
+pub fn generate_room_layout<R: Rng>(room: &room_descriptor_json, keep: &[[usize; 2]], terrain_archetypes: &FxHashMap<CompactString, terrain_archetype_json>, rng: &mut R) -> Result<room_descriptor_json, PError> {
+    if room.room_type == RoomType::Authored {
+        return Ok(room.clone());
+    }
+    let name = &room.name;
+    let generation = punwrap!(room.generation.as_ref(), JSONValidationError, "Room: {} is procedural but has no generation settings", name);
+    let (width, height) = (room.width, room.height);
+    if width < 3 || height < 3 {
+        return Err(perror!(JSONValidationError, "Room: {} is procedural and {}x{}, it should be at least 3x3", name, width, height));
+    }
+    let wall_blocks = ptry!(blocks_movement(&generation.wall, name, terrain_archetypes));
+    if !wall_blocks {
+        return Err(perror!(JSONValidationError, "Room: {} uses terrain archetype {} as its wall but it doesn't block movement", name, generation.wall));
+    }
+    if let Some(floor) = generation.floor.as_ref() {
+        if ptry!(blocks_movement(floor, name, terrain_archetypes)) {
+            return Err(perror!(JSONValidationError, "Room: {} uses terrain archetype {} as its floor but it blocks movement", name, floor));
+        }
+    }
+
+    let mut floor = match room.room_type {
+        RoomType::Cave => {
+            if !(0.0..1.0).contains(&generation.fill_chance) {
+                return Err(perror!(JSONValidationError, "Room: {} has a fill chance of {}, it should be at least 0 and less than 1", name, generation.fill_chance));
+            }
+            cave(width, height, generation, rng)
+        },
+        RoomType::Bsp => {
+            if generation.min_leaf_size < 3 {
+                return Err(perror!(JSONValidationError, "Room: {} has a min leaf size of {}, it should be at least 3", name, generation.min_leaf_size));
+            }
+            bsp(width, height, generation, rng)
+        },
+        RoomType::Authored => unreachable!(),
+    };
+
+    let keep: Vec<[usize; 2]> = [room.entrance, room.exit].into_iter()
+        .chain(room.entities.iter().map(|x| [x.x, x.y]))
+        .chain(room.reward_position)
+        .chain(keep.iter().copied())
+        .collect();
+    for position in keep.iter() {
+        if position[0] >= width || position[1] >= height {
+            return Err(perror!(JSONValidationError, "Room: {} needs {:?} to be floor but it is outside of the room ({}x{})", name, position, width, height));
+        }
+        floor[position[1]][position[0]] = true;
+        if !reachable_blocks(room.entrance, width, height, &floor)[position[1]][position[0]] {
+            carve_corridor(&mut floor, room.entrance, *position);
+        }
+    }
+    // pockets that can't be reached would only hold spawns the player can't get to
+    let floor = reachable_blocks(room.entrance, width, height, &floor);
+
+    let mut decorations: Vec<Vec<Option<&CompactString>>> = vec![vec![None; width]; height];
+    let mut reachable_count = floor.iter().flatten().filter(|x| **x).count();
+    let mut walkable = floor.clone();
+    for (i, decoration) in generation.decorations.iter().enumerate() {
+        if !(0.0..=1.0).contains(&decoration.threshold) || decoration.scale <= 0.0 {
+            return Err(perror!(JSONValidationError, "Room: {} has decoration {} with threshold {} and scale {}, the threshold should be between 0 and 1 and the scale more than 0", name, decoration.terrain_archetype, decoration.threshold, decoration.scale));
+        }
+        let blocks = ptry!(blocks_movement(&decoration.terrain_archetype, name, terrain_archetypes));
+        let seed: u32 = rng.gen::<u32>().wrapping_add(i as u32);
+        for y in 0..height {
+            for x in 0..width {
+                if !walkable[y][x] || decorations[y][x].is_some() || keep.iter().any(|k| k[0].abs_diff(x) <= 1 && k[1].abs_diff(y) <= 1) {
+                    continue;
+                }
+                if value_noise(x as f32 / decoration.scale, y as f32 / decoration.scale, seed) <= decoration.threshold {
+                    continue;
+                }
+                if blocks {
+                    walkable[y][x] = false;
+                    let count = reachable_blocks(room.entrance, width, height, &walkable).iter().flatten().filter(|x| **x).count();
+                    if count != reachable_count - 1 {
+                        walkable[y][x] = true;
+                        continue;
+                    }
+                    reachable_count = count;
+                }
+                decorations[y][x] = Some(&decoration.terrain_archetype);
+            }
+        }
+    }
+
+    let mut layout = room.clone();
+    layout.room_type = RoomType::Authored;
+    layout.generation = None;
+    layout.terrain = Vec::new();
+    for y in 0..height {
+        let mut x = 0;
+        while x < width {
+            let archetype = tile_archetype(&floor, &decorations, generation, x, y);
+            let mut run = 1;
+            while x + run < width && tile_archetype(&floor, &decorations, generation, x + run, y) == archetype {
+                run += 1;
+            }
+            if let Some(archetype) = archetype {
+                layout.terrain.push(terrain_json {x, y, width: run, height: 1, terrain_archetype: archetype.clone()});
+            }
+            x += run;
+        }
+    }
+    layout.spawnable = Vec::new();
+    for (y, row) in walkable.iter().enumerate() {
+        for (x, tile) in row.iter().enumerate() {
+            let near_entrance = room.entrance[0].abs_diff(x) <= SPAWN_SAFE_DISTANCE && room.entrance[1].abs_diff(y) <= SPAWN_SAFE_DISTANCE;
+            if *tile && !near_entrance && !keep.contains(&[x, y]) {
+                layout.spawnable.push([x, y]);
+            }
+        }
+    }
+    Ok(layout)
+}
+
+fn blocks_movement(archetype: &CompactString, room: &str, terrain_archetypes: &FxHashMap<CompactString, terrain_archetype_json>) -> Result<bool, PError> {
+    let archetype = punwrap!(terrain_archetypes.get(archetype), JSONValidationError, "Room: {} uses terrain archetype {} which doesn't exist", room, archetype);
+    let tags = ptry!(match_terrain_tags(archetype), "while generating room {}", room);
+    Ok(tags.contains(&TerrainTags::BlocksMovement) || tags.contains(&TerrainTags::Water))
+}
+
+fn tile_archetype<'a>(floor: &[Vec<bool>], decorations: &[Vec<Option<&'a CompactString>>], generation: &'a RoomGeneration, x: usize, y: usize) -> Option<&'a CompactString> {
+    if !floor[y][x] {
+        return Some(&generation.wall);
+    }
+    decorations[y][x].or(generation.floor.as_ref())
+}
+
+// floor is true, indexed [y][x]. The edge of the room is always wall
+fn cave<R: Rng>(width: usize, height: usize, generation: &RoomGeneration, rng: &mut R) -> Vec<Vec<bool>> {
+    let is_edge = |x: usize, y: usize| x == 0 || y == 0 || x == width - 1 || y == height - 1;
+    let mut floor: Vec<Vec<bool>> = (0..height).map(|y| (0..width).map(|x| !is_edge(x, y) && rng.gen::<f32>() >= generation.fill_chance).collect()).collect();
+    for _ in 0..generation.smoothing_steps {
+        let mut next = floor.clone();
+        for (y, row) in next.iter_mut().enumerate() {
+            for (x, tile) in row.iter_mut().enumerate() {
+                if is_edge(x, y) {
+                    continue;
+                }
+                let around: usize = floor[y - 1..=y + 1].iter().map(|row| row[x - 1..=x + 1].iter().filter(|x| !**x).count()).sum();
+                let walls = around - usize::from(!floor[y][x]);
+                if walls > 4 {
+                    *tile = false;
+                } else if walls < 4 {
+                    *tile = true;
+                }
+            }
+        }
+        floor = next;
+    }
+    floor
+}
+
+fn bsp<R: Rng>(width: usize, height: usize, generation: &RoomGeneration, rng: &mut R) -> Vec<Vec<bool>> {
+    let mut floor = vec![vec![false; width]; height];
+    split_leaf(&mut floor, [1, 1, width - 2, height - 2], generation.min_leaf_size, rng);
+    floor
+}
+
+// carves rooms in the leaves of [x, y, w, h] and joins the halves of every split, returns a tile inside one of the rooms
+fn split_leaf<R: Rng>(floor: &mut [Vec<bool>], leaf: [usize; 4], min_leaf_size: usize, rng: &mut R) -> [usize; 2] {
+    let [x, y, w, h] = leaf;
+    let can_split_x = w >= min_leaf_size * 2;
+    let can_split_y = h >= min_leaf_size * 2;
+    if can_split_x || can_split_y {
+        let (first, second) = if can_split_x && (w >= h || !can_split_y) {
+            let split = rng.gen_range(min_leaf_size..=w - min_leaf_size);
+            ([x, y, split, h], [x + split, y, w - split, h])
+        } else {
+            let split = rng.gen_range(min_leaf_size..=h - min_leaf_size);
+            ([x, y, w, split], [x, y + split, w, h - split])
+        };
+        let a = split_leaf(floor, first, min_leaf_size, rng);
+        let b = split_leaf(floor, second, min_leaf_size, rng);
+        carve_corridor(floor, a, b);
+        return if rng.gen_bool(0.5) {a} else {b};
+    }
+    // leave a wall on the right and bottom so neighbouring rooms don't merge
+    let room_w = if w <= 2 {w} else {rng.gen_range(2..w)};
+    let room_h = if h <= 2 {h} else {rng.gen_range(2..h)};
+    let room_x = x + rng.gen_range(0..(w - room_w).max(1));
+    let room_y = y + rng.gen_range(0..(h - room_h).max(1));
+    for row in floor.iter_mut().skip(room_y).take(room_h) {
+        for tile in row.iter_mut().skip(room_x).take(room_w) {
+            *tile = true;
+        }
+    }
+    [room_x + room_w / 2, room_y + room_h / 2]
+}
+
+// L shaped, horizontal first
+fn carve_corridor(floor: &mut [Vec<bool>], from: [usize; 2], to: [usize; 2]) {
+    floor[from[1]][from[0].min(to[0])..=from[0].max(to[0])].fill(true);
+    for row in floor.iter_mut().take(from[1].max(to[1]) + 1).skip(from[1].min(to[1])) {
+        row[to[0]] = true;
+    }
+}
+
+// smooth noise between 0 and 1 from a random value at every whole coordinate
+pub fn value_noise(x: f32, y: f32, seed: u32) -> f32 {
+    let (x0, y0) = (x.floor(), y.floor());
+    let (tx, ty) = (x - x0, y - y0);
+    let (sx, sy) = (tx * tx * (3.0 - 2.0 * tx), ty * ty * (3.0 - 2.0 * ty));
+    let (x0, y0) = (x0 as i32, y0 as i32);
+    let top = lattice(x0, y0, seed) + (lattice(x0 + 1, y0, seed) - lattice(x0, y0, seed)) * sx;
+    let bottom = lattice(x0, y0 + 1, seed) + (lattice(x0 + 1, y0 + 1, seed) - lattice(x0, y0 + 1, seed)) * sx;
+    top + (bottom - top) * sy
+}
+
+fn lattice(x: i32, y: i32, seed: u32) -> f32 {
+    let mut hash = (x as u32).wrapping_mul(0x27d4_eb2d) ^ (y as u32).wrapping_mul(0x1656_67b1) ^ seed.wrapping_mul(0x9e37_79b9);
+    hash ^= hash >> 15;
+    hash = hash.wrapping_mul(0x85eb_ca6b);
+    hash ^= hash >> 13;
+    hash = hash.wrapping_mul(0xc2b2_ae35);
+    hash ^= hash >> 16;
+    hash as f32 / u32::MAX as f32
+}
@@ -0,0 +1,119 @@
+use compact_str::CompactString;
+use serde::{Deserialize, Serialize};
+
+use crate::error::PError;
+use crate::world::World;
+use crate::{ptry, punwrap};
+
+use super::json_parsing::room_descriptor_json;
+use super::starting_level_generator::spawn_from_archetype;
+
+// What has to happen in a room before its exit unlocks
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum RoomObjective {
+    None, // the exit is open from the start
+    #[default]
+    Clear, // kill everything that was spawned
+    Survive {seconds: f32},
+    Waves {waves: Vec<CompactString>}, // spawn archetypes spawned one after another once the previous wave is dead, after the room's own spawns
+}
+
+// The room the player is currently in, the starting level has none
+#[derive(Debug, Clone)]
+pub struct RoomState {
+    pub name: CompactString,
+    pub origin: [usize; 2], // tile the room was generated at
+    pub objective: RoomObjective,
+    pub spawned: Vec<usize>, // entities that have to die for the room to be cleared
+    pub spawnable: Vec<[usize; 2]>, // where waves spawn, procedural rooms have a new set every time
+    pub time_left: f32, // seconds left in survive rooms
+    pub next_wave: usize,
+    pub cleared: bool,
+    pub darkness: f32,
+}
+
+impl World {
+    pub fn start_room(&mut self, room: &room_descriptor_json, origin: [usize; 2], spawned: Vec<usize>) {
+        let time_left = if let RoomObjective::Survive{seconds} = room.objective {seconds} else {0.0};
+        self.cur_room = Some(RoomState {
+            name: room.name.clone(),
+            origin,
+            objective: room.objective.clone(),
+            spawned,
+            spawnable: room.spawnable.iter().filter(|position| !room.entities.iter().any(|x| [x.x, x.y] == **position)).copied().collect(),
+            time_left,
+            next_wave: 0,
+            cleared: room.objective == RoomObjective::None,
+            darkness: room.darkness,
+        });
+    }
+    pub fn is_exit_locked(&self) -> bool {
+        self.cur_room.as_ref().is_some_and(|x| !x.cleared)
+    }
+    pub fn update_room_objective(&mut self) -> Result<(), PError> {
+        let Some(room) = self.cur_room.as_mut() else {
+            return Ok(());
+        };
+        if room.cleared {
+            return Ok(());
+        }
+        let components = &self.components;
+        let to_be_killed = self.entities_to_be_killed_at_end_of_frame.borrow();
+        room.spawned.retain(|x| components.position_components.get(*x).is_some_and(|x| x.is_some()) && !to_be_killed.contains(x));
+        drop(to_be_killed);
+        let done = match &room.objective {
+            RoomObjective::None => true,
+            RoomObjective::Clear => room.spawned.is_empty(),
+            RoomObjective::Survive{..} => {
+                room.time_left -= 1.0/60.0;
+                room.time_left <= 0.0
+            },
+            RoomObjective::Waves{waves} => {
+                if room.spawned.is_empty() && room.next_wave < waves.len() {
+                    let wave = waves[room.next_wave].clone();
+                    room.next_wave += 1;
+                    let name = room.name.clone();
+                    let spawned = ptry!(self.spawn_room_wave(&wave), "while spawning a wave in room {}", name);
+                    punwrap!(self.cur_room.as_mut(), Expected, "the current room went away while spawning a wave").spawned = spawned;
+                    return Ok(());
+                }
+                room.spawned.is_empty()
+            }
+        };
+        if done {
+            ptry!(self.clear_room());
+        }
+        Ok(())
+    }
+    pub fn spawn_room_wave(&mut self, wave: &str) -> Result<Vec<usize>, PError> {
+        let room = punwrap!(self.cur_room.as_ref(), Expected, "tried to spawn a wave while not in a room");
+        let (origin, mut spawnable) = (room.origin, room.spawnable.clone());
+        let spawn_archetype = punwrap!(self.spawn_archetype_descriptors.get(wave), Invalid, "Room {} has a wave of spawn archetype {} but there is no spawn archetype with name {}", room.name, wave, wave).clone();
+        spawn_from_archetype(self, &spawn_archetype, &mut spawnable, origin[0], origin[1])
+    }
+    // unlocks the exit and drops the room's reward
+    pub fn clear_room(&mut self) -> Result<(), PError> {
+        let room = punwrap!(self.cur_room.as_mut(), Expected, "tried to clear a room while not in one");
+        room.cleared = true;
+        let (name, origin) = (room.name.clone(), room.origin);
+        let room_descriptor = punwrap!(self.room_descriptors.get(&name), NotFound, "Could not find room {}", name);
+        if let Some(reward) = room_descriptor.reward.clone() {
+            let position = room_descriptor.reward_position.unwrap_or(room_descriptor.entrance);
+            ptry!(self.create_entity_with_archetype((position[0] + origin[0]) as f32 * 32.0, (position[1] + origin[1]) as f32 * 32.0, reward), "while placing the reward of room {}", name);
+        }
+        Ok(())
+    }
+    pub fn get_room_objective_text(&self) -> Option<String> {
+        let room = self.cur_room.as_ref()?;
+        if room.cleared {
+            return None;
+        }
+        match &room.objective {
+            RoomObjective::None => None,
+            RoomObjective::Clear => Some(format!("Enemies left: {}", room.spawned.len())),
+            RoomObjective::Survive{..} => Some(format!("Survive: {}s", room.time_left.ceil() as usize)),
+            RoomObjective::Waves{waves} => Some(format!("Wave {}/{}, enemies left: {}", room.next_wave + 1, waves.len() + 1, room.spawned.len())),
+        }
+    }
+}
@@ -1,9 +1,11 @@
 use compact_str::CompactString;
 
 use rand::prelude::*;
+use crate::game_engine::room_generation::generate_room_layout;
+use crate::game_engine::lighting::TORCH_COLOR;
 use crate::game_engine::terrain::TerrainTags;
 use crate::game_engine::world::World;
-use crate::json_parsing::ParsedData;
+use crate::json_parsing::{spawn_archetype_json, terrain_archetype_json, ParsedData};
 use crate::game_engine::player::Player;
 use crate::{perror, ptry, punwrap};
 use crate::error::PError;
@@ -16,6 +18,13 @@ pub fn generate_world_from_json_parsed_data(data: &ParsedData) -> Result<World,
     let mut world = ptry!(World::new(Player::new(player_descriptor.x, player_descriptor.y, player_descriptor.health, player_descriptor.max_health, player_descriptor.movement_speed, data.sprites.get_sprite_id("player_front").expect("Couldn't find player_front sprite")), data.sprites.clone()), "while generating world from json data");
     world.item_archetype_lookup = data.item_archetypes.clone();
     world.loot_table_lookup = data.loot_table_lookup.clone();
+    world.crafting_book = data.crafting_book.clone();
+    world.progression = data.progression.clone();
+    world.biomes = data.biomes.clone();
+    world.particles.emitters = data.particle_emitters.clone();
+    for ability in data.progression.starting_abilities.iter() {
+        ptry!(world.unlock_ability(ability), "while giving the player their starting abilities");
+    }
 
     for archetype in data.entity_archetypes.iter(){
         world.add_entity_archetype(archetype.0.clone(), archetype.1.clone());
@@ -34,7 +43,7 @@ pub fn generate_world_from_json_parsed_data(data: &ParsedData) -> Result<World,
     world.terrain_archetype_jsons = data.terrain_archetypes.clone();
 
     for archetype in data.terrain_archetypes.iter(){
-        world.add_terrain_archetype(archetype.0.clone(), ptry!(match_terrain_tags(&archetype.1.basic_tags), "while generating world from json data"));
+        world.add_terrain_archetype(archetype.0.clone(), ptry!(match_terrain_tags(archetype.1), "while generating world from json data"));
     }
     for terrain_json in starting_level_descriptor.terrain.iter(){
         ptry!(world.generate_terrain_from_descriptor(terrain_json, 0, 0));
@@ -45,16 +54,64 @@ pub fn generate_world_from_json_parsed_data(data: &ParsedData) -> Result<World,
     Ok(world)
 }
 
+pub fn give_starting_items(world: &mut World) -> Result<(), PError> {
+    let sword = world.inventory.add_item(
+        ptry!(world.create_item_with_archetype(CompactString::from("basic sword")))
+    );
+    let spear = world.inventory.add_item(
+        ptry!(world.create_item_with_archetype(CompactString::from("basic spear")))
+    );
+    world.inventory.init_ui();
+    ptry!(world.inventory.set_hotbar_slot_item(3, spear));
+    ptry!(world.inventory.set_hotbar_slot_item(0, sword));
+    Ok(())
+}
+
 
 
 
-pub fn match_terrain_tags (tags: &Vec<CompactString>) -> Result<Vec<TerrainTags>, PError> {
+pub fn match_terrain_tags (archetype: &terrain_archetype_json) -> Result<Vec<TerrainTags>, PError> {
     let mut tags_ = Vec::new();
-    for tag in tags{
+    for tag in archetype.basic_tags.iter(){
         match tag.as_str(){
             "blocksMovement" => {
                 tags_.push(TerrainTags::BlocksMovement);
             },
+            "blocksProjectiles" => {
+                tags_.push(TerrainTags::BlocksProjectiles);
+            },
+            "water" => {
+                tags_.push(TerrainTags::Water);
+            },
+            "ice" => {
+                tags_.push(TerrainTags::Ice);
+            },
+            "slowsMovement" => {
+                let multiplier = punwrap!(archetype.movement_multiplier, JSONValidationError, "terrain archetype {} has the slowsMovement tag but no movement multiplier", archetype.name);
+                tags_.push(TerrainTags::SlowsMovement(multiplier));
+            },
+            "damagesOnContact" => {
+                let damage = punwrap!(archetype.contact_damage, JSONValidationError, "terrain archetype {} has the damagesOnContact tag but no contact damage", archetype.name);
+                let interval = punwrap!(archetype.contact_interval, JSONValidationError, "terrain archetype {} has the damagesOnContact tag but no contact interval", archetype.name);
+                if interval == 0 {
+                    return Err(perror!(JSONValidationError, "terrain archetype {} has a contact interval of 0", archetype.name));
+                }
+                tags_.push(TerrainTags::DamagesOnContact{damage, interval});
+            },
+            "appliesStatus" => {
+                let status = punwrap!(archetype.status.clone(), JSONValidationError, "terrain archetype {} has the appliesStatus tag but no status", archetype.name);
+                if status != "fire" && status != "poison" {
+                    return Err(perror!(JSONValidationError, "terrain archetype {} applies status {}, only fire and poison exist", archetype.name, status));
+                }
+                tags_.push(TerrainTags::AppliesStatus(status));
+            },
+            "light" => {
+                let radius = punwrap!(archetype.light_radius, JSONValidationError, "terrain archetype {} has the light tag but no light radius", archetype.name);
+                if radius <= 0.0 {
+                    return Err(perror!(JSONValidationError, "terrain archetype {} has a light radius of {}, it should be more than 0", archetype.name, radius));
+                }
+                tags_.push(TerrainTags::Light{radius, color: archetype.light_color.unwrap_or(TORCH_COLOR)});
+            },
             _ => {
                 return Err(perror!(Invalid, "Found unknown terrain tag: {}", tag));
             }
@@ -65,41 +122,88 @@ pub fn match_terrain_tags (tags: &Vec<CompactString>) -> Result<Vec<TerrainTags>
 
 
 pub fn generate_room(world: &mut World, room: CompactString, x: usize, y: usize) -> Result<(), PError> {
-    let room_descriptor = punwrap!(world.room_descriptors.get(&room), NotFound, "Could not find room {}", room).clone();
-    let spawn_archetype = punwrap!(world.spawn_archetype_descriptors.get(&room_descriptor.spawn_archetype), Invalid, "Room {} refers to spawn archetype {} but there is no spawn archetype with name {}", room, room_descriptor.spawn_archetype, room_descriptor.spawn_archetype).clone();
+    let mut room_descriptor = punwrap!(world.room_descriptors.get(&room), NotFound, "Could not find room {}", room).clone();
+    let mut spawn_archetype = punwrap!(world.spawn_archetype_descriptors.get(&room_descriptor.spawn_archetype), Invalid, "Room {} refers to spawn archetype {} but there is no spawn archetype with name {}", room, room_descriptor.spawn_archetype, room_descriptor.spawn_archetype).clone();
+    ptry!(world.apply_biome(&mut room_descriptor, &mut spawn_archetype), "while applying the biome to room {}", room);
+    let specials: Vec<[usize; 2]> = spawn_archetype.special.iter().map(|x| [x.x, x.y]).collect();
+    let room_descriptor = ptry!(generate_room_layout(&room_descriptor, &specials, &world.terrain_archetype_jsons, &mut rand::thread_rng()), "while generating the layout of room {}", room);
 
     let mut spawnable = room_descriptor.spawnable.clone();
     for terrain in room_descriptor.terrain.iter(){
         ptry!(world.generate_terrain_from_descriptor(terrain, x as i32, y as i32));
     }
+    for entity in room_descriptor.entities.iter() {
+        spawnable.retain(|position| *position != [entity.x, entity.y]);
+        ptry!(world.create_entity_with_archetype(entity.x as f32 * 32.0 + x as f32 * 32.0, entity.y as f32 * 32.0 + y as f32 * 32.0, entity.archetype.clone()), "while placing entities in room {}", room);
+    }
 
-    let mut cur_points = 0;
+    let spawned = ptry!(spawn_from_archetype(world, &spawn_archetype, &mut spawnable, x, y), "while spawning enemies in room {}", room);
+    world.start_room(&room_descriptor, [x, y], spawned);
+    Ok(())
+}
+
+pub const ELITE_POINT_MULTIPLIER: usize = 2;
+
+// places the special spawns, then spawns entities from the spawn archetype on the spawnable positions, taking the positions it uses
+pub fn spawn_from_archetype(world: &mut World, spawn_archetype: &spawn_archetype_json, spawnable: &mut Vec<[usize; 2]>, x: usize, y: usize) -> Result<Vec<usize>, PError> {
+    let mut spawned = Vec::new();
     let mut rng = rand::thread_rng();
 
-    while cur_points < spawn_archetype.total_points_to_spawn && !spawnable.is_empty() {
-        let mut options = Vec::new();
-        for option in spawn_archetype.basic.iter() {
-            if option.points + option.points <= spawn_archetype.total_points_to_spawn {
-                options.push(option);
+    for special in spawn_archetype.special.iter() {
+        spawnable.retain(|position| *position != [special.x, special.y]);
+        spawned.push(ptry!(world.create_entity_with_archetype((special.x + x) as f32 * 32.0, (special.y + y) as f32 * 32.0, special.archetype.clone()), "while placing the special spawns of {}", spawn_archetype.name));
+    }
+    let choices = ptry!(choose_spawns(spawn_archetype, world.depth, spawnable.len(), &mut rng));
+    for archetype in choices {
+        let position = spawnable.swap_remove(rng.gen_range(0..spawnable.len()));
+        let real_position = [position[0] as f32 * 32.0 + x as f32 * 32.0, position[1] as f32 * 32.0 + y as f32 * 32.0];
+        spawned.push(ptry!(world.create_entity_with_archetype(real_position[0], real_position[1], archetype)));
+    }
+    Ok(spawned)
+}
+
+// picks what to spawn with the points of the spawn archetype, at most one entity per slot
+// minimums come first, then options are picked by weight until nothing fits in the points left
+pub fn choose_spawns<R: Rng>(spawn_archetype: &spawn_archetype_json, depth: usize, slots: usize, rng: &mut R) -> Result<Vec<CompactString>, PError> {
+    let total_points = spawn_archetype.total_points_to_spawn + spawn_archetype.points_per_depth * depth;
+    let elite_bonus = spawn_archetype.elite_chance_per_depth * depth as f32;
+    let mut counts = vec![0; spawn_archetype.basic.len()];
+    let mut cur_points = 0;
+    let mut chosen = Vec::new();
+
+    let pick = |option: usize, cur_points: &mut usize, rng: &mut R| {
+        let option = &spawn_archetype.basic[option];
+        let elite_points = option.points * ELITE_POINT_MULTIPLIER;
+        if let Some(elite) = option.elite.as_ref() {
+            if *cur_points + elite_points <= total_points && rng.gen::<f32>() < option.elite_chance + elite_bonus {
+                *cur_points += elite_points;
+                return elite.clone();
+            }
+        }
+        *cur_points += option.points;
+        option.archetype.clone()
+    };
+
+    for (i, option) in spawn_archetype.basic.iter().enumerate() {
+        for _ in 0..option.min {
+            if chosen.len() >= slots {
+                return Ok(chosen);
             }
+            chosen.push(pick(i, &mut cur_points, rng));
+            counts[i] += 1;
         }
+    }
+    while chosen.len() < slots {
+        let options: Vec<usize> = (0..spawn_archetype.basic.len()).filter(|i| {
+            let option = &spawn_archetype.basic[*i];
+            option.weight > 0.0 && cur_points + option.points <= total_points && option.max.is_none_or(|max| counts[*i] < max)
+        }).collect();
         if options.is_empty() {
             break;
         }
-        let choice = if options.len() == 1 {
-            options[0]
-        } else {
-            options[rng.gen_range(0..options.len() - 1)]
-        };
-        let position = if spawnable.len() == 1 {
-            spawnable.pop().unwrap()
-        } else {
-            spawnable.remove(rng.gen_range(0..spawnable.len() - 1))
-        };
-        let real_position = [position[0] as f32 * 32.0 + x as f32 * 32.0, position[1] as f32 * 32.0 + y as f32 * 32.0];
-        ptry!(world.create_entity_with_archetype(real_position[0], real_position[1], choice.archetype.clone()));
-        cur_points += choice.points;
+        let choice = *options.choose_weighted(rng, |i| spawn_archetype.basic[*i].weight).map_err(|e| perror!(Invalid, "Could not pick a spawn from spawn archetype {}: {}", spawn_archetype.name, e))?;
+        chosen.push(pick(choice, &mut cur_points, rng));
+        counts[choice] += 1;
     }
-    
-    Ok(())
+    Ok(chosen)
 }
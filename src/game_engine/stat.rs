@@ -3,7 +3,8 @@ use rand::Rng;
 use serde::Deserialize;
 use serde::Serialize;
 
-#[derive(Debug, Clone, Default, Copy)]
+#[derive(Debug, Clone, Default, Copy, Serialize, Deserialize)]
+#[serde(default)]
 pub struct StatC {
     pub flat: f32,
     pub percent: f32,
@@ -51,7 +52,7 @@ impl GearStatC {
 }
 macro_rules! create_stat_lists {
     ($($stat_name:ident => $def:expr),*) => {
-        #[derive(Debug, Clone, Default)]
+        #[derive(Debug, Clone, Default, Serialize, Deserialize)]
         pub struct StatList {
             $(pub $stat_name: Option<StatC>,)*
         }
@@ -73,6 +74,12 @@ macro_rules! create_stat_lists {
                     )*
                 }
             }
+            pub fn get_stat_mut(&mut self, name: &str) -> Option<&mut Option<StatC>> {
+                match name {
+                    $( stringify!($stat_name) => Some(&mut self.$stat_name), )*
+                    _ => None
+                }
+            }
         }
         #[derive(Debug, Clone, Serialize, Deserialize, Default)]
         pub struct GearStatList {
@@ -119,12 +126,15 @@ create_stat_lists!(
     defense => 0.0, // 100.0 defense should mean half damage, -100.0 defense should mean double
                     // damage taken
     health_regen => 1.0,
+    block => 0.0, // percent of damage blocked from the front while blocking with a shield
     healing_effectiveness => 100.0, // 0% healing effectiveness = 0 healing
     damage => 0.0,
     lifesteal => 0.0, // 100.0 = 100% of damage is lifestealed
     attack_cooldown => 0.0,
     crit_chance => 3.0,
     crit_damage => 175.0,
+    knockback => 3.0, // initial knockback speed given to hit entities, in pixels per frame
+    hitstun => 10.0, // frames hit entities can't move or attack for
 
     max_mana => 100.0,
     mana_regen => 6.0,
@@ -146,9 +156,78 @@ create_stat_lists!(
     fire_damage => 0.0,
     fire_tick_speed => 1.0, 
     fire_duration => 120.0,
+    fire_resistance => 0.0, // resistances work like defense does for physical damage
+    poison_resistance => 0.0,
+    lightning_resistance => 0.0,
+    arcane_resistance => 0.0,
     loot => 100.0
 );
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DamageType {
+    #[default]
+    Physical,
+    Fire,
+    Poison,
+    Lightning,
+    Arcane
+}
+
+impl DamageType {
+    pub fn color(&self) -> [f32; 4] {
+        match self {
+            DamageType::Physical => [1.0, 1.0, 1.0, 1.0],
+            DamageType::Fire => [1.0, 0.4, 0.0, 1.0],
+            DamageType::Poison => [0.6, 0.0, 0.8, 1.0],
+            DamageType::Lightning => [1.0, 0.95, 0.3, 1.0],
+            DamageType::Arcane => [0.3, 0.6, 1.0, 1.0],
+        }
+    }
+}
+
+// Per type resistances for entities, the player gets these from their stats instead
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Resistances {
+    pub physical: f32,
+    pub fire: f32,
+    pub poison: f32,
+    pub lightning: f32,
+    pub arcane: f32,
+}
+
+impl Resistances {
+    pub fn get(&self, damage_type: DamageType) -> f32 {
+        match damage_type {
+            DamageType::Physical => self.physical,
+            DamageType::Fire => self.fire,
+            DamageType::Poison => self.poison,
+            DamageType::Lightning => self.lightning,
+            DamageType::Arcane => self.arcane,
+        }
+    }
+}
+
+impl StatList {
+    // physical damage is mitigated by defense
+    pub fn get_resistance(&self, damage_type: DamageType) -> f32 {
+        let stat = match damage_type {
+            DamageType::Physical => self.defense,
+            DamageType::Fire => self.fire_resistance,
+            DamageType::Poison => self.poison_resistance,
+            DamageType::Lightning => self.lightning_resistance,
+            DamageType::Arcane => self.arcane_resistance,
+        };
+        stat.map(|x| x.get_value()).unwrap_or(0.0)
+    }
+}
+
+// 100.0 resistance means half damage, -100.0 means double damage
+pub fn resistance_multiplier(resistance: f32) -> f32 {
+    if resistance < 0.0 {resistance.abs()/100.0 + 1.0} else {1.0/(resistance/100.0 + 1.0)}
+}
+
 // cooldown is number of frames, 60 fps, the display is adjusted. so for a 1s cooldown, do a cooldown of 60.
 
 #[macro_export]
@@ -1,11 +1,25 @@
+use compact_str::CompactString;
+
 #[derive(Copy, Clone, Debug)]
 pub struct Terrain{ // terrain is always 32x32 pixels
     pub element_id: usize,
     pub x: usize,
     pub y: usize
 }
-#[derive(Copy, Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum TerrainTags {
     BlocksMovement,
-    Sprite(usize)
+    Sprite(usize),
+    SlowsMovement(f32), // multiplier on the speed of anything walking over it
+    DamagesOnContact{damage: f32, interval: usize}, // damage dealt every interval frames to anything standing on it
+    AppliesStatus(CompactString), // fire or poison
+    BlocksProjectiles,
+    Water, // blocks anything that walks, flying entities can cross it
+    Ice, // anything walking keeps sliding in the direction it was going
+    Light{radius: f32, color: [f32; 4]}, // torches and anything else that glows
 }
+
+pub const ICE_TRACTION: f32 = 0.05; // fraction of the wanted movement that is picked up each frame on ice
+pub const TERRAIN_STATUS_DAMAGE: f32 = 1.0;
+pub const TERRAIN_STATUS_LIFETIME: f32 = 120.0;
+pub const TERRAIN_STATUS_TIME_PER_TICK: f32 = 30.0;
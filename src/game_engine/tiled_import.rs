@@ -0,0 +1,393 @@
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+
+use compact_str::CompactString;
+use rustc_hash::FxHashMap;
+use serde::Deserialize;
+
+use crate::error::PError;
+use crate::{perror, ptry, punwrap};
+
+use super::json_parsing::{entity_json, player_json, room_descriptor_json, special_spawn_json, starting_level_json, terrain_json, JSON_parser, PATH_BUNDLE};
+use super::room_format::validate_room;
+use super::room_generation::RoomType;
+use super::room_objectives::RoomObjective;
+
+pub const TILED_IMPORT_COMMAND: &str = "import-tiled";
+pub const TILED_TERRAIN_PROPERTY: &str = "terrain_archetype";
+pub const TILED_ARCHETYPE_PROPERTY: &str = "archetype";
+pub const TILED_SPAWN_ARCHETYPE_PROPERTY: &str = "spawn_archetype";
+
+// Tiled stores flipped and rotated tiles in the top bits of the gid
+const TILED_GID_MASK: u32 = 0x1FFFFFFF;
+
+// Only the parts of the Tiled JSON format (.tmj/.tsj) the importer uses
+#[derive(Debug, Deserialize)]
+pub struct TiledMap {
+    pub width: usize,
+    pub height: usize,
+    pub tilewidth: f32,
+    pub tileheight: f32,
+    #[serde(default)]
+    pub infinite: bool,
+    #[serde(default)]
+    pub properties: Vec<TiledProperty>,
+    pub layers: Vec<TiledLayer>,
+    #[serde(default)]
+    pub tilesets: Vec<TiledTileset>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TiledProperty {
+    pub name: CompactString,
+    pub value: serde_json::Value,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type")]
+pub enum TiledLayer {
+    #[serde(rename = "tilelayer")]
+    TileLayer {
+        name: CompactString,
+        #[serde(default)]
+        data: Option<TiledLayerData>,
+    },
+    #[serde(rename = "objectgroup")]
+    ObjectGroup {
+        name: CompactString,
+        objects: Vec<TiledObject>,
+    },
+    #[serde(rename = "group")]
+    Group {
+        layers: Vec<TiledLayer>,
+    },
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub enum TiledLayerData {
+    Csv(Vec<u32>),
+    Encoded(String), // base64, possibly compressed
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TiledObject {
+    #[serde(default)]
+    pub name: CompactString,
+    #[serde(default, rename = "type")]
+    pub r#type: CompactString,
+    #[serde(default)]
+    pub class: CompactString, // Tiled 1.9 called the object type its class
+    pub x: f32,
+    pub y: f32,
+    #[serde(default)]
+    pub width: f32,
+    #[serde(default)]
+    pub height: f32,
+    #[serde(default)]
+    pub gid: Option<u32>,
+    #[serde(default)]
+    pub properties: Vec<TiledProperty>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TiledTileset {
+    pub firstgid: u32,
+    #[serde(default)]
+    pub source: Option<String>, // external .tsj tileset, relative to the map
+    #[serde(default)]
+    pub tiles: Vec<TiledTile>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TiledTilesetFile {
+    #[serde(default)]
+    pub tiles: Vec<TiledTile>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TiledTile {
+    pub id: u32,
+    #[serde(default)]
+    pub properties: Vec<TiledProperty>,
+}
+
+pub fn get_tiled_property<'a>(properties: &'a [TiledProperty], name: &str) -> Option<&'a str> {
+    properties.iter().find(|x| x.name == name).and_then(|x| x.value.as_str())
+}
+
+impl TiledObject {
+    pub fn kind(&self) -> &str {
+        if self.class.is_empty() {self.r#type.as_str()} else {self.class.as_str()}
+    }
+}
+
+// what a map turned into, before it is made into a room or a starting level
+#[derive(Debug, Clone, Default)]
+pub struct TiledImport {
+    pub width: usize,
+    pub height: usize,
+    pub terrain: Vec<terrain_json>,
+    pub entities: Vec<entity_json>, // in game pixels
+    pub entrance: Option<[usize; 2]>,
+    pub exit: Option<[usize; 2]>,
+    pub spawnable: Vec<[usize; 2]>,
+    pub spawn_archetype: Option<CompactString>,
+}
+
+pub fn read_tiled_map(path: &Path) -> Result<TiledMap, PError> {
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(e) => return Err(perror!(NotFound, "could not open Tiled map {}: {}", path.display(), e))
+    };
+    let mut map: TiledMap = match serde_json::from_reader(BufReader::new(file)) {
+        Ok(map) => map,
+        Err(e) => return Err(perror!(Invalid, "Tiled map {} is not a valid Tiled JSON map: {}", path.display(), e))
+    };
+    let directory = path.parent().unwrap_or(Path::new("."));
+    for tileset in map.tilesets.iter_mut() {
+        let Some(source) = tileset.source.as_ref() else {
+            continue;
+        };
+        let tileset_path = directory.join(source);
+        let file = match File::open(&tileset_path) {
+            Ok(file) => file,
+            Err(e) => return Err(perror!(NotFound, "could not open tileset {} used by Tiled map {}: {}", tileset_path.display(), path.display(), e))
+        };
+        let data: TiledTilesetFile = match serde_json::from_reader(BufReader::new(file)) {
+            Ok(data) => data,
+            Err(e) => return Err(perror!(Invalid, "tileset {} is not a valid Tiled JSON tileset, only .tsj tilesets can be imported: {}", tileset_path.display(), e))
+        };
+        tileset.tiles = data.tiles;
+    }
+    Ok(map)
+}
+
+pub fn import_tiled_map(map: &TiledMap) -> Result<TiledImport, PError> {
+    if map.infinite {
+        return Err(perror!(Invalid, "infinite Tiled maps can't be imported, turn off infinite in the map properties"));
+    }
+    let mut archetypes: FxHashMap<u32, CompactString> = FxHashMap::default();
+    for tileset in map.tilesets.iter() {
+        for tile in tileset.tiles.iter() {
+            if let Some(archetype) = get_tiled_property(&tile.properties, TILED_TERRAIN_PROPERTY) {
+                archetypes.insert(tileset.firstgid + tile.id, CompactString::from(archetype));
+            }
+        }
+    }
+    let mut import = TiledImport {
+        width: map.width,
+        height: map.height,
+        spawn_archetype: get_tiled_property(&map.properties, TILED_SPAWN_ARCHETYPE_PROPERTY).map(CompactString::from),
+        ..Default::default()
+    };
+    ptry!(import_layers(map, &map.layers, &archetypes, &mut import));
+    Ok(import)
+}
+
+fn import_layers(map: &TiledMap, layers: &[TiledLayer], archetypes: &FxHashMap<u32, CompactString>, import: &mut TiledImport) -> Result<(), PError> {
+    for layer in layers.iter() {
+        match layer {
+            TiledLayer::TileLayer { name, data } => {
+                let data = match data {
+                    Some(TiledLayerData::Csv(data)) => data,
+                    Some(TiledLayerData::Encoded(_)) => return Err(perror!(Invalid, "tile layer {} is base64 encoded, set the layer format to CSV to import it", name)),
+                    None => continue
+                };
+                if data.len() != map.width * map.height {
+                    return Err(perror!(Invalid, "tile layer {} has {} tiles but the map is {}x{}", name, data.len(), map.width, map.height));
+                }
+                let mut tiles = Vec::with_capacity(data.len());
+                for (i, gid) in data.iter().enumerate() {
+                    let gid = gid & TILED_GID_MASK;
+                    if gid == 0 {
+                        tiles.push(None);
+                        continue;
+                    }
+                    let archetype = punwrap!(archetypes.get(&gid), Invalid, "tile {} at [{}, {}] in layer {} has no {} property", gid, i % map.width, i / map.width, name, TILED_TERRAIN_PROPERTY);
+                    tiles.push(Some(archetype.clone()));
+                }
+                import.terrain.extend(merge_tiles(&tiles, map.width, map.height));
+            },
+            TiledLayer::ObjectGroup { name, objects } => {
+                for object in objects.iter() {
+                    ptry!(import_object(map, object, import), "while importing object layer {}", name);
+                }
+            },
+            TiledLayer::Group { layers } => {
+                ptry!(import_layers(map, layers, archetypes, import));
+            },
+            TiledLayer::Other => {}
+        }
+    }
+    Ok(())
+}
+
+fn import_object(map: &TiledMap, object: &TiledObject, import: &mut TiledImport) -> Result<(), PError> {
+    // tile objects are anchored at their bottom left instead of their top left
+    let top = if object.gid.is_some() {object.y - object.height} else {object.y};
+    let x = object.x.max(0.0);
+    let y = top.max(0.0);
+    let tile = [(x / map.tilewidth).floor() as usize, (y / map.tileheight).floor() as usize];
+    match object.kind() {
+        "entity" => {
+            let archetype = get_tiled_property(&object.properties, TILED_ARCHETYPE_PROPERTY).unwrap_or(object.name.as_str());
+            if archetype.is_empty() {
+                return Err(perror!(Invalid, "entity object at [{}, {}] has no {} property or name", object.x, object.y, TILED_ARCHETYPE_PROPERTY));
+            }
+            import.entities.push(entity_json {
+                x: x / map.tilewidth * 32.0,
+                y: y / map.tileheight * 32.0,
+                archetype: CompactString::from(archetype)
+            });
+        },
+        "entrance" | "player" => {
+            import.entrance = Some(tile);
+        },
+        "exit" => {
+            import.exit = Some(tile);
+        },
+        "spawnable" => {
+            // rectangles mark every tile they cover
+            let right = ((x + object.width) / map.tilewidth).ceil().max(tile[0] as f32 + 1.0) as usize;
+            let bottom = ((y + object.height) / map.tileheight).ceil().max(tile[1] as f32 + 1.0) as usize;
+            for ty in tile[1]..bottom {
+                for tx in tile[0]..right {
+                    if !import.spawnable.contains(&[tx, ty]) {
+                        import.spawnable.push([tx, ty]);
+                    }
+                }
+            }
+        },
+        kind => {
+            return Err(perror!(Invalid, "object {} at [{}, {}] has unknown type {}, expected entity, entrance, player, exit or spawnable", object.name, object.x, object.y, kind));
+        }
+    }
+    Ok(())
+}
+
+// merges a grid of tiles into as few terrain rectangles as it easily can, rows first then matching rows below them
+pub fn merge_tiles(tiles: &[Option<CompactString>], width: usize, height: usize) -> Vec<terrain_json> {
+    let mut terrain: Vec<terrain_json> = Vec::new();
+    for y in 0..height {
+        let mut x = 0;
+        while x < width {
+            let Some(archetype) = tiles[y * width + x].as_ref() else {
+                x += 1;
+                continue;
+            };
+            let start = x;
+            while x < width && tiles[y * width + x].as_ref() == Some(archetype) {
+                x += 1;
+            }
+            let above = terrain.iter_mut().find(|rect| rect.x == start && rect.width == x - start && rect.y + rect.height == y && rect.terrain_archetype == archetype);
+            match above {
+                Some(rect) => rect.height += 1,
+                None => terrain.push(terrain_json {x: start, y, width: x - start, height: 1, terrain_archetype: archetype.clone()})
+            }
+        }
+    }
+    terrain
+}
+
+pub fn tiled_to_room(import: &TiledImport, name: CompactString) -> Result<room_descriptor_json, PError> {
+    Ok(room_descriptor_json {
+        spawn_archetype: punwrap!(import.spawn_archetype.clone(), Invalid, "room {} needs a {} map property", name, TILED_SPAWN_ARCHETYPE_PROPERTY),
+        entrance: punwrap!(import.entrance, Invalid, "room {} needs an entrance object", name),
+        exit: punwrap!(import.exit, Invalid, "room {} needs an exit object", name),
+        terrain: import.terrain.clone(),
+        width: import.width,
+        height: import.height,
+        spawnable: import.spawnable.clone(),
+        entities: import.entities.iter().map(|x| special_spawn_json {
+            x: (x.x / 32.0).floor() as usize,
+            y: (x.y / 32.0).floor() as usize,
+            archetype: x.archetype.clone()
+        }).collect(),
+        objective: RoomObjective::default(),
+        reward: None,
+        reward_position: None,
+        room_type: RoomType::Authored,
+        generation: None,
+        darkness: 0.0,
+        name,
+    })
+}
+
+// the player keeps its stats, only its position comes from the map
+pub fn tiled_to_level(import: &TiledImport, player: &player_json) -> starting_level_json {
+    let mut player = player.clone();
+    if let Some(entrance) = import.entrance {
+        player.x = entrance[0] as f32 * 32.0;
+        player.y = entrance[1] as f32 * 32.0;
+    }
+    starting_level_json {
+        player,
+        entities: import.entities.clone(),
+        terrain: import.terrain.clone()
+    }
+}
+
+// checks that everything the import refers to exists in the game data
+pub fn validate_tiled_import(import: &TiledImport, parser: &JSON_parser) -> Result<(), PError> {
+    for terrain in import.terrain.iter() {
+        if !parser.terrain_archetypes_json.contains_key(&terrain.terrain_archetype) {
+            return Err(perror!(JSONValidationError, "imported map uses terrain archetype {} which doesn't exist", terrain.terrain_archetype));
+        }
+    }
+    for entity in import.entities.iter() {
+        if !parser.entity_archetypes_json.contains_key(&entity.archetype) {
+            return Err(perror!(JSONValidationError, "imported map places entity archetype {} which doesn't exist", entity.archetype));
+        }
+    }
+    Ok(())
+}
+
+// import-tiled <map.tmj> room <name> | level [--print]
+pub fn run_tiled_import_command(args: &[String]) -> Result<(), PError> {
+    let usage = "usage: import-tiled <map.tmj> room <name> [--print] | import-tiled <map.tmj> level [--print]";
+    let print = args.iter().any(|x| x == "--print");
+    let args: Vec<&String> = args.iter().filter(|x| *x != "--print").collect();
+    let path = punwrap!(args.first(), InputFailed, "{}", usage);
+    let target = punwrap!(args.get(1), InputFailed, "{}", usage);
+
+    let mut parser = JSON_parser::new();
+    parser.parse_terrain_archetypes(PATH_BUNDLE.terrain_archetypes_path);
+    parser.parse_entity_archetypes(PATH_BUNDLE.entity_archetypes_path);
+    parser.parse_starting_level(PATH_BUNDLE.starting_level_path);
+    parser.parse_rooms(PATH_BUNDLE.rooms_path);
+    parser.parse_spawn_archetypes(PATH_BUNDLE.spawn_archetypes_path);
+
+    let map = ptry!(read_tiled_map(Path::new(path.as_str())));
+    let import = ptry!(import_tiled_map(&map), "while importing Tiled map {}", path);
+    ptry!(validate_tiled_import(&import, &parser), "while importing Tiled map {}", path);
+
+    let (json, result) = match target.as_str() {
+        "room" => {
+            let name = CompactString::from(punwrap!(args.get(2), InputFailed, "{}", usage).as_str());
+            let room = ptry!(tiled_to_room(&import, name.clone()));
+            ptry!(validate_room(&room, &parser.terrain_archetypes_json, &parser.spawn_archetypes_json), "while importing Tiled map {}", path);
+            let json = serde_json::to_string_pretty(&room);
+            parser.rooms_json.insert(name, room);
+            (json, if print {Ok(())} else {parser.write_rooms(PATH_BUNDLE.rooms_path)})
+        },
+        "level" => {
+            parser.starting_level_json = tiled_to_level(&import, &parser.starting_level_json.player);
+            let json = serde_json::to_string_pretty(&parser.starting_level_json);
+            (json, if print {Ok(())} else {parser.write_starting_level(PATH_BUNDLE.starting_level_path)})
+        },
+        _ => return Err(perror!(InputFailed, "{}", usage))
+    };
+    if let Err(e) = result {
+        return Err(perror!(Error, "failed to write the imported {}: {}", target, e));
+    }
+    match json {
+        Ok(json) if print => println!("{}", json),
+        Ok(_) => println!("Imported {} as {}", path, target),
+        Err(e) => return Err(perror!(Error, "failed to serialize the imported {}: {}", target, e))
+    }
+    Ok(())
+}
@@ -0,0 +1,90 @@
+use std::cell::RefCell;
+
+use crate::error::PError;
+use crate::world::World;
+use crate::{ptry, punwrap};
+
+use super::ability_unlocks::SHRINE_INTERACTION_RANGE;
+use super::item::Item;
+
+pub const INTERACT_KEY: &str = "f";
+pub const VENDOR_INTERACTION_RANGE: f32 = 64.0;
+pub const DEFAULT_STOCK_SIZE: usize = 6;
+
+// The stock is moved out of the vendor's component while the shop is open and put back when it closes
+#[derive(Debug, Clone)]
+pub struct Shop {
+    pub vendor: usize,
+    pub stock: Vec<Item>,
+}
+
+impl World {
+    pub fn get_vendor_in_range(&self) -> Option<usize> {
+        self.get_closest_in_range(&self.components.vendor_components, VENDOR_INTERACTION_RANGE)
+    }
+    pub fn get_shrine_in_range(&self) -> Option<usize> {
+        self.get_closest_in_range(&self.components.shrine_components, SHRINE_INTERACTION_RANGE)
+    }
+    // closest entity to the player that has the given component
+    pub fn get_closest_in_range<T>(&self, components: &[Option<RefCell<T>>], range: f32) -> Option<usize> {
+        self.get_closest_in_range_where(components, range, |_| true)
+    }
+    pub fn get_closest_in_range_where<T>(&self, components: &[Option<RefCell<T>>], range: f32, filter: impl Fn(&T) -> bool) -> Option<usize> {
+        let player = self.player.borrow();
+        let px = player.x + 16.0;
+        let py = player.y + 22.0;
+        let mut closest = None;
+        let mut closest_dist = range;
+        for (id, component) in components.iter().enumerate() {
+            if !component.as_ref().is_some_and(|x| filter(&x.borrow())) {
+                continue;
+            }
+            if let Some(position) = self.components.position_components.get(id).and_then(|x| x.as_ref()) {
+                let position = position.borrow();
+                let dist = f32::sqrt((position.x + 16.0 - px).powf(2.0) + (position.y + 16.0 - py).powf(2.0));
+                if dist <= closest_dist {
+                    closest_dist = dist;
+                    closest = Some(id);
+                }
+            }
+        }
+        closest
+    }
+    pub fn interact(&mut self) -> Result<(), PError> {
+        if self.inventory.shop.is_some() {
+            return Ok(());
+        }
+        if let Some(shrine) = self.get_shrine_in_range() {
+            ptry!(self.activate_shrine(shrine));
+            return Ok(());
+        }
+        if let Some(interactable) = self.get_interactable_in_range() {
+            ptry!(self.activate_interactable(interactable));
+            return Ok(());
+        }
+        if let Some(vendor) = self.get_vendor_in_range() {
+            let vendor_component = punwrap!(self.components.vendor_components[vendor].as_ref(), Invalid, "vendor with id {} in range has no vendor component", vendor);
+            let stock = std::mem::take(&mut vendor_component.borrow_mut().stock);
+            self.inventory.shop = Some(Shop {
+                vendor,
+                stock
+            });
+        }
+        Ok(())
+    }
+    pub fn close_shop(&mut self) -> Result<(), PError> {
+        if let Some(shop) = self.inventory.shop.take() {
+            let vendor_component = punwrap!(self.components.vendor_components.get(shop.vendor).and_then(|x| x.as_ref()), Invalid, "shop was opened for vendor {} which no longer has a vendor component", shop.vendor);
+            vendor_component.borrow_mut().stock = shop.stock;
+        }
+        Ok(())
+    }
+    pub fn roll_vendor_stock(&self, stock_table: &str, stock_size: usize) -> Result<Vec<Item>, PError> {
+        let table = punwrap!(self.loot_table_lookup.get(stock_table), NotFound, "could not find stock table {}", stock_table);
+        let mut stock = Vec::new();
+        for archetype in table.roll_items(stock_size as f32 * 100.0) {
+            stock.push(ptry!(self.create_item_with_archetype(archetype), "while rolling stock from stock table {}", stock_table));
+        }
+        Ok(stock)
+    }
+}
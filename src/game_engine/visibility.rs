@@ -0,0 +1,120 @@
+use rustc_hash::FxHashSet;
+
+use crate::world::World;
+
+use super::terrain::TerrainTags;
+
+pub const SIGHT_RADIUS: usize = 12; // tiles the player can see in every direction
+pub const UNSEEN_BRIGHTNESS: f32 = 0.35; // explored terrain the player can't see right now is drawn this dark
+
+// Every octant as the multipliers that turn its row and column into x and y
+const OCTANTS: [[i64; 4]; 8] = [[1, 0, 0, 1], [0, 1, 1, 0], [0, -1, 1, 0], [-1, 0, 0, 1], [-1, 0, 0, -1], [0, -1, -1, 0], [0, 1, -1, 0], [1, 0, 0, -1]];
+
+// Recursive shadowcasting, the tiles seen from the origin out to the radius, opaque tiles are seen but hide what's behind them
+pub fn field_of_view(origin: [usize; 2], radius: usize, opaque: &dyn Fn([usize; 2]) -> bool) -> FxHashSet<[usize; 2]> {
+    let mut visible = FxHashSet::default();
+    visible.insert(origin);
+    for octant in OCTANTS.iter() {
+        cast_light(origin, radius, 1, 1.0, 0.0, octant, opaque, &mut visible);
+    }
+    visible
+}
+
+// lights one row of an octant at a time between the start and end slopes, splitting the light around anything opaque
+fn cast_light(origin: [usize; 2], radius: usize, row: usize, mut start: f32, end: f32, octant: &[i64; 4], opaque: &dyn Fn([usize; 2]) -> bool, visible: &mut FxHashSet<[usize; 2]>) {
+    if start < end {
+        return;
+    }
+    let radius = radius as i64;
+    let [xx, xy, yx, yy] = *octant;
+    let mut new_start = 0.0;
+    for distance in row as i64..=radius {
+        let dy = -distance;
+        let mut blocked = false;
+        for dx in -distance..=0 {
+            let left_slope = (dx as f32 - 0.5) / (dy as f32 + 0.5);
+            let right_slope = (dx as f32 + 0.5) / (dy as f32 - 0.5);
+            if start < right_slope {
+                continue;
+            }
+            if end > left_slope {
+                break;
+            }
+            // off the top or left of the world is treated like a wall
+            let x = origin[0] as i64 + dx * xx + dy * xy;
+            let y = origin[1] as i64 + dx * yx + dy * yy;
+            let tile = [x.max(0) as usize, y.max(0) as usize];
+            let is_opaque = x < 0 || y < 0 || opaque(tile);
+            if x >= 0 && y >= 0 && dx * dx + dy * dy <= radius * radius {
+                visible.insert(tile);
+            }
+            if blocked {
+                if is_opaque {
+                    new_start = right_slope;
+                    continue;
+                }
+                blocked = false;
+                start = new_start;
+            } else if is_opaque && distance < radius {
+                blocked = true;
+                cast_light(origin, radius as usize, distance as usize + 1, start, left_slope, octant, opaque, visible);
+                new_start = right_slope;
+            }
+        }
+        if blocked {
+            break;
+        }
+    }
+}
+
+impl World {
+    // tiles in the rectangle with terrain that blocks movement, which blocks sight too
+    pub fn get_opaque_tiles(&self, x: usize, y: usize, width: usize, height: usize) -> FxHashSet<[usize; 2]> {
+        let mut tiles = FxHashSet::default();
+        let chunks_ref = self.chunks.borrow();
+        for chunk_x in World::coord_to_chunk_coord(x * 32)..=World::coord_to_chunk_coord((x + width) * 32) {
+            for chunk_y in World::coord_to_chunk_coord(y * 32)..=World::coord_to_chunk_coord((y + height) * 32) {
+                let Some(chunk) = self.get_chunk_from_chunk_xy(chunk_x, chunk_y) else {
+                    continue;
+                };
+                for terrain_id in chunks_ref[chunk].terrain_ids.iter() {
+                    let terrain = self.terrain[terrain_id];
+                    let tile = [terrain.x / 32, terrain.y / 32];
+                    if tile[0] >= x && tile[0] < x + width && tile[1] >= y && tile[1] < y + height && self.get_terrain_tags(*terrain_id).is_some_and(|tags| tags.contains(&TerrainTags::BlocksMovement)) {
+                        tiles.insert(tile);
+                    }
+                }
+            }
+        }
+        tiles
+    }
+    // what can be seen from a tile, for the player or anything else that needs to look around
+    pub fn get_field_of_view(&self, origin: [usize; 2], radius: usize) -> FxHashSet<[usize; 2]> {
+        let (x, y) = (origin[0].saturating_sub(radius), origin[1].saturating_sub(radius));
+        let opaque = self.get_opaque_tiles(x, y, origin[0] + radius + 1 - x, origin[1] + radius + 1 - y);
+        field_of_view(origin, radius, &|tile| opaque.contains(&tile))
+    }
+    // whether the tile something at one position is mostly on can see the tile of another, used for entities noticing the player
+    pub fn can_see(&self, from: [f32; 2], to: [f32; 2]) -> bool {
+        let tile = |[x, y]: [f32; 2]| [(x.max(0.0) as usize + 16) / 32, (y.max(0.0) as usize + 16) / 32];
+        let (from, to) = (tile(from), tile(to));
+        let radius = ((from[0].abs_diff(to[0]).pow(2) + from[1].abs_diff(to[1]).pow(2)) as f32).sqrt().ceil() as usize;
+        self.get_field_of_view(from, radius).contains(&to)
+    }
+    pub fn update_visibility(&mut self) {
+        self.visible_tiles = self.get_field_of_view(self.player_tile(), SIGHT_RADIUS);
+    }
+    pub fn is_tile_visible(&self, tile: [usize; 2]) -> bool {
+        self.visible_tiles.contains(&tile)
+    }
+    // whether the player can see the tile something at the position is mostly on
+    pub fn is_position_visible(&self, x: f32, y: f32) -> bool {
+        self.is_tile_visible([(x.max(0.0) as usize + 16) / 32, (y.max(0.0) as usize + 16) / 32])
+    }
+    pub fn is_entity_visible(&self, entity: usize) -> bool {
+        self.components.position_components.get(entity).and_then(|x| x.as_ref()).is_some_and(|x| {
+            let position = x.borrow();
+            self.is_position_visible(position.x, position.y)
+        })
+    }
+}
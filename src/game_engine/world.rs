@@ -12,23 +12,33 @@ use crate::error::PError;
 use crate::{error_prolif_allow, ptry, punwrap};
 use crate::rendering_engine::abstractions::SpriteContainer;
 use crate::game_engine::player::Player;
-use crate::game_engine::terrain::{Terrain, TerrainTags};
+use crate::game_engine::terrain::{Terrain, TerrainTags, ICE_TRACTION, TERRAIN_STATUS_DAMAGE, TERRAIN_STATUS_LIFETIME, TERRAIN_STATUS_TIME_PER_TICK};
 
 use super::camera::Camera;
 use super::components::ComponentContainer;
+use super::crafting::CraftingBook;
 use super::entities::EntityAttackPattern;
 use super::entity_attacks::{EntityAttackBox, EntityAttackDescriptor};
-use super::entity_components::{AggroComponent, DamageableComponent, Poison, PositionComponent};
+use super::factions::Faction;
+use super::entity_components::{AggroComponent, DamageableComponent, Poison, PositionComponent, KNOCKBACK_DECAY, MIN_KNOCKBACK};
 use super::game::MousePosition;
+use super::room_objectives::RoomState;
 use super::inventory::Inventory;
 use super::item::{Item, ItemArchetype, ItemType};
-use super::items_on_floor::ItemOnFloor;
+use super::items_on_floor::{GoldOnFloor, ItemOnFloor};
 use super::json_parsing::{entity_archetype_json, room_descriptor_json, spawn_archetype_json, terrain_archetype_json, terrain_json};
-use super::loot::LootTable;
-use super::player::{PlayerDir, PlayerState, TICKS_PER_REGEN_TICK};
+use super::loot::{LootDrop, LootTable};
+use super::player::{PlayerDir, PlayerState, PLAYER_IFRAMES, TICKS_PER_REGEN_TICK};
 use super::player_attacks::{PlayerAbilityAttackTag, PlayerAttack, PlayerAttackType};
-use super::player_abilities::{AbilityStateInformation, PlayerAbilityDescriptorName, PlayerAbilityActionDescriptor, PlayerAbilityDescriptor};
-use super::stat::{StatC, StatList};
+use super::player_defense::{BlockResult, PARRY_STUN};
+use super::progression::Progression;
+use super::biomes::Biomes;
+use super::particles::ParticleSystem;
+use super::animation::DeathAnimation;
+use super::minimap::Minimap;
+use super::ranged_weapons::QueuedShot;
+use super::player_abilities::{AbilityStateInformation, PlayerAbilityActionDescriptor, PlayerAbilityDescriptor};
+use super::stat::{resistance_multiplier, DamageType, StatC, StatList};
 use super::utils::{self, Rectangle};
 #[derive(Debug, Clone)]
 pub struct DamageTextDescriptor {
@@ -62,6 +72,9 @@ pub struct World{
 
     pub collision_cache: RefCell<FxHashMap<[usize; 2], Vec<usize>>>,
     pub damage_cache: RefCell<FxHashMap<[usize; 2], Vec<usize>>>, 
+    pub projectile_cache: RefCell<FxHashMap<[usize; 2], Vec<usize>>>, // terrain that stops projectiles
+    pub terrain_effect_cache: RefCell<FxHashMap<[usize; 2], Vec<usize>>>, // terrain that slows, damages, applies statuses or is slippery
+    pub terrain_effect_frame: usize,
    
     pub pathfinding_frames: FxHashMap<usize, usize>, // entity id to frame of pathfinding
     pub next_pathfinding_frame_for_entity: usize,
@@ -92,14 +105,25 @@ pub struct World{
     pub damage_text: RefCell<Vec<DamageTextDescriptor>>,
 
     pub items_on_floor: RefCell<Vec<ItemOnFloor>>,
+    pub gold_on_floor: RefCell<Vec<GoldOnFloor>>,
+    pub queued_shots: RefCell<Vec<QueuedShot>>,
 
     pub loot_table_lookup: FxHashMap<CompactString, LootTable>, // loot table id to loot table object,
+    pub crafting_book: CraftingBook,
+    pub progression: Progression,
+    pub biomes: Biomes,
+    pub minimap: Minimap,
+    pub visible_tiles: FxHashSet<[usize; 2]>, // what the player can see this frame
+    pub particles: ParticleSystem,
+    pub death_animations: Vec<DeathAnimation>,
 
     pub cur_ability_charging: Option<usize>, // cur ability id charging
     pub player_ability_descriptors: Vec<PlayerAbilityDescriptor>, // corresponds player ability descriptor id to object
     
     pub terrain_archetype_jsons: FxHashMap<CompactString, terrain_archetype_json>,
     pub cur_exit: Option<[usize; 2]>,
+    pub cur_room: Option<RoomState>,
+    pub depth: usize, // rooms the player has gone through, makes spawns harder
 
     pub room_descriptors: FxHashMap<CompactString, room_descriptor_json>,
     pub spawn_archetype_descriptors: FxHashMap<CompactString, spawn_archetype_json>,
@@ -112,31 +136,11 @@ impl World{
     pub fn new(player: Player, sprite_container: SpriteContainer) -> Result<Self, PError>{
         let iof = vec![];
         let mut inventory_test = Inventory::default();
-        let test_ability_descriptors = vec![
-            super::player_abilities::get_ability_descriptor(PlayerAbilityDescriptorName::Cyclone),
-            super::player_abilities::get_ability_descriptor(PlayerAbilityDescriptorName::Dash),
-            super::player_abilities::get_ability_descriptor(PlayerAbilityDescriptorName::LightningTrap),
-            super::player_abilities::get_ability_descriptor(PlayerAbilityDescriptorName::LightningBolts),
-            super::player_abilities::get_ability_descriptor(PlayerAbilityDescriptorName::SlimeBall),
-        ];
-        let test_abilities = vec![
-            test_ability_descriptors[0].create_player_ability(0),
-            test_ability_descriptors[1].create_player_ability(1),
-            test_ability_descriptors[2].create_player_ability(2),
-            test_ability_descriptors[3].create_player_ability(3),
-            test_ability_descriptors[4].create_player_ability(4),
-        ];
         ptry!(inventory_test.add_ability_slot_for_key("z".into()));
         ptry!(inventory_test.add_ability_slot_for_key("x".into()));
         ptry!(inventory_test.add_ability_slot_for_key("c".into()));
         ptry!(inventory_test.add_ability_slot_for_key("v".into()));
         ptry!(inventory_test.add_ability_slot_for_key("b".into()));
-        ptry!(inventory_test.set_ability_on_key("z".into(), Some(0)));
-        ptry!(inventory_test.set_ability_on_key("x".into(), Some(1)));
-        ptry!(inventory_test.set_ability_on_key("c".into(), Some(2)));
-        ptry!(inventory_test.set_ability_on_key("v".into(), Some(3)));
-        ptry!(inventory_test.set_ability_on_key("b".into(), Some(4)));
-        inventory_test.player_abilities = test_abilities;
 
         Ok(Self{
             chunks: RefCell::new(Vec::new()),
@@ -154,6 +158,9 @@ impl World{
             item_archetype_lookup: FxHashMap::default(),
             loaded_chunks: Vec::new(),
             collision_cache: RefCell::new(FxHashMap::default()),
+            projectile_cache: RefCell::new(FxHashMap::default()),
+            terrain_effect_cache: RefCell::new(FxHashMap::default()),
+            terrain_effect_frame: 0,
             damage_cache: RefCell::new(FxHashMap::default()),
             pathfinding_frames: FxHashMap::default(),
             next_pathfinding_frame_for_entity: 0,
@@ -166,11 +173,22 @@ impl World{
             entity_attack_descriptor_lookup: FxHashMap::default(),
             damage_text: RefCell::new(Vec::new()),
             items_on_floor: RefCell::new(iof),
+            gold_on_floor: RefCell::new(Vec::new()),
+            queued_shots: RefCell::new(Vec::new()),
             loot_table_lookup: FxHashMap::default(),
-            player_ability_descriptors: test_ability_descriptors,
+            crafting_book: CraftingBook::default(),
+            progression: Progression::default(),
+            biomes: Biomes::default(),
+            minimap: Minimap::default(),
+            visible_tiles: FxHashSet::default(),
+            particles: ParticleSystem::default(),
+            death_animations: Vec::new(),
+            player_ability_descriptors: super::player_abilities::get_player_ability_descriptors(),
             cur_ability_charging: None,
             terrain_archetype_jsons: FxHashMap::default(),
             cur_exit: Some([68,21]),
+            cur_room: None,
+            depth: 0,
             room_descriptors: FxHashMap::default(),
             spawn_archetype_descriptors: FxHashMap::default(),
             mana: 100.0,
@@ -213,6 +231,7 @@ impl World{
         chunk.terrain_ids.remove(index);
         self.terrain.remove(&element_id);
         self.terrain_archetype_lookup.remove(&element_id);
+        self.terrain_sprite_lookup.remove(&element_id);
         Ok(())
     }
     pub fn set_loaded_chunks(&mut self, chunk_ids: Vec<usize>){
@@ -271,6 +290,16 @@ impl World{
                     }
                 }
             },
+            "autotile" => {
+                for x in start_x..start_x + width{
+                    for y in start_y..start_y + height{
+                        let terrain = self.add_terrain(x * 32, y * 32);
+                        self.set_terrain_archetype(terrain, descriptor.terrain_archetype.clone());
+                    }
+                }
+                // tiles placed before this may border it, so they get picked again too
+                ptry!(self.resolve_autotiles(start_x.saturating_sub(1), start_y.saturating_sub(1), width + 2, height + 2, &archetype_descriptor), "while autotiling {}", descriptor.terrain_archetype);
+            },
             _ => {
                 return Err(perror!(Invalid, "Found unknown terrain type: {} while generating terrain from json data", archetype_descriptor.r#type));
             }
@@ -355,8 +384,12 @@ impl World{
     pub fn generate_collision_cache_and_damage_cache(&mut self) -> Result<(), PError>{
         let mut collision_cache_ref = self.collision_cache.borrow_mut();
         let mut damage_cache_ref = self.damage_cache.borrow_mut();
+        let mut projectile_cache_ref = self.projectile_cache.borrow_mut();
+        let mut terrain_effect_cache_ref = self.terrain_effect_cache.borrow_mut();
         collision_cache_ref.clear();
         damage_cache_ref.clear();
+        projectile_cache_ref.clear();
+        terrain_effect_cache_ref.clear();
         for chunk_id in self.loaded_chunks.iter(){ 
             let chunk = &self.chunks.borrow()[*chunk_id];
             for terrain_id in chunk.terrain_ids.iter(){
@@ -366,20 +399,15 @@ impl World{
                     continue;
                 }
                 let terrain_tags = terrain_tags_potentially.unwrap();
-                for tag in terrain_tags.iter(){
-                    match tag{
-                        TerrainTags::BlocksMovement => {
-                        let tiles_blocked: Vec<[usize; 2]> = World::get_terrain_tiles(terrain.x, terrain.y, 32, 32);
-                        for tile in tiles_blocked.iter(){
-                            let collision_cache_entry = collision_cache_ref.get_mut(&[tile[0],tile[1]]);
-                            if let Some(entry) = collision_cache_entry {
-                                entry.push(*terrain_id);
-                            }else{
-                                collision_cache_ref.insert([tile[0],tile[1]], vec![*terrain_id]);
-                            }
-                        }
-                        }
-                        _ => ()
+                let blocks_movement = terrain_tags.iter().any(|tag| matches!(tag, TerrainTags::BlocksMovement | TerrainTags::Water));
+                let blocks_projectiles = terrain_tags.contains(&TerrainTags::BlocksProjectiles);
+                let has_effect = terrain_tags.iter().any(|tag| matches!(tag, TerrainTags::SlowsMovement(_) | TerrainTags::DamagesOnContact{..} | TerrainTags::AppliesStatus(_) | TerrainTags::Ice));
+                for (cache, in_cache) in [(&mut collision_cache_ref, blocks_movement), (&mut projectile_cache_ref, blocks_projectiles), (&mut terrain_effect_cache_ref, has_effect)] {
+                    if !in_cache {
+                        continue;
+                    }
+                    for tile in World::get_terrain_tiles(terrain.x, terrain.y, 32, 32).iter(){
+                        cache.entry(*tile).or_default().push(*terrain_id);
                     }
                 }
             }
@@ -399,7 +427,8 @@ impl World{
                     Some((i, position_component.as_ref().unwrap().borrow(), collision_component.as_ref().map(|x| x.borrow()), damageable_component.as_ref().map(|x| x.borrow())))
                 }else{None}
             ){
-                if let Some(collision_component) = collision_component{
+                // open doors let everything through until they close again
+                if let Some(collision_component) = collision_component.filter(|_| !self.is_interactable_open(i)){
                     let tiles_blocked: Vec<[usize; 2]> = World::get_terrain_tiles(position_component.x as usize, position_component.y as usize, collision_component.collision_box.w as usize, collision_component.collision_box.h as usize);
                     for tile in tiles_blocked.iter(){
                         let collision_cache_entry = collision_cache_ref.get_mut(&[tile[0],tile[1]]);
@@ -427,6 +456,13 @@ impl World{
         }  
         Ok(())
     }
+    // the entity being moved is the one collision is checked for, flying entities cross water
+    pub fn is_flying(&self, entity_id: Option<usize>) -> bool {
+        entity_id.and_then(|id| self.components.collision_components.get(id)).and_then(|x| x.as_ref()).is_some_and(|x| x.borrow().flying)
+    }
+    pub fn is_water(&self, terrain_id: usize) -> bool {
+        self.get_terrain_tags(terrain_id).is_some_and(|tags| tags.contains(&TerrainTags::Water) && !tags.contains(&TerrainTags::BlocksMovement))
+    }
     pub fn check_collision(&self, player: bool, id_to_ignore: Option<usize>, x: f32, y: f32, w: usize, h: usize, entity: bool) -> Result<bool, PError>{
         if !player {
             let player = self.player.borrow();
@@ -448,6 +484,7 @@ impl World{
             }
         }
         let idti: usize = id_to_ignore.unwrap_or(usize::MAX);
+        let flying = self.is_flying(id_to_ignore);
         for id in ids_to_check{
             if id == idti{
                 continue;
@@ -467,6 +504,9 @@ impl World{
                     }
                 }
             }else{
+                if flying && self.is_water(id) {
+                    continue;
+                }
                 let terrain = terrain_potentially.unwrap();
                 if (terrain.x as f32) < (x + w as f32) && terrain.x as f32 + 32.0 > x && (terrain.y as f32) < (y + h as f32) && (terrain.y as f32 + 32.0) > y{
                     return Ok(true);
@@ -529,10 +569,118 @@ impl World{
         Ok(false)
     }
     
-    pub fn get_attacked_rotated_rect(&self, player: bool, id_to_ignore: Option<usize>, x: usize, y: usize, w: usize, h: usize, rotation: f32, entity: bool) -> Result<Vec<usize>, PError>{
-        if !player {
-            unimplemented!("non-player get attacks not implemented");
+    // projectiles stop on terrain that blocks projectiles and on entities that can't be damaged
+    pub fn check_projectile_collision(&self, x: usize, y: usize, w: usize, h: usize) -> Result<bool, PError>{
+        for tile in World::get_terrain_tiles(x, y, w, h).iter(){
+            for id in self.projectile_cache.borrow().get(tile).into_iter().flatten(){
+                let terrain = punwrap!(self.terrain.get(id), Invalid, "projectile cache refers to terrain with id {} which doesn't exist", id);
+                if terrain.x < x + w && terrain.x + 32 > x && terrain.y < y + h && terrain.y + 32 > y{
+                    return Ok(true);
+                }
+            }
+            for id in self.collision_cache.borrow().get(tile).into_iter().flatten(){
+                if self.terrain.contains_key(id) || self.components.damageable_components[*id].is_some(){
+                    continue;
+                }
+                let entity_collision_box = punwrap!(&self.components.collision_components[*id], Invalid, "all entities in the collision cache should have a collision box, but entity with id {} does not have one", id).borrow().collision_box;
+                let entity_position = punwrap!(&self.components.position_components[*id], Invalid, "all entities in the collision cache should have a position component").borrow();
+                let ex = entity_position.x + entity_collision_box.x_offset;
+                let ey = entity_position.y + entity_collision_box.y_offset;
+                if ex < (x + w) as f32 && ex + entity_collision_box.w > x as f32 && ey < (y + h) as f32 && ey + entity_collision_box.h > y as f32{
+                    return Ok(true);
+                }
+            }
         }
+        Ok(false)
+    }
+    // walls block sight, terrain that can be seen over like water doesn't
+    // tags of the slowing, damaging, status and ice terrain under a box
+    pub fn get_terrain_effects(&self, x: f32, y: f32, w: f32, h: f32) -> Vec<TerrainTags> {
+        let mut effects = Vec::new();
+        let mut seen = FxHashSet::default();
+        for tile in World::get_terrain_tiles(x.max(0.0) as usize, y.max(0.0) as usize, w as usize, h as usize).iter(){
+            for id in self.terrain_effect_cache.borrow().get(tile).into_iter().flatten(){
+                if !seen.insert(*id) {
+                    continue;
+                }
+                let Some(terrain) = self.terrain.get(id) else {
+                    continue;
+                };
+                if (terrain.x as f32) < x + w && terrain.x as f32 + 32.0 > x && (terrain.y as f32) < y + h && terrain.y as f32 + 32.0 > y {
+                    effects.extend(self.get_terrain_tags(*id).into_iter().flatten().cloned());
+                }
+            }
+        }
+        effects
+    }
+    pub fn get_terrain_speed_multiplier(effects: &[TerrainTags]) -> f32 {
+        effects.iter().filter_map(|tag| if let TerrainTags::SlowsMovement(multiplier) = tag {Some(*multiplier)} else {None}).fold(1.0, f32::min)
+    }
+    pub fn update_terrain_effects(&mut self, camera: &mut Camera) -> Result<(), PError> {
+        self.terrain_effect_frame += 1;
+        let frame = self.terrain_effect_frame;
+        let player_box = {
+            let player = self.player.borrow();
+            [player.x + player.collision_box.x_offset, player.y + player.collision_box.y_offset, player.collision_box.w, player.collision_box.h]
+        };
+        for effect in self.get_terrain_effects(player_box[0], player_box[1], player_box[2], player_box[3]).iter() {
+            match effect {
+                TerrainTags::DamagesOnContact{damage, interval} if frame.is_multiple_of(*interval) => {
+                    ptry!(self.damage_player(*damage, DamageType::Physical, camera));
+                    self.spawn_player_particles(super::particles::HIT_EMITTER);
+                },
+                TerrainTags::AppliesStatus(status) => {
+                    let mut player = self.player.borrow_mut();
+                    if status == "fire" && player.fire.is_empty() {
+                        player.fire.push(Fire {damage: TERRAIN_STATUS_DAMAGE, lifetime: TERRAIN_STATUS_LIFETIME, time_alive: 0.0, time_per_tick: TERRAIN_STATUS_TIME_PER_TICK});
+                    }
+                    if status == "poison" && player.poison.is_empty() {
+                        player.poison.push(Poison {damage: TERRAIN_STATUS_DAMAGE, lifetime: TERRAIN_STATUS_LIFETIME, time_alive: 0.0, time_per_tick: TERRAIN_STATUS_TIME_PER_TICK});
+                    }
+                },
+                _ => {}
+            }
+        }
+
+        // only entities that walk on the ground are affected
+        let mut entities = Vec::new();
+        for chunk in self.loaded_chunks.iter() {
+            entities.extend(self.chunks.borrow()[*chunk].entities_ids.iter().copied());
+        }
+        for entity in entities {
+            let Some(collision_component) = self.components.collision_components[entity].as_ref().map(|x| x.borrow()) else {
+                continue;
+            };
+            if !collision_component.respects_collision || collision_component.flying {
+                continue;
+            }
+            let (Some(position_component), Some(damageable_component)) = (&self.components.position_components[entity], &self.components.damageable_components[entity]) else {
+                continue;
+            };
+            let position_component = position_component.borrow();
+            let mut damageable_component = damageable_component.borrow_mut();
+            let collision_box = collision_component.collision_box;
+            for effect in self.get_terrain_effects(position_component.x + collision_box.x_offset, position_component.y + collision_box.y_offset, collision_box.w, collision_box.h).iter() {
+                match effect {
+                    TerrainTags::DamagesOnContact{damage, interval} if frame.is_multiple_of(*interval) => {
+                        self.damage_entity_dot(&position_component, &mut damageable_component, *damage, DamageType::Physical, camera);
+                    },
+                    TerrainTags::AppliesStatus(status) => {
+                        if status == "fire" && damageable_component.fire.is_none() {
+                            damageable_component.fire = Some(Fire {damage: TERRAIN_STATUS_DAMAGE, lifetime: TERRAIN_STATUS_LIFETIME, time_alive: 0.0, time_per_tick: TERRAIN_STATUS_TIME_PER_TICK});
+                        }
+                        if status == "poison" && damageable_component.poisons.is_empty() {
+                            damageable_component.poisons.push(Poison {damage: TERRAIN_STATUS_DAMAGE, lifetime: TERRAIN_STATUS_LIFETIME, time_alive: 0.0, time_per_tick: TERRAIN_STATUS_TIME_PER_TICK});
+                        }
+                    },
+                    _ => {}
+                }
+            }
+        }
+        Ok(())
+    }
+    
+    pub fn get_attacked_rotated_rect(&self, player: bool, id_to_ignore: Option<usize>, x: usize, y: usize, w: usize, h: usize, rotation: f32, entity: bool) -> Result<Vec<usize>, PError>{
         let tiles_to_check = World::get_collision_tiles_rotated_rect(x, y, w, h, rotation);
         let mut ids_to_check = FxHashSet::default();
         for tile in tiles_to_check.iter(){
@@ -551,6 +699,10 @@ impl World{
             let terrain_potentially = self.terrain.get(&id);
             
             if terrain_potentially.is_none(){
+                // attacks from the player's side only hit hostile entities, everything else only hits the player's allies
+                if entity && (self.get_faction(id) == Faction::Player) == player {
+                    continue;
+                }
                 if entity{
                     let entity_damage_box = punwrap!(&self.components.damageable_components[id], Invalid, "All entities in damage cache should have damage boxes").borrow().damage_box;
                     let entity_position = punwrap!(&self.components.position_components[id], Invalid, "All entities in damage cache should have position components").borrow();
@@ -583,9 +735,6 @@ impl World{
         Ok(colliding)
     }
     pub fn get_attacked(&self, player: bool, id_to_ignore: Option<usize>, x: usize, y: usize, w: usize, h: usize, entity: bool) -> Result<Vec<usize>, PError>{
-        if !player {
-            unimplemented!("non-player get_attack not implemented");
-        }
         let tiles_to_check = World::get_terrain_tiles(x, y, w, h);
         let mut ids_to_check = FxHashSet::default();
         for tile in tiles_to_check.iter(){
@@ -604,6 +753,10 @@ impl World{
             let terrain_potentially = self.terrain.get(&id);
             
             if terrain_potentially.is_none(){
+                // attacks from the player's side only hit hostile entities, everything else only hits the player's allies
+                if entity && (self.get_faction(id) == Faction::Player) == player {
+                    continue;
+                }
                 if entity{
                     let entity_damage_box = punwrap!(&self.components.damageable_components[id], Invalid, "All entities in damage cache should have damage boxes").borrow().damage_box;
                     let entity_position = punwrap!(&self.components.position_components[id], Invalid, "All entities in damage cache should have position components").borrow();
@@ -755,15 +908,27 @@ impl World{
         } else if player.player_state == PlayerState::Walking{
             player.player_state = PlayerState::Idle;
         }
+        let effects = self.get_terrain_effects(player.x + player.collision_box.x_offset, player.y + player.collision_box.y_offset, player.collision_box.w, player.collision_box.h);
+        let speed = movement_speed * World::get_terrain_speed_multiplier(&effects);
+        let on_ice = effects.contains(&TerrainTags::Ice);
         let magnitude: f32 = f32::sqrt(direction[0].powf(2.0) + direction[1].powf(2.0));
+        let wanted = if magnitude > 0.0 {[direction[0] / magnitude * speed, direction[1] / magnitude * speed]} else {[0.0, 0.0]};
+        // on ice the player only slowly picks up the movement they want and keeps sliding when they let go
+        let movement = if on_ice {
+            [player.velocity[0] + (wanted[0] - player.velocity[0]) * ICE_TRACTION, player.velocity[1] + (wanted[1] - player.velocity[1]) * ICE_TRACTION]
+        } else {
+            wanted
+        };
+        player.velocity = movement;
         
-        if magnitude > 0.0{
-            let movement = [(direction[0] / magnitude * movement_speed), (direction[1] / magnitude * movement_speed)];
-            
+        if movement[0] != 0.0 || movement[1] != 0.0 {
+            let slide = if on_ice {movement} else {[direction[0] * speed, direction[1] * speed]};
             if !ptry!(self.can_move_player(&mut player, [movement[0], 0.0])){
-                ptry!(self.attempt_move_player(&mut player, [0.0, (direction[1] * movement_speed)]));
+                player.velocity[0] = 0.0;
+                ptry!(self.attempt_move_player(&mut player, [0.0, slide[1]]));
             }else if !ptry!(self.can_move_player(&mut player, [0.0, movement[1]])){
-                ptry!(self.attempt_move_player(&mut player, [(direction[0] * movement_speed), 0.0]));
+                player.velocity[1] = 0.0;
+                ptry!(self.attempt_move_player(&mut player, [slide[0], 0.0]));
             }else{
                 ptry!(self.attempt_move_player(&mut player, movement));
             }
@@ -782,21 +947,21 @@ impl World{
         match attack_item.item_type {
             ItemType::MeleeWeapon => {
                 self.player_attacks.borrow_mut().push(
-                    PlayerAttack::new(stats.clone(), PlayerAttackType::Melee, punwrap!(attack_item.attack_sprite.clone(), Expected, "all melee weapons should have an attack sprite"), attack_item.width_to_length_ratio.unwrap_or(1.0), x, y, angle, vec![])
+                    PlayerAttack::new(stats.clone(), PlayerAttackType::Melee, attack_item.damage_type, punwrap!(attack_item.attack_sprite.clone(), Expected, "all melee weapons should have an attack sprite"), attack_item.width_to_length_ratio.unwrap_or(1.0), x, y, angle, vec![])
                 );
             }
             ItemType::RangedWeapon => {
-                self.player_attacks.borrow_mut().push(
-                    PlayerAttack::new(stats.clone(), PlayerAttackType::Ranged, punwrap!(attack_item.attack_sprite.clone(), Expected, "all ranged weapons should have an attack sprite"),attack_item.width_to_length_ratio.unwrap_or(1.0), x, y, angle, vec![])
-                );
+                let mut attack = PlayerAttack::new(stats.clone(), PlayerAttackType::Ranged, attack_item.damage_type, punwrap!(attack_item.attack_sprite.clone(), Expected, "all ranged weapons should have an attack sprite"),attack_item.width_to_length_ratio.unwrap_or(1.0), x, y, angle, vec![]);
+                attack.gravity = self.get_ranged_descriptor(attack_item).gravity;
+                self.player_attacks.borrow_mut().push(attack);
             }
             _ => {}
         }
         Ok(())
     }
-    pub fn add_player_attack_custom(&self, stats: &StatList, attack_sprite: CompactString, width_to_length_ratio: f32, attack_type: PlayerAttackType, x: f32, y: f32, angle: f32, tags: Vec<PlayerAbilityAttackTag>) -> Result<(), PError>{    
+    pub fn add_player_attack_custom(&self, stats: &StatList, attack_sprite: CompactString, width_to_length_ratio: f32, attack_type: PlayerAttackType, damage_type: DamageType, x: f32, y: f32, angle: f32, tags: Vec<PlayerAbilityAttackTag>) -> Result<(), PError>{    
         self.player_attacks.borrow_mut().push(
-            PlayerAttack::new(stats.clone(), attack_type, attack_sprite,width_to_length_ratio, x, y, angle, tags)
+            PlayerAttack::new(stats.clone(), attack_type, damage_type, attack_sprite,width_to_length_ratio, x, y, angle, tags)
         );
         Ok(())
     }
@@ -805,11 +970,43 @@ impl World{
         let mut attacks = self.entity_attacks.borrow_mut();
        let mut attacks_to_be_deleted = Vec::new();
         for (i, attack) in attacks.iter_mut().enumerate(){
+            let interrupted = self.components.damageable_components.get(attack.entity).and_then(|x| x.as_ref()).map(|x| x.borrow().hitstun > 0.0).unwrap_or(false);
+            if interrupted {
+                attacks_to_be_deleted.push(i);
+                continue;
+            }
             attack.time_charged += 1.0;
             let descriptor = punwrap!(self.get_attack_descriptor(attack), Expected, "Couldn't find attack descriptor for entity attack: {:?}", attack);
             if attack.time_charged.floor() as usize >= descriptor.time_to_charge {
-                if self.check_collision_with_player(attack.x, attack.y, descriptor.reach as f32, descriptor.width as f32, attack.rotation * 180.0/PI){
-                    ptry!(self.damage_player(descriptor.damage, camera, [1.0, 0.0, 0.0, 1.0]));
+                ptry!(self.entity_attack_hit_entities(attack, descriptor, camera));
+                if self.get_faction(attack.entity) == Faction::Player {
+                    attacks_to_be_deleted.push(i);
+                    continue;
+                }
+                if self.player.borrow().iframes <= 0.0 && self.check_collision_with_player(attack.x, attack.y, descriptor.reach as f32, descriptor.width as f32, attack.rotation * 180.0/PI){
+                    let block_result = ptry!(self.get_block_result(attack.rotation));
+                    if block_result == BlockResult::Parried {
+                        if let Some(attacker) = self.components.damageable_components.get(attack.entity).and_then(|x| x.as_ref()) {
+                            let mut attacker = attacker.borrow_mut();
+                            attacker.hitstun = attacker.hitstun.max(PARRY_STUN);
+                        }
+                        attacks_to_be_deleted.push(i);
+                        continue;
+                    }
+                    self.spawn_player_particles(super::particles::HIT_EMITTER);
+                    if let BlockResult::Blocked(multiplier) = block_result {
+                        ptry!(self.damage_player(descriptor.damage * multiplier, descriptor.damage_type, camera));
+                        self.player.borrow_mut().iframes = PLAYER_IFRAMES;
+                        attacks_to_be_deleted.push(i);
+                        continue;
+                    }
+                    ptry!(self.damage_player(descriptor.damage, descriptor.damage_type, camera));
+                    let mut player = self.player.borrow_mut();
+                    player.iframes = PLAYER_IFRAMES;
+                    if descriptor.knockback > 0.0 {
+                        player.knockback = [attack.rotation.cos() * descriptor.knockback, attack.rotation.sin() * descriptor.knockback];
+                    }
+                    drop(player);
                     if let Some(poison) = &descriptor.poison {
                         self.player.borrow_mut().poison.push(Poison {
                             lifetime: poison.lifetime,
@@ -843,6 +1040,12 @@ impl World{
         let mut attacks_to_add = Vec::new();
         for attack in attacks.iter_mut(){
             match attack.attack_type{
+                PlayerAttackType::Melee if attack.swing.is_some() => {
+                    if ptry!(self.update_melee_swing(attack, camera)) {
+                        attacks_to_be_deleted.push(i);
+                        self.player.borrow_mut().player_state = PlayerState::Idle;
+                    }
+                }
                 PlayerAttackType::Melee | PlayerAttackType::MeleeAbility => {
                     attack.time_alive += 1.0;
                     if attack.time_alive > 3.0{
@@ -866,9 +1069,9 @@ impl World{
                                 let entity_position = self.components.position_components[*collision].as_ref().unwrap().borrow();
                                 let aggro_potentially = self.components.aggro_components[*collision].as_ref();
                                 if let Some(aggro) = aggro_potentially{
-                                    ptry!(self.damage_entity(&entity_position, Some(&mut health_component), Some(&mut aggro.borrow_mut()),  &attack.stats, camera, [1.0, 1.0, 1.0, 1.0]));
+                                    ptry!(self.damage_entity(&entity_position, Some(&mut health_component), Some(&mut aggro.borrow_mut()),  &attack.stats, attack.damage_type, attack.angle, camera));
                                 }else {
-                                    ptry!(self.damage_entity(&entity_position, Some(&mut health_component), None,  &attack.stats, camera, [1.0, 1.0, 1.0, 1.0]));
+                                    ptry!(self.damage_entity(&entity_position, Some(&mut health_component), None,  &attack.stats, attack.damage_type, attack.angle, camera));
                                 }
                                 attack.dealt_damage = true;
                             }
@@ -877,8 +1080,18 @@ impl World{
                 }
                 PlayerAttackType::Ranged => {
                     let angle = attack.angle * PI/180.0;
-                    attack.x += angle.cos() * attack.stats.speed.map(|x| x.get_value()).unwrap_or(0.0);
-                    attack.y += angle.sin() * attack.stats.speed.map(|x| x.get_value()).unwrap_or(0.0);
+                    if attack.gravity != 0.0 {
+                        let speed = attack.stats.speed.map(|x| x.get_value()).unwrap_or(0.0);
+                        let mut velocity = attack.velocity.unwrap_or([angle.cos() * speed, angle.sin() * speed]);
+                        velocity[1] += attack.gravity;
+                        attack.x += velocity[0];
+                        attack.y += velocity[1];
+                        attack.angle = velocity[1].atan2(velocity[0]) * 180.0/PI;
+                        attack.velocity = Some(velocity);
+                    } else {
+                        attack.x += angle.cos() * attack.stats.speed.map(|x| x.get_value()).unwrap_or(0.0);
+                        attack.y += angle.sin() * attack.stats.speed.map(|x| x.get_value()).unwrap_or(0.0);
+                    }
                     attack.time_alive += 1.0;
                     if attack.time_alive > attack.stats.lifetime.map(|x| x.get_value()).unwrap_or(f32::MAX){
                         attacks_to_be_deleted.push(i);
@@ -911,9 +1124,9 @@ impl World{
                                 let entity_position = self.components.position_components[*collision].as_ref().unwrap().borrow();
                                 let aggro_potentially = self.components.aggro_components[*collision].as_ref();
                                 if let Some(aggro) = aggro_potentially{
-                                    ptry!(self.damage_entity( &entity_position, Some(&mut health_component), Some(&mut aggro.borrow_mut()),  &attack.stats, camera, [1.0, 1.0, 1.0, 1.0]));
+                                    ptry!(self.damage_entity( &entity_position, Some(&mut health_component), Some(&mut aggro.borrow_mut()),  &attack.stats, attack.damage_type, attack.angle, camera));
                                 }else {
-                                    ptry!(self.damage_entity( &entity_position, Some(&mut health_component), None, &attack.stats, camera, [1.0, 1.0, 1.0, 1.0]));
+                                    ptry!(self.damage_entity( &entity_position, Some(&mut health_component), None, &attack.stats, attack.damage_type, attack.angle, camera));
                                 }
                             }
                         }
@@ -921,7 +1134,7 @@ impl World{
                     }else {
                         let length = attack.stats.size.map(|x| x.get_value()).unwrap_or(0.0).floor();
                         let width = attack.width_to_length_ratio * length;
-                        let c = ptry!(self.check_collision_non_damageable(true, None, (attack.x - length/2.0) as usize, (attack.y-width/2.0) as usize, length as usize, width as usize, true));
+                        let c = ptry!(self.check_projectile_collision((attack.x - length/2.0) as usize, (attack.y-width/2.0) as usize, length as usize, width as usize));
                         if c{
                             attacks_to_be_deleted.push(i);
                         }
@@ -972,7 +1185,7 @@ impl World{
                     if hit {
                         if let Some(chains_left) = chaining {
                             let mut close_entity_pos = None;
-                            for pos in izip!(self.components.position_components.iter(), self.components.damageable_components.iter()).filter_map(|(pos, damageable)| if pos.is_some() && damageable.is_some(){Some(pos.as_ref().unwrap().borrow())}else{None}){
+                            for pos in izip!(self.components.position_components.iter(), self.components.damageable_components.iter()).enumerate().filter_map(|(i, (pos, damageable))| if pos.is_some() && damageable.is_some() && self.are_enemies(None, i){Some(pos.as_ref().unwrap().borrow())}else{None}){
                                 if (pos.x - attack.x).powi(2) + (pos.y - attack.y).powi(2) < 10000.0 {
                                     close_entity_pos = Some(pos.clone());
                                 }
@@ -993,6 +1206,7 @@ impl World{
                                     PlayerAttack {
                                         stats: new_stats.clone(),
                                         attack_type: PlayerAttackType::RangedAbility,
+                                        damage_type: attack.damage_type,
                                         sprite: attack.sprite.clone(),
                                         width_to_length_ratio: attack.width_to_length_ratio,
                                         time_alive: 0.0,
@@ -1002,7 +1216,10 @@ impl World{
                                         dealt_damage: false,
                                         last_damage: None,
                                         enemies_pierced: 0,
-                                        ability_tags: vec![]
+                                        ability_tags: vec![],
+                                        gravity: 0.0,
+                                        velocity: None,
+                                        swing: None
                                     });
                                 }
                         }
@@ -1012,9 +1229,9 @@ impl World{
                                 let entity_position = self.components.position_components[*collision].as_ref().unwrap().borrow();
                                 let aggro_potentially = self.components.aggro_components[*collision].as_ref();
                                 if let Some(aggro) = aggro_potentially{
-                                    ptry!(self.damage_entity( &entity_position, Some(&mut health_component), Some(&mut aggro.borrow_mut()),  &attack.stats, camera, [1.0, 1.0, 1.0, 1.0]));
+                                    ptry!(self.damage_entity( &entity_position, Some(&mut health_component), Some(&mut aggro.borrow_mut()),  &attack.stats, attack.damage_type, attack.angle, camera));
                                 }else {
-                                    ptry!(self.damage_entity( &entity_position, Some(&mut health_component), None, &attack.stats, camera, [1.0, 1.0, 1.0, 1.0]));
+                                    ptry!(self.damage_entity( &entity_position, Some(&mut health_component), None, &attack.stats, attack.damage_type, attack.angle, camera));
                                 }
                             }
                         }
@@ -1022,7 +1239,7 @@ impl World{
                     }else {
                         let length = attack.stats.size.map(|x| x.get_value()).unwrap_or(0.0).floor();
                         let width = attack.width_to_length_ratio * length;
-                        let c = ptry!(self.check_collision_non_damageable(true, None, (attack.x - length/2.0) as usize, (attack.y-width/2.0) as usize, length as usize, width as usize, true));
+                        let c = ptry!(self.check_projectile_collision((attack.x - length/2.0) as usize, (attack.y-width/2.0) as usize, length as usize, width as usize));
                         let mut bounce = None;
                         for tag in attack.ability_tags.iter_mut() {
                             match tag {
@@ -1064,13 +1281,20 @@ impl World{
     }
 
 
-    pub fn damage_entity(&self, entity_position_component: &PositionComponent, entity_damageable_component: Option<&mut DamageableComponent>, entity_aggro_component: Option<&mut AggroComponent>, stats: &StatList, camera: &mut Camera, color: [f32; 4]) -> Result<(), PError>{
+    pub fn damage_entity(&self, entity_position_component: &PositionComponent, entity_damageable_component: Option<&mut DamageableComponent>, entity_aggro_component: Option<&mut AggroComponent>, stats: &StatList, damage_type: DamageType, angle: f32, camera: &mut Camera) -> Result<(), PError>{
         let rand = rand::thread_rng().gen::<f32>();
         let crit = rand < stats.crit_chance.map(|x| x.get_value()/100.0).unwrap_or(0.0);
         let mut damage = stats.damage.map(|x| x.get_value()).unwrap_or(0.0);
         if crit {damage *= stats.crit_damage.map(|x| x.get_value()).unwrap_or(100.0)/100.0;}
         if entity_damageable_component.is_some() {
             let ehc = entity_damageable_component.unwrap();
+            damage *= resistance_multiplier(ehc.resistances.get(damage_type));
+            let knockback = stats.knockback.map(|x| x.get_value()).unwrap_or(0.0);
+            if knockback > 0.0 {
+                let angle = angle * PI/180.0;
+                ehc.knockback = [angle.cos() * knockback, angle.sin() * knockback];
+            }
+            ehc.hitstun = ehc.hitstun.max(stats.hitstun.map(|x| x.get_value()).unwrap_or(0.0));
             let real_damage = f32::min(f32::min(damage, ehc.health), 0.0);
             ehc.health -= damage;
             if ehc.health >= ehc.max_health as f32 {
@@ -1115,6 +1339,7 @@ impl World{
                 ptry!(self.heal_player(lifesteal/100.0 * real_damage, camera));
             }
         }
+        self.spawn_particles(super::particles::HIT_EMITTER, entity_position_component.x + 16.0, entity_position_component.y + 16.0);
         if entity_aggro_component.is_some() {
             let aggro = entity_aggro_component.unwrap();
             if !aggro.aggroed{
@@ -1128,30 +1353,38 @@ impl World{
             self.damage_text.borrow_mut().push(DamageTextDescriptor{world_text_id: text_2, lifespan: 0.0, crit: true});
         }else {
             let text_1 = camera.add_world_text(((damage * 10.0).round() / 10.0).to_string(), super::camera::Font::B, entity_position_component.x + 11.0, entity_position_component.y + 7.0, 150.0, 50.0, 55.0, [0.0, 0.0, 0.0, 1.0], wgpu_text::glyph_brush::HorizontalAlign::Center);
-            let text_2 = camera.add_world_text(((damage * 10.0).round() / 10.0).to_string(), super::camera::Font::B, entity_position_component.x + 9.0, entity_position_component.y + 5.0, 150.0, 50.0, 55.0, color, wgpu_text::glyph_brush::HorizontalAlign::Center);
+            let text_2 = camera.add_world_text(((damage * 10.0).round() / 10.0).to_string(), super::camera::Font::B, entity_position_component.x + 9.0, entity_position_component.y + 5.0, 150.0, 50.0, 55.0, damage_type.color(), wgpu_text::glyph_brush::HorizontalAlign::Center);
             self.damage_text.borrow_mut().push(DamageTextDescriptor{world_text_id: text_1, lifespan: 0.0, crit: false});
             self.damage_text.borrow_mut().push(DamageTextDescriptor{world_text_id: text_2, lifespan: 0.0, crit: false});
         }
         Ok(())
     }
 
-    pub fn damage_entity_dot(&self, entity_position_component: &PositionComponent, entity_damageable_component: &mut DamageableComponent, damage: f32, camera: &mut Camera, color: [f32; 4]) {
+    pub fn damage_entity_dot(&self, entity_position_component: &PositionComponent, entity_damageable_component: &mut DamageableComponent, damage: f32, damage_type: DamageType, camera: &mut Camera) {
+        let emitter = match damage_type {
+            DamageType::Poison => super::particles::POISON_EMITTER,
+            DamageType::Fire => super::particles::BURN_EMITTER,
+            _ => super::particles::HIT_EMITTER
+        };
+        self.spawn_particles(emitter, entity_position_component.x + 16.0, entity_position_component.y + 16.0);
+        let damage = damage * resistance_multiplier(entity_damageable_component.resistances.get(damage_type));
         entity_damageable_component.health -= damage;
         if entity_damageable_component.health >= entity_damageable_component.max_health as f32 {
             entity_damageable_component.health = entity_damageable_component.max_health as f32;
         }
         let text_1 = camera.add_world_text(((damage * 10.0).round() / 10.0).to_string(), super::camera::Font::B, entity_position_component.x + 11.0, entity_position_component.y + 7.0, 150.0, 50.0, 50.0, [0.0, 0.0, 0.0, 1.0], wgpu_text::glyph_brush::HorizontalAlign::Center);
-        let text_2 = camera.add_world_text(((damage * 10.0).round() / 10.0).to_string(), super::camera::Font::B, entity_position_component.x + 9.0, entity_position_component.y + 5.0, 150.0, 50.0, 50.0, color, wgpu_text::glyph_brush::HorizontalAlign::Center);
+        let text_2 = camera.add_world_text(((damage * 10.0).round() / 10.0).to_string(), super::camera::Font::B, entity_position_component.x + 9.0, entity_position_component.y + 5.0, 150.0, 50.0, 50.0, damage_type.color(), wgpu_text::glyph_brush::HorizontalAlign::Center);
         self.damage_text.borrow_mut().push(DamageTextDescriptor{world_text_id: text_1, lifespan: 0.0, crit: false});
         self.damage_text.borrow_mut().push(DamageTextDescriptor{world_text_id: text_2, lifespan: 0.0, crit: false});
     }
 
     
 
-    pub fn damage_player(&self, damage: f32, camera: &mut Camera, color: [f32; 4]) -> Result<(), PError> {
-        let defense = ptry!(self.inventory.get_combined_stats()).defense.map(|x| x.get_value()).unwrap_or(0.0);
-        let def_multi = if defense < 0.0 {defense.abs()/100.0 +1.0} else {1.0/(defense/100.0 + 1.0)};
-        let dmg = damage * def_multi;
+    pub fn damage_player(&self, damage: f32, damage_type: DamageType, camera: &mut Camera) -> Result<(), PError> {
+        let resistance = ptry!(self.inventory.get_combined_stats()).get_resistance(damage_type);
+        let dmg = damage * resistance_multiplier(resistance);
+        // physical hits on the player stay red so they don't read like the player's own damage
+        let color = if damage_type == DamageType::Physical {[1.0, 0.0, 0.0, 1.0]} else {damage_type.color()};
         self.player.borrow_mut().health -= dmg;
         let player = self.player.borrow();
         let text_1 = camera.add_world_text(((dmg * 10.0).round() / 10.0).to_string(), super::camera::Font::B, player.x + 32.0, player.y + 7.0, 150.0, 50.0, 50.0, [0.0, 0.0, 0.0, 1.0], wgpu_text::glyph_brush::HorizontalAlign::Center);
@@ -1193,30 +1426,56 @@ impl World{
         Ok(())
     }
     pub fn kill_entity(&self, entity_id: usize){
-        self.entities_to_be_killed_at_end_of_frame.borrow_mut().push(entity_id);
+        let mut to_be_killed = self.entities_to_be_killed_at_end_of_frame.borrow_mut();
+        if to_be_killed.contains(&entity_id) {
+            return;
+        }
+        to_be_killed.push(entity_id);
     }
-    pub fn kill_entities_to_be_killed(&mut self) -> Result<(), PError>{
-        let entities = self.entities_to_be_killed_at_end_of_frame.borrow().clone();
-        for entity in entities{
-            if let Some(entity_position) = self.components.position_components[entity].as_ref() {
-                let entity_position = entity_position.borrow();
-                let lc = self.components.loot_components[entity].as_ref();
-                if let Some(lc) = lc {
-                    let tables = &lc.borrow().loot_tables;
-                    for table in tables.iter() {
-                        let table = punwrap!(self.loot_table_lookup.get(table), "entity with id {} has a loot table with id {} which doesn't exist", entity, table);
-                        let items = table.roll(ptry!(self.inventory.get_combined_stats()).loot.map(|x| x.get_value()).unwrap_or(100.0));
-                        for item in items.iter() {
-                            let it = ptry!(self.create_item_with_archetype(item.clone()), "while attempting to drop item {} from entity with id {}", item, entity);
-                            self.items_on_floor.borrow_mut().push(ItemOnFloor{
-                                item: it,
-                                x: entity_position.x,
-                                y: entity_position.y,
-                            });
+    // rolls the entity's loot tables and drops the results where it stands
+    pub fn drop_entity_loot(&self, entity: usize) -> Result<(), PError>{
+        if let Some(entity_position) = self.components.position_components[entity].as_ref() {
+            let entity_position = entity_position.borrow();
+            let lc = self.components.loot_components[entity].as_ref();
+            if let Some(lc) = lc {
+                let tables = &lc.borrow().loot_tables;
+                for table in tables.iter() {
+                    let table = self.get_biome_loot_table(table);
+                    let table = punwrap!(self.loot_table_lookup.get(table), "entity with id {} has a loot table with id {} which doesn't exist", entity, table);
+                    let drops = table.roll(ptry!(self.inventory.get_combined_stats()).loot.map(|x| x.get_value()).unwrap_or(100.0) * self.get_biome_loot_multiplier());
+                    for drop in drops.iter() {
+                        match drop {
+                            LootDrop::Item(item) => {
+                                let it = ptry!(self.create_item_with_archetype(item.clone()), "while attempting to drop item {} from entity with id {}", item, entity);
+                                self.items_on_floor.borrow_mut().push(ItemOnFloor{
+                                    item: it,
+                                    x: entity_position.x,
+                                    y: entity_position.y,
+                                });
+                            },
+                            LootDrop::Gold(amount) => {
+                                self.gold_on_floor.borrow_mut().push(GoldOnFloor {
+                                    amount: *amount,
+                                    x: entity_position.x,
+                                    y: entity_position.y + 16.0,
+                                });
+                            }
                         }
                     }
                 }
             }
+        }
+        Ok(())
+    }
+    pub fn kill_entities_to_be_killed(&mut self) -> Result<(), PError>{
+        let entities = self.entities_to_be_killed_at_end_of_frame.borrow().clone();
+        for entity in entities{
+            if let Some(position) = self.components.position_components[entity].as_ref() {
+                let position = position.borrow();
+                self.spawn_particles(super::particles::DEATH_EMITTER, position.x + 16.0, position.y + 16.0);
+            }
+            self.start_death_animation(entity);
+            ptry!(self.drop_entity_loot(entity));
             ptry!(self.remove_entity(entity));
         }
         self.entities_to_be_killed_at_end_of_frame.borrow_mut().clear();
@@ -1235,6 +1494,15 @@ impl World{
                 self.inventory.set_hotbar_slot(num - 1);
             }
         }
+        if key == super::vendor::INTERACT_KEY {
+            ptry!(self.interact());
+        }
+        if key == super::player_defense::ROLL_KEY {
+            ptry!(self.start_roll());
+        }
+        if key == super::minimap::MAP_KEY {
+            self.toggle_full_map();
+        }
         let state = self.player.borrow().player_state.clone();
         let mut ability_to_start = None;
         let mut ability_to_start_fn = None;
@@ -1270,7 +1538,9 @@ impl World{
             if let Some(on_start_function) = ability_to_start_fn {
                 if let Some(ability_descriptor) = ability_descriptor_start{
                     let stats = ptry!(self.inventory.get_combined_stats());
-                    let mana_cost = ptry!(self.adjusted_mana_cost(ability_descriptor.mana_cost));
+                    let player_ability = punwrap!(self.inventory.get_ability_mut(ability_id), Invalid, "attempting to start non-existent player ability with id {}", ability_id);
+                    let rank = player_ability.rank;
+                    let mana_cost = ptry!(self.adjusted_mana_cost(ability_descriptor.get_mana_cost(rank)));
                     let player_ability = punwrap!(self.inventory.get_ability_mut(ability_id), Invalid, "attempting to start non-existent player ability with id {}", ability_id);
                     ability_descriptor.setup_player_ability(player_ability, &stats);
                     let player = self.player.borrow();
@@ -1287,6 +1557,7 @@ impl World{
                             player_position: (x, y),
                             player_direction: dir,
                         }), "while starting up ability with id {} that was invoked by the hotkey {}", ability_id, key);
+                        self.spawn_player_particles(super::particles::CAST_EMITTER);
                     }
                 }
             }
@@ -1305,6 +1576,7 @@ impl World{
                 let stats = ptry!(self.inventory.get_combined_stats());
                 let pitem = self.inventory.get_cur_held_item();
                 let mut attacked = false;
+                let mut summon = None;
                 if let Some(item) = pitem {
                     if item.time_til_usable <= 0.0 && (item.item_type == ItemType::MeleeWeapon || item.item_type == ItemType::MagicWeapon){
                         let mouse_direction_unnormalized = [(mouse_position.x_world - player.x - 16.0), (mouse_position.y_world - player.y - 22.0)];
@@ -1314,7 +1586,10 @@ impl World{
                             mouse_direction_unnormalized[1] / magnitude
                         ];
                         let shots = stats.shots.map(|x| x.get_value()).unwrap_or(1.0).floor() as usize;
-                        if shots > 1 && (item.item_type == ItemType::RangedWeapon || item.item_type == ItemType::MagicWeapon) {
+                        if let Some(descriptor) = self.get_item_archetype(&item.archetype).and_then(|x| x.summon.clone()) {
+                            summon = Some(descriptor);
+                            attacked = true;
+                        } else if shots > 1 && (item.item_type == ItemType::RangedWeapon || item.item_type == ItemType::MagicWeapon) {
                             let mut spread = f32::min(PI/8.0, PI/shots as f32);
                             spread /= stats.focus.map(|x| x.get_value()).unwrap_or(1.0);
                             let angle = mouse_direction_normalized[1].atan2(mouse_direction_normalized[0]) - (shots as f32 - 1.0) * spread/2.0;
@@ -1328,6 +1603,10 @@ impl World{
                                         ang_adjusted * 180.0/PI));
                             }
                             attacked = true;
+                        } else if let Some(swing) = (item.item_type == ItemType::MeleeWeapon).then(|| self.next_melee_swing(&mut player)).flatten() {
+                            let angle = mouse_direction_normalized[1].atan2(mouse_direction_normalized[0]);
+                            ptry!(self.start_melee_swing(&mut player, &stats, item, &swing, angle));
+                            attacked = true;
                         } else {
                             let angle = mouse_direction_normalized[1].atan2(mouse_direction_normalized[0]);
                             ptry!(self.add_player_attack(
@@ -1347,6 +1626,10 @@ impl World{
                     }
                     item.time_til_usable = stats.attack_cooldown.map(|x| x.get_value()).unwrap_or(0.0);
                 }
+                if let Some(summon) = summon {
+                    drop(player);
+                    ptry!(self.summon_minions(&summon, mouse_position.x_world - 16.0, mouse_position.y_world - 16.0), "while summoning with held item");
+                }
             }
             else{
                 // NOTHING FOR NOW
@@ -1356,12 +1639,13 @@ impl World{
         Ok(())
     }
     pub fn process_mouse_input(&mut self, mouse_position: MousePosition, mouse_left: bool, mouse_right: bool) -> Result<(), PError>{
+        ptry!(self.update_block(mouse_right));
         let mut player = self.player.borrow_mut();
         if mouse_left{
             if player.player_state == PlayerState::Idle || player.player_state == PlayerState::Walking || player.player_state == PlayerState::AttackingRanged {
                 let stats = ptry!(self.inventory.get_combined_stats());
                 let pitem = self.inventory.get_cur_held_item();
-                let mut attacked = false;
+                let mut fire = false;
                 let mut ranged = false;
                 if let Some(item) = pitem {
                     if item.item_type == ItemType::RangedWeapon {
@@ -1369,39 +1653,17 @@ impl World{
                         player.player_state = PlayerState::AttackingRanged;
                     }
                     if item.time_til_usable <= 0.0 && item.item_type == ItemType::RangedWeapon {
-                        let mouse_direction_unnormalized = [(mouse_position.x_world - player.x - 16.0), (mouse_position.y_world - player.y - 22.0)];
-                        let magnitude = f32::sqrt(mouse_direction_unnormalized[0].powf(2.0) + mouse_direction_unnormalized[1].powf(2.0));
-                        let mouse_direction_normalized = [
-                            mouse_direction_unnormalized[0] / magnitude,
-                            mouse_direction_unnormalized[1] / magnitude
-                        ];
-                        let shots = stats.shots.map(|x| x.get_value()).unwrap_or(1.0).floor() as usize;
-                        if shots > 1 && (item.item_type == ItemType::RangedWeapon || item.item_type == ItemType::MagicWeapon) {
-                            let mut spread = f32::min(PI/8.0, PI/shots as f32);
-                            spread /= stats.focus.map(|x| x.get_value()).unwrap_or(1.0);
-                            let angle = mouse_direction_normalized[1].atan2(mouse_direction_normalized[0]) - (shots as f32 - 1.0) * spread/2.0;
-                            for i in 0..shots {
-                                let ang_adjusted = angle + spread * i as f32;
-                                ptry!(self.add_player_attack(
-                                        &stats,
-                                        item, 
-                                        player.x + 16.0 + ang_adjusted.cos() * 25.0,
-                                        player.y + 22.0 + ang_adjusted.sin() * 25.0,
-                                        ang_adjusted * 180.0/PI));
-                            }
-                            attacked = true;
+                        let charge_time = self.get_ranged_descriptor(item).charge_time;
+                        if charge_time > 0.0 {
+                            // charged weapons fire when the button is released
+                            player.ranged_charge = f32::min(player.ranged_charge + 1.0, charge_time);
                         } else {
-                            let angle = mouse_direction_normalized[1].atan2(mouse_direction_normalized[0]);
-                            ptry!(self.add_player_attack(
-                                    &stats, 
-                                    item,
-                                    player.x + 16.0 + angle.cos() * 25.0,
-                                    player.y + 22.0 + angle.sin() * 25.0,
-                                    angle * 180.0/PI));
-                            attacked = true;
+                            fire = true;
                         }
                     }
                 }
+                drop(player);
+                let attacked = fire && ptry!(self.fire_ranged_weapon(mouse_position, 1.0));
                 if attacked {
                     let item = punwrap!(self.inventory.get_cur_held_item_mut(), Expected, "attacked with no item?");
                     item.time_til_usable = stats.attack_cooldown.map(|x| x.get_value()).unwrap_or(0.0);
@@ -1414,13 +1676,19 @@ impl World{
                 // NOTHING FOR NOW
             }
                 
-        }else {
-            let pitem = self.inventory.get_cur_held_item();
-            if let Some(item) = pitem{
-                if player.player_state == PlayerState::AttackingRanged {
-                    player.player_state = PlayerState::Idle;
+        }else if player.player_state == PlayerState::AttackingRanged {
+            player.player_state = PlayerState::Idle;
+            let charged = player.ranged_charge;
+            player.ranged_charge = 0.0;
+            drop(player);
+            if charged > 0.0 {
+                let charge_time = self.inventory.get_cur_held_item().map(|x| self.get_ranged_descriptor(x).charge_time).unwrap_or(0.0);
+                if charge_time > 0.0 && ptry!(self.fire_ranged_weapon(mouse_position, charged/charge_time)) {
+                    let cooldown = ptry!(self.inventory.get_combined_stats()).attack_cooldown.map(|x| x.get_value()).unwrap_or(0.0);
+                    let item = punwrap!(self.inventory.get_cur_held_item_mut(), Expected, "fired a charged shot with no item?");
+                    item.time_til_usable = cooldown;
                 }
-            } 
+            }
         }
         Ok(())
     }
@@ -1442,7 +1710,7 @@ impl World{
                     self.player.borrow_mut().holding_texture_sprite = None; 
                 }
             }
-            PlayerState::AttackingRanged | PlayerState::AttackingMelee | PlayerState::ChargingAbility => {
+            PlayerState::AttackingRanged | PlayerState::AttackingMelee | PlayerState::ChargingAbility | PlayerState::Blocking => {
                 drop(player);
                 ptry!(self.process_player_input(keys, move_speed/3.0));
                 let player = self.player.borrow();
@@ -1456,6 +1724,10 @@ impl World{
                     self.player.borrow_mut().holding_texture_sprite = None; 
                 }
             }
+            PlayerState::Rolling => {
+                // rolls move the player on their own in update_player_defense
+                camera.update_camera_position(player.x, player.y);
+            }
 
         }
         Ok(())
@@ -1480,7 +1752,11 @@ impl World{
             lore: archetype_i.lore.clone(),
             sprite: archetype_i.sprite.clone(),
             time_til_usable: stat_variation.attack_cooldown.map(|x| x.get_value()).unwrap_or(0.0),
-            stats: stat_variation
+            stats: stat_variation,
+            archetype,
+            rarity: archetype_i.rarity,
+            damage_type: archetype_i.damage_type,
+            uses: archetype_i.uses
         })
     }
     pub fn get_item_archetype(&self, archetype: &CompactString) -> Option<&ItemArchetype>{
@@ -1522,6 +1798,7 @@ impl World{
             ptry!(self.inventory.remove_item(*item), "while closing inventory");
         }
         self.inventory.items_waiting_to_be_dropped.clear();
+        ptry!(self.close_shop(), "while closing inventory");
         Ok(())
     }
     pub fn update_items_on_ground(&mut self) -> Result<(), PError> {
@@ -1530,6 +1807,7 @@ impl World{
         let px = player.x + 16.0;
         let py = player.y + 22.0;
         let mut to_be_removed = Vec::new();
+        let mut abilities_learned = Vec::new();
         for (i, item) in items_on_ground.iter_mut().enumerate(){
             let dir_to_player = [px - item.x, py - item.y];
             let dist_from_player = f32::sqrt(dir_to_player[0].powf(2.0) + dir_to_player[1].powf(2.0));
@@ -1540,6 +1818,11 @@ impl World{
                 item.y += dir_to_player_normalized[1] * speed;
             }
             else if dist_from_player <= 15.0 {
+                if let Some(ability) = self.get_item_archetype(&item.item.archetype).and_then(|x| x.teaches_ability.clone()) {
+                    abilities_learned.push(ability);
+                    to_be_removed.push(i);
+                    continue;
+                }
                 let e = error_prolif_allow!(
                     self.inventory.add_to_slot(item.item.clone()),
                     NoSpace);
@@ -1552,6 +1835,26 @@ impl World{
         for (offset, item) in to_be_removed.iter().enumerate(){
             items_on_ground.remove(*item - offset);
         }
+        let mut gold_on_ground = self.gold_on_floor.borrow_mut();
+        for gold in gold_on_ground.iter_mut() {
+            let dir_to_player = [px - gold.x, py - gold.y];
+            let dist_from_player = f32::sqrt(dir_to_player[0].powf(2.0) + dir_to_player[1].powf(2.0));
+            if dist_from_player <= 120.0 && dist_from_player > 15.0 {
+                let speed = 2.6/120.0 * (120.0 - dist_from_player) + 0.2;
+                gold.x += dir_to_player[0] / dist_from_player * speed;
+                gold.y += dir_to_player[1] / dist_from_player * speed;
+            } else if dist_from_player <= 15.0 {
+                self.inventory.gold += gold.amount;
+                gold.amount = 0;
+            }
+        }
+        gold_on_ground.retain(|x| x.amount > 0);
+        drop(gold_on_ground);
+        drop(items_on_ground);
+        drop(player);
+        for ability in abilities_learned {
+            ptry!(self.unlock_ability(&ability), "while learning an ability from an item");
+        }
         Ok(())
     }
     pub fn update_items_in_inventory_cd(&mut self) -> Result<(), PError> {
@@ -1577,7 +1880,8 @@ impl World{
                 PlayerState::ChargingAbility => {
 
                     let descriptor = ptry!(self.get_cur_ability_descriptor());
-                    let mcwc = ptry!(self.adjusted_mana_cost(descriptor.mana_cost_while_charging));
+                    let rank = punwrap!(self.inventory.get_ability(cur_ability_charging), Invalid, "cur ability charging refers to player ability with id {} but there is no player ability with id {}", cur_ability_charging, cur_ability_charging).rank;
+                    let mcwc = ptry!(self.adjusted_mana_cost(descriptor.get_mana_cost_while_charging(rank)));
                     let cur_ability_actions = ptry!(self.get_cur_ability_actions());
                     let while_charging_func = cur_ability_actions.while_charging;
                     ptry!(while_charging_func(self, *punwrap!(self.cur_ability_charging.as_ref()), &AbilityStateInformation {ability_key_held: correct_key, mouse_position: input_state.mouse_position, player_position: (px, py), player_direction: pdir}), "while calling charging func on current_ability with id {}", *punwrap!(self.cur_ability_charging.as_ref())); // unwrap should never fail as for cur_ability_actions to succeed, cur_ability_charging should be Some
@@ -1650,25 +1954,24 @@ impl World{
             let pw = player_ref.collision_box.w;
             let ph = player_ref.collision_box.h;
 
-            if px + pw > ex && px < ex + 46.0 && py + ph > ey && py < ey + 46.0 {
-                let mut rng = rand::thread_rng();
-                let index = rng.gen_range(0..self.room_descriptors.len()); 
-                let mut name = None;
-                let mut ent = None;
-                let mut exi = None;
-                for (i, (n, desc)) in self.room_descriptors.iter().enumerate() {
-                    if i == index {
-                        name = Some(n);
-                        ent = Some(desc.entrance);
-                        exi = Some(desc.exit);
-                        break;
-                    }
-                }
+            if px + pw > ex && px < ex + 46.0 && py + ph > ey && py < ey + 46.0 && !self.is_exit_locked() {
                 drop(player_ref);
+                // the next room already belongs to the next depth, so it's picked and generated in the biome it's in,
+                // the depth goes back down if no room ends up being generated
+                self.depth += 1;
+                let name = self.pick_room(&mut rand::thread_rng());
+                let descriptor = name.as_ref().and_then(|x| self.room_descriptors.get(x));
+                let ent = descriptor.map(|x| x.entrance);
+                let exi = descriptor.map(|x| x.exit);
                 if let Some(name) = name {
                     let y = if cur_exit[1] > ent.unwrap()[1] {cur_exit[1] - ent.unwrap()[1]} else{0};
-                    
-                    ptry!(super::starting_level_generator::generate_room(self,name.clone(), cur_exit[0] + 7, y));
+                    let generated = super::starting_level_generator::generate_room(self,name.clone(), cur_exit[0] + 7, y);
+                    if generated.is_err() {
+                        self.depth -= 1;
+                    }
+                    ptry!(generated);
+                    // the last room is left behind, so nothing dying in it is seen again
+                    self.death_animations.clear();
                     let mut ent = ent.unwrap();
                     ent[0] += cur_exit[0] + 7;
                     ent[1] += y;
@@ -1679,6 +1982,8 @@ impl World{
                     let mut player_ref = self.player.borrow_mut();
                     player_ref.x = ent[0] as f32 * 32.0;
                     player_ref.y = ent[1] as f32 * 32.0;
+                } else {
+                    self.depth -= 1;
                 }
             } 
         }
@@ -1726,17 +2031,29 @@ impl World{
 
         drop(mut_player_ref);
         if poison_tick > 0.0 {
-            ptry!(self.damage_player(poison_tick, camera, [0.6, 0.0, 0.8, 1.0]));
+            ptry!(self.damage_player(poison_tick, DamageType::Poison, camera));
+            self.spawn_player_particles(super::particles::POISON_EMITTER);
         }
         if fire_tick > 0.0 {
-            ptry!(self.damage_player(fire_tick, camera, [1.0, 0.4, 0.0, 1.0]));
+            ptry!(self.damage_player(fire_tick, DamageType::Fire, camera));
+            self.spawn_player_particles(super::particles::BURN_EMITTER);
         }
 
         Ok(())
     }
-    pub fn update_player_anim(&self) {
-        self.player.borrow_mut().anim_frame += 1;
-        self.player.borrow_mut().anim_frame %= 120;
+    pub fn update_player_hit_state(&self) -> Result<(), PError> {
+        let mut player = self.player.borrow_mut();
+        if player.iframes > 0.0 {
+            player.iframes -= 1.0;
+        }
+        let knockback = player.knockback;
+        if f32::sqrt(knockback[0].powf(2.0) + knockback[1].powf(2.0)) < MIN_KNOCKBACK {
+            player.knockback = [0.0, 0.0];
+            return Ok(());
+        }
+        ptry!(self.attempt_move_player(&mut player, knockback), "while applying knockback to the player");
+        player.knockback = [knockback[0] * KNOCKBACK_DECAY, knockback[1] * KNOCKBACK_DECAY];
+        Ok(())
     }
     pub fn player_health_regen(&self, camera: &mut Camera) -> Result<(), PError>{
         let health_regen = ptry!(self.inventory.get_combined_stats()).health_regen.map(|x| x.get_value()).unwrap_or(0.0);
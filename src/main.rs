@@ -13,7 +13,7 @@ use crate::error::PError;
 pub mod rendering_engine;
 use rendering_engine::{renderer, texture, vertex, window};
 pub mod game_engine;
-use game_engine::{camera, json_parsing::{self, PATH_BUNDLE}, starting_level_generator, stat, ui::UIElementDescriptor, world};
+use game_engine::{camera, json_parsing::{self, PATH_BUNDLE}, level_editor::{LevelEditor, EDITOR_FLAG}, room_format::{self, ROOM_LEVEL_COMMAND}, starting_level_generator, stat, tiled_import::{self, TILED_IMPORT_COMMAND}, ui::UIElementDescriptor, world};
 use compact_str::CompactString;
 pub mod tests;
 
@@ -29,6 +29,15 @@ fn main() {
 
 
 fn startup() -> Result<(), PError> {
+    let mut args: Vec<String> = env::args().collect();
+    let importing = args.get(1).is_some_and(|x| x == TILED_IMPORT_COMMAND);
+    let converting_room = args.get(1).is_some_and(|x| x == ROOM_LEVEL_COMMAND);
+    if importing || converting_room {
+        // the file path is relative to where the command was run, not the project root
+        if let (Some(path), Ok(dir)) = (args.get_mut(2), env::current_dir()) {
+            *path = dir.join(&path).to_string_lossy().to_string();
+        }
+    }
     let mut current_dir = match env::current_exe() {
         Ok(val) => val,
         Err(e) => return Err(perror!(NotFound, "failed to find executable path because: {}", e))
@@ -45,20 +54,29 @@ fn startup() -> Result<(), PError> {
         Err(e) => return Err(perror!(NotFound, "failed to change working directory to project root because: {}", e))
     }
     println!("Changed working directory to project root: {:?}", current_dir);
+    if importing {
+        return tiled_import::run_tiled_import_command(&args[2..]);
+    }
+    if converting_room {
+        return room_format::run_room_level_command(&args[2..]);
+    }
     let mut parser = json_parsing::JSON_parser::new();
     let load_time = Instant::now();
     let parsed_data = parser.parse_and_convert_game_data(PATH_BUNDLE);
     let mut camera = camera::Camera::new(1152,720);
-    let mut world = ptry!(starting_level_generator::generate_world_from_json_parsed_data(&parsed_data));
-    let sword = world.inventory.add_item(
-        ptry!(world.create_item_with_archetype(CompactString::from("basic sword")))
-    );
-    let spear = world.inventory.add_item(
-        ptry!(world.create_item_with_archetype(CompactString::from("basic spear")))
-    );
-    world.inventory.init_ui();
-    ptry!(world.inventory.set_hotbar_slot_item(3, spear));
-    ptry!(world.inventory.set_hotbar_slot_item(0, sword));
+    let editor = if args.iter().any(|x| x == EDITOR_FLAG) {
+        Some(LevelEditor::new(parser.clone(), parsed_data.clone()))
+    } else {
+        None
+    };
+    let world = match editor.as_ref() {
+        Some(editor) => ptry!(editor.build_world()),
+        None => {
+            let mut world = ptry!(starting_level_generator::generate_world_from_json_parsed_data(&parsed_data));
+            ptry!(starting_level_generator::give_starting_items(&mut world));
+            world
+        }
+    };
     camera.level_text_id = Some(camera.add_text(
             "Lv. 0".into(),
             camera::Font::A,
@@ -128,7 +146,7 @@ fn startup() -> Result<(), PError> {
     });
 
     println!("Time to load: {:?} ms", load_time.elapsed().as_millis());
-    pollster::block_on(window::run(world, camera, &parsed_data.sprites_to_load_json));
+    pollster::block_on(window::run(world, camera, &parsed_data.sprites_to_load_json, editor));
     Ok(())
 }
 
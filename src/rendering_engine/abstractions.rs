@@ -2,9 +2,9 @@ use std::{collections::HashMap, path::Path};
 use compact_str::CompactString;
 use image::io::Reader;
 use wgpu_text::glyph_brush::{HorizontalAlign, Layout, Section as TextSection, Text};
-use crate::game_engine::{camera::Camera, json_parsing::{sprite_sheet_json, sprite_sheet_sprite_json, sprites_json_descriptor}, ui::UIESprite, utils::{get_rotated_corners, Rectangle}};
+use crate::game_engine::{animation::AnimationClip, camera::Camera, json_parsing::{sprite_sheet_json, sprite_sheet_sprite_json, sprites_json_descriptor}, ui::UIESprite, utils::{get_rotated_corners, Rectangle}};
 
-use super::{sprite_sheet_generation_abstraction::SpriteSheetSheet, vertex::Vertex};
+use super::{sprite_sheet_generation_abstraction::SpriteSheetSheet, vertex::{Light, Vertex, WHITE}};
 
 #[derive(Debug, Clone, Copy)]
 pub struct SpriteSheet {
@@ -55,10 +55,10 @@ impl Sprite {
         let x: f32 = (screen_x + (vertex_offset_x as f32)) * screen_to_render_ratio_x - 1.0;
         let y: f32 = -1.0 * ((screen_y + (vertex_offset_y as f32)) * screen_to_render_ratio_y - 1.0) - h;
         let vertex: Vec<Vertex> = vec![
-            Vertex { position: [x, y, 0.0], tex_coords: [self.tex_x, self.tex_y + self.tex_h], index: self.texture_index },
-            Vertex { position: [x + w, y, 0.0], tex_coords: [self.tex_x + self.tex_w, self.tex_y + self.tex_h], index: self.texture_index },
-            Vertex { position: [x + w, y + h, 0.0], tex_coords: [self.tex_x + self.tex_w, self.tex_y], index: self.texture_index },
-            Vertex { position: [x, y + h, 0.0], tex_coords: [self.tex_x, self.tex_y], index: self.texture_index },
+            Vertex { position: [x, y, 0.0], tex_coords: [self.tex_x, self.tex_y + self.tex_h], index: self.texture_index, color: WHITE },
+            Vertex { position: [x + w, y, 0.0], tex_coords: [self.tex_x + self.tex_w, self.tex_y + self.tex_h], index: self.texture_index, color: WHITE },
+            Vertex { position: [x + w, y + h, 0.0], tex_coords: [self.tex_x + self.tex_w, self.tex_y], index: self.texture_index, color: WHITE },
+            Vertex { position: [x, y + h, 0.0], tex_coords: [self.tex_x, self.tex_y], index: self.texture_index, color: WHITE },
         ];
         let index: Vec<u32> = vec![index_offset, 1 + index_offset, 2 + index_offset, index_offset, 2 + index_offset, 3 + index_offset];
 
@@ -100,6 +100,7 @@ impl Sprite {
                 position: [x, y, 0.0],
                 tex_coords: tex[i],
                 index: self.texture_index,
+                color: WHITE,
             });
         }
 
@@ -144,6 +145,9 @@ impl RenderData{
             sections_a_t: Vec::new(),
             sections_b_t: Vec::new(),
             index_behind_text: 0,
+            ambient_light: 1.0,
+            lights: Vec::new(),
+            light_aspect: 1.0,
         }
     }
 }
@@ -156,7 +160,9 @@ pub struct RenderDataFull<'a>{
     pub sections_a_t: Vec<TextSection<'a>>,
     pub sections_b_t: Vec<TextSection<'a>>,
     pub index_behind_text: u32,
-
+    pub ambient_light: f32, // how lit everything behind the text is without any lights, 1 is fully lit
+    pub lights: Vec<Light>,
+    pub light_aspect: f32, // width over height of the view the lights were placed in, so they stay round
 }
 
 impl Default for RenderDataFull<'_> {
@@ -167,14 +173,15 @@ impl Default for RenderDataFull<'_> {
 
 impl RenderDataFull<'_>{
     pub fn new() -> Self{
-        Self{ vertex: Vec::new(), index: Vec::new(), sections_a_b: Vec::new(), sections_b_b: Vec::new(), sections_a_t: Vec::new(), sections_b_t: Vec::new(), index_behind_text: 0 }
+        Self{ vertex: Vec::new(), index: Vec::new(), sections_a_b: Vec::new(), sections_b_b: Vec::new(), sections_a_t: Vec::new(), sections_b_t: Vec::new(), index_behind_text: 0, ambient_light: 1.0, lights: Vec::new(), light_aspect: 1.0 }
     }
 }
 
 #[derive(Debug, Clone)]
 pub struct SpriteContainer{
     pub sprites: Vec<Sprite>,
-    pub sprite_id_lookup: HashMap<CompactString, usize>
+    pub sprite_id_lookup: HashMap<CompactString, usize>,
+    pub animations: HashMap<CompactString, AnimationClip>
 }
 
 impl Default for SpriteContainer {
@@ -187,7 +194,8 @@ impl SpriteContainer{
     pub fn new() -> SpriteContainer{
         SpriteContainer{
             sprites: Vec::new(),
-            sprite_id_lookup: HashMap::new()
+            sprite_id_lookup: HashMap::new(),
+            animations: HashMap::new()
         }
     }
     pub fn create_from_json(descriptor: &sprites_json_descriptor) -> (Vec<String>, SpriteContainer){
@@ -229,10 +237,17 @@ impl SpriteContainer{
                 sprite_id_lookup.insert(names[i].clone(), sprites.len() - 1);
             }
         }
-        (sprites_to_load, SpriteContainer{
+        let mut container = SpriteContainer{
             sprites,
-            sprite_id_lookup
-        })
+            sprite_id_lookup,
+            animations: HashMap::new()
+        };
+        // clips are made of sprites, so they're resolved once every sprite has an id
+        for clip in descriptor.animations.iter() {
+            let animation = crate::ok_or_panic!(AnimationClip::from_json(clip, &container));
+            container.animations.insert(clip.name.clone(), animation);
+        }
+        (sprites_to_load, container)
     }
 
     pub fn get_sprite_by_name(&self, name: &str) -> Option<&Sprite>{
@@ -245,6 +260,9 @@ impl SpriteContainer{
     pub fn get_sprite(&self, id: usize) -> Option<&Sprite>{
         self.sprites.get(id)
     }
+    pub fn get_animation(&self, name: &str) -> Option<&AnimationClip>{
+        self.animations.get(name)
+    }
     pub fn get_texture_index_by_name(&self, name: &str) -> Option<i32>{
         let id = self.sprite_id_lookup.get(name)?;
         self.sprites.get(*id).map(|s| s.texture_index)
@@ -1,7 +1,7 @@
 use wgpu_text::{BrushBuilder, TextBrush, glyph_brush::ab_glyph::FontRef};
 use winit::window::Window;
 use wgpu::util::DeviceExt;
-use crate::vertex::Vertex;
+use crate::vertex::{Light, Vertex, MAX_LIGHTS};
 use crate::texture::{create_texture_bind_group, Texture};
 use std::num::{NonZeroU64, NonZeroU32};
 use std::fs;
@@ -16,6 +16,16 @@ pub const BACKGROUND_COLOR: wgpu::Color = wgpu::Color {
     a: 1.0,
 };
 
+// Laid out like Lighting in shader.wgsl
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct LightingUniform {
+    screen: [f32; 4],
+    ambient: f32,
+    count: u32,
+    _padding: [u32; 2],
+    lights: [Light; MAX_LIGHTS],
+}
 
 pub struct Renderer<'a> {
     pub surface: wgpu::Surface<'a>,
@@ -23,8 +33,11 @@ pub struct Renderer<'a> {
     pub queue: wgpu::Queue,
     pub config: wgpu::SurfaceConfiguration,
     pub size: winit::dpi::PhysicalSize<u32>,
-    pub render_pipeline: wgpu::RenderPipeline,
+    pub render_pipeline: wgpu::RenderPipeline, // the hud, drawn as is
+    pub lit_render_pipeline: wgpu::RenderPipeline, // the world, drawn with the lights
     pub diffuse_bind_group: wgpu::BindGroup,
+    pub lighting_buffer: wgpu::Buffer,
+    pub lighting_bind_group: wgpu::BindGroup,
     pub text_brush_a_b: TextBrush<FontRef<'a>>,
     pub text_brush_b_b: TextBrush<FontRef<'a>>,
     pub text_brush_a_t: TextBrush<FontRef<'a>>,
@@ -97,14 +110,44 @@ impl<'a> Renderer<'a> {
         let bytes_b_2 = include_bytes!("./img/fontb.ttf");
         let brush_b_2: TextBrush<FontRef<'_>> = BrushBuilder::using_font_bytes(bytes_b_2).unwrap().build(&device, config.width, config.height, config.format);
 
+        let lighting_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Lighting Buffer"),
+            size: size_of::<LightingUniform>() as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let lighting_bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Lighting Bind Group Layout"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+        });
+        let lighting_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Lighting Bind Group"),
+            layout: &lighting_bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: lighting_buffer.as_entire_binding(),
+            }],
+        });
+
         let render_pipeline_layout =
             device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
                 label: Some("Render Pipeline Layout"),
-                bind_group_layouts: &[&texture_bind_group_layout],
+                bind_group_layouts: &[&texture_bind_group_layout, &lighting_bind_group_layout],
                 push_constant_ranges: &[],
             });
-            let render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-                label: Some("Render Pipeline"),
+        // the same pipeline twice, only the fragment entry point changes
+        let create_pipeline = |label: &str, fragment_entry_point: &str| {
+            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some(label),
                 layout: Some(&render_pipeline_layout),
                 vertex: wgpu::VertexState {
                     module: &shader,
@@ -112,13 +155,13 @@ impl<'a> Renderer<'a> {
                     buffers: &[wgpu::VertexBufferLayout {
                         array_stride: vertex_size as wgpu::BufferAddress,
                         step_mode: wgpu::VertexStepMode::Vertex,
-                        attributes: &wgpu::vertex_attr_array![0 => Float32x3, 1 => Float32x2, 2 => Sint32],
+                        attributes: &wgpu::vertex_attr_array![0 => Float32x3, 1 => Float32x2, 2 => Sint32, 3 => Float32x4],
                     }],
                     compilation_options: wgpu::PipelineCompilationOptions::default(),
                 },
                 fragment: Some(wgpu::FragmentState {
                     module: &shader,
-                    entry_point: Some("non_uniform_main"),
+                    entry_point: Some(fragment_entry_point),
                     targets: &[Some(wgpu::ColorTargetState {
                         format: config.format,
                         blend: Some(wgpu::BlendState::ALPHA_BLENDING),
@@ -143,7 +186,10 @@ impl<'a> Renderer<'a> {
                 },
                 multiview: None,
                 cache: None,
-            });
+            })
+        };
+        let render_pipeline = create_pipeline("Render Pipeline", "non_uniform_main");
+        let lit_render_pipeline = create_pipeline("Lit Render Pipeline", "lit_main");
         Self {
             window,
             surface,
@@ -152,7 +198,10 @@ impl<'a> Renderer<'a> {
             config,
             size,
             render_pipeline,
+            lit_render_pipeline,
             diffuse_bind_group,
+            lighting_buffer,
+            lighting_bind_group,
             text_brush_a_b: brush_a,
             text_brush_b_b: brush_b,
             text_brush_a_t: brush_a_2,
@@ -203,6 +252,18 @@ impl<'a> Renderer<'a> {
             }
         );
 
+        let mut lighting = LightingUniform {
+            screen: [self.config.width as f32, self.config.height as f32, render_data.light_aspect, 0.0],
+            ambient: render_data.ambient_light,
+            count: render_data.lights.len().min(MAX_LIGHTS) as u32,
+            _padding: [0; 2],
+            lights: [Light {position: [0.0; 2], radius: 1.0, intensity: 0.0, color: [0.0; 4]}; MAX_LIGHTS],
+        };
+        for (slot, light) in lighting.lights.iter_mut().zip(render_data.lights.iter()) {
+            *slot = *light;
+        }
+        self.queue.write_buffer(&self.lighting_buffer, 0, bytemuck::bytes_of(&lighting));
+
         let output = self.surface.get_current_texture()?;
         let view = output.texture.create_view(&wgpu::TextureViewDescriptor::default());
 
@@ -230,8 +291,9 @@ impl<'a> Renderer<'a> {
                         timestamp_writes: None,
             });
 
-            render_pass.set_pipeline(&self.render_pipeline);
+            render_pass.set_pipeline(&self.lit_render_pipeline);
             render_pass.set_bind_group(0, &self.diffuse_bind_group, &[0]);
+            render_pass.set_bind_group(1, &self.lighting_bind_group, &[]);
             render_pass.set_vertex_buffer(0, vertex_buffer.slice(..));
             render_pass.set_index_buffer(index_buffer.slice(..), wgpu::IndexFormat::Uint32);
             render_pass.draw_indexed(0..render_data.index_behind_text,0, 0..1);
@@ -2,12 +2,14 @@ struct VertexInput {
     @location(0) position: vec4<f32>,
     @location(1) tex_coords: vec2<f32>,
     @location(2) index: i32,
+    @location(3) color: vec4<f32>,
 }
 
 struct VertexOutput {
     @builtin(position) position: vec4<f32>,
     @location(0) tex_coords: vec2<f32>,
     @location(1) index: i32,
+    @location(2) color: vec4<f32>,
 }
 
 @vertex
@@ -16,13 +18,30 @@ fn vert_main(vertex: VertexInput) -> VertexOutput {
     outval.position = vec4<f32>(vertex.position.x, vertex.position.y, 0.0, 1.0);
     outval.tex_coords = vertex.tex_coords;
     outval.index = vertex.index;
+    outval.color = vertex.color;
     return outval;
 }
 
 
 struct FragmentInput {
+    @builtin(position) position: vec4<f32>,
     @location(0) tex_coords: vec2<f32>,
     @location(1) index: i32,
+    @location(2) color: vec4<f32>,
+}
+
+struct Light {
+    position: vec2<f32>, // 0 to 1 across the screen
+    radius: f32, // fraction of the screen height
+    intensity: f32,
+    color: vec4<f32>,
+}
+
+struct Lighting {
+    screen: vec4<f32>, // width and height in pixels, then the aspect the lights were placed with
+    ambient: f32,
+    count: u32,
+    lights: array<Light, 64>,
 }
 
 @group(0) @binding(0)
@@ -30,8 +49,26 @@ var texture_array: binding_array<texture_2d<f32>>;
 @group(0) @binding(1)
 var sampler_array: binding_array<sampler>;
 
+@group(1) @binding(0)
+var<uniform> lighting: Lighting;
+
 @fragment
 fn non_uniform_main(fragment: FragmentInput) -> @location(0) vec4<f32> {
-   return textureSample(texture_array[fragment.index], sampler_array[fragment.index], fragment.tex_coords);
+   return textureSample(texture_array[fragment.index], sampler_array[fragment.index], fragment.tex_coords) * fragment.color;
 };
 
+// the world is drawn darkened by the ambient light and brightened again by every light that reaches it
+@fragment
+fn lit_main(fragment: FragmentInput) -> @location(0) vec4<f32> {
+    let base = textureSample(texture_array[fragment.index], sampler_array[fragment.index], fragment.tex_coords) * fragment.color;
+    let uv = fragment.position.xy / lighting.screen.xy;
+    var light = vec3<f32>(lighting.ambient);
+    for (var i = 0u; i < lighting.count; i++) {
+        let source = lighting.lights[i];
+        let offset = (uv - source.position) * vec2<f32>(lighting.screen.z, 1.0);
+        let falloff = clamp(1.0 - length(offset) / source.radius, 0.0, 1.0);
+        light += source.color.rgb * source.intensity * falloff * falloff;
+    }
+    return vec4<f32>(base.rgb * min(light, vec3<f32>(1.0)), base.a);
+}
+
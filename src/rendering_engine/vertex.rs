@@ -4,4 +4,18 @@ pub struct Vertex {
     pub position: [f32; 3],
     pub tex_coords: [f32; 2],
     pub index: i32,
-}
\ No newline at end of file
+    pub color: [f32; 4], // multiplied into the sampled texture, white leaves it as is
+}
+
+pub const WHITE: [f32; 4] = [1.0, 1.0, 1.0, 1.0];
+pub const MAX_LIGHTS: usize = 64;
+
+// A point light in screen space, x and y go from 0 to 1 across the screen and the radius is a fraction of its height
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct Light {
+    pub position: [f32; 2],
+    pub radius: f32,
+    pub intensity: f32,
+    pub color: [f32; 4],
+}
@@ -4,17 +4,18 @@ use winit::{
 };
 use crate::error::PE;
 use crate::game_engine::game::Game;
+use crate::game_engine::level_editor::LevelEditor;
 use crate::print_error;
 use crate::renderer::Renderer;
 use crate::world::World;
 use crate::camera::Camera;
 
-pub async fn run(world: World, camera: Camera, sprites_json_to_load: &[String]) {
+pub async fn run(world: World, camera: Camera, sprites_json_to_load: &[String], editor: Option<LevelEditor>) {
     let event_loop = EventLoop::new().unwrap();
     let title = "小丰";
     let window = WindowBuilder::new().with_title(title).with_inner_size(winit::dpi::LogicalSize::new(1152, 720)).build(&event_loop).unwrap();
     let renderer = Renderer::new(&window, sprites_json_to_load).await;
-    let mut game = Game::new(world, camera, renderer);
+    let mut game = Game::new(world, camera, renderer, editor);
     let mut focused: bool = false;
 
     let mut rolling_average = Vec::new();
@@ -0,0 +1,119 @@
+#![cfg(test)]
+
+use crate::game_engine::animation::{AnimationClip, AnimationFrame, LoopMode, BURNING_CLIP};
+use crate::game_engine::json_parsing;
+use crate::game_engine::particles::HIT_EMITTER;
+use crate::game_engine::player::{PlayerDir, PlayerState};
+use crate::ok_or_panic;
+
+#[test]
+fn animation_test(){
+    let mut parser = json_parsing::JSON_parser::new();
+    let parsed_data = parser.parse_and_convert_game_data(json_parsing::PATH_BUNDLE);
+    let mut world = ok_or_panic!(crate::game_engine::starting_level_generator::generate_world_from_json_parsed_data(&parsed_data));
+    let burning = world.sprites.get_animation(BURNING_CLIP).expect("There should be a burning animation").clone();
+    let fire = [world.sprites.get_sprite_id("fire1").unwrap(), world.sprites.get_sprite_id("fire2").unwrap()];
+    assert!(burning.sprite_at(0) == fire[0] && burning.sprite_at(60) == fire[1] && burning.sprite_at(120) == fire[0], "Looping clips should go back to the start");
+
+    let frame = |sprite| AnimationFrame {sprite, duration: 2};
+    let ping_pong = AnimationClip {frames: vec![frame(0), frame(1), frame(2)], mode: LoopMode::PingPong, events: Vec::new()};
+    let frames: Vec<usize> = (0..10).map(|x| ping_pong.step_at(x).1).collect();
+    assert!(frames == vec![0, 0, 1, 1, 2, 2, 1, 1, 0, 0], "Ping pong clips should play backwards after reaching the end");
+    let once = AnimationClip {mode: LoopMode::Once, ..ping_pong};
+    assert!(once.step_at(100).1 == 2, "Clips that play once should stop on their last frame");
+
+    world.update_player_anim();
+    assert!(world.player.borrow().animation.clip.as_deref() == Some("player_idle_down"), "The player should idle facing down to start with");
+    world.player.borrow_mut().player_state = PlayerState::Walking;
+    world.player.borrow_mut().direction = PlayerDir::UpLeft;
+    world.update_player_anim();
+    assert!(world.player.borrow().animation.clip.as_deref() == Some("player_walk_left"), "Walking diagonally should use the clip for the side");
+    let walk = world.sprites.get_animation("player_walk_left").expect("There should be a walk clip").clone();
+    assert!(walk.frames.len() > 1 && (0..walk.length()).any(|x| walk.sprite_at(x) != walk.sprite_at(0)), "The walk clip should move between frames");
+    world.sprites.animations.remove("player_walk_left");
+    world.update_player_anim();
+    assert!(world.player.borrow().animation.clip.as_deref() == Some("player_idle_left"), "Walking without a walk clip should fall back to idle");
+
+    let walk = ok_or_panic!(AnimationClip::from_json(&json_parsing::animation_clip_json {
+        name: "player_walk_left".into(),
+        frames: vec![json_parsing::animation_frame_json {sprite: "fire1".into(), duration: None}, json_parsing::animation_frame_json {sprite: "fire2".into(), duration: None}],
+        frame_duration: 12,
+        mode: LoopMode::Loop,
+        events: vec![json_parsing::animation_event_json {frame: 0, name: HIT_EMITTER.into()}, json_parsing::animation_event_json {frame: 1, name: HIT_EMITTER.into()}]
+    }, &world.sprites));
+    world.sprites.animations.insert("player_walk_left".into(), walk);
+    world.update_player_anim();
+    assert!(world.player.borrow().animation.clip.as_deref() == Some("player_walk_left"), "Walking should switch to the walk clip once there is one");
+    assert!(world.player.borrow().animation.events.contains(&HIT_EMITTER.into()), "Events on the first frame should fire when the clip starts");
+    for _ in 0..11 {
+        world.update_player_anim();
+        assert!(world.player.borrow().animation.events.is_empty(), "Events should only fire as their frame starts");
+    }
+    world.update_player_anim();
+    assert!(world.player.borrow().animation.events.contains(&HIT_EMITTER.into()), "Events should fire as their frame starts");
+    world.player.borrow_mut().player_state = PlayerState::AttackingMelee;
+    world.update_player_anim();
+    assert!(world.player.borrow().animation.clip.as_deref() == Some("player_attack_left"), "Attacking should play the attack clip");
+    world.player.borrow_mut().player_state = PlayerState::Idle;
+    world.player.borrow_mut().iframes = 10.0;
+    world.update_player_anim();
+    assert!(world.player.borrow().animation.clip.as_deref() == Some("player_hurt_left"), "Getting hit should play the hurt clip");
+    world.player.borrow_mut().iframes = 0.0;
+    world.sprites.animations.remove("player_attack_left");
+    world.player.borrow_mut().player_state = PlayerState::AttackingMelee;
+    world.update_player_anim();
+    assert!(world.player.borrow().animation.clip.as_deref() == Some("player_idle_left"), "States without a clip should fall back to idle");
+
+    world.player.borrow_mut().player_state = PlayerState::Idle;
+    world.player.borrow_mut().health = 0.0;
+    world.update_player_anim();
+    assert!(world.player.borrow().animation.clip.as_deref() == Some("player_death"), "The player should play their death clip once they're out of health");
+    let player_death = world.sprites.get_animation("player_death").expect("There should be a player death clip").clone();
+    for _ in 1..player_death.length() - 1 {
+        world.update_player_anim();
+    }
+    assert!(!world.player_death_finished(), "The run shouldn't end before the death clip has played");
+    world.update_player_anim();
+    assert!(world.player_death_finished(), "The run should end once the death clip has played");
+    world.player.borrow_mut().health = 10.0;
+
+    let mut death_json = json_parsing::animation_clip_json {
+        name: "ghost_death".into(),
+        frames: vec![json_parsing::animation_frame_json {sprite: "fire1".into(), duration: None}, json_parsing::animation_frame_json {sprite: "fire2".into(), duration: Some(4)}],
+        frame_duration: 4,
+        mode: LoopMode::Loop,
+        events: Vec::new()
+    };
+    assert!(AnimationClip::from_json(&death_json, &world.sprites).is_err(), "Death clips that loop should not load since they'd never end");
+    death_json.name = "ghost_death_left".into();
+    assert!(AnimationClip::from_json(&death_json, &world.sprites).is_err(), "Death clips facing a direction that loop should not load");
+    death_json.name = "ghost_deathly_idle".into();
+    assert!(AnimationClip::from_json(&death_json, &world.sprites).is_ok(), "Clips that aren't for dying can loop");
+    death_json.name = "ghost_death".into();
+    death_json.mode = LoopMode::Once;
+    assert!(AnimationClip::from_json(&death_json, &world.sprites).is_ok(), "Death clips that play once should load");
+    assert!(world.entity_archetype_descriptor_lookup.get("ghost").expect("There should be a ghost").animation.as_deref() == Some("ghost"), "Ghosts should be animated");
+    for state in ["idle", "walk", "attack", "hurt", "death"] {
+        assert!(world.sprites.get_animation(&format!("ghost_{}", state)).is_some(), "Ghosts should have a {} clip", state);
+    }
+    let death = world.sprites.get_animation("ghost_death").expect("There should be a ghost death clip").clone();
+    let ghost = ok_or_panic!(world.create_entity_with_archetype(900.0, 405.0, "ghost".into()));
+    world.kill_entity(ghost);
+    ok_or_panic!(world.kill_entities_to_be_killed());
+    assert!(world.death_animations.len() == 1, "Entities with a death clip should leave it playing when they die");
+    for _ in 0..death.length() {
+        world.update_death_animations();
+    }
+    assert!(world.death_animations.is_empty(), "Death animations should go away once they've played");
+
+    let ghost = ok_or_panic!(world.create_entity_with_archetype(900.0, 405.0, "ghost".into()));
+    world.kill_entity(ghost);
+    ok_or_panic!(world.kill_entities_to_be_killed());
+    world.cur_room = None;
+    world.cur_exit = Some([40, 12]);
+    world.player.borrow_mut().x = 40.0 * 32.0;
+    world.player.borrow_mut().y = 12.0 * 32.0;
+    ok_or_panic!(world.update_gen());
+    assert!(world.cur_exit != Some([40, 12]), "Walking into the exit should generate the next room");
+    assert!(world.death_animations.is_empty(), "Death animations should be cleared when moving to the next room");
+}
@@ -1,8 +1,17 @@
 #![cfg(test)]
-use compact_str::{CompactString, ToCompactString};
+use std::f32::consts::PI;
 
-use crate::game_engine::entity_attacks::EntityAttackDescriptor;
-use crate::ok_or_panic;
+use compact_str::ToCompactString;
+
+use crate::game_engine::camera::Camera;
+use crate::game_engine::entity_attacks::{EntityAttackBox, EntityAttackDescriptor};
+use crate::game_engine::entity_components::KNOCKBACK_DECAY;
+use crate::game_engine::factions::{Faction, SummonDescriptor};
+use crate::game_engine::player::{PlayerDir, PlayerState, PLAYER_IFRAMES};
+use crate::game_engine::player_defense::{PARRY_STUN, PARRY_WINDOW, ROLL_FRAMES, ROLL_SPEED};
+use crate::game_engine::stat::{DamageType, Resistances, StatC};
+use crate::game_engine::world::World;
+use crate::{create_stat_list, ok_or_panic};
 use crate::tests::test_framework::{basic_world, basic_camera, basic_entity};
 use crate::tests::lib::headless::HeadlessGame;
 use crate::game_engine::entities::{AttackType, EntityAttackPattern};
@@ -18,6 +27,8 @@ async fn test_entity_can_kill_player(){
         max_start_dist_from_entity: None,
         time_to_charge: 5,
         sprite: "attack_highlight".to_compact_string(),
+        damage_type: crate::game_engine::stat::DamageType::Physical,
+        knockback: 0.0,
         fire: None,
         poison: None
     });
@@ -44,3 +55,186 @@ async fn test_entity_can_kill_player(){
         "Player should die with an entity attacking it"
     );
 }
+
+#[tokio::test]
+async fn test_resistances_scale_damage_by_type(){
+    let mut world = basic_world().await;
+    let mut camera = basic_camera(&mut world).await;
+    let mut entity = basic_entity().await;
+    entity.health = Some(100);
+    entity.resistances = Resistances {fire: 100.0, poison: -50.0, ..Default::default()};
+    world.add_entity_archetype("resistant".into(), entity);
+    let entity = ok_or_panic!(world.create_entity_with_archetype(900.0, 400.0, "resistant".into()));
+    let stats = create_stat_list!(damage => StatC {flat: 20.0, percent: 0.0});
+    let position = world.components.position_components[entity].as_ref().unwrap().borrow().clone();
+    let health = |world: &World| world.components.damageable_components[entity].as_ref().unwrap().borrow().health;
+
+    for (damage_type, expected) in [(DamageType::Physical, 80.0), (DamageType::Fire, 70.0), (DamageType::Poison, 40.0)] {
+        let mut damageable = world.components.damageable_components[entity].as_ref().unwrap().borrow_mut();
+        ok_or_panic!(world.damage_entity(&position, Some(&mut damageable), None, &stats, damage_type, 0.0, &mut camera));
+        drop(damageable);
+        assert!(health(&world) == expected, "{:?} damage should be scaled by the matching resistance", damage_type);
+    }
+    let mut damageable = world.components.damageable_components[entity].as_ref().unwrap().borrow_mut();
+    world.damage_entity_dot(&position, &mut damageable, 10.0, DamageType::Fire, &mut camera);
+    drop(damageable);
+    assert!(health(&world) == 35.0, "Damage over time should be scaled by resistances too");
+}
+
+fn hit_player_with(world: &World, attacker: usize, rotation: f32, camera: &mut Camera) {
+    let (x, y) = {
+        let player = world.player.borrow();
+        (player.x, player.y)
+    };
+    world.entity_attacks.borrow_mut().push(EntityAttackBox {
+        archetype: "test_attack".into(),
+        entity: attacker,
+        x,
+        y,
+        rotation,
+        time_charged: 5.0,
+    });
+    ok_or_panic!(world.update_entity_attacks(camera));
+}
+
+fn add_test_attack(world: &mut World, knockback: f32) {
+    world.entity_attack_descriptor_lookup.insert("test_attack".to_compact_string(), EntityAttackDescriptor{
+        damage: 10.0,
+        reach: 50,
+        width: 50,
+        r#type: AttackType::Melee,
+        max_start_dist_from_entity: None,
+        time_to_charge: 5,
+        sprite: "attack_highlight".to_compact_string(),
+        damage_type: DamageType::Physical,
+        knockback,
+        fire: None,
+        poison: None
+    });
+}
+
+#[tokio::test]
+async fn test_knockback_hitstun_and_iframes(){
+    let mut world = basic_world().await;
+    let mut camera = basic_camera(&mut world).await;
+    add_test_attack(&mut world, 8.0);
+    let mut target = basic_entity().await;
+    target.basic_tags = vec!["damageable".into(), "hasCollision".into()];
+    target.health = Some(100);
+    world.add_entity_archetype("target".into(), target);
+    let entity = ok_or_panic!(world.create_entity_with_archetype(900.0, 400.0, "target".into()));
+
+    let health = world.player.borrow().health;
+    let x = world.player.borrow().x;
+    hit_player_with(&world, entity, 0.0, &mut camera);
+    assert!(world.player.borrow().health == health - 10.0, "The player should take the hit");
+    assert!(world.player.borrow().iframes == PLAYER_IFRAMES, "Getting hit should make the player invulnerable");
+    hit_player_with(&world, entity, 0.0, &mut camera);
+    assert!(world.player.borrow().health == health - 10.0, "Hits during invulnerability should do nothing");
+    ok_or_panic!(world.update_player_hit_state());
+    assert!(world.player.borrow().x > x, "The hit should knock the player back along its direction");
+
+    let stats = create_stat_list!(
+        damage => StatC {flat: 1.0, percent: 0.0},
+        knockback => StatC {flat: 10.0, percent: 0.0},
+        hitstun => StatC {flat: 20.0, percent: 0.0}
+    );
+    let position = world.components.position_components[entity].as_ref().unwrap().borrow().clone();
+    {
+        let mut damageable = world.components.damageable_components[entity].as_ref().unwrap().borrow_mut();
+        ok_or_panic!(world.damage_entity(&position, Some(&mut damageable), None, &stats, DamageType::Physical, 0.0, &mut camera));
+    }
+    let mut headless = HeadlessGame::new(world, camera);
+    ok_or_panic!(headless.run(10).await);
+    let damageable = headless.world.components.damageable_components[entity].as_ref().unwrap().borrow().clone();
+    assert!(damageable.hitstun == 10.0, "Hitstun should count down every frame");
+    assert!(headless.world.components.position_components[entity].as_ref().unwrap().borrow().x > position.x + 10.0, "Knockback should push the entity away");
+    assert!(damageable.knockback[0] < 10.0 * KNOCKBACK_DECAY.powi(5), "Knockback should decay");
+}
+
+#[tokio::test]
+async fn test_block_parry_and_roll(){
+    let mut world = basic_world().await;
+    let mut camera = basic_camera(&mut world).await;
+    add_test_attack(&mut world, 0.0);
+    let mut attacker = basic_entity().await;
+    attacker.basic_tags = vec!["damageable".into()];
+    world.add_entity_archetype("attacker".into(), attacker);
+    let attacker = ok_or_panic!(world.create_entity_with_archetype(500.0, 400.0, "attacker".into()));
+    world.inventory.progression_stats.block = Some(StatC {flat: 50.0, percent: 0.0});
+    {
+        let mut player = world.player.borrow_mut();
+        player.direction = PlayerDir::Left;
+        player.player_state = PlayerState::Blocking;
+        player.block_time = 0.0;
+    }
+    let health = world.player.borrow().health;
+    hit_player_with(&world, attacker, 0.0, &mut camera);
+    assert!(world.player.borrow().health == health, "A hit right after starting to block should be parried");
+    assert!(world.components.damageable_components[attacker].as_ref().unwrap().borrow().hitstun == PARRY_STUN, "Parrying should stun the attacker");
+
+    hit_player_with(&world, attacker, 0.0, &mut camera);
+    assert!(world.player.borrow().health == health, "Stunned attackers should not be able to hit");
+
+    world.components.damageable_components[attacker].as_ref().unwrap().borrow_mut().hitstun = 0.0;
+    world.player.borrow_mut().block_time = PARRY_WINDOW + 1.0;
+    hit_player_with(&world, attacker, 0.0, &mut camera);
+    let blocked_damage = health - world.player.borrow().health;
+    assert!(blocked_damage > 0.0 && blocked_damage < 10.0, "Blocking should only take some of the damage");
+
+    world.player.borrow_mut().iframes = 0.0;
+    let health = world.player.borrow().health;
+    hit_player_with(&world, attacker, PI, &mut camera);
+    assert!(world.player.borrow().health == health - 10.0, "Hits from behind should not be blocked");
+
+    {
+        let mut player = world.player.borrow_mut();
+        player.iframes = 0.0;
+        player.player_state = PlayerState::Idle;
+    }
+    let x = world.player.borrow().x;
+    ok_or_panic!(world.start_roll());
+    assert!(world.player.borrow().player_state == PlayerState::Rolling && world.player.borrow().iframes >= ROLL_FRAMES, "Rolling should make the player invulnerable");
+    ok_or_panic!(world.update_player_defense());
+    assert!(world.player.borrow().x == x - ROLL_SPEED, "Rolling should move the player the way they face");
+    let health = world.player.borrow().health;
+    hit_player_with(&world, attacker, 0.0, &mut camera);
+    assert!(world.player.borrow().health == health, "Hits during a roll should do nothing");
+}
+
+#[tokio::test]
+async fn test_minion_cap_and_ally_targeting(){
+    let mut world = basic_world().await;
+    let mut camera = basic_camera(&mut world).await;
+    world.add_entity_archetype("minion".into(), basic_entity().await);
+    world.add_entity_archetype("Test".into(), basic_entity().await);
+    let summon = SummonDescriptor {archetype: "minion".into(), count: 2, lifetime: None, max_minions: 3};
+    let first = ok_or_panic!(world.summon_minions(&summon, 600.0, 400.0));
+    let second = ok_or_panic!(world.summon_minions(&summon, 600.0, 400.0));
+    assert!(first.len() == 2 && second.len() == 2, "Every minion in a summon should be created");
+    assert!(world.get_faction(first[0]) == Faction::Player, "Minions should be on the player's side");
+    let killed = world.entities_to_be_killed_at_end_of_frame.borrow().clone();
+    assert!(killed == vec![first[0]], "Going over the cap should unsummon only the oldest minion");
+
+    let short = SummonDescriptor {archetype: "Test".into(), count: 1, lifetime: Some(2.0), max_minions: 3};
+    let short_lived = ok_or_panic!(world.summon_minions(&short, 600.0, 400.0))[0];
+    ok_or_panic!(world.update_minions());
+    assert!(!world.entities_to_be_killed_at_end_of_frame.borrow().contains(&short_lived), "Minions should last until their lifetime runs out");
+    ok_or_panic!(world.update_minions());
+    assert!(world.entities_to_be_killed_at_end_of_frame.borrow().contains(&short_lived), "Minions should be unsummoned once their lifetime runs out");
+
+    let ally = second[0];
+    assert!(world.get_attack_target(ally).is_none(), "Allies without a target should not attack the player");
+    let enemy = ok_or_panic!(world.create_entity_with_archetype(700.0, 400.0, "Test".into()));
+    assert!(world.get_attack_target(enemy).is_some(), "Hostile entities should attack the player");
+    let aggro = world.components.aggro_components[ally].as_ref().unwrap();
+    ok_or_panic!(world.update_aggro_target(ally, 600.0, 400.0, &mut aggro.borrow_mut(), &[first[0], second[1], enemy]));
+    assert!(aggro.borrow().target == Some(enemy), "Allies should target the closest living enemy, not dead or friendly minions");
+
+    let stats = create_stat_list!(damage => StatC {flat: 5.0, percent: 0.0});
+    assert!(!ok_or_panic!(world.damage_entity_from(None, ally, &stats, DamageType::Physical, 0.0, &mut camera)), "The player should not hurt allies");
+    assert!(!ok_or_panic!(world.damage_entity_from(Some(ally), second[1], &stats, DamageType::Physical, 0.0, &mut camera)), "Allies should not hurt each other");
+    assert!(ok_or_panic!(world.damage_entity_from(Some(ally), enemy, &stats, DamageType::Physical, 0.0, &mut camera)), "Allies should hurt enemies");
+    let enemy_target = world.components.aggro_components[enemy].as_ref().unwrap().borrow().target;
+    assert!(enemy_target == Some(ally), "Enemies attacked by an ally should go after it");
+}
@@ -1,13 +1,15 @@
 #![cfg(test)]
-use compact_str::{CompactString, ToCompactString};
+use compact_str::ToCompactString;
 
 use crate::game_engine::entities::{AttackType, EntityAttackPattern};
 use crate::game_engine::entity_attacks::EntityAttackDescriptor;
+use crate::game_engine::entity_components::CollisionBox;
 use crate::game_engine::terrain::TerrainTags;
+use crate::game_engine::visibility::field_of_view;
 use crate::ok_or_panic;
+use crate::world::World;
 use crate::tests::test_framework::{basic_camera, basic_entity, basic_world};
 use crate::tests::lib::headless::HeadlessGame;
-use crate::game_engine::entity_components;
 
 #[tokio::test]
 async fn test_terrain_should_block_entities(){
@@ -31,7 +33,11 @@ async fn test_terrain_should_block_entities(){
         r#type: AttackType::Melee,
         max_start_dist_from_entity: None,
         time_to_charge: 5,
-        sprite: "attack_highlight".to_compact_string()
+        sprite: "attack_highlight".to_compact_string(),
+        damage_type: crate::game_engine::stat::DamageType::Physical,
+        knockback: 0.0,
+        fire: None,
+        poison: None
     });
     let attack_pattern = EntityAttackPattern::new(vec!["test_attack".to_compact_string()], vec![0.1]);
     world.entity_attack_pattern_lookup.insert("test".into(), attack_pattern);
@@ -80,11 +86,16 @@ async fn test_entities_should_pathfind_around_terrain(){
         r#type: AttackType::Melee,
         max_start_dist_from_entity: None,
         time_to_charge: 5,
-        sprite: "attack_highlight".to_compact_string()
+        sprite: "attack_highlight".to_compact_string(),
+        damage_type: crate::game_engine::stat::DamageType::Physical,
+        knockback: 0.0,
+        fire: None,
+        poison: None
     });
     let attack_pattern = EntityAttackPattern::new(vec!["test_attack".to_compact_string()], vec![0.1]);
     world.entity_attack_pattern_lookup.insert("test".into(), attack_pattern);
     world.add_entity_archetype("Test".into(), basic_entity().await);
+    ok_or_panic!(world.create_entity_with_archetype(900.0, 405.0, "Test".into()));
     let player_starting_position_x = world.player.borrow().x;
     let player_starting_position_y = world.player.borrow().y;
 
@@ -104,3 +115,171 @@ async fn test_entities_should_pathfind_around_terrain(){
         "Player should die because the attacking entity should pathfind around the terrain"
     );
 }
+
+#[test]
+fn field_of_view_test(){
+    let visible = field_of_view([5, 5], 4, &|tile| tile[0] == 7);
+    assert!(visible.contains(&[5, 5]), "The origin should always be visible");
+    assert!(visible.contains(&[6, 5]), "Open tiles in front of the wall should be visible");
+    assert!(visible.contains(&[7, 5]), "The wall itself should be visible");
+    assert!(!visible.contains(&[8, 5]), "Tiles behind the wall should be hidden");
+    assert!(visible.contains(&[5, 1]) && visible.contains(&[1, 5]), "Open tiles at the edge of the radius should be visible");
+    assert!(!visible.contains(&[5, 0]), "Tiles past the radius should be hidden");
+    let visible = field_of_view([1, 1], 4, &|_| false);
+    assert!(visible.contains(&[0, 0]) && visible.iter().all(|x| x[0] <= 5 && x[1] <= 5), "Looking off the edge of the world should not wrap around");
+}
+
+#[tokio::test]
+async fn test_walls_should_hide_entities_from_the_player(){
+    let mut world = basic_world().await;
+    world.add_terrain_archetype(
+        "test".into(),
+        vec![
+            TerrainTags::BlocksMovement,
+            TerrainTags::Sprite(0)
+        ]
+    );
+    for y in 0..25{
+        let terrain_blocker = world.add_terrain(704, y * 32);
+        world.set_terrain_archetype(terrain_blocker, "test".into());
+    }
+    world.add_entity_archetype("Test".into(), basic_entity().await);
+    let hidden = ok_or_panic!(world.create_entity_with_archetype(900.0, 405.0, "Test".into()));
+    let seen = ok_or_panic!(world.create_entity_with_archetype(650.0, 405.0, "Test".into()));
+    world.update_visibility();
+    assert!(world.is_entity_visible(seen), "Entities on the player's side of the wall should be visible");
+    assert!(!world.is_entity_visible(hidden), "Entities behind the wall should be hidden");
+    assert!(world.is_tile_visible([22, 12]), "The wall should be visible");
+    world.update_explored();
+    assert!(world.is_explored([22, 12]) && !world.is_explored([28, 12]), "Only what the player saw should be explored");
+    assert!((23..=26).all(|x| !world.is_explored([x, 13])), "Tiles right behind the wall should not be explored");
+}
+
+#[tokio::test]
+async fn test_entities_slide_on_ice(){
+    let mut world = basic_world().await;
+    let camera = basic_camera(&mut world).await;
+    world.add_terrain_archetype(
+        "ice".into(),
+        vec![
+            TerrainTags::Ice,
+            TerrainTags::Sprite(0)
+        ]
+    );
+    for x in 24..32 {
+        for y in 11..15 {
+            let ice = world.add_terrain(x * 32, y * 32);
+            world.set_terrain_archetype(ice, "ice".into());
+        }
+    }
+    let mut walker = basic_entity().await;
+    walker.basic_tags = vec!["aggressive".into(), "respectsCollision".into(), "hasCollision".into()];
+    world.add_entity_archetype("walker".into(), walker);
+    let on_ice = ok_or_panic!(world.create_entity_with_archetype(900.0, 405.0, "walker".into()));
+    let on_floor = ok_or_panic!(world.create_entity_with_archetype(900.0, 650.0, "walker".into()));
+    let start = |headless: &HeadlessGame, entity: usize| {
+        let position = headless.world.components.position_components[entity].as_ref().unwrap().borrow();
+        (position.x, position.y)
+    };
+
+    let mut headless = HeadlessGame::new(world, camera);
+    let ice_start = start(&headless, on_ice);
+    let floor_start = start(&headless, on_floor);
+    ok_or_panic!(headless.run(10).await);
+    let moved = |headless: &HeadlessGame, entity: usize, from: (f32, f32)| {
+        let now = start(headless, entity);
+        f32::sqrt((now.0 - from.0).powf(2.0) + (now.1 - from.1).powf(2.0))
+    };
+    let ice_moved = moved(&headless, on_ice, ice_start);
+    let floor_moved = moved(&headless, on_floor, floor_start);
+    assert!(floor_moved > 0.0, "Entities off the ice should walk towards the player");
+    assert!(ice_moved > 0.0 && ice_moved < floor_moved / 2.0, "Entities on ice should only slowly pick up speed");
+}
+
+#[tokio::test]
+async fn test_terrain_effects_use_the_entity_collision_box(){
+    let mut world = basic_world().await;
+    let camera = basic_camera(&mut world).await;
+    world.add_terrain_archetype("slow".into(), vec![TerrainTags::SlowsMovement(0.25), TerrainTags::Sprite(0)]);
+    world.add_terrain_archetype("ice".into(), vec![TerrainTags::Ice, TerrainTags::Sprite(0)]);
+    // the terrain only reaches under where the collision boxes are, not the positions
+    for (archetype, y) in [("slow", 12), ("ice", 16)] {
+        for x in 24..32 {
+            let terrain = world.add_terrain(x * 32, y * 32);
+            world.set_terrain_archetype(terrain, archetype.into());
+        }
+    }
+    let mut walker = basic_entity().await;
+    walker.basic_tags = vec!["aggressive".into(), "respectsCollision".into(), "hasCollision".into()];
+    walker.collision_box = Some(CollisionBox {w: 32.0, h: 32.0, x_offset: -96.0, y_offset: 0.0});
+    world.add_entity_archetype("walker".into(), walker);
+    let slowed = ok_or_panic!(world.create_entity_with_archetype(1060.0, 12.0 * 32.0, "walker".into()));
+    let on_ice = ok_or_panic!(world.create_entity_with_archetype(1060.0, 16.0 * 32.0, "walker".into()));
+    let on_floor = ok_or_panic!(world.create_entity_with_archetype(1060.0, 650.0, "walker".into()));
+    let position = |headless: &HeadlessGame, entity: usize| {
+        let position = headless.world.components.position_components[entity].as_ref().unwrap().borrow();
+        (position.x, position.y)
+    };
+
+    let mut headless = HeadlessGame::new(world, camera);
+    let slowed_start = position(&headless, slowed);
+    let ice_start = position(&headless, on_ice);
+    let floor_start = position(&headless, on_floor);
+    ok_or_panic!(headless.run(10).await);
+    let moved = |headless: &HeadlessGame, entity: usize, from: (f32, f32)| {
+        let now = position(headless, entity);
+        f32::sqrt((now.0 - from.0).powf(2.0) + (now.1 - from.1).powf(2.0))
+    };
+    let slowed_moved = moved(&headless, slowed, slowed_start);
+    let ice_moved = moved(&headless, on_ice, ice_start);
+    let floor_moved = moved(&headless, on_floor, floor_start);
+    assert!(floor_moved > 0.0, "Entities off the terrain should walk towards the player");
+    assert!(slowed_moved > 0.0 && slowed_moved < floor_moved / 2.0, "Slowing terrain under the collision box should slow the entity");
+    assert!(ice_moved < floor_moved / 2.0, "Ice under the collision box should make the entity slide");
+}
+
+#[tokio::test]
+async fn test_line_of_sight_blocking(){
+    let mut world = basic_world().await;
+    world.add_terrain_archetype("wall".into(), vec![TerrainTags::BlocksMovement, TerrainTags::Sprite(0)]);
+    world.add_terrain_archetype("water".into(), vec![TerrainTags::Water, TerrainTags::Sprite(0)]);
+    let wall = world.add_terrain(20 * 32, 5 * 32);
+    world.set_terrain_archetype(wall, "wall".into());
+    let water = world.add_terrain(20 * 32, 10 * 32);
+    world.set_terrain_archetype(water, "water".into());
+    world.add_entity_archetype("Test".into(), basic_entity().await);
+    ok_or_panic!(world.create_entity_with_archetype(20.0 * 32.0, 15.0 * 32.0, "Test".into()));
+    let chunks = (0..world.chunks.borrow().len()).collect();
+    world.set_loaded_chunks(chunks);
+    ok_or_panic!(world.generate_collision_cache_and_damage_cache());
+    let across = |world: &World, y: usize| ok_or_panic!(world.is_line_of_sight(16.0 * 32.0, y as f32 * 32.0, 24.0 * 32.0, y as f32 * 32.0));
+    assert!(across(&world, 20), "Nothing should block sight across open floor");
+    assert!(!across(&world, 5), "Walls should block sight");
+    assert!(!across(&world, 10), "Water should block sight");
+    assert!(across(&world, 15), "Entities should not block sight");
+}
+
+#[tokio::test]
+async fn test_entities_notice_the_player_with_field_of_view(){
+    let mut world = basic_world().await;
+    let camera = basic_camera(&mut world).await;
+    world.add_terrain_archetype("wall".into(), vec![TerrainTags::BlocksMovement, TerrainTags::Sprite(0)]);
+    world.add_terrain_archetype("water".into(), vec![TerrainTags::Water, TerrainTags::Sprite(0)]);
+    // a column between the player and the entities, wall above the player's row and water from it down
+    for y in 0..25 {
+        let terrain = world.add_terrain(704, y * 32);
+        world.set_terrain_archetype(terrain, if y < 13 {"wall".into()} else {"water".into()});
+    }
+    // entities that respect collision aggro through walls, so only sight can stop these
+    let mut archetype = basic_entity().await;
+    archetype.basic_tags.retain(|x| x != "respectsCollision");
+    world.add_entity_archetype("Test".into(), archetype);
+    let behind_wall = ok_or_panic!(world.create_entity_with_archetype(900.0, 100.0, "Test".into()));
+    let across_water = ok_or_panic!(world.create_entity_with_archetype(900.0, 600.0, "Test".into()));
+    let mut headless = HeadlessGame::new(world, camera);
+    ok_or_panic!(headless.run(1).await);
+    let aggroed = |entity: usize| headless.world.components.aggro_components[entity].as_ref().unwrap().borrow().aggroed;
+    assert!(!aggroed(behind_wall), "Entities should not notice the player through walls");
+    assert!(aggroed(across_water), "Entities should see the player across water, the same as the player sees them");
+    assert!(headless.world.is_entity_visible(across_water) && !headless.world.is_entity_visible(behind_wall), "The player should see the same entities that see them");
+}
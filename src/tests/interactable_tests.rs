@@ -0,0 +1,106 @@
+#![cfg(test)]
+use compact_str::CompactString;
+
+use crate::game_engine::json_parsing::room_descriptor_json;
+use crate::game_engine::room_generation::RoomType;
+use crate::game_engine::room_objectives::RoomObjective;
+use crate::ok_or_panic;
+use crate::tests::test_framework::{basic_camera, basic_entity, basic_world};
+use crate::tests::lib::headless::HeadlessGame;
+
+#[tokio::test]
+async fn test_lever_opens_door_on_its_channel(){
+    let mut world = basic_world().await;
+    let camera = basic_camera(&mut world).await;
+    let mut door = basic_entity().await;
+    door.basic_tags = vec!["door".into(), "respectsCollision".into(), "hasCollision".into()];
+    door.channel = Some("gate".into());
+    world.add_entity_archetype("door".into(), door);
+    let mut lever = basic_entity().await;
+    lever.basic_tags = vec!["lever".into()];
+    lever.channel = Some("gate".into());
+    world.add_entity_archetype("lever".into(), lever);
+    let door = ok_or_panic!(world.create_entity_with_archetype(640.0, 400.0, "door".into()));
+    let lever = ok_or_panic!(world.create_entity_with_archetype(596.0, 440.0, "lever".into()));
+    let player_starting_x = world.player.borrow().x;
+
+    let mut headless = HeadlessGame::new(world, camera);
+    headless.state.keys_down.insert(CompactString::from("d"), true);
+    ok_or_panic!(headless.run(60).await);
+    assert!(
+        headless.world.player.borrow().x < player_starting_x + 50.0,
+        "Player should not be able to walk through a closed door"
+    );
+
+    ok_or_panic!(headless.world.activate_interactable(lever));
+    assert!(headless.world.is_interactable_open(door), "Pulling the lever should open the door on its channel");
+    assert!(
+        !headless.world.collision_cache.borrow().values().any(|x| x.contains(&door)),
+        "An open door should be taken out of the collision cache"
+    );
+    ok_or_panic!(headless.run(60).await);
+    assert!(
+        headless.world.player.borrow().x > player_starting_x + 50.0,
+        "Player should be able to walk through an open door"
+    );
+}
+
+#[tokio::test]
+async fn test_door_opens_when_room_is_cleared(){
+    let mut world = basic_world().await;
+    let camera = basic_camera(&mut world).await;
+    let mut door = basic_entity().await;
+    door.basic_tags = vec!["door".into(), "opensOnClear".into(), "respectsCollision".into(), "hasCollision".into()];
+    world.add_entity_archetype("door".into(), door);
+    world.add_entity_archetype("test".into(), basic_entity().await);
+    let door = ok_or_panic!(world.create_entity_with_archetype(800.0, 400.0, "door".into()));
+    let enemy = ok_or_panic!(world.create_entity_with_archetype(1000.0, 600.0, "test".into()));
+
+    let mut headless = HeadlessGame::new(world, camera);
+    ok_or_panic!(headless.run(2).await);
+    assert!(!headless.world.is_interactable_open(door), "The door should stay closed while an enemy is alive");
+    headless.world.kill_entity(enemy);
+    ok_or_panic!(headless.run(2).await);
+    assert!(headless.world.is_interactable_open(door), "The door should open once every enemy is dead");
+}
+
+#[tokio::test]
+async fn test_room_exit_unlocks_and_drops_reward_when_cleared(){
+    let mut world = basic_world().await;
+    let camera = basic_camera(&mut world).await;
+    world.add_entity_archetype("test".into(), basic_entity().await);
+    let mut chest = basic_entity().await;
+    chest.basic_tags = vec!["chest".into()];
+    world.add_entity_archetype("chest".into(), chest);
+    let room = room_descriptor_json{
+        name: "arena".into(),
+        terrain: Vec::new(),
+        width: 10,
+        height: 10,
+        spawnable: Vec::new(),
+        spawn_archetype: "test".into(),
+        entrance: [1, 1],
+        exit: [8, 8],
+        entities: Vec::new(),
+        objective: RoomObjective::Clear,
+        reward: Some("chest".into()),
+        reward_position: Some([5, 5]),
+        room_type: RoomType::Authored,
+        generation: None,
+        darkness: 0.0,
+    };
+    world.room_descriptors.insert("arena".into(), room.clone());
+    let enemy = ok_or_panic!(world.create_entity_with_archetype(1000.0, 600.0, "test".into()));
+    world.start_room(&room, [20, 10], vec![enemy]);
+
+    let mut headless = HeadlessGame::new(world, camera);
+    ok_or_panic!(headless.run(2).await);
+    assert!(headless.world.is_exit_locked(), "The exit should stay locked while a spawned enemy is alive");
+    headless.world.kill_entity(enemy);
+    ok_or_panic!(headless.run(2).await);
+    assert!(!headless.world.is_exit_locked(), "The exit should unlock once every spawned enemy is dead");
+    let reward = headless.world.components.interactable_components.iter().position(|x| x.is_some());
+    let reward = reward.expect("The room reward should be placed once the room is cleared");
+    let position = headless.world.components.position_components[reward].as_ref().expect("The reward should have a position").borrow();
+    assert!(position.x == 25.0 * 32.0 && position.y == 15.0 * 32.0, "The reward should be placed at its position in the room");
+}
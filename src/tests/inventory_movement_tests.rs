@@ -5,7 +5,7 @@ use core::panic;
 use compact_str::{CompactString, ToCompactString};
 use rustc_hash::FxHashMap;
 
-use crate::{create_stat_list, game_engine::{entity_components::CollisionBox, game::MousePosition, item::{Item, ItemType}, stat::StatC}, ok_or_panic, tests::{lib::headless::HeadlessGame, test_framework::{basic_camera, basic_world}}};
+use crate::{create_stat_list, game_engine::{crafting::{CraftingBook, ItemMatcher, Recipe, RecipeMode, SalvageEntry}, game::MousePosition, item::{Item, ItemType, Rarity}, stat::StatC}, ok_or_panic, tests::{lib::headless::HeadlessGame, test_framework::{basic_camera, basic_entity, basic_world}}};
 
 #[tokio::test]
 async fn test_inventory_clicking_blank_slot_in_blank_inventory(){
@@ -26,7 +26,7 @@ async fn test_inventory_clicking_blank_slot_in_blank_inventory(){
         y_screen: 201.0,
         x_world: 579.0 + headless.camera.camera_x,
         y_world: 201.0 + headless.camera.camera_y,
-    }, true, false));
+    }, true, false, &headless.world.crafting_book));
     ok_or_panic!(headless.run(5).await);
     ok_or_panic!(headless.world.process_mouse_input(MousePosition {
         x_screen: 521.0,
@@ -40,7 +40,7 @@ async fn test_inventory_clicking_blank_slot_in_blank_inventory(){
         y_screen: 201.0,
         x_world: 521.0 + headless.camera.camera_x,
         y_world: 201.0 + headless.camera.camera_y,
-    }, true, false)); 
+    }, true, false, &headless.world.crafting_book)); 
     ok_or_panic!(headless.run(5).await);
     for i in 0..headless.world.inventory.slots.len(){
         assert!(
@@ -96,7 +96,7 @@ async fn test_inventory_clicking_blank_slot_in_inventory_with_items(){
         y_screen: 201.0,
         x_world: 579.0 + headless.camera.camera_x,
         y_world: 201.0 + headless.camera.camera_y,
-    }, true, false));
+    }, true, false, &headless.world.crafting_book));
     ok_or_panic!(headless.run(5).await);
     ok_or_panic!(headless.world.process_mouse_input(MousePosition {
         x_screen: 521.0,
@@ -110,7 +110,7 @@ async fn test_inventory_clicking_blank_slot_in_inventory_with_items(){
         y_screen: 201.0,
         x_world: 521.0 + headless.camera.camera_x,
         y_world: 201.0 + headless.camera.camera_y,
-    }, true, false)); 
+    }, true, false, &headless.world.crafting_book)); 
     ok_or_panic!(headless.run(5).await);
     assert!(
         headless.world.inventory.get_slot(&7).unwrap().item.is_some(),
@@ -178,7 +178,7 @@ async fn test_inventory_item_move(){
         y_screen: 201.0,
         x_world: 579.0 + headless.camera.camera_x,
         y_world: 201.0 + headless.camera.camera_y,
-    }, true, false));
+    }, true, false, &headless.world.crafting_book));
     ok_or_panic!(headless.run(5).await);
     ok_or_panic!(headless.world.process_mouse_input(MousePosition {
         x_screen: 521.0,
@@ -192,7 +192,7 @@ async fn test_inventory_item_move(){
         y_screen: 201.0,
         x_world: 521.0 + headless.camera.camera_x,
         y_world: 201.0 + headless.camera.camera_y,
-    }, true, false)); 
+    }, true, false, &headless.world.crafting_book)); 
     ok_or_panic!(headless.run(5).await);
     assert!(
         headless.world.inventory.get_slot(&5).unwrap().item.is_some(),
@@ -264,7 +264,7 @@ async fn test_inventory_item_swap(){
         y_screen: 201.0,
         x_world: 579.0 + headless.camera.camera_x,
         y_world: 201.0 + headless.camera.camera_y,
-    }, true, false));
+    }, true, false, &headless.world.crafting_book));
     ok_or_panic!(headless.run(5).await);
     ok_or_panic!(headless.world.process_mouse_input(MousePosition {
         x_screen: 521.0,
@@ -278,7 +278,7 @@ async fn test_inventory_item_swap(){
         y_screen: 201.0,
         x_world: 521.0 + headless.camera.camera_x,
         y_world: 201.0 + headless.camera.camera_y,
-    }, true, false)); 
+    }, true, false, &headless.world.crafting_book)); 
     ok_or_panic!(headless.run(5).await);
     ok_or_panic!(headless.world.process_mouse_input(MousePosition {
         x_screen: 579.0,
@@ -292,7 +292,7 @@ async fn test_inventory_item_swap(){
         y_screen: 201.0,
         x_world: 579.0 + headless.camera.camera_x,
         y_world: 201.0 + headless.camera.camera_y,
-    }, true, false)); 
+    }, true, false, &headless.world.crafting_book)); 
     assert!(
         headless.world.inventory.get_slot(&5).unwrap().item.is_some(),
         "There should be an item in slot 6"
@@ -354,7 +354,7 @@ async fn test_close_inventory_with_item_held_basic(){
         y_screen: 201.0,
         x_world: 579.0 + headless.camera.camera_x,
         y_world: 201.0 + headless.camera.camera_y,
-    }, true, false));
+    }, true, false, &headless.world.crafting_book));
     ok_or_panic!(headless.run(5).await);
     
     match headless.world.inventory.hide_inventory() {
@@ -425,7 +425,7 @@ async fn test_close_inventory_with_item_held_after_swap(){
         y_screen: 201.0,
         x_world: 579.0 + headless.camera.camera_x,
         y_world: 201.0 + headless.camera.camera_y,
-    }, true, false));
+    }, true, false, &headless.world.crafting_book));
     ok_or_panic!(headless.run(5).await);
     ok_or_panic!(headless.world.process_mouse_input(MousePosition {
         x_screen: 521.0,
@@ -439,7 +439,7 @@ async fn test_close_inventory_with_item_held_after_swap(){
         y_screen: 201.0,
         x_world: 521.0 + headless.camera.camera_x,
         y_world: 201.0 + headless.camera.camera_y,
-    }, true, false));
+    }, true, false, &headless.world.crafting_book));
     ok_or_panic!(headless.run(5).await);
     match headless.world.inventory.hide_inventory() {
         Ok(_) => {},
@@ -477,17 +477,10 @@ async fn test_close_inventory_with_item_held_after_swap(){
 pub async fn test_melee_player_attack_after_inventory_movement() {
     let mut world = basic_world().await;
     let camera = basic_camera(&mut world).await;
-    world.add_entity_archetype(CompactString::from("test_attackable_entity"), vec![
-        crate::game_engine::entities::EntityTags::BaseHealth(100),
-        crate::game_engine::entities::EntityTags::Damageable(
-            CollisionBox {
-                x_offset: 0.0,
-                y_offset: 0.0,
-                w: 32.0,
-                h: 32.0
-            }
-        )
-    ]);
+    let mut attackable = basic_entity().await;
+    attackable.basic_tags = vec!["damageable".into()];
+    attackable.health = Some(100);
+    world.add_entity_archetype(CompactString::from("test_attackable_entity"), attackable);
     let item = world.inventory.add_item(
         Item {
             name: CompactString::from("test_sword"),
@@ -501,10 +494,14 @@ pub async fn test_melee_player_attack_after_inventory_movement() {
                 width => StatC {flat: 50.0, percent: 0.0},
                 reach => StatC {flat: 65.0, percent: 0.0}
             ),
-            time_til_usable: 0.0
+            time_til_usable: 0.0,
+            archetype: CompactString::from("test"),
+            rarity: crate::game_engine::item::Rarity::Common,
+            damage_type: crate::game_engine::stat::DamageType::Physical,
+            uses: None
         }
     );
-    world.create_entity_with_archetype(639.0, 400.0, CompactString::from("test_attackable_entity"));
+    let entity = ok_or_panic!(world.create_entity_with_archetype(639.0, 400.0, CompactString::from("test_attackable_entity")));
     let res = world.inventory.set_slot_item(6, item);
     if res.is_err() {
         panic!("set slot 6 to item {} failed with error: {}", item, res.err().unwrap())
@@ -517,7 +514,7 @@ pub async fn test_melee_player_attack_after_inventory_movement() {
         y_screen: 201.0,
         x_world: 579.0 + headless.camera.camera_x,
         y_world: 201.0 + headless.camera.camera_y,
-    }, true, false));
+    }, true, false, &headless.world.crafting_book));
     ok_or_panic!(headless.run(5).await);
     ok_or_panic!(headless.world.process_mouse_input(MousePosition {
         x_screen: 521.0,
@@ -531,7 +528,7 @@ pub async fn test_melee_player_attack_after_inventory_movement() {
         y_screen: 381.0,
         x_world: 521.0 + headless.camera.camera_x,
         y_world: 381.0 + headless.camera.camera_y,
-    }, true, false));
+    }, true, false, &headless.world.crafting_book));
     ok_or_panic!(headless.run(5).await);
     match headless.world.inventory.hide_inventory() {
         Ok(_) => {},
@@ -545,12 +542,12 @@ pub async fn test_melee_player_attack_after_inventory_movement() {
             y_world: 400.0 + headless.camera.camera_y,
     }, true, false, headless.camera.viewpoint_width as f32, headless.camera.viewpoint_height as f32));
     assert!(
-        headless.world.entity_health_components.get(&0).is_some(),
+        headless.world.components.damageable_components[entity].is_some(),
         "Entity should have a health component prior to player attack"
     );
     ok_or_panic!(headless.run(20).await);
     assert!(
-        headless.world.entity_health_components.get(&0).is_none(),
+        headless.world.components.damageable_components[entity].is_none(),
         "Entity should be killed by player attack"
     );
 }
@@ -613,9 +610,70 @@ async fn test_item_drop_from_inventory(){
     }
 
     ok_or_panic!(headless.run(50000).await);
+    // the dropped item gets picked back up into the first free slot
     assert!(
-        headless.world.inventory.get_hotbar_slot(1).unwrap().item.is_some(),
-        "There should be an item in hotbar slot 1"
+        headless.world.inventory.get_hotbar_slot(0).unwrap().item.is_some(),
+        "There should be an item in hotbar slot 0"
     );
     
 }
+
+fn crafting_test_item(damage: f32, rarity: Rarity) -> Item {
+    Item {
+        name: CompactString::from("test"),
+        attack_sprite: Some(CompactString::from("melee_attack")),
+        item_type: ItemType::MeleeWeapon,
+        width_to_length_ratio: None,
+        lore: String::from("test"),
+        sprite: CompactString::from("sword"),
+        stats: create_stat_list!(
+            damage => StatC {flat: damage, percent: 0.0}
+        ),
+        time_til_usable: 0.0,
+        archetype: CompactString::from("test"),
+        rarity,
+        damage_type: crate::game_engine::stat::DamageType::Physical,
+        uses: None
+    }
+}
+
+#[tokio::test]
+async fn test_crafting_upgrade_and_salvage(){
+    let mut world = basic_world().await;
+    let _camera = basic_camera(&mut world).await;
+    world.crafting_book = CraftingBook {
+        materials: vec!["essence".into(), "scrap".into()],
+        recipes: vec![Recipe {
+            name: "Combine".into(),
+            inputs: vec![ItemMatcher::default(), ItemMatcher::default()],
+            inputs_must_match: true,
+            materials: vec![("essence".into(), 2)],
+            mode: RecipeMode::Upgrade,
+            output: None,
+            upgrade_multiplier: 1.5,
+            upgraded_stats: vec!["damage".into()],
+        }],
+        salvage: vec![
+            SalvageEntry {matches: ItemMatcher {rarity: Some(Rarity::Rare), ..Default::default()}, materials: vec![("scrap".into(), 3)]},
+            SalvageEntry {matches: ItemMatcher::default(), materials: vec![("scrap".into(), 1)]},
+        ],
+    };
+    ok_or_panic!(world.inventory.add_to_crafting_slot(crafting_test_item(10.0, Rarity::Common)));
+    ok_or_panic!(world.inventory.add_to_crafting_slot(crafting_test_item(10.0, Rarity::Common)));
+    assert!(world.craft(0).is_err(), "Crafting without the materials should fail");
+    assert!(world.inventory.get_crafting_items().len() == 2, "A failed craft should not consume anything");
+
+    world.inventory.materials.insert("essence".into(), 3);
+    ok_or_panic!(world.craft(0));
+    let items = world.inventory.get_crafting_items();
+    assert!(items.len() == 1, "Upgrading should consume the second item");
+    let (_, upgraded) = items[0];
+    assert!(upgraded.rarity == Rarity::Rare, "Upgrading should raise the rarity");
+    assert!(upgraded.stats.damage.is_some_and(|x| x.flat == 15.0), "Upgrading should multiply the upgraded stats");
+    assert!(world.inventory.materials.get("essence") == Some(&1), "Crafting should spend the materials");
+
+    ok_or_panic!(world.inventory.add_to_crafting_slot(crafting_test_item(10.0, Rarity::Common)));
+    ok_or_panic!(world.salvage_crafting_slots());
+    assert!(world.inventory.get_crafting_items().is_empty(), "Salvaging should destroy the items");
+    assert!(world.inventory.materials.get("scrap") == Some(&4), "The first matching salvage entry of each item should be used");
+}
@@ -3,7 +3,7 @@
 
 use compact_str::{CompactString, ToCompactString};
 
-use crate::{create_stat_list, game_engine::{entity_components::CollisionBox, game::MousePosition, item::{Item, ItemArchetype, ItemType}, json_parsing::entity_archetype_json, loot::{LootTable, LootTableEntry}, stat::{GearStatList, StatC}}, ok_or_panic, tests::{lib::headless::HeadlessGame, test_framework::{basic_camera, basic_world}}};
+use crate::{create_stat_list, game_engine::{game::MousePosition, item::{Item, ItemArchetype, ItemType}, loot::{LootTable, LootTableEntry}, stat::{GearStatList, StatC}}, ok_or_panic, tests::{lib::headless::HeadlessGame, test_framework::{basic_camera, basic_entity, basic_world}}};
 #[tokio::test]
 pub async fn test_enemy_item_drops() {
     let mut world = basic_world().await;
@@ -15,34 +15,25 @@ pub async fn test_enemy_item_drops() {
         item_type: ItemType::MeleeWeapon,
         width_to_length_ratio: None,
         sprite: "spear".to_compact_string(),
-        attack_sprite: Some("attack_highlight".to_compact_string())
-    });
-    world.loot_table_lookup = vec![
-        LootTable::new(vec![LootTableEntry {
-            item: Some("test_item".to_compact_string()),
-            weight: 10
-        }])
-    ];
-    world.add_entity_archetype("test".into(), entity_archetype_json {
-        name: "test".into(),
-        basic_tags: vec!["damageable".into()],
-        collision_box: None,
-        damage_box: Some(CollisionBox {
-            x_offset: 0.0,
-            y_offset: 0.0,
-            w: 32.0,
-            h: 32.0
-        }),
-        health: Some(10.0),
-        monster_type: "Undead".into(),
-        movement_speed: None,
-        range: None,
-        aggro_range: None,
-        attack_type: "Melee".into(),
-        attack_pattern: None,
-        loot_table: vec![],
-
+        attack_sprite: Some("attack_highlight".to_compact_string()),
+        rarity: crate::game_engine::item::Rarity::Common,
+        damage_type: crate::game_engine::stat::DamageType::Physical,
+        uses: None,
+        ranged: None,
+        combo: None,
+        teaches_ability: None,
+        summon: None
     });
+    world.loot_table_lookup.insert("test_loot_table".into(), LootTable::new(vec![LootTableEntry {
+        item: Some("test_item".to_compact_string()),
+        gold: None,
+        weight: 10
+    }]));
+    let mut entity = basic_entity().await;
+    entity.basic_tags = vec!["damageable".into()];
+    entity.collision_box = None;
+    entity.loot_table = vec!["test_loot_table".into()];
+    world.add_entity_archetype("test".into(), entity);
     let item = world.inventory.add_item(
         Item {
             name: CompactString::from("test_sword"),
@@ -56,11 +47,15 @@ pub async fn test_enemy_item_drops() {
                 width => StatC { flat: 50.0, percent: 0.0},
                 reach => StatC { flat: 65., percent: 0.0},
             ),
-            time_til_usable: 0.0
+            time_til_usable: 0.0,
+            archetype: CompactString::from("test"),
+            rarity: crate::game_engine::item::Rarity::Common,
+            damage_type: crate::game_engine::stat::DamageType::Physical,
+            uses: None
         }
     );
     ok_or_panic!(world.inventory.set_hotbar_slot_item(0, item)); 
-    world.create_entity_with_archetype(639.0, 400.0, CompactString::from("test_attackable_entity"));
+    let entity = ok_or_panic!(world.create_entity_with_archetype(639.0, 400.0, CompactString::from("test")));
     let mut headless = HeadlessGame::new(world, camera);
     ok_or_panic!(headless.world.on_mouse_click(MousePosition {
             x_screen: 639.0,
@@ -73,12 +68,12 @@ pub async fn test_enemy_item_drops() {
         "Player should not pick up an item prior to killing enemy"
     );
     assert!(
-        headless.world.entity_health_components.get(&0).is_some(),
+        headless.world.components.damageable_components[entity].is_some(),
         "Entity should have a health component prior to player attack"
     );
     ok_or_panic!(headless.run(500).await);
     assert!(
-        headless.world.entity_health_components.get(&0).is_none(),
+        headless.world.components.damageable_components[entity].is_none(),
         "Entity should be killed by player attack"
     );
     assert!(
@@ -87,3 +82,77 @@ pub async fn test_enemy_item_drops() {
     )
 
 }
+
+#[tokio::test]
+async fn test_vendor_buy_and_sell(){
+    let mut world = basic_world().await;
+    let _camera = basic_camera(&mut world).await;
+    world.item_archetype_lookup.insert("test_item".to_compact_string(), ItemArchetype {
+        name: "test_item".to_compact_string(),
+        stats: GearStatList::default(),
+        lore: "d".to_string(),
+        item_type: ItemType::MeleeWeapon,
+        width_to_length_ratio: None,
+        sprite: "spear".to_compact_string(),
+        attack_sprite: Some("attack_highlight".to_compact_string()),
+        rarity: crate::game_engine::item::Rarity::Common,
+        damage_type: crate::game_engine::stat::DamageType::Physical,
+        uses: None,
+        ranged: None,
+        combo: None,
+        teaches_ability: None,
+        summon: None
+    });
+    world.loot_table_lookup.insert("stock".into(), LootTable::new(vec![LootTableEntry {
+        item: Some("test_item".to_compact_string()),
+        gold: None,
+        weight: 10
+    }]));
+    let mut vendor = basic_entity().await;
+    vendor.basic_tags = vec!["vendor".into()];
+    vendor.stock_table = Some("stock".into());
+    vendor.stock_size = Some(1);
+    world.add_entity_archetype("vendor".into(), vendor);
+    let vendor = ok_or_panic!(world.create_entity_with_archetype(620.0, 400.0, "vendor".into()));
+    let stock_item = ok_or_panic!(world.create_item_with_archetype("test_item".into()));
+    let price = stock_item.get_price();
+    world.components.vendor_components[vendor].as_ref().unwrap().borrow_mut().stock = vec![stock_item];
+
+    ok_or_panic!(world.interact());
+    assert!(world.inventory.shop.as_ref().is_some_and(|x| x.vendor == vendor && x.stock.len() == 1), "Interacting next to a vendor should open its shop");
+    let buy = MousePosition {x_screen: 946.0, y_screen: 245.0, x_world: 0.0, y_world: 0.0};
+    world.inventory.gold = price - 1;
+    ok_or_panic!(world.inventory.on_shop_click(buy));
+    assert!(world.inventory.shop.as_ref().unwrap().stock.len() == 1 && world.inventory.gold == price - 1, "Items the player can't afford should not be bought");
+    world.inventory.gold = price + 5;
+    ok_or_panic!(world.inventory.on_shop_click(buy));
+    assert!(world.inventory.shop.as_ref().unwrap().stock.is_empty(), "Buying should take the item from the stock");
+    assert!(world.inventory.gold == 5, "Buying should cost the item's price");
+
+    let sword = world.inventory.add_item(Item {
+        name: CompactString::from("test_sword"),
+        attack_sprite: Some(CompactString::from("melee_attack")),
+        item_type: ItemType::MeleeWeapon,
+        width_to_length_ratio: None,
+        lore: String::from("test"),
+        sprite: CompactString::from("sword"),
+        stats: create_stat_list!(
+            damage => StatC {flat: 40.0, percent: 0.0}
+        ),
+        time_til_usable: 0.0,
+        archetype: CompactString::from("test"),
+        rarity: crate::game_engine::item::Rarity::Common,
+        damage_type: crate::game_engine::stat::DamageType::Physical,
+        uses: None
+    });
+    let sell_price = world.inventory.get_item(&sword).unwrap().get_sell_price();
+    ok_or_panic!(world.inventory.set_slot_item(6, sword));
+    ok_or_panic!(world.inventory.on_mouse_click(MousePosition {x_screen: 579.0, y_screen: 201.0, x_world: 0.0, y_world: 0.0}, true, false, &world.crafting_book));
+    ok_or_panic!(world.inventory.on_mouse_click(MousePosition {x_screen: 986.0, y_screen: 506.0, x_world: 0.0, y_world: 0.0}, true, false, &world.crafting_book));
+    assert!(world.inventory.get_item(&sword).is_none(), "Selling should take the item from the player");
+    assert!(world.inventory.gold == 5 + sell_price, "Selling should give the item's sell price");
+
+    ok_or_panic!(world.close_shop());
+    assert!(world.inventory.shop.is_none(), "The shop should close");
+    assert!(world.components.vendor_components[vendor].as_ref().unwrap().borrow().stock.len() == 1, "Sold items should go back to the vendor's stock");
+}
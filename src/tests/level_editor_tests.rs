@@ -0,0 +1,67 @@
+#![cfg(test)]
+
+use crate::game_engine::json_parsing;
+use crate::game_engine::level_editor::{EditorBrush, EditorTarget, LevelEditor};
+use crate::game_engine::room_format::validate_room;
+use crate::game_engine::room_generation::RoomType;
+use crate::ok_or_panic;
+use crate::world::World;
+
+// archetype and sprite of every autotile on the tile, other terrain can roll random sprites so it isn't compared
+fn autotiles_at(world: &World, tile: [usize; 2]) -> Vec<(String, Option<usize>)> {
+    let mut autotiles: Vec<(String, Option<usize>)> = world.terrain.iter()
+        .filter(|(_, terrain)| terrain.x == tile[0] * 32 && terrain.y == tile[1] * 32)
+        .filter_map(|(id, _)| world.get_terrain_archetype(*id).filter(|x| world.terrain_archetype_jsons[*x].r#type == "autotile").map(|x| (x.to_string(), world.get_terrain_sprite(*id))))
+        .collect();
+    autotiles.sort();
+    autotiles
+}
+
+#[tokio::test]
+async fn level_editor_paint_and_save_test(){
+    let mut parser = json_parsing::JSON_parser::new();
+    let parsed_data = parser.parse_and_convert_game_data(json_parsing::PATH_BUNDLE);
+    let mut editor = LevelEditor::new(parser, parsed_data);
+    editor.target = editor.targets.iter().position(|x| matches!(x, EditorTarget::Room(room) if editor.parser.rooms_json[room].room_type == RoomType::Authored)).expect("There should be an authored room");
+    let room = editor.target_name();
+    let mut world = ok_or_panic!(editor.build_world());
+    let entrance = editor.parser.rooms_json[&room].entrance;
+    let next_to = [entrance[0] + 1, entrance[1]];
+
+    editor.brush = EditorBrush::Terrain;
+    editor.terrain_choice = editor.terrain_archetypes.iter().position(|x| x == "wall").expect("There should be a wall terrain archetype");
+    let untouched: Vec<usize> = world.terrain.iter().filter(|(_, x)| [x.x / 32, x.y / 32] != entrance).map(|(id, _)| *id).collect();
+    ok_or_panic!(editor.apply_brush(&mut world, entrance, false));
+    assert!(untouched.iter().all(|x| world.terrain.contains_key(x)), "Painting should only replace the terrain on the painted tile");
+    assert!(autotiles_at(&world, entrance).iter().any(|x| x.0 == "wall"), "The painted terrain should be in the world");
+    assert!(editor.unsaved.contains_key(&editor.target), "Painting should mark the room as unsaved");
+    ok_or_panic!(editor.apply_brush(&mut world, next_to, false));
+    let rebuilt = ok_or_panic!(editor.build_world());
+    for tile in [entrance, next_to] {
+        assert!(autotiles_at(&world, tile) == autotiles_at(&rebuilt, tile), "Painting next to an autotile should pick its sprite again");
+    }
+
+    ok_or_panic!(editor.save());
+    assert!(editor.status.starts_with("Not saved"), "A room with a wall on its entrance should not be saved");
+    assert!(editor.unsaved.contains_key(&editor.target), "A room that failed to save should still be unsaved");
+    let room_target = editor.target;
+    editor.target = 0;
+    editor.status.clear();
+    ok_or_panic!(editor.save());
+    assert!(editor.status.starts_with("Not saved"), "Nothing should be saved while another edited room is broken");
+    editor.target = room_target;
+
+    ok_or_panic!(editor.apply_brush(&mut world, next_to, true));
+    assert!(autotiles_at(&world, next_to).is_empty(), "Erasing should remove the terrain from the world");
+    let rebuilt = ok_or_panic!(editor.build_world());
+    assert!(autotiles_at(&world, entrance) == autotiles_at(&rebuilt, entrance), "Erasing next to an autotile should pick its sprite again");
+    ok_or_panic!(editor.apply_brush(&mut world, entrance, true));
+    ok_or_panic!(validate_room(&editor.parser.rooms_json[&room], &editor.parser.terrain_archetypes_json, &editor.parser.spawn_archetypes_json));
+
+    editor.brush = EditorBrush::Entity;
+    let entities = world.components.position_components.iter().flatten().count();
+    ok_or_panic!(editor.apply_brush(&mut world, next_to, false));
+    assert!(world.components.position_components.iter().flatten().count() == entities + 1, "Painting an entity should add it to the world");
+    ok_or_panic!(editor.apply_brush(&mut world, next_to, true));
+    assert!(world.components.position_components.iter().flatten().count() == entities, "Erasing an entity should remove it from the world");
+}
@@ -45,6 +45,12 @@ impl HeadlessState{
         }));
         ptry!(world.update_entities(camera));
         ptry!(world.update_entity_attacks(camera));
+        ptry!(world.update_terrain_effects(camera));
+        ptry!(world.update_room_objective());
+        ptry!(world.update_interactables());
+        world.update_visibility();
+        world.update_explored();
+        ptry!(world.update_minions());
         ptry!(world.update_player_abilities(&InputState {
             keys_down: self.keys_down.clone(),
             mouse_position: MousePosition::default(),
@@ -52,9 +58,15 @@ impl HeadlessState{
             mouse_right: false
             
         }));
+        ptry!(world.update_queued_shots());
         ptry!(world.update_player_attacks(camera));
+        world.update_particles();
         ptry!(world.update_items_in_inventory_cd());
+        ptry!(world.update_player_hit_state());
+        ptry!(world.update_player_defense());
+        ptry!(world.update_combo_window());
         ptry!(world.kill_entities_to_be_killed());
+        world.update_death_animations();
         ptry!(world.update_items_on_ground());
         Ok(())
     }
@@ -0,0 +1,25 @@
+#![cfg(test)]
+
+use crate::game_engine::json_parsing::{self, terrain_json};
+use crate::game_engine::lighting::PLAYER_LIGHT_RADIUS;
+use crate::ok_or_panic;
+
+#[test]
+fn lighting_test(){
+    let mut parser = json_parsing::JSON_parser::new();
+    let parsed_data = parser.parse_and_convert_game_data(json_parsing::PATH_BUNDLE);
+    let mut world = ok_or_panic!(crate::game_engine::starting_level_generator::generate_world_from_json_parsed_data(&parsed_data));
+    assert!(world.get_ambient_light() == 1.0, "The starting level should be fully lit");
+    let room = world.room_descriptors.get("cave").expect("There should be a cave room").clone();
+    world.start_room(&room, [0, 0], Vec::new());
+    assert!((world.get_ambient_light() - (1.0 - room.darkness)).abs() < 0.0001, "Rooms should take their darkness away from the ambient light");
+
+    ok_or_panic!(world.generate_terrain_from_descriptor(&terrain_json{x: 0, y: 0, width: 1, height: 1, terrain_archetype: "torch".into()}, 200, 200));
+    world.player.borrow_mut().x = 205.0 * 32.0;
+    world.player.borrow_mut().y = 200.0 * 32.0;
+    let lights = world.get_light_sources(195.0 * 32.0, 195.0 * 32.0, 20.0 * 32.0, 10.0 * 32.0);
+    assert!(lights[0].radius == PLAYER_LIGHT_RADIUS, "The player's light should come first");
+    assert!(lights.iter().any(|x| x.x == 200.0 * 32.0 + 16.0 && x.y == 200.0 * 32.0 + 16.0 && x.radius == 192.0), "Torches should light up the area around them");
+    let lights = world.get_light_sources(0.0, 0.0, 32.0, 32.0);
+    assert!(lights.is_empty(), "Lights that don't reach the area should be left out");
+}
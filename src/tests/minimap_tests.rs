@@ -0,0 +1,41 @@
+#![cfg(test)]
+
+use crate::game_engine::json_parsing::{self, terrain_json};
+use crate::ok_or_panic;
+
+#[test]
+fn minimap_exploration_test(){
+    let mut parser = json_parsing::JSON_parser::new();
+    let parsed_data = parser.parse_and_convert_game_data(json_parsing::PATH_BUNDLE);
+    let mut world = ok_or_panic!(crate::game_engine::starting_level_generator::generate_world_from_json_parsed_data(&parsed_data));
+    // a strip of floor split by a wall, away from the starting level
+    ok_or_panic!(world.generate_terrain_from_descriptor(&terrain_json{x: 0, y: 0, width: 6, height: 1, terrain_archetype: "path".into()}, 200, 200));
+    ok_or_panic!(world.generate_terrain_from_descriptor(&terrain_json{x: 6, y: 0, width: 1, height: 1, terrain_archetype: "wallr".into()}, 200, 200));
+    ok_or_panic!(world.generate_terrain_from_descriptor(&terrain_json{x: 7, y: 0, width: 6, height: 1, terrain_archetype: "path".into()}, 200, 200));
+    world.player.borrow_mut().x = 200.0 * 32.0;
+    world.player.borrow_mut().y = 200.0 * 32.0;
+    world.update_visibility();
+    world.update_explored();
+    assert!(world.is_explored([200, 200]), "The tile the player is on should be explored");
+    assert!(world.is_explored([206, 200]), "The wall the player can see should be explored");
+    assert!(!world.is_explored([207, 200]), "Tiles behind the wall should not be explored");
+
+    let tiles = world.get_map_tiles(200, 200, 13, 1);
+    assert!(tiles.len() == 7, "Only explored tiles should be on the map");
+    assert!(tiles.get(&[206, 200]) == Some(&true), "Walls should show up as blocking");
+
+    world.player.borrow_mut().x = 209.0 * 32.0;
+    world.update_visibility();
+    world.update_explored();
+    let tiles = world.get_map_tiles(200, 200, 13, 1);
+    assert!(tiles.len() == 13, "Going past the wall should explore the rest of the strip");
+    assert!(tiles.iter().filter(|(_, blocks)| **blocks).count() == 1, "The floor should not block");
+
+    let save = ok_or_panic!(world.save_map());
+    let explored = world.minimap.explored.clone();
+    world.minimap.explored.clear();
+    world.toggle_full_map();
+    ok_or_panic!(world.load_map(&save));
+    assert!(world.minimap.explored == explored, "Loading a saved map should bring back what was explored");
+    assert!(world.minimap.full_map, "Loading a saved map should not close the full map");
+}
@@ -9,3 +9,11 @@ pub mod player_attack_tests;
 pub mod world_generation_tests;
 pub mod inventory_movement_tests;
 pub mod item_drop_tests;
+pub mod tiled_import_tests;
+pub mod interactable_tests;
+pub mod progression_tests;
+pub mod level_editor_tests;
+pub mod minimap_tests;
+pub mod lighting_tests;
+pub mod particles_tests;
+pub mod animation_tests;
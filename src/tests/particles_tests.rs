@@ -0,0 +1,33 @@
+#![cfg(test)]
+
+use crate::game_engine::json_parsing;
+use crate::game_engine::particles::{DEATH_EMITTER, HIT_EMITTER};
+use crate::ok_or_panic;
+
+#[test]
+fn particles_test(){
+    let mut parser = json_parsing::JSON_parser::new();
+    let parsed_data = parser.parse_and_convert_game_data(json_parsing::PATH_BUNDLE);
+    let mut world = ok_or_panic!(crate::game_engine::starting_level_generator::generate_world_from_json_parsed_data(&parsed_data));
+    world.spawn_particles("not_an_emitter", 100.0, 100.0);
+    assert!(world.particles.particles.borrow().is_empty(), "Emitters that don't exist should spawn nothing");
+
+    let hit = world.particles.emitters[world.particles.get_emitter_id(HIT_EMITTER).expect("There should be a hit emitter")].clone();
+    world.spawn_particles(HIT_EMITTER, 100.0, 100.0);
+    assert!(world.particles.particles.borrow().len() == hit.burst, "Emitters should spawn their burst straight away");
+    world.update_particles();
+    assert!(world.particles.particles.borrow().iter().all(|x| x.x != 100.0 || x.y != 100.0), "Particles should move");
+
+    let death = world.particles.emitters[world.particles.get_emitter_id(DEATH_EMITTER).expect("There should be a death emitter")].clone();
+    world.particles.particles.borrow_mut().clear();
+    world.spawn_particles(DEATH_EMITTER, 100.0, 100.0);
+    for _ in 0..death.duration as usize {
+        world.update_particles();
+    }
+    assert!(world.particles.active.borrow().is_empty(), "Emitters should stop once their duration is up");
+    assert!(world.particles.particles.borrow().len() == death.burst + (death.rate * death.duration) as usize, "Emitters should spawn at their rate while active");
+    for _ in 0..(death.lifetime + death.lifetime_spread) as usize {
+        world.update_particles();
+    }
+    assert!(world.particles.particles.borrow().is_empty(), "Particles should die at the end of their lifetime");
+}
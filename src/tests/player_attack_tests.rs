@@ -1,24 +1,17 @@
 #![cfg(test)]
 use compact_str::CompactString;
 
-use crate::{create_stat_list, game_engine::{entity_components::CollisionBox, game::MousePosition, item::{Item, ItemType}, stat::StatC}, ok_or_panic, tests::lib::headless::HeadlessGame};
+use crate::{create_stat_list, game_engine::{game::MousePosition, item::{Item, ItemArchetype, ItemType, Rarity}, melee_combos::MeleeSwing, ranged_weapons::{RangedDescriptor, ShotPattern}, stat::{DamageType, GearStatList, StatC, StatList}}, ok_or_panic, tests::lib::headless::HeadlessGame};
 
-use super::test_framework::{basic_world, basic_camera};
+use super::test_framework::{basic_camera, basic_entity, basic_world};
 #[tokio::test]
 pub async fn test_melee_player_attack() {
     let mut world = basic_world().await;
     let camera = basic_camera(&mut world).await;
-    world.add_entity_archetype(CompactString::from("test_attackable_entity"), vec![
-        crate::game_engine::entities::EntityTags::BaseHealth(100),
-        crate::game_engine::entities::EntityTags::Damageable(
-            CollisionBox {
-                x_offset: 0.0,
-                y_offset: 0.0,
-                w: 32.0,
-                h: 32.0
-            }
-        )
-    ]);
+    let mut attackable = basic_entity().await;
+    attackable.basic_tags = vec!["damageable".into()];
+    attackable.health = Some(100);
+    world.add_entity_archetype(CompactString::from("test_attackable_entity"), attackable);
     let item = world.inventory.add_item(
         Item {
             name: CompactString::from("test_sword"),
@@ -32,11 +25,15 @@ pub async fn test_melee_player_attack() {
                 width => StatC { flat: 50.0, percent: 0.0},
                 reach => StatC { flat: 65.0, percent: 0.0},
             ),
-            time_til_usable: 0.0
+            time_til_usable: 0.0,
+            archetype: CompactString::from("test"),
+            rarity: crate::game_engine::item::Rarity::Common,
+            damage_type: crate::game_engine::stat::DamageType::Physical,
+            uses: None
         }
     );
     ok_or_panic!(world.inventory.set_hotbar_slot_item(0, item));
-    world.create_entity_with_archetype(639.0, 400.0, CompactString::from("test_attackable_entity"));
+    let entity = ok_or_panic!(world.create_entity_with_archetype(639.0, 400.0, CompactString::from("test_attackable_entity")));
     let mut headless = HeadlessGame::new(world, camera);
     ok_or_panic!(headless.world.on_mouse_click(MousePosition {
             x_screen: 639.0,
@@ -45,12 +42,12 @@ pub async fn test_melee_player_attack() {
             y_world: 400.0 + headless.camera.camera_y,
     }, true, false, headless.camera.viewpoint_width as f32, headless.camera.viewpoint_height as f32));
     assert!(
-        headless.world.entity_health_components.get(&0).is_some(),
+        headless.world.components.damageable_components[entity].is_some(),
         "Entity should have a health component prior to player attack"
     );
     ok_or_panic!(headless.run(20).await);
     assert!(
-        headless.world.entity_health_components.get(&0).is_none(),
+        headless.world.components.damageable_components[entity].is_none(),
         "Entity should be killed by player attack"
     );
 }
@@ -59,17 +56,10 @@ pub async fn test_melee_player_attack() {
 pub async fn test_ranged_player_attack() {
     let mut world = basic_world().await;
     let camera = basic_camera(&mut world).await;
-    world.add_entity_archetype(CompactString::from("test_attackable_entity"), vec![
-        crate::game_engine::entities::EntityTags::BaseHealth(100),
-        crate::game_engine::entities::EntityTags::Damageable(
-            CollisionBox {
-                x_offset: 0.0,
-                y_offset: 0.0,
-                w: 32.0,
-                h: 32.0
-            }
-        )
-    ]);
+    let mut attackable = basic_entity().await;
+    attackable.basic_tags = vec!["damageable".into()];
+    attackable.health = Some(100);
+    world.add_entity_archetype(CompactString::from("test_attackable_entity"), attackable);
     let item = world.inventory.add_item(
         Item {
             name: CompactString::from("test_spear"),
@@ -85,10 +75,14 @@ pub async fn test_ranged_player_attack() {
                 size => StatC { flat: 30.0, percent: 0.0},
             ),
             time_til_usable: 0.0,
+            archetype: CompactString::from("test"),
+            rarity: crate::game_engine::item::Rarity::Common,
+            damage_type: crate::game_engine::stat::DamageType::Physical,
+            uses: None
         }
     );
     ok_or_panic!(world.inventory.set_hotbar_slot_item(0, item));
-    world.create_entity_with_archetype(689.0, 400.0, CompactString::from("test_attackable_entity"));
+    let entity = ok_or_panic!(world.create_entity_with_archetype(689.0, 400.0, CompactString::from("test_attackable_entity")));
     let mut headless = HeadlessGame::new(world, camera);
     ok_or_panic!(headless.world.process_mouse_input(MousePosition {
             x_screen: 689.0,
@@ -97,12 +91,145 @@ pub async fn test_ranged_player_attack() {
             y_world: 400.0 + headless.camera.camera_y,
     }, true, false));
     assert!(
-        headless.world.entity_health_components.get(&0).is_some(),
+        headless.world.components.damageable_components[entity].is_some(),
         "Entity should have a health component prior to player attack"
     );
     ok_or_panic!(headless.run(200).await);
     assert!(
-        headless.world.entity_health_components.get(&0).is_none(),
+        headless.world.components.damageable_components[entity].is_none(),
         "Entity should be killed by player attack"
     );
 }
+
+fn test_archetype(name: &str, item_type: ItemType, ranged: Option<RangedDescriptor>, combo: Option<Vec<MeleeSwing>>) -> ItemArchetype {
+    ItemArchetype {
+        name: name.into(),
+        stats: GearStatList::default(),
+        lore: String::from("test"),
+        item_type,
+        width_to_length_ratio: None,
+        sprite: "sword".into(),
+        attack_sprite: Some("melee_attack".into()),
+        rarity: Rarity::Common,
+        damage_type: DamageType::Physical,
+        uses: None,
+        ranged,
+        combo,
+        teaches_ability: None,
+        summon: None
+    }
+}
+
+fn test_item(archetype: &str, item_type: ItemType, stats: StatList, uses: Option<usize>) -> Item {
+    Item {
+        name: archetype.into(),
+        attack_sprite: Some("melee_attack".into()),
+        item_type,
+        width_to_length_ratio: None,
+        lore: String::from("test"),
+        sprite: "sword".into(),
+        stats,
+        time_til_usable: 0.0,
+        archetype: archetype.into(),
+        rarity: Rarity::Common,
+        damage_type: DamageType::Physical,
+        uses
+    }
+}
+
+#[tokio::test]
+async fn test_charge_shots_burst_and_ammo(){
+    let mut world = basic_world().await;
+    let _camera = basic_camera(&mut world).await;
+    world.item_archetype_lookup.insert("bow".into(), test_archetype("bow", ItemType::RangedWeapon, Some(RangedDescriptor {
+        charge_time: 30.0,
+        min_charge: 0.5,
+        max_charge: 2.0,
+        pattern: ShotPattern::Burst,
+        burst_delay: 6.0,
+        ammo: Some("arrow".into()),
+        gravity: 0.0
+    }), None));
+    let bow = world.inventory.add_item(test_item("bow", ItemType::RangedWeapon, create_stat_list!(
+        damage => StatC {flat: 10.0, percent: 0.0},
+        shots => StatC {flat: 3.0, percent: 0.0}
+    ), None));
+    ok_or_panic!(world.inventory.set_hotbar_slot_item(0, bow));
+    world.inventory.set_hotbar_slot(0);
+    let stats = ok_or_panic!(world.inventory.get_combined_stats());
+    let damage = stats.damage.unwrap().flat;
+    let shots = stats.shots.unwrap().get_value() as usize;
+    let mouse = MousePosition {x_screen: 0.0, y_screen: 0.0, x_world: 900.0, y_world: 422.0};
+
+    assert!(!ok_or_panic!(world.fire_ranged_weapon(mouse, 1.0)), "Nothing should be fired without ammo");
+    assert!(world.player_attacks.borrow().is_empty(), "Nothing should be fired without ammo");
+
+    let arrows = world.inventory.add_item(test_item("arrow", ItemType::Ammo, StatList::default(), Some(2)));
+    assert!(ok_or_panic!(world.fire_ranged_weapon(mouse, 1.0)), "The bow should fire with ammo");
+    assert!(world.inventory.get_item(&arrows).unwrap().uses == Some(1), "Firing a volley should use up one ammo");
+    assert!(world.player_attacks.borrow().len() == 1 && world.queued_shots.borrow().len() == shots - 1, "A burst should fire its first shot right away and queue the rest");
+    assert!(world.player_attacks.borrow()[0].stats.damage.unwrap().flat == damage * 2.0, "A fully charged shot should do max charge damage");
+    for _ in 0..20 {
+        ok_or_panic!(world.update_queued_shots());
+    }
+    assert!(world.player_attacks.borrow().len() == shots && world.queued_shots.borrow().is_empty(), "Queued burst shots should fire after their delay");
+
+    world.player_attacks.borrow_mut().clear();
+    assert!(ok_or_panic!(world.fire_ranged_weapon(mouse, 0.0)), "The last ammo should still fire");
+    assert!(world.player_attacks.borrow()[0].stats.damage.unwrap().flat == damage * 0.5, "An uncharged shot should do min charge damage");
+    assert!(world.inventory.get_item(&arrows).is_none(), "Used up ammo should be destroyed");
+    assert!(!ok_or_panic!(world.fire_ranged_weapon(mouse, 1.0)), "Nothing should be fired once the ammo runs out");
+}
+
+#[tokio::test]
+async fn test_combo_window(){
+    let mut world = basic_world().await;
+    let mut camera = basic_camera(&mut world).await;
+    let combo = vec![
+        MeleeSwing {arc: 90.0, duration: 4.0, window: 10.0, ..Default::default()},
+        MeleeSwing {arc: -90.0, duration: 4.0, window: 10.0, ..Default::default()},
+        MeleeSwing {arc: 0.0, duration: 6.0, damage_multiplier: 2.0, window: 10.0, ..Default::default()},
+    ];
+    world.item_archetype_lookup.insert("combo_sword".into(), test_archetype("combo_sword", ItemType::MeleeWeapon, None, Some(combo)));
+    world.item_archetype_lookup.insert("other_sword".into(), test_archetype("other_sword", ItemType::MeleeWeapon, None, Some(vec![MeleeSwing::default(), MeleeSwing::default()])));
+    let sword = world.inventory.add_item(test_item("combo_sword", ItemType::MeleeWeapon, create_stat_list!(
+        damage => StatC {flat: 10.0, percent: 0.0},
+        reach => StatC {flat: 30.0, percent: 0.0},
+        width => StatC {flat: 10.0, percent: 0.0}
+    ), None));
+    let other = world.inventory.add_item(test_item("other_sword", ItemType::MeleeWeapon, StatList::default(), None));
+    ok_or_panic!(world.inventory.set_hotbar_slot_item(0, sword));
+    ok_or_panic!(world.inventory.set_hotbar_slot_item(1, other));
+    world.inventory.set_hotbar_slot(0);
+    let stats = ok_or_panic!(world.inventory.get_combined_stats());
+    let item = world.inventory.get_item(&sword).unwrap().clone();
+
+    let swing = world.next_melee_swing(&mut world.player.borrow_mut()).unwrap();
+    assert!(swing.arc == 90.0, "The first attack should use the first swing");
+    ok_or_panic!(world.start_melee_swing(&mut world.player.borrow_mut(), &stats, &item, &swing, 0.0));
+    for _ in 0..5 {
+        ok_or_panic!(world.update_player_attacks(&mut camera));
+    }
+    assert!(world.player_attacks.borrow().is_empty(), "The swing should end after its duration");
+    assert!(world.player.borrow().combo_window == 10.0, "Ending a swing should open the combo window");
+
+    let swing = world.next_melee_swing(&mut world.player.borrow_mut()).unwrap();
+    assert!(swing.arc == -90.0, "Attacking inside the window should continue the combo");
+    world.player.borrow_mut().combo_window = 10.0;
+    let swing = world.next_melee_swing(&mut world.player.borrow_mut()).unwrap();
+    assert!(swing.damage_multiplier == 2.0, "The combo should reach its last swing");
+    assert!(swing.apply_to_stats(&stats).damage.unwrap().flat == 20.0, "A swing should scale the weapon's damage");
+    world.player.borrow_mut().combo_window = 10.0;
+    assert!(world.next_melee_swing(&mut world.player.borrow_mut()).unwrap().arc == 90.0, "The combo should loop after its last swing");
+
+    world.player.borrow_mut().combo_window = 10.0;
+    for _ in 0..10 {
+        ok_or_panic!(world.update_combo_window());
+    }
+    assert!(world.next_melee_swing(&mut world.player.borrow_mut()).unwrap().arc == 90.0, "Attacking after the window closes should start the combo over");
+
+    world.player.borrow_mut().combo_window = 10.0;
+    world.inventory.set_hotbar_slot(1);
+    world.next_melee_swing(&mut world.player.borrow_mut()).unwrap();
+    assert!(world.player.borrow().combo_step == 0, "Switching weapons should start the combo over");
+}
@@ -2,9 +2,9 @@
 use compact_str::CompactString;
 
 use crate::ok_or_panic;
-use crate::tests::test_framework::{basic_camera, basic_world};
+use crate::tests::test_framework::{basic_camera, basic_entity, basic_world};
 use crate::tests::lib::headless::HeadlessGame;
-use crate::game_engine::{terrain::TerrainTags, entities::EntityTags, entity_components};
+use crate::game_engine::terrain::TerrainTags;
 
 #[tokio::test]
 async fn test_player_terrain_collision_moving_right(){
@@ -102,22 +102,10 @@ async fn test_player_terrain_collision_moving_down(){
 async fn test_player_entity_collision_moving_down(){
     let mut world = basic_world().await;
     let camera = basic_camera(&mut world).await;
-    let entity_blocker = world.add_entity(576.0, 442.0);
-    world.add_entity_archetype(
-        CompactString::from("Test"),
-        vec![
-            EntityTags::HasCollision(
-                entity_components::CollisionBox{
-                    w: 32.0,
-                    h: 32.0,
-                    x_offset: 0.0,
-                    y_offset: 0.0
-                }
-            ),
-            EntityTags::Sprite(0)
-        ]
-    );
-    world.set_entity_archetype(entity_blocker, CompactString::from("Test"));
+    let mut blocker = basic_entity().await;
+    blocker.basic_tags = vec!["hasCollision".into()];
+    world.add_entity_archetype(CompactString::from("Test"), blocker);
+    ok_or_panic!(world.create_entity_with_archetype(576.0, 442.0, CompactString::from("Test")));
     let player_starting_y = world.player.borrow().y;
     let mut headless = HeadlessGame::new(world, camera);
     headless.state.keys_down.insert(CompactString::from("s"), true);
@@ -132,22 +120,10 @@ async fn test_player_entity_collision_moving_down(){
 async fn test_player_entity_collision_moving_up(){
     let mut world = basic_world().await;
     let camera = basic_camera(&mut world).await;
-    let entity_blocker = world.add_entity(576.0, 358.0);
-    world.add_entity_archetype(
-        CompactString::from("Test"),
-        vec![
-            EntityTags::HasCollision(
-                entity_components::CollisionBox{
-                    w: 32.0,
-                    h: 32.0,
-                    x_offset: 0.0,
-                    y_offset: 0.0
-                }
-            ),
-            EntityTags::Sprite(0)
-        ]
-    );
-    world.set_entity_archetype(entity_blocker, CompactString::from("Test"));
+    let mut blocker = basic_entity().await;
+    blocker.basic_tags = vec!["hasCollision".into()];
+    world.add_entity_archetype(CompactString::from("Test"), blocker);
+    ok_or_panic!(world.create_entity_with_archetype(576.0, 358.0, CompactString::from("Test")));
     let player_starting_y = world.player.borrow().y;
     let mut headless = HeadlessGame::new(world, camera);
     headless.state.keys_down.insert(CompactString::from("w"), true);
@@ -162,22 +138,10 @@ async fn test_player_entity_collision_moving_up(){
 async fn test_player_entity_collision_moving_left(){
     let mut world = basic_world().await;
     let camera = basic_camera(&mut world).await;
-    let entity_blocker = world.add_entity(554.0, 402.0);
-    world.add_entity_archetype(
-        CompactString::from("Test"),
-        vec![
-            EntityTags::HasCollision(
-                entity_components::CollisionBox{
-                    w: 32.0,
-                    h: 32.0,
-                    x_offset: 0.0,
-                    y_offset: 0.0
-                }
-            ),
-            EntityTags::Sprite(0)
-        ]
-    );
-    world.set_entity_archetype(entity_blocker, CompactString::from("Test"));
+    let mut blocker = basic_entity().await;
+    blocker.basic_tags = vec!["hasCollision".into()];
+    world.add_entity_archetype(CompactString::from("Test"), blocker);
+    ok_or_panic!(world.create_entity_with_archetype(554.0, 402.0, CompactString::from("Test")));
     let player_starting_x = world.player.borrow().x;
     let mut headless = HeadlessGame::new(world, camera);
     headless.state.keys_down.insert(CompactString::from("a"), true);
@@ -192,29 +156,69 @@ async fn test_player_entity_collision_moving_left(){
 async fn test_player_entity_collision_moving_right(){
     let mut world = basic_world().await;
     let camera = basic_camera(&mut world).await;
-    let entity_blocker = world.add_entity(648.0, 402.0);
-    world.add_entity_archetype(
-        CompactString::from("Test"),
+    let mut blocker = basic_entity().await;
+    blocker.basic_tags = vec!["hasCollision".into()];
+    world.add_entity_archetype(CompactString::from("Test"), blocker);
+    ok_or_panic!(world.create_entity_with_archetype(648.0, 402.0, CompactString::from("Test")));
+    let player_starting_x = world.player.borrow().x;
+    let mut headless = HeadlessGame::new(world, camera);
+    headless.state.keys_down.insert(CompactString::from("d"), true);
+    ok_or_panic!(headless.run(200).await);
+    println!("Player X: {}", headless.world.player.borrow().x);
+    assert!(
+        headless.world.player.borrow().x < player_starting_x + 50.0,
+        "Player should not be able to move right through an entity blocker"
+    )
+}
+
+#[tokio::test]
+async fn test_player_water_collision_moving_right(){
+    let mut world = basic_world().await;
+    let camera = basic_camera(&mut world).await;
+    let water = world.add_terrain(638, 402);
+    world.add_terrain_archetype(
+        "water".into(),
         vec![
-            EntityTags::HasCollision(
-                entity_components::CollisionBox{
-                    w: 32.0,
-                    h: 32.0,
-                    x_offset: 0.0,
-                    y_offset: 0.0
-                }
-            ),
-            EntityTags::Sprite(0)
+            TerrainTags::Water,
+            TerrainTags::Sprite(0)
         ]
     );
-    world.set_entity_archetype(entity_blocker, CompactString::from("Test"));
+    world.set_terrain_archetype(water, "water".into());
     let player_starting_x = world.player.borrow().x;
     let mut headless = HeadlessGame::new(world, camera);
     headless.state.keys_down.insert(CompactString::from("d"), true);
     ok_or_panic!(headless.run(200).await);
-    println!("Player X: {}", headless.world.player.borrow().x);
     assert!(
         headless.world.player.borrow().x < player_starting_x + 50.0,
-        "Player should not be able to move right through an entity blocker"
+        "Player should not be able to walk into water"
+    )
+}
+
+#[tokio::test]
+async fn test_player_slowed_by_terrain(){
+    let mut world = basic_world().await;
+    let camera = basic_camera(&mut world).await;
+    let player_starting_x = world.player.borrow().x;
+    let player_starting_y = world.player.borrow().y;
+    world.add_terrain_archetype(
+        "mud".into(),
+        vec![
+            TerrainTags::SlowsMovement(0.5),
+            TerrainTags::Sprite(0)
+        ]
+    );
+    for x in 0..12 {
+        for y in 0..3 {
+            let mud = world.add_terrain(player_starting_x as usize - 64 + x * 32, player_starting_y as usize - 32 + y * 32);
+            world.set_terrain_archetype(mud, "mud".into());
+        }
+    }
+    let movement_speed = world.player.borrow().movement_speed;
+    let mut headless = HeadlessGame::new(world, camera);
+    headless.state.keys_down.insert(CompactString::from("d"), true);
+    ok_or_panic!(headless.run(20).await);
+    assert!(
+        headless.world.player.borrow().x < player_starting_x + movement_speed * 20.0 * 0.75,
+        "Player should move slower over terrain that slows movement"
     )
 }
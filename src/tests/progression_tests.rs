@@ -0,0 +1,149 @@
+#![cfg(test)]
+use crate::game_engine::ability_unlocks::{AbilityLevelReward, AbilityUnlockResult};
+use crate::game_engine::player_abilities::get_player_ability_descriptors;
+use crate::game_engine::progression::Progression;
+use crate::ok_or_panic;
+use crate::tests::test_framework::{basic_entity, basic_world};
+
+fn test_progression() -> Progression {
+    serde_json::from_str(r#"{
+        "exp_curve": {"base": 100.0, "growth": 2.0, "increment": 10.0, "max_level": 3},
+        "stat_points_per_level": 2,
+        "skill_points_per_level": 1,
+        "allocatable_stats": [{"stat": "damage", "per_point": {"flat": 5.0}}],
+        "skill_tree": [
+            {"name": "First", "description": "", "position": [0.0, 0.0], "stats": {"defense": {"flat": 10.0}}},
+            {"name": "Second", "description": "", "position": [0.0, 60.0], "prerequisites": ["First"], "ability": "Cyclone"}
+        ]
+    }"#).expect("The test progression should parse")
+}
+
+#[test]
+fn exp_curve_test(){
+    let progression = test_progression();
+    assert!(progression.exp_curve.exp_required(0) == 100.0, "Level 0 should need the base exp");
+    assert!(progression.exp_curve.exp_required(2) == 420.0, "Later levels should grow and add the increment");
+    ok_or_panic!(progression.validate(&get_player_ability_descriptors()));
+}
+
+#[tokio::test]
+async fn test_exp_levels_up_and_gives_points(){
+    let mut world = basic_world().await;
+    world.progression = test_progression();
+    ok_or_panic!(world.add_player_exp(150.0));
+    {
+        let player = world.player.borrow();
+        assert!(player.level == 1, "Going past the exp required should level up");
+        assert!(player.exp == 50.0, "Exp past the level up should carry over");
+        assert!(player.stat_points == 2 && player.skill_points == 1, "Levelling up should give stat and skill points");
+    }
+    ok_or_panic!(world.add_player_exp(10000.0));
+    assert!(world.player.borrow().level == 3, "Levels should stop at the max level");
+}
+
+#[tokio::test]
+async fn test_stat_point_allocation(){
+    let mut world = basic_world().await;
+    world.progression = test_progression();
+    ok_or_panic!(world.allocate_stat_point("damage"));
+    assert!(world.inventory.progression_stats.damage.is_none(), "Allocating without stat points should do nothing");
+    world.player.borrow_mut().stat_points = 2;
+    ok_or_panic!(world.allocate_stat_point("damage"));
+    ok_or_panic!(world.allocate_stat_point("damage"));
+    assert!(world.player.borrow().stat_points == 0, "Allocating should spend stat points");
+    assert!(world.inventory.progression_stats.damage.is_some_and(|x| x.flat == 10.0), "Each point should add its stats");
+}
+
+#[tokio::test]
+async fn test_skill_prerequisites(){
+    let mut world = basic_world().await;
+    world.progression = test_progression();
+    world.player.borrow_mut().skill_points = 2;
+    assert!(!world.can_unlock_skill("Second"), "Skills should need their prerequisites");
+    ok_or_panic!(world.unlock_skill("Second"));
+    assert!(world.player.borrow().skill_points == 2, "Locked skills should not spend points");
+    ok_or_panic!(world.unlock_skill("First"));
+    assert!(world.inventory.progression_stats.defense.is_some_and(|x| x.flat == 10.0), "Unlocked skills should give their stats");
+    ok_or_panic!(world.unlock_skill("Second"));
+    assert!(world.player.borrow().unlocked_skills.len() == 2 && world.player.borrow().skill_points == 0, "Skills should unlock once their prerequisites are");
+    let cyclone = world.get_ability_descriptor_id("Cyclone");
+    assert!(world.inventory.player_abilities.iter().any(|x| Some(x.descriptor_id) == cyclone), "Skills should unlock their ability");
+}
+
+#[tokio::test]
+async fn test_skill_with_missing_ability_spends_nothing(){
+    let mut world = basic_world().await;
+    let mut progression = test_progression();
+    progression.skill_tree[0].ability = Some("Not An Ability".into());
+    assert!(progression.validate(&get_player_ability_descriptors()).is_err(), "Skills with missing abilities should fail validation");
+    world.progression = progression;
+    world.player.borrow_mut().skill_points = 1;
+    assert!(world.unlock_skill("First").is_err(), "Unlocking a skill with a missing ability should fail");
+    let player = world.player.borrow();
+    assert!(player.skill_points == 1 && player.unlocked_skills.is_empty(), "A failed unlock should not spend points or unlock the skill");
+}
+
+#[tokio::test]
+async fn test_unlocking_an_owned_ability_upgrades_it(){
+    let mut world = basic_world().await;
+    let dash = world.get_ability_descriptor_id("Dash").expect("Dash should exist");
+    let max_rank = world.player_ability_descriptors[dash].max_rank();
+    assert!(ok_or_panic!(world.unlock_ability("Dash")) == AbilityUnlockResult::Unlocked, "A new ability should be unlocked");
+    for rank in 1..=max_rank {
+        assert!(ok_or_panic!(world.unlock_ability("Dash")) == AbilityUnlockResult::Upgraded(rank), "An owned ability should go up a rank");
+    }
+    assert!(ok_or_panic!(world.unlock_ability("Dash")) == AbilityUnlockResult::AlreadyMaxed, "An ability at its max rank should not go further");
+    assert!(world.inventory.player_abilities.len() == 1, "Upgrading should not add another copy of the ability");
+    assert!(world.unlock_ability("Not An Ability").is_err(), "Unlocking a missing ability should fail");
+}
+
+#[tokio::test]
+async fn test_unlocked_abilities_fill_empty_slots(){
+    let mut world = basic_world().await;
+    ok_or_panic!(world.unlock_ability("Dash"));
+    ok_or_panic!(world.unlock_ability("Dash"));
+    ok_or_panic!(world.unlock_ability("Cyclone"));
+    assert!(world.inventory.ability_slots[0].cur_ability == Some(0), "The first ability should go in the first slot");
+    assert!(world.inventory.ability_slots[1].cur_ability == Some(1), "Upgrades should not take a slot");
+    assert!(world.inventory.ability_slots[2].cur_ability.is_none(), "Slots past the unlocked abilities should stay empty");
+    for ability in get_player_ability_descriptors().iter() {
+        ok_or_panic!(world.unlock_ability(&ability.name));
+    }
+    assert!(world.inventory.player_abilities.len() > world.inventory.ability_slots.len(), "Every ability should unlock even without a free slot");
+    assert!(world.inventory.ability_slots.iter().all(|x| x.cur_ability.is_some()), "Every slot should be filled");
+}
+
+#[tokio::test]
+async fn test_shrine_teaches_one_ability(){
+    let mut world = basic_world().await;
+    let mut shrine = basic_entity().await;
+    shrine.basic_tags = vec!["shrine".into()];
+    world.add_entity_archetype("shrine".into(), shrine);
+    let shrine = ok_or_panic!(world.create_entity_with_archetype(640.0, 400.0, "shrine".into()));
+    ok_or_panic!(world.activate_shrine(shrine));
+    assert!(world.inventory.player_abilities.len() == 1, "A shrine should teach an ability");
+    ok_or_panic!(world.activate_shrine(shrine));
+    assert!(world.inventory.player_abilities.len() == 1 && world.inventory.player_abilities[0].rank == 0, "A used shrine should do nothing");
+}
+
+#[tokio::test]
+async fn test_level_rewards(){
+    let mut world = basic_world().await;
+    let mut progression = test_progression();
+    progression.ability_rewards = vec![
+        AbilityLevelReward {level: 1, ability: "Dash".into()},
+        AbilityLevelReward {level: 3, ability: "Dash".into()},
+    ];
+    world.progression = progression;
+    ok_or_panic!(world.add_player_exp(150.0));
+    assert!(world.inventory.player_abilities.len() == 1 && world.inventory.player_abilities[0].rank == 0, "Reaching a level should unlock its ability");
+    ok_or_panic!(world.add_player_exp(10000.0));
+    assert!(world.inventory.player_abilities[0].rank == 1, "A reward the player already has should upgrade it");
+
+    let mut progression = test_progression();
+    progression.ability_rewards = vec![AbilityLevelReward {level: 1, ability: "Not An Ability".into()}];
+    assert!(progression.validate(&get_player_ability_descriptors()).is_err(), "Rewards with missing abilities should fail validation");
+    let mut progression = test_progression();
+    progression.starting_abilities = vec!["Not An Ability".into()];
+    assert!(progression.validate(&get_player_ability_descriptors()).is_err(), "Starting with a missing ability should fail validation");
+}
@@ -34,6 +34,7 @@ pub async fn basic_world() -> world::World {
     sprites.sprite_id_lookup.insert(CompactString::from("health_bar_back"), 0);
     sprites.sprite_id_lookup.insert(CompactString::from("health"), 0);
     sprites.sprite_id_lookup.insert(CompactString::from("level_editor_menu_background"), 0);
+    sprites.sprite_id_lookup.insert(CompactString::from("map_tile"), 0);
     let mut world = ok_or_panic!(world::World::new(player::Player::new(596.0, 400.0, 10.0, 10, 1.0, 0),sprites));
     world.inventory.add_item(Item {
         name: CompactString::from("test1"),
@@ -47,7 +48,11 @@ pub async fn basic_world() -> world::World {
             width => StatC {flat: 50.0, percent: 0.0},
             reach => StatC {flat: 65.0, percent: 0.0}
         ),
-        time_til_usable: 0.0
+        time_til_usable: 0.0,
+        archetype: CompactString::from("test"),
+        rarity: crate::game_engine::item::Rarity::Common,
+        damage_type: crate::game_engine::stat::DamageType::Physical,
+        uses: None
     });
     world.inventory.add_item(Item {
         name: CompactString::from("test2"),
@@ -61,7 +66,11 @@ pub async fn basic_world() -> world::World {
             width => StatC {flat: 50.0, percent: 0.0},
             reach => StatC {flat: 65.0, percent: 0.0}
         ), 
-        time_til_usable: 0.0
+        time_til_usable: 0.0,
+        archetype: CompactString::from("test"),
+        rarity: crate::game_engine::item::Rarity::Common,
+        damage_type: crate::game_engine::stat::DamageType::Physical,
+        uses: None
     });
     world
 
@@ -74,7 +83,7 @@ pub async fn basic_camera(world: &mut World) -> Camera {
 
 pub async fn basic_entity() -> entity_archetype_json {
     entity_archetype_json {
-        basic_tags: vec!["aggressive".into(), "hasCollision".into(), "attacker".into(), "damageable".into(), "respects_collision".into()],
+        basic_tags: vec!["aggressive".into(), "hasCollision".into(), "attacker".into(), "damageable".into(), "respectsCollision".into()],
         name: "Test".into(),
         collision_box: Some(entity_components::CollisionBox {
             w: 32.0,
@@ -96,6 +105,13 @@ pub async fn basic_entity() -> entity_archetype_json {
         attack_type: CompactString::new("Melee"),
         attack_pattern: Some("test".into()),
         loot_table: vec![],
-        sprite: Some("test_sprite".into())
+        sprite: Some("test_sprite".into()),
+        stock_table: None,
+        stock_size: None,
+        resistances: Default::default(),
+        open_sprite: None,
+        key: None,
+        channel: None,
+        animation: None
     }
 }
@@ -0,0 +1,54 @@
+#![cfg(test)]
+
+use std::path::Path;
+
+use compact_str::CompactString;
+
+use crate::{game_engine::{json_parsing::{self, PATH_BUNDLE}, room_format::validate_room, tiled_import::{import_tiled_map, read_tiled_map, tiled_to_level, tiled_to_room, validate_tiled_import}}, ok_or_panic};
+
+fn game_data_parser() -> json_parsing::JSON_parser {
+    let mut parser = json_parsing::JSON_parser::new();
+    parser.parse_terrain_archetypes(PATH_BUNDLE.terrain_archetypes_path);
+    parser.parse_entity_archetypes(PATH_BUNDLE.entity_archetypes_path);
+    parser.parse_starting_level(PATH_BUNDLE.starting_level_path);
+    parser.parse_spawn_archetypes(PATH_BUNDLE.spawn_archetypes_path);
+    parser
+}
+
+#[tokio::test]
+async fn tiled_room_import_test(){
+    let parser = game_data_parser();
+    let map = ok_or_panic!(read_tiled_map(Path::new("src/tests/test_game_data/tiled/room.tmj")));
+    let import = ok_or_panic!(import_tiled_map(&map));
+    ok_or_panic!(validate_tiled_import(&import, &parser));
+    let room = ok_or_panic!(tiled_to_room(&import, CompactString::from("tiled_room")));
+    ok_or_panic!(validate_room(&room, &parser.terrain_archetypes_json, &parser.spawn_archetypes_json));
+
+    assert!(room.width == 8 && room.height == 6, "Room should be the size of the map");
+    assert!(room.spawn_archetype == "test", "Spawn archetype should come from the map properties");
+    assert!(room.entrance == [1, 2], "Entrance should be at [1, 2]");
+    assert!(room.exit == [6, 2], "Exit should be at [6, 2]");
+    assert!(room.spawnable == vec![[3, 1], [4, 1]], "The spawnable rectangle should cover two tiles");
+    assert!(room.entities.len() == 1 && room.entities[0].archetype == "ghost" && [room.entities[0].x, room.entities[0].y] == [4, 3], "There should be a ghost at [4, 3]");
+    // the floor merges into one rectangle and the walls into eight, the flipped wall tile is still a wall
+    assert!(room.terrain.len() == 9, "Terrain should be merged into 9 rectangles");
+    assert!(room.terrain.iter().any(|x| x.terrain_archetype == "random_dirt" && [x.x, x.y, x.width, x.height] == [1, 1, 6, 4]), "The floor should be a single 6x4 rectangle");
+    assert!(room.terrain.iter().any(|x| x.terrain_archetype == "wallt" && [x.x, x.y, x.width, x.height] == [1, 0, 6, 1]), "The top wall should be a single 6x1 rectangle");
+}
+
+#[tokio::test]
+async fn tiled_level_import_test(){
+    let parser = game_data_parser();
+    // uses an external tileset and puts its walls inside of a group layer
+    let map = ok_or_panic!(read_tiled_map(Path::new("src/tests/test_game_data/tiled/level.tmj")));
+    let import = ok_or_panic!(import_tiled_map(&map));
+    ok_or_panic!(validate_tiled_import(&import, &parser));
+    let level = tiled_to_level(&import, &parser.starting_level_json.player);
+
+    assert!(level.player.x == 64.0 && level.player.y == 96.0, "Player should start on the player object");
+    assert!(level.player.max_health == parser.starting_level_json.player.max_health, "Player stats should be kept");
+    assert!(level.entities.len() == 1 && level.entities[0].archetype == "wizard", "There should be one wizard");
+    assert!(level.entities[0].x == 160.0 && level.entities[0].y == 64.0, "Wizard should be at 160, 64");
+    assert!(level.terrain.len() == 9, "Terrain should be merged into 9 rectangles");
+    assert!(tiled_to_room(&import, CompactString::from("level")).is_err(), "A map without a spawn archetype or exit can't be a room");
+}
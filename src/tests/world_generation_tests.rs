@@ -1,6 +1,10 @@
 #![cfg(test)]
 
-use crate::{game_engine::{entities::AttackType, json_parsing::{self, PathBundle}, pathfinding::EntityDirectionOptions}, ok_or_panic};
+use rand::{rngs::StdRng, SeedableRng};
+use rustc_hash::FxHashMap;
+
+use crate::{game_engine::{autotile::{autotile_index, blob_index, cardinal_index, BLOB_MASKS, BLOB_TILE_COUNT, CARDINAL_TILE_COUNT}, biomes::{Biome, Biomes, NO_TINT}, entities::AttackType, json_parsing::{self, entity_spawn_json, spawn_archetype_json, special_spawn_json, terrain_json, PathBundle}, pathfinding::EntityDirectionOptions, pathfinding::reachable_blocks, room_format::{room_from_level, room_to_level, validate_room, walkable_tiles}, room_generation::{generate_room_layout, Decoration, RoomGeneration, RoomType}, starting_level_generator::{choose_spawns, spawn_from_archetype}}, ok_or_panic};
+use crate::tests::test_framework::{basic_entity, basic_world};
 pub const TEST_PATH_BUNDLE: PathBundle = PathBundle{
     entity_archetypes_path: "src/tests/test_game_data/entity_archetypes.json",
     entity_attack_patterns_path: "src/tests/test_game_data/entity_attack_patterns.json",
@@ -11,7 +15,11 @@ pub const TEST_PATH_BUNDLE: PathBundle = PathBundle{
     item_archetypes_path: "src/tests/test_game_data/items.json",
     loot_table_path: "src/tests/test_game_data/loot_tables.json",
     spawn_archetypes_path: "src/game_data/spawn_archetypes.json",
-    rooms_path: "src/game_data/rooms.json",
+    rooms_path: "src/tests/test_game_data/rooms.json",
+    crafting_path: "src/tests/test_game_data/crafting.json",
+    progression_path: "src/game_data/progression.json",
+    biomes_path: "src/tests/test_game_data/biomes.json",
+    particles_path: "src/tests/test_game_data/particles.json",
 };
 
 
@@ -27,74 +35,18 @@ async fn json_parsing_test(){
     assert!(parsed_data.entity_attack_patterns.len() == 1, "There should be one entity attack pattern");
     assert!(parsed_data.entity_attacks.len() == 1, "There should be one entity attack");
     let archetype = parsed_data.entity_archetypes.get("ghost").expect("There should be a ghost archetype");
-    let mut found_aggressive = false;
-    let mut found_follows_player = false;
-    let mut found_movement_speed = false;
-    let mut found_range = false;
-    let mut found_aggro_range = false;
-    let mut found_attack = false;
-    let mut found_attack_type = false;
-    let mut found_monster_type = false;
-    let mut found_health = false;
-    for tag in archetype.iter(){
-        match tag{
-            crate::game_engine::entities::EntityTags::BaseHealth(health) => {   
-                assert!(!found_health, "BaseHealth tag should only be found once");
-                found_health = true;
-                assert!(*health == 100, "BaseHealth should be 100");
-            }
-            crate::game_engine::entities::EntityTags::Aggressive => {
-                assert!(!found_aggressive, "Aggressive tag should only be found once");
-                found_aggressive = true;
-            },
-            crate::game_engine::entities::EntityTags::FollowsPlayer => {
-                assert!(!found_follows_player, "FollowsPlayer tag should only be found once");
-                found_follows_player = true;
-            },
-            crate::game_engine::entities::EntityTags::MovementSpeed(speed) => {
-                assert!(!found_movement_speed, "MovementSpeed tag should only be found once");
-                found_movement_speed = true;
-                assert!(*speed == 2.0, "Movement speed should be 2.0");
-            },
-            crate::game_engine::entities::EntityTags::Range(range) => {
-                assert!(!found_range, "Range tag should only be found once");
-                found_range = true;
-                assert!(*range == 47, "Range should be 47");
-            },
-            crate::game_engine::entities::EntityTags::AggroRange(aggro_range) => {
-                assert!(!found_aggro_range, "AggroRange tag should only be found once");
-                found_aggro_range = true;
-                assert!(*aggro_range == 1000, "AggroRange should be 1000");
-            },
-            crate::game_engine::entities::EntityTags::Attacks(entity_attack_pattern) => {
-                assert!(!found_attack, "Attack Pattern tag should only be found once");
-                found_attack = true;
-                let attack_pattern = entity_attack_pattern.clone();
-                assert!(attack_pattern.attacks.len() == 1, "There should be one attack in the attack pattern");
-                assert!(attack_pattern.attack_cooldowns.len() == 1, "There should be one attack cooldown in the attack pattern");
-                assert!(attack_pattern.attack_cooldowns[0] == 0.1, "Attack cooldown should be 0.1");
-            },
-            crate::game_engine::entities::EntityTags::AttackType(attack_type) => {
-                assert!(!found_attack_type, "AttackType tag should only be found once");
-                found_attack_type = true;
-                assert!(*attack_type == AttackType::Melee, "Attack type should be melee");
-            },
-            crate::game_engine::entities::EntityTags::MonsterType(monster_type) => {
-                assert!(!found_monster_type, "MonsterType tag should only be found once");
-                found_monster_type = true;
-                assert!(*monster_type == crate::game_engine::entities::MonsterType::Undead, "Monster type should be ghost");
-            },
-            _ => {}
-        }
-    }
-    assert!(found_aggressive, "Aggressive tag should be found");
-    assert!(found_follows_player, "FollowsPlayer tag should be found");
-    assert!(found_movement_speed, "MovementSpeed tag should be found");
-    assert!(found_range, "Range tag should be found");
-    assert!(found_aggro_range, "AggroRange tag should be found");
-    assert!(found_attack, "Attack tag should be found");
-    assert!(found_attack_type, "AttackType tag should be found");
-    assert!(found_monster_type, "MonsterType tag should be found");
+    assert!(archetype.basic_tags.contains(&"aggressive".into()), "The ghost should be aggressive");
+    assert!(archetype.basic_tags.contains(&"attacker".into()), "The ghost should be an attacker");
+    assert!(archetype.health == Some(100), "Health should be 100");
+    assert!(archetype.movement_speed == Some(2.0), "Movement speed should be 2.0");
+    assert!(archetype.range == Some(47), "Range should be 47");
+    assert!(archetype.aggro_range == Some(1000), "Aggro range should be 1000");
+    assert!(archetype.attack_type == "Melee", "Attack type should be melee");
+    assert!(archetype.monster_type == "Undead", "Monster type should be undead");
+    let attack_pattern = archetype.attack_pattern.as_ref().and_then(|x| parsed_data.entity_attack_patterns.get(x)).expect("The ghost's attack pattern should exist");
+    assert!(attack_pattern.attacks.len() == 1, "There should be one attack in the attack pattern");
+    assert!(attack_pattern.attack_cooldowns.len() == 1, "There should be one attack cooldown in the attack pattern");
+    assert!(attack_pattern.attack_cooldowns[0] == 0.1, "Attack cooldown should be 0.1");
     let terrain_archetype = parsed_data.terrain_archetypes.get("basic_outside").expect("There should be a basic terrain archetype");
     assert!(terrain_archetype.name == "basic_outside", "Terrain archetype name should be basic_outside");
     assert!(terrain_archetype.r#type == "basic", "Terrain archetype type should be basic");
@@ -135,92 +87,30 @@ async fn world_generation_test(){
     assert!(chunk.entities_ids.len() == 1, "There should be one entity in the chunk");
     assert!(chunk.terrain_ids.len() == 1, "There should be one terrain in the chunk");
     let entity_id = chunk.entities_ids[0];
-    let enitty_position = *world.entity_position_components.get(&entity_id).expect("There should be an entity position component").borrow();
-    assert!(enitty_position.x == 900.0, "Entity x should be 900.0");
-    assert!(enitty_position.y == 405.0, "Entity y should be 405.0");
-    let entity_collision_box = world.get_entity_collision_box(entity_id).expect("There should be an entity collision box component");
+    let entity_position = world.components.position_components[entity_id].as_ref().expect("There should be an entity position component").borrow();
+    assert!(entity_position.x == 900.0, "Entity x should be 900.0");
+    assert!(entity_position.y == 405.0, "Entity y should be 405.0");
+    let entity_collision_box = world.components.collision_components[entity_id].as_ref().expect("There should be an entity collision component").borrow().collision_box;
     assert!(entity_collision_box.w == 32.0, "Entity collision box width should be 32.0");
     assert!(entity_collision_box.h == 32.0, "Entity collision box height should be 32.0");
     assert!(entity_collision_box.x_offset == 0.0, "Entity collision box x offset should be 0.0");
     assert!(entity_collision_box.y_offset == 0.0, "Entity collision box y offset should be 0.0");
-    let entity_pathfinding = *world.entity_pathfinding_components.get(&entity_id).expect("There should be an entity pathfinding component").borrow();
-    assert!(entity_pathfinding.cur_direction ==EntityDirectionOptions::None,  "Entity direction should be none priot to the update of the world");
-    let entity_attack = *world.entity_attack_components.get(&entity_id).expect("There should be an entity attack component").borrow();
+    let entity_pathfinding = *world.components.pathfinding_components[entity_id].as_ref().expect("There should be an entity pathfinding component").borrow();
+    assert!(entity_pathfinding.cur_direction == EntityDirectionOptions::None, "Entity direction should be none prior to the update of the world");
+    let entity_attack = world.components.attack_components[entity_id].as_ref().expect("There should be an entity attack component").borrow();
     assert!(entity_attack.cur_attack == 0, "The entity should start at attack 0");
     assert!(entity_attack.cur_attack_cooldown == 0.0, "The entity should start with a cooldown of 0.0");
-    let entity_archetype = world.get_entity_archetype(&entity_id).expect("There should be an entity archetype").clone();
-    assert!(entity_archetype == "ghost", "The entity archetype should be ghost");
-    let entity_tags = world.get_entity_tags(entity_id).expect("There should be entity tags").clone();
-    let mut found_aggressive = false;
-    let mut found_follows_player = false;
-    let mut found_movement_speed = false;
-    let mut found_range = false;
-    let mut found_aggro_range = false;
-    let mut found_attack = false;
-    let mut found_attack_type = false;
-    let mut found_monster_type = false;
-    for tag in entity_tags.iter(){
-        match tag{
-            crate::game_engine::entities::EntityTags::Aggressive => {
-                assert!(!found_aggressive, "Aggressive tag should only be found once");
-                found_aggressive = true;
-            },
-            crate::game_engine::entities::EntityTags::FollowsPlayer => {
-                assert!(!found_follows_player, "FollowsPlayer tag should only be found once");
-                found_follows_player = true;
-            },
-            crate::game_engine::entities::EntityTags::MovementSpeed(speed) => {
-                assert!(!found_movement_speed, "MovementSpeed tag should only be found once");
-                found_movement_speed = true;
-                assert!(*speed == 2.0, "Movement speed should be 2.0");
-            },
-            crate::game_engine::entities::EntityTags::Range(range) => {
-                assert!(!found_range, "Range tag should only be found once");
-                found_range = true;
-                assert!(*range == 47, "Range should be 47");
-            },
-            crate::game_engine::entities::EntityTags::AggroRange(aggro_range) => {
-                assert!(!found_aggro_range, "AggroRange tag should only be found once");
-                found_aggro_range = true;
-                assert!(*aggro_range == 1000, "AggroRange should be 1000");
-            },
-            crate::game_engine::entities::EntityTags::Attacks(entity_attack_pattern) => {
-                assert!(!found_attack, "Attack Pattern tag should only be found once");
-                found_attack = true;
-                let attack_pattern = entity_attack_pattern.clone();
-                assert!(attack_pattern.attacks.len() == 1, "There should be one attack in the attack pattern");
-                assert!(attack_pattern.attack_cooldowns.len() == 1, "There should be one attack cooldown in the attack pattern");
-                assert!(attack_pattern.attack_cooldowns[0] == 0.1, "Attack cooldown should be 0.1");
-                let attack = world.get_attack_descriptor_by_name(&attack_pattern.attacks[0]).expect("There should be an attack descriptor");
-                assert!(attack.damage == 150.0, "Attack damage should be 150.0");
-                assert!(attack.reach == 50, "Attack reach should be 50");
-                assert!(attack.width == 50, "Attack width should be 50");
-                assert!(attack.time_to_charge == 5, "Attack time to charge should be 5");
-                assert!(attack.sprite == "attack_highlight", "Attack sprite should be attack_highlight");
-
-
-            },
-            crate::game_engine::entities::EntityTags::AttackType(attack_type) => {
-                assert!(!found_attack_type, "AttackType tag should only be found once");
-                found_attack_type = true;
-                assert!(*attack_type == AttackType::Melee, "Attack type should be melee");
-            },
-            crate::game_engine::entities::EntityTags::MonsterType(monster_type) => {
-                assert!(!found_monster_type, "MonsterType tag should only be found once");
-                found_monster_type = true;
-                assert!(*monster_type == crate::game_engine::entities::MonsterType::Undead, "Monster type should be ghost");
-            },
-            _ => {}
-        }
-    }
-    assert!(found_aggressive, "Aggressive tag should be found");
-    assert!(found_follows_player, "FollowsPlayer tag should be found");
-    assert!(found_movement_speed, "MovementSpeed tag should be found");
-    assert!(found_range, "Range tag should be found");
-    assert!(found_aggro_range, "AggroRange tag should be found");
-    assert!(found_attack, "Attack tag should be found");
-    assert!(found_attack_type, "AttackType tag should be found");
-    assert!(found_monster_type, "MonsterType tag should be found"); 
+    let attack_pattern = world.entity_attack_pattern_lookup.get(&entity_attack.entity_attack_pattern).expect("The entity's attack pattern should exist");
+    assert!(attack_pattern.attacks.len() == 1, "There should be one attack in the attack pattern");
+    assert!(attack_pattern.attack_cooldowns[0] == 0.1, "Attack cooldown should be 0.1");
+    let attack = world.get_attack_descriptor_by_name(&attack_pattern.attacks[0]).expect("There should be an attack descriptor");
+    assert!(attack.damage == 150.0, "Attack damage should be 150.0");
+    assert!(attack.reach == 50, "Attack reach should be 50");
+    assert!(attack.width == 50, "Attack width should be 50");
+    assert!(attack.time_to_charge == 5, "Attack time to charge should be 5");
+    assert!(attack.sprite == "attack_highlight", "Attack sprite should be attack_highlight");
+    assert!(attack.r#type == AttackType::Melee, "Attack type should be melee");
+    assert!(world.components.aggro_components[entity_id].is_some(), "Aggressive entities should have an aggro component");
     let terrain_id = chunk.terrain_ids[0];
     let terrain = world.terrain.get(&terrain_id).expect("There should be a terrain");
     assert!(terrain.x == 0, "Terrain x should be 0");
@@ -230,3 +120,293 @@ async fn world_generation_test(){
     assert!(terrain_tags[0] == crate::game_engine::terrain::TerrainTags::BlocksMovement, "The one terrain tag should be BlocksMovement");
 
 }
+
+#[tokio::test]
+async fn room_validation_test(){
+    let mut parser = json_parsing::JSON_parser::new();
+    parser.parse_terrain_archetypes(json_parsing::PATH_BUNDLE.terrain_archetypes_path);
+    parser.parse_rooms(json_parsing::PATH_BUNDLE.rooms_path);
+    parser.parse_spawn_archetypes(json_parsing::PATH_BUNDLE.spawn_archetypes_path);
+    ok_or_panic!(parser.validate_rooms());
+
+    let mut room = parser.rooms_json.get("test").expect("There should be a test room").clone();
+    room.exit = [0, 0];
+    assert!(validate_room(&room, &parser.terrain_archetypes_json, &parser.spawn_archetypes_json).is_err(), "An exit in a wall should not validate");
+
+    let mut room = parser.rooms_json.get("test").expect("There should be a test room").clone();
+    room.spawnable.push([room.width, 0]);
+    assert!(validate_room(&room, &parser.terrain_archetypes_json, &parser.spawn_archetypes_json).is_err(), "A spawn point outside of the room should not validate");
+
+    let room = parser.rooms_json.get("test").expect("There should be a test room").clone();
+    let level = room_to_level(&room, &parser.starting_level_json.player);
+    assert!(room_from_level(&level, &room) == room, "A room should survive being converted to the starting level format and back");
+}
+
+fn spawn_option(archetype: &str, points: usize) -> entity_spawn_json {
+    entity_spawn_json{
+        archetype: archetype.into(),
+        points,
+        weight: 1.0,
+        min: 0,
+        max: None,
+        elite: None,
+        elite_chance: 0.0,
+    }
+}
+
+fn spawn_archetype(basic: Vec<entity_spawn_json>, total_points_to_spawn: usize) -> spawn_archetype_json {
+    spawn_archetype_json{
+        name: "test".into(),
+        basic,
+        total_points_to_spawn,
+        special: Vec::new(),
+        points_per_depth: 0,
+        elite_chance_per_depth: 0.0,
+    }
+}
+
+#[test]
+fn spawn_selection_test(){
+    let mut rng = StdRng::seed_from_u64(0);
+
+    let archetype = spawn_archetype(vec![spawn_option("ghost", 1), spawn_option("wizard", 1)], 100);
+    let chosen = ok_or_panic!(choose_spawns(&archetype, 0, 100, &mut rng));
+    assert!(chosen.iter().any(|x| x == "wizard"), "The last spawn option should be picked too");
+    assert!(chosen.len() == 100, "Every point should be spent when there are enough spawn points");
+
+    let archetype = spawn_archetype(vec![spawn_option("ghost", 3)], 4);
+    let chosen = ok_or_panic!(choose_spawns(&archetype, 0, 10, &mut rng));
+    assert!(chosen.len() == 1, "An option costing more than half of the points should still be spawned once");
+
+    let archetype = spawn_archetype(vec![spawn_option("ghost", 1), spawn_option("wizard", 3)], 10);
+    for _ in 0..20 {
+        let chosen = ok_or_panic!(choose_spawns(&archetype, 0, 100, &mut rng));
+        let points: usize = chosen.iter().map(|x| if x == "ghost" {1} else {3}).sum();
+        assert!(points <= 10, "The spawns should never go over the points");
+    }
+
+    let mut ghost = spawn_option("ghost", 1);
+    ghost.weight = 0.0;
+    let archetype = spawn_archetype(vec![ghost, spawn_option("wizard", 1)], 20);
+    let chosen = ok_or_panic!(choose_spawns(&archetype, 0, 100, &mut rng));
+    assert!(chosen.iter().all(|x| x == "wizard"), "An option with no weight should never be picked");
+
+    let chosen = ok_or_panic!(choose_spawns(&archetype, 0, 3, &mut rng));
+    assert!(chosen.len() == 3, "There should never be more spawns than spawn points");
+}
+
+#[test]
+fn spawn_min_max_elite_and_depth_test(){
+    let mut rng = StdRng::seed_from_u64(1);
+
+    let mut ghost = spawn_option("ghost", 1);
+    ghost.max = Some(2);
+    let mut wizard = spawn_option("wizard", 5);
+    wizard.min = 1;
+    let archetype = spawn_archetype(vec![ghost, wizard], 4);
+    let chosen = ok_or_panic!(choose_spawns(&archetype, 0, 100, &mut rng));
+    assert!(chosen.iter().filter(|x| *x == "wizard").count() == 1, "The minimum should be spawned even when it costs more than the points");
+    let chosen = ok_or_panic!(choose_spawns(&spawn_archetype(vec![archetype.basic[0].clone()], 10), 0, 100, &mut rng));
+    assert!(chosen.len() == 2, "An option should not be spawned more than its maximum");
+
+    let mut ghost = spawn_option("ghost", 1);
+    ghost.elite = Some("elite_ghost".into());
+    ghost.elite_chance = 1.0;
+    let archetype = spawn_archetype(vec![ghost], 5);
+    let chosen = ok_or_panic!(choose_spawns(&archetype, 0, 100, &mut rng));
+    assert!(chosen.iter().filter(|x| *x == "elite_ghost").count() == 2, "Elites should cost more points");
+    assert!(chosen.len() == 3, "Points left over after the elites should go to normal spawns");
+
+    let mut archetype = spawn_archetype(vec![spawn_option("ghost", 1)], 2);
+    archetype.points_per_depth = 3;
+    assert!(ok_or_panic!(choose_spawns(&archetype, 0, 100, &mut rng)).len() == 2, "The first room should only use the base points");
+    assert!(ok_or_panic!(choose_spawns(&archetype, 2, 100, &mut rng)).len() == 8, "Deeper rooms should get more points");
+
+    let mut ghost = spawn_option("ghost", 1);
+    ghost.elite = Some("elite_ghost".into());
+    let mut archetype = spawn_archetype(vec![ghost], 20);
+    archetype.elite_chance_per_depth = 0.5;
+    assert!(ok_or_panic!(choose_spawns(&archetype, 0, 100, &mut rng)).iter().all(|x| x == "ghost"), "There should be no elites without an elite chance");
+    assert!(ok_or_panic!(choose_spawns(&archetype, 2, 100, &mut rng)).iter().all(|x| x == "elite_ghost"), "Deeper rooms should get more elites");
+}
+
+#[tokio::test]
+async fn special_spawn_test(){
+    let mut world = basic_world().await;
+    world.add_entity_archetype("test".into(), basic_entity().await);
+    let mut archetype = spawn_archetype(vec![spawn_option("test", 1)], 2);
+    archetype.special.push(special_spawn_json{x: 3, y: 4, archetype: "test".into()});
+    let mut spawnable = vec![[1, 1], [2, 2], [3, 4]];
+    let spawned = ok_or_panic!(spawn_from_archetype(&mut world, &archetype, &mut spawnable, 10, 20));
+    assert!(spawned.len() == 3, "The special spawn should be placed on top of the spawns bought with points");
+    let position = world.components.position_components[spawned[0]].as_ref().expect("The special spawn should have a position").borrow();
+    assert!(position.x == 13.0 * 32.0 && position.y == 24.0 * 32.0, "The special spawn should be placed at its position in the room");
+    assert!(spawnable.is_empty(), "The special spawn should take its spawn point");
+}
+
+#[test]
+fn procedural_room_test(){
+    let mut parser = json_parsing::JSON_parser::new();
+    parser.parse_terrain_archetypes(json_parsing::PATH_BUNDLE.terrain_archetypes_path);
+    parser.parse_rooms(json_parsing::PATH_BUNDLE.rooms_path);
+    parser.parse_spawn_archetypes(json_parsing::PATH_BUNDLE.spawn_archetypes_path);
+    let mut room = parser.rooms_json.get("test").expect("There should be a test room").clone();
+    room.terrain = Vec::new();
+    room.spawnable = Vec::new();
+    room.generation = Some(RoomGeneration{
+        wall: "wallt".into(),
+        floor: Some("random_dirt".into()),
+        fill_chance: 0.45,
+        smoothing_steps: 4,
+        min_leaf_size: 5,
+        decorations: vec![Decoration{terrain_archetype: "small_emblem".into(), scale: 2.0, threshold: 0.5}],
+    });
+
+    for room_type in [RoomType::Cave, RoomType::Bsp] {
+        room.room_type = room_type;
+        for seed in 0..20 {
+            let layout = ok_or_panic!(generate_room_layout(&room, &[[10, 15]], &parser.terrain_archetypes_json, &mut StdRng::seed_from_u64(seed)));
+            assert!(layout.room_type == RoomType::Authored, "A generated room should be a plain room");
+            ok_or_panic!(validate_room(&layout, &parser.terrain_archetypes_json, &parser.spawn_archetypes_json));
+            let walkable = ok_or_panic!(walkable_tiles(&layout, &parser.terrain_archetypes_json));
+            let reachable = reachable_blocks(layout.entrance, layout.width, layout.height, &walkable);
+            assert!(reachable[layout.exit[1]][layout.exit[0]], "The exit of a {:?} room should be reachable from the entrance", room_type);
+            assert!(reachable[15][10], "Positions that have to be kept should be reachable");
+            assert!(!layout.spawnable.is_empty(), "A {:?} room should have somewhere to spawn enemies", room_type);
+            assert!(layout.spawnable.iter().all(|x| x[0].abs_diff(layout.entrance[0]) > 3 || x[1].abs_diff(layout.entrance[1]) > 3), "Nothing should spawn next to the entrance");
+            let open = walkable.iter().flatten().filter(|x| **x).count();
+            let reached = reachable.iter().flatten().filter(|x| **x).count();
+            assert!(open == reached, "Every open tile of a {:?} room should be reachable, decorations should not cut any of it off", room_type);
+            assert!((0..layout.width).all(|x| !walkable[0][x] || x == layout.entrance[0] || x == layout.exit[0]), "The edge of the room should be wall");
+        }
+    }
+
+    let layout = ok_or_panic!(generate_room_layout(&room, &[], &parser.terrain_archetypes_json, &mut StdRng::seed_from_u64(0)));
+    assert!(layout == ok_or_panic!(generate_room_layout(&room, &[], &parser.terrain_archetypes_json, &mut StdRng::seed_from_u64(0))), "The same seed should generate the same room");
+
+    let mut spawn_archetypes = parser.spawn_archetypes_json.clone();
+    let spawn_archetype = spawn_archetypes.get_mut(&room.spawn_archetype).expect("The test room's spawn archetype should exist");
+    spawn_archetype.special = vec![special_spawn_json {x: 1, y: 1, archetype: "vendor".into()}];
+    for room_type in [RoomType::Cave, RoomType::Bsp] {
+        room.room_type = room_type;
+        ok_or_panic!(validate_room(&room, &parser.terrain_archetypes_json, &spawn_archetypes));
+    }
+
+    room.generation.as_mut().expect("The room should have generation settings").wall = "random_dirt".into();
+    assert!(generate_room_layout(&room, &[], &parser.terrain_archetypes_json, &mut StdRng::seed_from_u64(0)).is_err(), "A wall that doesn't block movement should not be allowed");
+    room.generation = None;
+    assert!(validate_room(&room, &parser.terrain_archetypes_json, &parser.spawn_archetypes_json).is_err(), "A procedural room without generation settings should not validate");
+}
+
+#[test]
+fn autotile_index_test(){
+    let mut connected = [false; 8];
+    assert!(cardinal_index(connected) == 0 && blob_index(connected) == 0, "A lone tile should use the first sprite");
+    connected[2] = true;
+    connected[4] = true;
+    assert!(cardinal_index(connected) == 6, "East and south should be 2 + 4");
+    assert!(BLOB_MASKS[blob_index(connected)] == 4 | 16, "A corner should not count without both of its sides");
+    connected[3] = true;
+    assert!(BLOB_MASKS[blob_index(connected)] == 4 | 8 | 16, "A corner should count with both of its sides");
+    connected[1] = true;
+    assert!(BLOB_MASKS[blob_index(connected)] == 4 | 8 | 16, "A corner with only one of its sides should be dropped");
+    assert!(blob_index([true; 8]) == BLOB_TILE_COUNT - 1, "A tile surrounded on every side should use the last sprite");
+}
+
+#[test]
+fn autotile_generation_test(){
+    let mut parser = json_parsing::JSON_parser::new();
+    let parsed_data = parser.parse_and_convert_game_data(json_parsing::PATH_BUNDLE);
+    let mut world = ok_or_panic!(crate::game_engine::starting_level_generator::generate_world_from_json_parsed_data(&parsed_data));
+    // painted in two pieces so the first row has to be picked again once the rest is there
+    ok_or_panic!(world.generate_terrain_from_descriptor(&terrain_json{x: 0, y: 0, width: 3, height: 1, terrain_archetype: "wall".into()}, 200, 200));
+    ok_or_panic!(world.generate_terrain_from_descriptor(&terrain_json{x: 0, y: 1, width: 3, height: 2, terrain_archetype: "wall".into()}, 200, 200));
+    let sprite_at = |x: usize, y: usize| {
+        let terrain = world.terrain.iter().find(|(_, t)| t.x == (200 + x) * 32 && t.y == (200 + y) * 32).map(|(id, _)| *id).expect("There should be terrain there");
+        *world.terrain_sprite_lookup.get(&terrain).expect("Autotiled terrain should have a sprite")
+    };
+    for (x, y, sprite) in [(0, 0, "walltl"), (1, 0, "wallt"), (2, 0, "walltr"), (0, 1, "walll"), (1, 1, "castlewalla"), (2, 1, "wallr"), (0, 2, "wallbl"), (1, 2, "wallb"), (2, 2, "wallbr")] {
+        let expected = world.sprites.get_sprite_id(sprite).expect("The wall sprites should exist");
+        assert!(sprite_at(x, y) == expected, "The wall at {}, {} should use {}", x, y, sprite);
+    }
+
+    let mut wall = parser.terrain_archetypes_json.get("wall").expect("There should be a wall terrain archetype").clone();
+    wall.sprites.truncate(CARDINAL_TILE_COUNT);
+    assert!(autotile_index(&wall).is_ok(), "An autotile with 16 sprites should only look at its sides");
+    wall.sprites.pop();
+    assert!(autotile_index(&wall).is_err(), "An autotile with a sprite count other than 16 or 47 should not load");
+    assert!(world.resolve_autotiles(200, 200, 3, 3, &wall).is_err(), "An autotile with a sprite count other than 16 or 47 should not be resolved");
+}
+
+#[test]
+fn biome_progression_test(){
+    let mut parser = json_parsing::JSON_parser::new();
+    let parsed_data = parser.parse_and_convert_game_data(json_parsing::PATH_BUNDLE);
+    let mut world = ok_or_panic!(crate::game_engine::starting_level_generator::generate_world_from_json_parsed_data(&parsed_data));
+    world.biomes = Biomes{
+        rooms_per_floor: 2,
+        biomes: vec![
+            Biome{
+                name: "first".into(),
+                floors: 1,
+                rooms: vec!["test".into()],
+                terrain: FxHashMap::default(),
+                spawn_archetype: None,
+                bonus_points: 0,
+                loot_tables: FxHashMap::default(),
+                loot_multiplier: 1.0,
+                tint: NO_TINT,
+            },
+            Biome{
+                name: "second".into(),
+                floors: 1,
+                rooms: vec!["test2".into()],
+                terrain: [("random_dirt".into(), "path".into())].into_iter().collect(),
+                spawn_archetype: None,
+                bonus_points: 3,
+                loot_tables: [("ghost_loot_table".into(), "wizard_loot_table".into())].into_iter().collect(),
+                loot_multiplier: 2.0,
+                tint: [0.5, 0.5, 0.5, 1.0],
+            },
+        ],
+    };
+    let mut rng = StdRng::seed_from_u64(0);
+
+    world.depth = 1;
+    assert!(world.get_biome().expect("There should be a biome").name == "first", "The first rooms should be in the first biome");
+    assert!(world.get_ambient_tint() == NO_TINT, "The first biome should not tint anything");
+    assert!(world.pick_room(&mut rng).expect("There should be a room to pick") == "test", "Rooms should come from the biome's pool");
+
+    world.depth = 2;
+    assert!(world.get_floor() == 1, "Every 2 rooms should be a floor");
+    assert!(world.get_biome().expect("There should be a biome").name == "second", "The next floor should move on to the next biome");
+    world.depth = 40;
+    assert!(world.get_biome().expect("There should be a biome").name == "second", "The last biome should never end");
+    assert!(world.get_ambient_tint() == [0.5, 0.5, 0.5, 1.0], "The biome should set the tint");
+    assert!(world.pick_room(&mut rng).expect("There should be a room to pick") == "test2", "Rooms should come from the biome's pool");
+    assert!(world.get_biome_loot_table(&"ghost_loot_table".into()) == "wizard_loot_table", "The biome should swap loot tables");
+    assert!(world.get_biome_loot_table(&"merchant_stock".into()) == "merchant_stock", "Loot tables the biome doesn't swap should stay");
+
+    let mut room = world.room_descriptors.get("test").expect("There should be a test room").clone();
+    let mut spawn_archetype = world.spawn_archetype_descriptors.get(&room.spawn_archetype).expect("There should be a spawn archetype").clone();
+    let points = spawn_archetype.total_points_to_spawn;
+    assert!(room.terrain.iter().any(|x| x.terrain_archetype == "random_dirt"), "The test room should have dirt to swap");
+    ok_or_panic!(world.apply_biome(&mut room, &mut spawn_archetype));
+    assert!(room.terrain.iter().all(|x| x.terrain_archetype != "random_dirt"), "The biome should swap the room's terrain");
+    assert!(spawn_archetype.total_points_to_spawn == points + 3, "The biome should make rooms spawn more");
+
+    world.depth = 3;
+    let rooms = std::mem::take(&mut world.room_descriptors);
+    world.biomes.biomes.iter_mut().for_each(|x| x.rooms.clear());
+    world.cur_room = None;
+    world.cur_exit = Some([40, 12]);
+    world.player.borrow_mut().x = 40.0 * 32.0;
+    world.player.borrow_mut().y = 12.0 * 32.0;
+    for _ in 0..3 {
+        ok_or_panic!(world.update_gen());
+    }
+    assert!(world.depth == 3, "Standing on the exit with no room to move into should stay at the same depth");
+    world.room_descriptors = rooms;
+    ok_or_panic!(world.update_gen());
+    assert!(world.depth == 4 && world.cur_exit != Some([40, 12]), "Moving into the next room should go one deeper");
+}